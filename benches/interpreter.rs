@@ -0,0 +1,93 @@
+// Timings of whole programs, from the source to the last statement, so that
+// changes to any stage of the interpreter show up. Every program runs in a
+// fresh global scope, with what it prints kept out of the report. `isolates`
+// times starting interpreters from a compiled prelude, as a server would for
+// each request.
+//
+// `cargo bench` runs them all; `cargo bench -- fib` only the ones whose name
+// contains `fib`.
+
+use std::time::{Duration, Instant};
+
+use xi::{
+    env::Env,
+    interpreter::{Interpreter, Program},
+    runtime::Runtime,
+    sync::{Rc, RefCell},
+};
+
+const PROGRAMS: [(&str, &str); 4] = [
+    ("fib", include_str!("programs/fib.xi")),
+    ("nbody", include_str!("programs/nbody.xi")),
+    ("dict", include_str!("programs/dict.xi")),
+    ("strings", include_str!("programs/strings.xi")),
+];
+
+const PRELUDE: &str = include_str!("programs/prelude.xi");
+const ISOLATES: usize = 100;
+
+const WARM_UP: usize = 3;
+const SAMPLES: usize = 20;
+
+fn runtime() -> Rc<Runtime> {
+    let output = Rc::new(RefCell::new(String::new()));
+    Rc::new(Runtime::default().with_output(Some(output)))
+}
+
+fn run(source: &str) -> Duration {
+    let interpreter = Interpreter::new(&Env::global(), runtime());
+
+    let start = Instant::now();
+    if let Err(report) = interpreter.run(source) {
+        panic!("{:?}", report);
+    }
+    start.elapsed()
+}
+
+fn isolates(prelude: &Program) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ISOLATES {
+        let result = Interpreter::with_prelude(prelude, runtime())
+            .and_then(|interpreter| interpreter.run("println(sum_of_squares(10));"));
+        if let Err(report) = result {
+            panic!("{:?}", report);
+        }
+    }
+    start.elapsed()
+}
+
+fn bench(name: &str, filter: &Option<String>, sample: impl Fn() -> Duration) {
+    if filter
+        .as_ref()
+        .is_some_and(|filter| !name.contains(filter.as_str()))
+    {
+        return;
+    }
+    for _ in 0..WARM_UP {
+        sample();
+    }
+    let mut samples: Vec<_> = (0..SAMPLES).map(|_| sample()).collect();
+    samples.sort();
+    println!(
+        "{:<10} min {:>10.3?}  median {:>10.3?}  max {:>10.3?}",
+        name,
+        samples[0],
+        samples[SAMPLES / 2],
+        samples[SAMPLES - 1]
+    );
+}
+
+fn main() {
+    // `cargo bench` passes `--bench` along with the filter
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with('-'));
+
+    for (name, source) in PROGRAMS {
+        bench(name, &filter, || run(source));
+    }
+
+    let prelude = match Interpreter::default().compile(PRELUDE) {
+        Ok(prelude) => prelude,
+        Err(report) => panic!("{:?}", report),
+    };
+    bench("isolates", &filter, || isolates(&prelude));
+}
@@ -0,0 +1,64 @@
+// A native plugin for `import native`, see src/native.rs. `cargo test` builds
+// it, as does `cargo build --example plugin`, into
+// target/debug/examples/libplugin.so for tests/native.xi to import.
+
+use xi::native::{XiRegistry, XiValue, ABI_VERSION, XI_INT, XI_NIL, XI_STRING};
+
+#[no_mangle]
+pub static XI_ABI_VERSION: u32 = ABI_VERSION;
+
+/// # Safety
+///
+/// `registry` has to point to a valid registry, as it does when xi calls it.
+#[no_mangle]
+pub unsafe extern "C" fn xi_register(registry: *const XiRegistry) {
+    let registry = &*registry;
+    (registry.register_function)(registry.context, c"add".as_ptr(), 2, add);
+    (registry.register_function)(registry.context, c"fail".as_ptr(), 0, fail);
+}
+
+fn value(kind: u32) -> XiValue {
+    XiValue {
+        kind,
+        boolean: false,
+        int: 0,
+        float: 0.0,
+        string: std::ptr::null(),
+        length: 0,
+    }
+}
+
+// a failure, with `message` to report
+fn error(out: &mut XiValue, message: &'static str) -> i32 {
+    *out = XiValue {
+        string: message.as_ptr().cast(),
+        length: message.len(),
+        ..value(XI_STRING)
+    };
+    1
+}
+
+// the sum of two integers
+unsafe extern "C" fn add(args: *const XiValue, count: usize, out: *mut XiValue) -> i32 {
+    let args = std::slice::from_raw_parts(args, count);
+    let out = &mut *out;
+    if args.iter().any(|arg| arg.kind != XI_INT) {
+        return error(out, "add takes integers");
+    }
+    match args[0].int.checked_add(args[1].int) {
+        Some(sum) => {
+            *out = XiValue {
+                int: sum,
+                ..value(XI_INT)
+            };
+            0
+        }
+        None => error(out, "the sum does not fit in 64 bits"),
+    }
+}
+
+// always fails, without a message
+unsafe extern "C" fn fail(_: *const XiValue, _: usize, out: *mut XiValue) -> i32 {
+    *out = value(XI_NIL);
+    2
+}
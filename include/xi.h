@@ -0,0 +1,48 @@
+/* Native extension interface for xi, see src/native.rs. */
+
+#ifndef XI_H
+#define XI_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#define XI_ABI 1
+
+enum {
+    XI_NIL = 0,
+    XI_BOOL = 1,
+    XI_INT = 2,
+    XI_FLOAT = 3,
+    XI_STRING = 4,
+};
+
+/* Only the field selected by `kind` is meaningful. Strings are UTF-8 and not
+ * nul-terminated; a returned string only has to outlive the call. */
+struct xi_value {
+    uint32_t kind;
+    bool boolean;
+    int64_t int_;
+    double float_;
+    const char *string;
+    size_t length;
+};
+
+/* Returns 0 on success; on failure `out` may hold a string message. */
+typedef int32_t (*xi_function)(const struct xi_value *args, size_t count,
+                               struct xi_value *out);
+
+struct xi_registry {
+    uint32_t abi_version;
+    void *context;
+    void (*register_function)(void *context, const char *name, size_t arity,
+                              xi_function function);
+};
+
+/* Every plugin defines these:
+ *
+ *     const uint32_t XI_ABI_VERSION = XI_ABI;
+ *     void xi_register(const struct xi_registry *registry);
+ */
+
+#endif
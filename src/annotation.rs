@@ -0,0 +1,58 @@
+use crate::{
+    sync::Rc,
+    token::{Literal, Span},
+    value::Value,
+};
+
+// `@name` or `@name(arguments)` in front of a `fn` or a `let`, the arguments
+// literals. They change how the declaration is run or checked:
+//
+//   @pure               the function changes nothing and its result depends
+//                       only on its arguments, so loops may hoist calls to it
+//   @memo               the function caches its results, as `memo` would
+//   @test               `--test` calls the function after the script runs
+//   @deprecated("why")  uses of the name are warned about, with the reason
+//
+// Only `@deprecated` applies to a `let`.
+
+#[derive(Debug, Clone)]
+pub struct Annotation {
+    pub name: Rc<str>,
+    pub args: Vec<Value>,
+    pub span: Span,
+}
+
+// the annotation `name` among `annotations`
+pub fn find<'a>(annotations: &'a [Annotation], name: &str) -> Option<&'a Annotation> {
+    annotations
+        .iter()
+        .find(|annotation| annotation.name.as_ref() == name)
+}
+
+// the message `@deprecated` gives, if any
+pub fn deprecation(annotations: &[Annotation]) -> Option<Option<String>> {
+    let deprecated = find(annotations, "deprecated")?;
+    Some(deprecated.args.first().map(|reason| reason.to_string()))
+}
+
+// why `annotation` can't go on a declaration; `params` is the number of
+// parameters of the function, None for a `let`
+pub fn check(annotation: &Annotation, params: Option<usize>) -> Result<(), String> {
+    let name = annotation.name.as_ref();
+    match (name, params) {
+        ("pure" | "memo" | "test", None) => Err(format!("`@{}` only applies to functions", name)),
+        ("test", Some(params)) if params > 0 => Err("a test takes no arguments".to_string()),
+        ("pure" | "memo" | "test", _) if !annotation.args.is_empty() => {
+            Err(format!("`@{}` takes no arguments", name))
+        }
+        ("deprecated", _) => match annotation.args.as_slice() {
+            [] | [Value::Literal(Literal::String(_))] => Ok(()),
+            _ => Err("`@deprecated` takes at most a string, the reason".to_string()),
+        },
+        ("pure" | "memo" | "test", _) => Ok(()),
+        _ => Err(format!(
+            "`@{}` isn't an annotation, try @pure, @memo, @test or @deprecated",
+            name
+        )),
+    }
+}
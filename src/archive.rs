@@ -0,0 +1,165 @@
+use miniz_oxide::{deflate::compress_to_vec, inflate::decompress_to_vec};
+
+use crate::checksum::crc32;
+
+// Gzip members (RFC 1952) and the entries of zip files, both around
+// DEFLATE. Zip entries can be stored or deflated; ZIP64, encryption and
+// archives spanning several files aren't supported.
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const DEFLATE: u8 = 8;
+
+// the flags of a gzip header that say which optional fields follow it
+const FHCRC: u8 = 0x02;
+const FEXTRA: u8 = 0x04;
+const FNAME: u8 = 0x08;
+const FCOMMENT: u8 = 0x10;
+
+const LOCAL_HEADER: u32 = 0x04034b50;
+const CENTRAL_HEADER: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+
+// a gzip member with no name or time
+pub fn gzip_compress(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len() / 2 + 18);
+    output.extend(GZIP_MAGIC);
+    // deflate, no flags, no time, default compression, unknown system
+    output.extend([DEFLATE, 0, 0, 0, 0, 0, 0, 255]);
+    output.extend(compress_to_vec(data, 6));
+    output.extend(crc32(data).to_le_bytes());
+    output.extend((data.len() as u32).to_le_bytes());
+    output
+}
+
+// the bytes at `at..at + len`
+fn slice(data: &[u8], at: usize, len: usize) -> Result<&[u8], String> {
+    at.checked_add(len)
+        .and_then(|end| data.get(at..end))
+        .ok_or_else(|| "it is truncated".to_string())
+}
+
+fn u16_at(data: &[u8], at: usize) -> Result<u16, String> {
+    Ok(u16::from_le_bytes(slice(data, at, 2)?.try_into().unwrap()))
+}
+
+fn u32_at(data: &[u8], at: usize) -> Result<u32, String> {
+    Ok(u32::from_le_bytes(slice(data, at, 4)?.try_into().unwrap()))
+}
+
+fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    decompress_to_vec(data).map_err(|err| format!("its data is corrupt ({:?})", err.status))
+}
+
+// the contents of a single gzip member, checked against its trailer
+pub fn gzip_decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < 18 || data[..2] != GZIP_MAGIC {
+        return Err("it isn't gzip data".to_string());
+    }
+    if data[2] != DEFLATE {
+        return Err(format!("compression method {} isn't deflate", data[2]));
+    }
+    let flags = data[3];
+    let mut at = 10;
+    if flags & FEXTRA != 0 {
+        at += 2 + u16_at(data, at)? as usize;
+    }
+    // the name and the comment end with a zero byte
+    for flag in [FNAME, FCOMMENT] {
+        if flag & flags != 0 {
+            let rest = data.get(at..).unwrap_or_default();
+            let end = rest.iter().position(|&byte| byte == 0);
+            at += end.ok_or("it is truncated")? + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        at += 2;
+    }
+
+    let trailer = data.len() - 8;
+    let body = data.get(at..trailer).ok_or("it is truncated")?;
+    let output = inflate(body)?;
+    if crc32(&output) != u32_at(data, trailer)? || output.len() as u32 != u32_at(data, trailer + 4)?
+    {
+        return Err("its checksum doesn't match".to_string());
+    }
+    Ok(output)
+}
+
+pub struct ZipEntry {
+    pub name: String,
+    pub size: u64,
+    pub compressed_size: u64,
+    method: u16,
+    crc: u32,
+    offset: usize,
+}
+
+// the entries of a zip file as its central directory lists them
+pub fn zip_entries(data: &[u8]) -> Result<Vec<ZipEntry>, String> {
+    // the end record is the last 22 bytes, unless a comment of up to 64 KiB
+    // follows it
+    let start = data.len().saturating_sub(22 + u16::MAX as usize);
+    let end = (start..=data.len().saturating_sub(22))
+        .rev()
+        .find(|&at| u32_at(data, at) == Ok(END_OF_CENTRAL_DIRECTORY))
+        .ok_or("it isn't a zip file")?;
+    let count = u16_at(data, end + 10)?;
+    let mut at = u32_at(data, end + 16)? as usize;
+
+    let mut entries = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        if u32_at(data, at)? != CENTRAL_HEADER {
+            return Err("its central directory is corrupt".to_string());
+        }
+        let compressed_size = u32_at(data, at + 20)?;
+        let size = u32_at(data, at + 24)?;
+        let offset = u32_at(data, at + 42)?;
+        if [compressed_size, size, offset].contains(&u32::MAX) {
+            return Err("it is a ZIP64 file".to_string());
+        }
+        let (name_len, extra_len, comment_len) = (
+            u16_at(data, at + 28)? as usize,
+            u16_at(data, at + 30)? as usize,
+            u16_at(data, at + 32)? as usize,
+        );
+        entries.push(ZipEntry {
+            name: String::from_utf8_lossy(slice(data, at + 46, name_len)?).into_owned(),
+            size: size.into(),
+            compressed_size: compressed_size.into(),
+            method: u16_at(data, at + 10)?,
+            crc: u32_at(data, at + 16)?,
+            offset: offset as usize,
+        });
+        at += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+// the contents of `entry`, checked against its CRC
+pub fn zip_extract(data: &[u8], entry: &ZipEntry) -> Result<Vec<u8>, String> {
+    let at = entry.offset;
+    if u32_at(data, at)? != LOCAL_HEADER {
+        return Err(format!("the header of `{}` is corrupt", entry.name));
+    }
+    let name_len = u16_at(data, at + 26)? as usize;
+    let extra_len = u16_at(data, at + 28)? as usize;
+    let body = slice(
+        data,
+        at + 30 + name_len + extra_len,
+        entry.compressed_size as usize,
+    )?;
+    let output = match entry.method {
+        0 => body.to_vec(),
+        8 => inflate(body)?,
+        method => {
+            return Err(format!(
+                "`{}` uses compression method {}, not stored or deflate",
+                entry.name, method
+            ))
+        }
+    };
+    match crc32(&output) == entry.crc {
+        true => Ok(output),
+        false => Err(format!("the checksum of `{}` doesn't match", entry.name)),
+    }
+}
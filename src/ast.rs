@@ -0,0 +1,875 @@
+use std::collections::HashMap;
+
+use rug::Integer;
+
+use crate::{
+    annotation::{self, Annotation},
+    dict::Dict,
+    expr::{Arm, Expr, ExprKind, Pattern, PatternKind, Stmt, StmtKind},
+    list::List,
+    printer,
+    sync::{Rc, RefCell},
+    token::{Literal, Operator, Span, TokenKind},
+    value::{Value, ValueKey},
+};
+
+// Statements and expressions as values scripts can inspect: a dict for every
+// node, with its "kind" ("binary", "if", ...), "span" as [offset, length] in
+// the source, and a field for each of its parts named as in the tree. Missing
+// parts (no `else`, a bare `return`) are nil, operators are strings and
+// literals are their values.
+
+pub const BINARY: [TokenKind; 11] = [
+    TokenKind::Minus,
+    TokenKind::Plus,
+    TokenKind::Slash,
+    TokenKind::Star,
+    TokenKind::Pipe,
+    TokenKind::BangEqual,
+    TokenKind::EqualEqual,
+    TokenKind::Greater,
+    TokenKind::GreaterEqual,
+    TokenKind::Less,
+    TokenKind::LessEqual,
+];
+pub const LOGICAL: [TokenKind; 2] = [TokenKind::And, TokenKind::Or];
+pub const UNARY: [TokenKind; 2] = [TokenKind::Bang, TokenKind::Minus];
+
+fn node(kind: &str, span: Span, fields: Vec<(&str, Value)>) -> Value {
+    let span = vec![
+        Integer::from(span.offset).into(),
+        Integer::from(span.length).into(),
+    ];
+    let mut entries = HashMap::new();
+    let mut insert = |key: &str, value: Value| {
+        entries.insert(ValueKey::try_from(Value::from(key)).unwrap(), value);
+    };
+    insert("kind", kind.into());
+    insert("span", list(span));
+    for (key, value) in fields {
+        insert(key, value);
+    }
+    Value::Dict(Rc::new(RefCell::new(Dict(entries))))
+}
+
+fn list(items: Vec<Value>) -> Value {
+    Value::List(Rc::new(RefCell::new(List(items))))
+}
+
+fn name(name: &str) -> Value {
+    name.into()
+}
+
+// a dict with a string "kind"
+fn is_node(value: &Value) -> bool {
+    match value {
+        Value::Dict(dict) => {
+            let key = ValueKey::try_from(Value::from("kind")).unwrap();
+            matches!(
+                dict.borrow().0.get(&key),
+                Some(Value::Literal(Literal::String(_)))
+            )
+        }
+        _ => false,
+    }
+}
+
+// "annotation" nodes, with their "name" and their "args" as values
+fn annotations(annotations: &[Annotation]) -> Value {
+    let annotations = annotations
+        .iter()
+        .map(|annotation| {
+            let fields = vec![
+                ("name", self::name(&annotation.name)),
+                ("args", list(annotation.args.clone())),
+            ];
+            node("annotation", annotation.span, fields)
+        })
+        .collect();
+    list(annotations)
+}
+
+// patterns are nodes too, "list_pattern" with its "rest" or nil and
+// "dict_pattern" with its "entries" as [key, pattern] pairs
+fn pattern(pattern: &Pattern) -> Value {
+    let (kind, fields) = match &pattern.kind {
+        PatternKind::Wildcard => ("wildcard_pattern", vec![]),
+        PatternKind::Literal { value } => ("literal_pattern", vec![("value", value.clone())]),
+        PatternKind::Binding { name } => ("binding_pattern", vec![("name", self::name(name))]),
+        PatternKind::List { items, rest } => (
+            "list_pattern",
+            vec![
+                ("items", list(items.iter().map(self::pattern).collect())),
+                ("rest", rest.as_deref().map_or(Value::Nil, self::pattern)),
+            ],
+        ),
+        PatternKind::Dict { entries } => {
+            let entries = entries
+                .iter()
+                .map(|(key, value)| list(vec![key.clone(), self::pattern(value)]))
+                .collect();
+            ("dict_pattern", vec![("entries", list(entries))])
+        }
+    };
+    node(kind, pattern.span, fields)
+}
+
+struct Tree<'a> {
+    // evaluates the `unquote`s of the quote being built; None for `parse`,
+    // and inside quotes nested in it, where they are kept as "unquote" nodes
+    splice: Option<&'a mut dyn FnMut(&Expr) -> Value>,
+}
+
+impl Tree<'_> {
+    fn optional<T: std::ops::Deref>(
+        &mut self,
+        value: &Option<T>,
+        f: impl Fn(&mut Self, &T::Target) -> Value,
+    ) -> Value {
+        match value.as_deref() {
+            Some(value) => f(self, value),
+            None => Value::Nil,
+        }
+    }
+
+    fn exprs(&mut self, exprs: &[Expr]) -> Value {
+        list(exprs.iter().map(|expr| self.expr(expr)).collect())
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Value {
+        let (kind, fields) = match &expr.kind {
+            ExprKind::Assign { name, value } => (
+                "assign",
+                vec![("name", self::name(name)), ("value", self.expr(value))],
+            ),
+            ExprKind::Binary { left, op, right } => (
+                "binary",
+                vec![
+                    ("left", self.expr(left)),
+                    ("op", printer::operator(op.kind).into()),
+                    ("right", self.expr(right)),
+                ],
+            ),
+            ExprKind::Call { callee, args } => (
+                "call",
+                vec![("callee", self.expr(callee)), ("args", self.exprs(args))],
+            ),
+            ExprKind::GetIndex { obj, index } => (
+                "get_index",
+                vec![("obj", self.expr(obj)), ("index", self.expr(index))],
+            ),
+            ExprKind::Spread { value } => ("spread", vec![("value", self.expr(value))]),
+            ExprKind::Slice { obj, start, end } => (
+                "slice",
+                vec![
+                    ("obj", self.expr(obj)),
+                    ("start", self.optional(start, Self::expr)),
+                    ("end", self.optional(end, Self::expr)),
+                ],
+            ),
+            ExprKind::SetIndex { obj, index, value } => (
+                "set_index",
+                vec![
+                    ("obj", self.expr(obj)),
+                    ("index", self.expr(index)),
+                    ("value", self.expr(value)),
+                ],
+            ),
+            ExprKind::List { items } => ("list", vec![("items", self.exprs(items))]),
+            ExprKind::SetLiteral { items } => ("set_literal", vec![("items", self.exprs(items))]),
+            ExprKind::Dict { items } => {
+                let items = items
+                    .iter()
+                    .map(|(key, value)| list(vec![self.expr(key), self.expr(value)]))
+                    .collect();
+                ("dict", vec![("items", list(items))])
+            }
+            ExprKind::Get { obj, name } => (
+                "get",
+                vec![("obj", self.expr(obj)), ("name", self::name(name))],
+            ),
+            ExprKind::Grouping { value } => ("grouping", vec![("value", self.expr(value))]),
+            ExprKind::Literal { value } => ("literal", vec![("value", value.clone())]),
+            ExprKind::Logical { left, op, right } => (
+                "logical",
+                vec![
+                    ("left", self.expr(left)),
+                    ("op", printer::operator(op.kind).into()),
+                    ("right", self.expr(right)),
+                ],
+            ),
+            ExprKind::Conditional {
+                cond,
+                then_branch,
+                else_branch,
+            } => (
+                "conditional",
+                vec![
+                    ("cond", self.expr(cond)),
+                    ("then_branch", self.expr(then_branch)),
+                    ("else_branch", self.expr(else_branch)),
+                ],
+            ),
+            ExprKind::Set { obj, name, value } => (
+                "set",
+                vec![
+                    ("obj", self.expr(obj)),
+                    ("name", self::name(name)),
+                    ("value", self.expr(value)),
+                ],
+            ),
+            ExprKind::Unary { op, right } => (
+                "unary",
+                vec![
+                    ("op", printer::operator(op.kind).into()),
+                    ("right", self.expr(right)),
+                ],
+            ),
+            ExprKind::Variable { name } => ("variable", vec![("name", self::name(name))]),
+            ExprKind::This => ("this", vec![]),
+            ExprKind::Super { method } => ("super", vec![("method", self::name(method))]),
+            ExprKind::Quote { body } => {
+                let splice = self.splice.take();
+                let body = self.stmts(body);
+                self.splice = splice;
+                ("quote", vec![("body", body)])
+            }
+            ExprKind::Unquote { value } => match &mut self.splice {
+                // nodes are spliced as they are, other values as literals
+                Some(splice) => {
+                    let value = splice(value);
+                    if is_node(&value) {
+                        return value;
+                    }
+                    ("literal", vec![("value", value)])
+                }
+                None => ("unquote", vec![("value", self.expr(value))]),
+            },
+            ExprKind::Match { scrutinee, arms } => {
+                let arms = arms
+                    .iter()
+                    .map(|arm| {
+                        let fields = vec![
+                            ("pattern", pattern(&arm.pattern)),
+                            ("body", self.expr(&arm.body)),
+                        ];
+                        node("arm", arm.pattern.span, fields)
+                    })
+                    .collect();
+                (
+                    "match",
+                    vec![("scrutinee", self.expr(scrutinee)), ("arms", list(arms))],
+                )
+            }
+        };
+        node(kind, expr.span, fields)
+    }
+
+    fn stmts(&mut self, stmts: &[Stmt]) -> Value {
+        list(stmts.iter().map(|stmt| self.stmt(stmt)).collect())
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) -> Value {
+        let binding = |binding: &Option<Rc<str>>| binding.as_deref().map_or(Value::Nil, name);
+        let (kind, fields) = match &stmt.kind {
+            StmtKind::Block { statements } => {
+                ("block", vec![("statements", self.stmts(statements))])
+            }
+            StmtKind::Expression { expr } => ("expression", vec![("expr", self.expr(expr))]),
+            StmtKind::Throw { value } => ("throw", vec![("value", self.expr(value))]),
+            StmtKind::Try {
+                body,
+                name,
+                handler,
+            } => (
+                "try",
+                vec![
+                    ("body", self.stmt(body)),
+                    ("name", self::name(name)),
+                    ("handler", self.stmt(handler)),
+                ],
+            ),
+            StmtKind::Class {
+                name,
+                superclass,
+                methods,
+                getters,
+                setters,
+            } => (
+                "class",
+                vec![
+                    ("name", self::name(name)),
+                    (
+                        "superclass",
+                        superclass.as_deref().map_or(Value::Nil, self::name),
+                    ),
+                    ("methods", self.stmts(methods)),
+                    ("getters", self.stmts(getters)),
+                    ("setters", self.stmts(setters)),
+                ],
+            ),
+            StmtKind::Function {
+                name,
+                params,
+                body,
+                annotations,
+            } => (
+                "function",
+                vec![
+                    ("name", self::name(name)),
+                    (
+                        "params",
+                        list(params.iter().map(|p| self::name(p)).collect()),
+                    ),
+                    ("body", self.stmts(body)),
+                    ("annotations", self::annotations(annotations)),
+                ],
+            ),
+            StmtKind::ForIn {
+                name,
+                value,
+                iterable,
+                body,
+            } => (
+                "for_in",
+                vec![
+                    ("name", self::name(name)),
+                    ("value", binding(value)),
+                    ("iterable", self.expr(iterable)),
+                    ("body", self.stmt(body)),
+                ],
+            ),
+            StmtKind::If {
+                binding: name,
+                cond,
+                then_branch,
+                else_branch,
+            } => (
+                "if",
+                vec![
+                    ("binding", binding(name)),
+                    ("cond", self.expr(cond)),
+                    ("then_branch", self.stmt(then_branch)),
+                    ("else_branch", self.optional(else_branch, Self::stmt)),
+                ],
+            ),
+            StmtKind::Return { expr } => {
+                ("return", vec![("expr", self.optional(expr, Self::expr))])
+            }
+            StmtKind::Let {
+                name,
+                initializer,
+                annotations,
+            } => (
+                "let",
+                vec![
+                    ("name", self::name(name)),
+                    ("initializer", self.optional(initializer, Self::expr)),
+                    ("annotations", self::annotations(annotations)),
+                ],
+            ),
+            StmtKind::NativeImport { path } => ("native_import", vec![("path", self::name(path))]),
+            StmtKind::While {
+                binding: name,
+                cond,
+                body,
+            } => (
+                "while",
+                vec![
+                    ("binding", binding(name)),
+                    ("cond", self.expr(cond)),
+                    ("body", self.stmt(body)),
+                ],
+            ),
+        };
+        node(kind, stmt.span, fields)
+    }
+}
+
+pub fn stmts(stmts: &[Stmt]) -> Value {
+    Tree { splice: None }.stmts(stmts)
+}
+
+// the tree of a `quote`'s body, with the value `splice` gives for each of its
+// `unquote`s in their place
+pub fn quote(body: &[Stmt], splice: &mut dyn FnMut(&Expr) -> Value) -> Value {
+    Tree {
+        splice: Some(splice),
+    }
+    .stmts(body)
+}
+
+// The other way, for `compile`: trees back into statements, numbered from 0.
+// A node without a valid "span" gets an empty one.
+#[derive(Default)]
+pub struct Builder {
+    next_id: usize,
+    quoting: usize,
+}
+
+fn field(node: &Dict, name: &str) -> Value {
+    let key = ValueKey::try_from(Value::from(name)).unwrap();
+    node.0.get(&key).cloned().unwrap_or(Value::Nil)
+}
+
+fn string(node: &Dict, name: &str) -> Result<String, String> {
+    match field(node, name) {
+        Value::Literal(Literal::String(s)) => Ok(s.to_string()),
+        other => Err(format!(
+            "\"{}\" is a {}, not a string",
+            name,
+            other.type_name()
+        )),
+    }
+}
+
+fn items(value: &Value, name: &str) -> Result<Vec<Value>, String> {
+    match value {
+        Value::List(list) => Ok(list.borrow().0.clone()),
+        other => Err(format!(
+            "\"{}\" is a {}, not a list",
+            name,
+            other.type_name()
+        )),
+    }
+}
+
+fn span(node: &Dict) -> Span {
+    let span = match field(node, "span") {
+        Value::List(list) => list.borrow().0.clone(),
+        _ => return Span::default(),
+    };
+    match span.as_slice() {
+        [Value::Literal(Literal::Integer(offset)), Value::Literal(Literal::Integer(length))] => {
+            match (offset.to_usize(), length.to_usize()) {
+                (Some(offset), Some(length)) => Span::new(offset, length),
+                _ => Span::default(),
+            }
+        }
+        _ => Span::default(),
+    }
+}
+
+fn operator(node: &Dict, allowed: &[TokenKind]) -> Result<Operator, String> {
+    let op = string(node, "op")?;
+    match allowed.iter().find(|&&kind| printer::operator(kind) == op) {
+        Some(&kind) => Ok(Operator::new(kind, span(node))),
+        None => Err(format!("`{}` is not an operator here", op)),
+    }
+}
+
+fn dict(value: &Value) -> Result<(Rc<RefCell<Dict>>, String), String> {
+    match value {
+        Value::Dict(dict) if is_node(value) => {
+            let kind = string(&dict.borrow(), "kind")?;
+            Ok((dict.clone(), kind))
+        }
+        other => Err(format!("expected a node, found {}", printer::value(other))),
+    }
+}
+
+impl Builder {
+    pub fn id(&mut self) -> usize {
+        self.next_id += 1;
+        self.next_id - 1
+    }
+
+    fn boxed(&mut self, node: &Dict, name: &str) -> Result<Box<Expr>, String> {
+        Ok(Box::new(self.expr(&field(node, name))?))
+    }
+
+    fn optional(&mut self, node: &Dict, name: &str) -> Result<Option<Box<Expr>>, String> {
+        match field(node, name) {
+            Value::Nil => Ok(None),
+            value => Ok(Some(Box::new(self.expr(&value)?))),
+        }
+    }
+
+    // the items of a list or set or the arguments of a call, the only places
+    // a "spread" can be
+    fn items(&mut self, node: &Dict, name: &str) -> Result<Vec<Expr>, String> {
+        items(&field(node, name), name)?
+            .iter()
+            .map(|item| {
+                let (node, kind) = dict(item)?;
+                if kind != "spread" {
+                    return self.expr(item);
+                }
+                let node = &node.borrow();
+                Ok(Expr {
+                    kind: ExprKind::Spread {
+                        value: self.boxed(node, "value")?,
+                    },
+                    span: span(node),
+                    id: self.id(),
+                })
+            })
+            .collect()
+    }
+
+    pub fn expr(&mut self, value: &Value) -> Result<Expr, String> {
+        let (node, kind) = dict(value)?;
+        let node = &node.borrow();
+        let kind = match kind.as_str() {
+            "assign" => ExprKind::Assign {
+                name: string(node, "name")?.into(),
+                value: self.boxed(node, "value")?,
+            },
+            "binary" => ExprKind::Binary {
+                left: self.boxed(node, "left")?,
+                op: operator(node, &BINARY)?,
+                right: self.boxed(node, "right")?,
+            },
+            "call" => ExprKind::Call {
+                callee: self.boxed(node, "callee")?,
+                args: self.items(node, "args")?,
+            },
+            "get_index" => ExprKind::GetIndex {
+                obj: self.boxed(node, "obj")?,
+                index: self.boxed(node, "index")?,
+            },
+            "spread" => {
+                return Err("a spread is only an item of a list or a set, or an argument".into())
+            }
+            "slice" => ExprKind::Slice {
+                obj: self.boxed(node, "obj")?,
+                start: self.optional(node, "start")?,
+                end: self.optional(node, "end")?,
+            },
+            "set_index" => ExprKind::SetIndex {
+                obj: self.boxed(node, "obj")?,
+                index: self.boxed(node, "index")?,
+                value: self.boxed(node, "value")?,
+            },
+            "list" => ExprKind::List {
+                items: self.items(node, "items")?,
+            },
+            "set_literal" => ExprKind::SetLiteral {
+                items: self.items(node, "items")?,
+            },
+            "dict" => {
+                let items = items(&field(node, "items"), "items")?
+                    .iter()
+                    .map(|item| match items(item, "items")?.as_slice() {
+                        [key, value] => Ok((self.expr(key)?, self.expr(value)?)),
+                        _ => Err("dict items are [key, value] pairs".to_string()),
+                    })
+                    .collect::<Result<_, String>>()?;
+                ExprKind::Dict { items }
+            }
+            "get" => ExprKind::Get {
+                obj: self.boxed(node, "obj")?,
+                name: string(node, "name")?.into(),
+            },
+            "grouping" => ExprKind::Grouping {
+                value: self.boxed(node, "value")?,
+            },
+            "literal" => ExprKind::Literal {
+                value: field(node, "value"),
+            },
+            "logical" => ExprKind::Logical {
+                left: self.boxed(node, "left")?,
+                op: operator(node, &LOGICAL)?,
+                right: self.boxed(node, "right")?,
+            },
+            "conditional" => ExprKind::Conditional {
+                cond: self.boxed(node, "cond")?,
+                then_branch: self.boxed(node, "then_branch")?,
+                else_branch: self.boxed(node, "else_branch")?,
+            },
+            "set" => ExprKind::Set {
+                obj: self.boxed(node, "obj")?,
+                name: string(node, "name")?.into(),
+                value: self.boxed(node, "value")?,
+            },
+            "unary" => ExprKind::Unary {
+                op: operator(node, &UNARY)?,
+                right: self.boxed(node, "right")?,
+            },
+            "variable" => ExprKind::Variable {
+                name: string(node, "name")?.into(),
+            },
+            "this" => ExprKind::This,
+            "super" => ExprKind::Super {
+                method: string(node, "method")?.into(),
+            },
+            "quote" => {
+                self.quoting += 1;
+                let body = self.stmts(&field(node, "body"));
+                self.quoting -= 1;
+                ExprKind::Quote { body: body? }
+            }
+            "unquote" if self.quoting == 0 => return Err("`unquote` outside of `quote`".into()),
+            "unquote" => {
+                self.quoting -= 1;
+                let value = self.boxed(node, "value");
+                self.quoting += 1;
+                ExprKind::Unquote { value: value? }
+            }
+            "match" => {
+                let arms = items(&field(node, "arms"), "arms")?
+                    .iter()
+                    .map(|arm| {
+                        let (arm, kind) = dict(arm)?;
+                        let arm = &arm.borrow();
+                        match kind.as_str() {
+                            "arm" => Ok(Arm {
+                                pattern: Self::pattern(&field(arm, "pattern"))?,
+                                body: self.boxed(arm, "body")?,
+                            }),
+                            other => Err(format!("expected an arm, found \"{}\"", other)),
+                        }
+                    })
+                    .collect::<Result<_, String>>()?;
+                ExprKind::Match {
+                    scrutinee: self.boxed(node, "scrutinee")?,
+                    arms,
+                }
+            }
+            other => return Err(format!("unknown expression kind \"{}\"", other)),
+        };
+        Ok(Expr {
+            kind,
+            span: span(node),
+            id: self.id(),
+        })
+    }
+
+    pub fn stmts(&mut self, value: &Value) -> Result<Vec<Stmt>, String> {
+        items(value, "body")?
+            .iter()
+            .map(|item| self.stmt(item))
+            .collect()
+    }
+
+    fn body(&mut self, node: &Dict, name: &str) -> Result<Box<Stmt>, String> {
+        Ok(Box::new(self.stmt(&field(node, name))?))
+    }
+
+    fn block(&mut self, node: &Dict, name: &str) -> Result<Box<Stmt>, String> {
+        let block = self.body(node, name)?;
+        match block.kind {
+            StmtKind::Block { .. } => Ok(block),
+            _ => Err(format!("{} {} is not a block", name, field(node, name))),
+        }
+    }
+
+    fn pattern(value: &Value) -> Result<Pattern, String> {
+        let (node, kind) = dict(value)?;
+        let node = &node.borrow();
+        let kind = match kind.as_str() {
+            "wildcard_pattern" => PatternKind::Wildcard,
+            "literal_pattern" => PatternKind::Literal {
+                value: field(node, "value"),
+            },
+            "binding_pattern" => PatternKind::Binding {
+                name: string(node, "name")?.into(),
+            },
+            "list_pattern" => {
+                let rest = match field(node, "rest") {
+                    Value::Nil => None,
+                    rest => Some(Box::new(Self::pattern(&rest)?)),
+                };
+                let takes_rest = |rest: &Pattern| {
+                    matches!(
+                        rest.kind,
+                        PatternKind::Binding { .. } | PatternKind::Wildcard
+                    )
+                };
+                if !rest.as_deref().is_none_or(takes_rest) {
+                    return Err("only a name or `_` takes the rest of a list".to_string());
+                }
+                PatternKind::List {
+                    items: items(&field(node, "items"), "items")?
+                        .iter()
+                        .map(Self::pattern)
+                        .collect::<Result<_, String>>()?,
+                    rest,
+                }
+            }
+            "dict_pattern" => {
+                let entries = items(&field(node, "entries"), "entries")?
+                    .iter()
+                    .map(|entry| match items(entry, "entries")?.as_slice() {
+                        [key, value] if ValueKey::try_from(key.clone()).is_ok() => {
+                            Ok((key.clone(), Self::pattern(value)?))
+                        }
+                        [key, _] => Err(format!("{} can't be a key", printer::value(key))),
+                        _ => Err("dict pattern entries are [key, pattern] pairs".to_string()),
+                    })
+                    .collect::<Result<_, String>>()?;
+                PatternKind::Dict { entries }
+            }
+            other => return Err(format!("unknown pattern kind \"{}\"", other)),
+        };
+        Ok(Pattern {
+            kind,
+            span: span(node),
+        })
+    }
+
+    // the functions of a class, without annotations; accessors take
+    // `params` parameters, and a class without any has nil for them
+    fn methods(
+        &mut self,
+        node: &Dict,
+        name: &str,
+        params: Option<usize>,
+    ) -> Result<Vec<Stmt>, String> {
+        let methods = match field(node, name) {
+            Value::Nil if params.is_some() => return Ok(Vec::new()),
+            methods => items(&methods, name)?,
+        };
+        methods
+            .iter()
+            .map(|method| match self.stmt(method)? {
+                Stmt {
+                    kind: StmtKind::Function { annotations, .. },
+                    ..
+                } if !annotations.is_empty() => Err("methods take no annotations".to_string()),
+                Stmt {
+                    kind: StmtKind::Function { params: found, .. },
+                    ..
+                } if params.is_some_and(|params| found.len() != params) => Err(format!(
+                    "{} take {} parameters",
+                    name,
+                    params.unwrap_or_default()
+                )),
+                method @ Stmt {
+                    kind: StmtKind::Function { .. },
+                    ..
+                } => Ok(method),
+                _ => Err(format!("method {} is not a function", method)),
+            })
+            .collect()
+    }
+
+    // none when the node has no "annotations"; `params` as in
+    // `annotation::check`
+    fn annotations(node: &Dict, params: Option<usize>) -> Result<Vec<Annotation>, String> {
+        let annotations = match field(node, "annotations") {
+            Value::Nil => return Ok(Vec::new()),
+            annotations => items(&annotations, "annotations")?,
+        };
+        annotations
+            .iter()
+            .map(|value| {
+                let (node, kind) = dict(value)?;
+                let node = &node.borrow();
+                if kind != "annotation" {
+                    return Err(format!("expected an annotation, found \"{}\"", kind));
+                }
+                let annotation = Annotation {
+                    name: string(node, "name")?.into(),
+                    args: items(&field(node, "args"), "args")?,
+                    span: span(node),
+                };
+                annotation::check(&annotation, params)?;
+                Ok(annotation)
+            })
+            .collect()
+    }
+
+    fn binding(node: &Dict) -> Result<Option<Rc<str>>, String> {
+        match field(node, "binding") {
+            Value::Nil => Ok(None),
+            _ => string(node, "binding").map(|name| Some(name.into())),
+        }
+    }
+
+    pub fn stmt(&mut self, value: &Value) -> Result<Stmt, String> {
+        let (node, kind) = dict(value)?;
+        let node = &node.borrow();
+        let kind = match kind.as_str() {
+            "block" => StmtKind::Block {
+                statements: self.stmts(&field(node, "statements"))?,
+            },
+            "expression" => StmtKind::Expression {
+                expr: self.boxed(node, "expr")?,
+            },
+            "throw" => StmtKind::Throw {
+                value: self.boxed(node, "value")?,
+            },
+            "try" => StmtKind::Try {
+                body: self.block(node, "body")?,
+                name: string(node, "name")?.into(),
+                handler: self.block(node, "handler")?,
+            },
+            "function" => {
+                let params: Vec<_> = items(&field(node, "params"), "params")?
+                    .into_iter()
+                    .map(|param| match param {
+                        Value::Literal(Literal::String(param)) => Ok(param),
+                        other => Err(format!("parameter {} is not a string", other)),
+                    })
+                    .collect::<Result<_, String>>()?;
+                StmtKind::Function {
+                    annotations: Self::annotations(node, Some(params.len()))?,
+                    name: string(node, "name")?.into(),
+                    params: Rc::new(params),
+                    body: Rc::new(self.stmts(&field(node, "body"))?),
+                }
+            }
+            "class" => StmtKind::Class {
+                name: string(node, "name")?.into(),
+                superclass: match field(node, "superclass") {
+                    Value::Nil => None,
+                    _ => Some(string(node, "superclass")?.into()),
+                },
+                methods: self.methods(node, "methods", None)?,
+                getters: self.methods(node, "getters", Some(0))?,
+                setters: self.methods(node, "setters", Some(1))?,
+            },
+            "for_in" => StmtKind::ForIn {
+                name: string(node, "name")?.into(),
+                value: match field(node, "value") {
+                    Value::Nil => None,
+                    _ => Some(string(node, "value")?.into()),
+                },
+                iterable: self.boxed(node, "iterable")?,
+                body: self.body(node, "body")?,
+            },
+            "if" => StmtKind::If {
+                binding: Self::binding(node)?,
+                cond: self.boxed(node, "cond")?,
+                then_branch: self.body(node, "then_branch")?,
+                else_branch: match field(node, "else_branch") {
+                    Value::Nil => None,
+                    value => Some(Box::new(self.stmt(&value)?)),
+                },
+            },
+            "return" => StmtKind::Return {
+                expr: self.optional(node, "expr")?,
+            },
+            "let" => StmtKind::Let {
+                name: string(node, "name")?.into(),
+                initializer: self.optional(node, "initializer")?,
+                annotations: Self::annotations(node, None)?,
+            },
+            "native_import" => StmtKind::NativeImport {
+                path: string(node, "path")?,
+            },
+            "while" => StmtKind::While {
+                binding: Self::binding(node)?,
+                cond: self.boxed(node, "cond")?,
+                body: self.body(node, "body")?,
+            },
+            // an expression where a statement goes is an expression statement
+            _ => {
+                let expr = self.expr(value)?;
+                return Ok(Stmt {
+                    span: expr.span,
+                    kind: StmtKind::Expression {
+                        expr: Box::new(expr),
+                    },
+                    id: self.id(),
+                });
+            }
+        };
+        Ok(Stmt {
+            kind,
+            span: span(node),
+            id: self.id(),
+        })
+    }
+}
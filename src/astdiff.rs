@@ -0,0 +1,99 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    expr::{Stmt, StmtKind},
+    printer,
+};
+
+// A structural difference between two versions of a script. Top-level
+// functions are matched by name, wherever they are in the file; the other
+// top-level statements are compared in order.
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    AddedFunction(String),
+    RemovedFunction(String),
+    ChangedFunction(String),
+    AddedStatement(String),
+    RemovedStatement(String),
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::AddedFunction(name) => write!(f, "+ fn {}", name),
+            Change::RemovedFunction(name) => write!(f, "- fn {}", name),
+            Change::ChangedFunction(name) => write!(f, "~ fn {}", name),
+            Change::AddedStatement(statement) => write!(f, "+ {}", statement),
+            Change::RemovedStatement(statement) => write!(f, "- {}", statement),
+        }
+    }
+}
+
+// (functions by name, in order, and the other statements), all printed
+fn split(statements: &[Stmt]) -> (Vec<(&str, String)>, Vec<String>) {
+    let mut functions = Vec::new();
+    let mut others = Vec::new();
+    for statement in statements {
+        match &statement.kind {
+            StmtKind::Function { name, .. } => {
+                functions.push((name.as_ref(), printer::stmt(statement)))
+            }
+            _ => others.push(printer::stmt(statement)),
+        }
+    }
+    (functions, others)
+}
+
+pub fn diff(old: &[Stmt], new: &[Stmt]) -> Vec<Change> {
+    let (old_functions, old_statements) = split(old);
+    let (new_functions, new_statements) = split(new);
+    let mut changes = Vec::new();
+
+    let old_by_name: HashMap<_, _> = old_functions.iter().cloned().collect();
+    let new_by_name: HashMap<_, _> = new_functions.iter().cloned().collect();
+    for (name, function) in &old_functions {
+        match new_by_name.get(name) {
+            None => changes.push(Change::RemovedFunction(name.to_string())),
+            Some(new) if new != function => changes.push(Change::ChangedFunction(name.to_string())),
+            Some(_) => {}
+        }
+    }
+    for (name, _) in &new_functions {
+        if !old_by_name.contains_key(name) {
+            changes.push(Change::AddedFunction(name.to_string()));
+        }
+    }
+
+    changes.extend(sequence(&old_statements, &new_statements));
+    changes
+}
+
+// the statements removed and added between two sequences, through their
+// longest common subsequence
+fn sequence(old: &[String], new: &[String]) -> Vec<Change> {
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = match old[i] == new[j] {
+                true => common[i + 1][j + 1] + 1,
+                false => common[i + 1][j].max(common[i][j + 1]),
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut changes = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            changes.push(Change::RemovedStatement(old[i].clone()));
+            i += 1;
+        } else {
+            changes.push(Change::AddedStatement(new[j].clone()));
+            j += 1;
+        }
+    }
+    changes
+}
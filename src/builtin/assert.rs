@@ -0,0 +1,98 @@
+use rug::Float;
+
+use super::{builtin, diff, extract};
+use crate::{
+    function::{Arity, Function},
+    interpreter::{error_value, RuntimeError},
+    printer,
+    report::AssertionFailed,
+    sync::Rc,
+    value::Value,
+};
+
+fn failed(message: impl Into<String>, help: Option<String>) -> RuntimeError {
+    RuntimeError::Report(
+        AssertionFailed {
+            message: message.into(),
+            help,
+        }
+        .into(),
+    )
+}
+
+// both values, and where they differ when they are lists or dicts
+fn sides(left: &Value, right: &Value, lines: Vec<(char, String)>) -> String {
+    let mut help = format!(
+        "left:  {}\nright: {}",
+        printer::value(left),
+        printer::value(right)
+    );
+    if matches!(
+        (left, right),
+        (Value::List(_), Value::List(_)) | (Value::Dict(_), Value::Dict(_))
+    ) {
+        help.push('\n');
+        for (sign, line) in lines {
+            help.push_str(&format!("\n{} {}", sign, line));
+        }
+    }
+    help
+}
+
+// assert(condition) or assert(condition, message)
+builtin!(AssertBuiltin, "assert", Arity::Range(1, 2), _ctx, args, {
+    let mut args = args.into_iter();
+    if args.next().unwrap().is_truthy() {
+        return Ok(Value::Nil);
+    }
+    match args.next() {
+        Some(message) => Err(failed(extract::<String>("assert", 1, message)?, None)),
+        None => Err(failed("the condition is false", None)),
+    }
+});
+
+// lists and dicts are equal when their items are, unlike with `==`
+builtin!(AssertEqBuiltin, "assert_eq", (left: Value, right: Value), {
+    let lines = diff::lines("assert_eq", &left, &right)?;
+    if lines.is_empty() {
+        return Ok(Value::Nil);
+    }
+    Err(failed("left == right", Some(sides(&left, &right, lines))))
+});
+
+builtin!(AssertNeBuiltin, "assert_ne", (left: Value, right: Value), {
+    if !diff::lines("assert_ne", &left, &right)?.is_empty() {
+        return Ok(Value::Nil);
+    }
+    Err(failed("left != right", Some(format!("both: {}", printer::value(&left)))))
+});
+
+// the difference is taken at the precision of the more precise operand, so
+// it is exact for floats of any precision
+builtin!(AssertCloseBuiltin, "assert_close", (left: Float, right: Float, epsilon: Float), {
+    let precision = left.prec().max(right.prec());
+    let difference = Float::with_val(precision, &left - &right).abs();
+    if difference <= epsilon {
+        return Ok(Value::Nil);
+    }
+    Err(failed(
+        "|left - right| <= epsilon",
+        Some(format!(
+            "left:       {}\nright:      {}\ndifference: {}\nepsilon:    {}",
+            left, right, difference, epsilon
+        )),
+    ))
+});
+
+// calls `function()`, which must fail, and returns its error as
+// {"code", "message"}, or the value it threw
+builtin!(AssertRaisesBuiltin, "assert_raises", ctx, (function: Rc<dyn Function>), {
+    match function.call(ctx, vec![]) {
+        Err(RuntimeError::Report(report)) => Ok(error_value(&report)),
+        Err(RuntimeError::Thrown(value, _)) => Ok(value),
+        Ok(value) | Err(RuntimeError::Return(value)) => Err(failed(
+            "the function raises an error",
+            Some(format!("it returned {}", printer::value(&value))),
+        )),
+    }
+});
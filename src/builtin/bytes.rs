@@ -0,0 +1,149 @@
+use rug::{integer::Order, Integer};
+
+use super::builtin;
+use crate::{
+    interpreter::RuntimeError, iterator::Iter, report::InvalidArgument, sync::Rc, token::Literal,
+    value::Value,
+};
+
+fn invalid(name: &str, message: impl Into<String>) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message: message.into(),
+        }
+        .into(),
+    )
+}
+
+fn order(name: &str, order: &str) -> Result<Order, RuntimeError> {
+    match order {
+        "big" => Ok(Order::Msf),
+        "little" => Ok(Order::Lsf),
+        other => Err(invalid(
+            name,
+            format!(
+                "byte order must be \"big\" or \"little\", found \"{}\"",
+                other
+            ),
+        )),
+    }
+}
+
+// bytes("text") encodes as UTF-8, bytes(iterable) takes integers from 0 to 255
+builtin!(BytesBuiltin, "bytes", ctx, (value: Value), {
+    if let Value::Literal(Literal::String(string)) = value {
+        return Ok(string.as_bytes().to_vec().into());
+    }
+
+    let iterator: Iter = super::extract("bytes", 0, value)?;
+    let mut bytes = Vec::new();
+    loop {
+        let item = iterator.borrow_mut().next(ctx)?;
+        match item {
+            None => return Ok(bytes.into()),
+            Some(Value::Literal(Literal::Integer(i))) if i.to_u8().is_some() => {
+                bytes.push(i.to_u8().unwrap())
+            }
+            Some(item) => return Err(invalid("bytes", format!("{} is not a byte", item))),
+        }
+    }
+});
+
+builtin!(DecodeBuiltin, "decode", (bytes: Rc<[u8]>), {
+    match std::str::from_utf8(&bytes) {
+        Ok(string) => Ok(string.into()),
+        Err(err) => Err(invalid("decode", err.to_string())),
+    }
+});
+
+builtin!(ToHexBuiltin, "to_hex", (bytes: Rc<[u8]>), {
+    let hex = bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    Ok(hex.into())
+});
+
+builtin!(FromHexBuiltin, "from_hex", (hex: String), {
+    let digits = hex
+        .chars()
+        .map(|c| c.to_digit(16))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| invalid("from_hex", "expected only hex digits"))?;
+    if digits.len() % 2 != 0 {
+        return Err(invalid("from_hex", "expected an even number of hex digits"));
+    }
+
+    let bytes = digits
+        .chunks(2)
+        .map(|pair| (pair[0] * 16 + pair[1]) as u8)
+        .collect::<Vec<_>>();
+    Ok(bytes.into())
+});
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+builtin!(ToBase64Builtin, "to_base64", (bytes: Rc<[u8]>), {
+    let mut output = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (i, &b)| group | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            match i <= chunk.len() {
+                true => output.push(BASE64[(group >> (18 - 6 * i) & 0x3f) as usize] as char),
+                false => output.push('='),
+            }
+        }
+    }
+    Ok(output.into())
+});
+
+builtin!(FromBase64Builtin, "from_base64", (text: String), {
+    let text = text.trim_end_matches('=');
+    let sextets = text
+        .bytes()
+        .map(|c| BASE64.iter().position(|&b| b == c).map(|p| p as u32))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| invalid("from_base64", "expected only base64 characters"))?;
+    if sextets.len() % 4 == 1 {
+        return Err(invalid("from_base64", "truncated base64 text"));
+    }
+
+    let mut bytes = Vec::with_capacity(sextets.len() * 3 / 4);
+    for chunk in sextets.chunks(4) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (i, &s)| group | s << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            bytes.push((group >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(bytes.into())
+});
+
+// unsigned, "big" or "little" endian
+builtin!(BytesToIntBuiltin, "bytes_to_int", (bytes: Rc<[u8]>, byte_order: String), {
+    let order = order("bytes_to_int", &byte_order)?;
+    Ok(Integer::from_digits(&bytes, order).into())
+});
+
+builtin!(IntToBytesBuiltin, "int_to_bytes", (integer: Integer, length: Integer, byte_order: String), {
+    let order = order("int_to_bytes", &byte_order)?;
+    let length = length
+        .to_usize()
+        .ok_or_else(|| invalid("int_to_bytes", "the length must be a non-negative integer"))?;
+    if integer < 0 {
+        return Err(invalid("int_to_bytes", "only non-negative integers can be converted"));
+    }
+    if integer.significant_digits::<u8>() > length {
+        return Err(invalid(
+            "int_to_bytes",
+            format!("{} does not fit in {} bytes", integer, length),
+        ));
+    }
+
+    let mut bytes = vec![0u8; length];
+    integer.write_digits(&mut bytes, order);
+    Ok(bytes.into())
+});
@@ -0,0 +1,7 @@
+use super::builtin;
+
+// the value of `--define name=value`, true for `--define name`, nil when
+// `name` wasn't defined
+builtin!(CfgBuiltin, "cfg", ctx, (name: String), {
+    Ok(ctx.borrow().runtime().config.define(&name))
+});
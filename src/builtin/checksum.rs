@@ -0,0 +1,31 @@
+use std::{fs::File, io::Read};
+
+use rug::Integer;
+
+use super::{builtin, io_error};
+use crate::{checksum, sync::Rc};
+
+builtin!(Crc32Builtin, "crc32", (bytes: Rc<[u8]>), {
+    Ok(Integer::from(checksum::crc32(&bytes)).into())
+});
+
+builtin!(Adler32Builtin, "adler32", (bytes: Rc<[u8]>), {
+    Ok(Integer::from(checksum::adler32(&bytes)).into())
+});
+
+// the SHA-256 of a file as lowercase hex, read 64 KiB at a time
+builtin!(FileSha256Builtin, "file_sha256", (path: String), {
+    let mut file = File::open(&path).map_err(|err| io_error(&path, err))?;
+    let mut sha = checksum::Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => sha.update(&buffer[..read]),
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(io_error(&path, err)),
+        }
+    }
+    let hex = sha.finish().iter().map(|b| format!("{:02x}", b)).collect::<String>();
+    Ok(hex.into())
+});
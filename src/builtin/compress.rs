@@ -0,0 +1,77 @@
+use std::{collections::HashMap, fs};
+
+use rug::Integer;
+
+use super::{builtin, extract, io_error};
+use crate::{
+    archive,
+    dict::Dict,
+    function::Arity,
+    interpreter::RuntimeError,
+    report::InvalidArgument,
+    sync::{Rc, RefCell},
+    value::{Value, ValueKey},
+};
+
+fn invalid(name: &str, message: String) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message,
+        }
+        .into(),
+    )
+}
+
+builtin!(GzipCompressBuiltin, "gzip_compress", (bytes: Rc<[u8]>), {
+    Ok(archive::gzip_compress(&bytes).into())
+});
+
+builtin!(GzipDecompressBuiltin, "gzip_decompress", (bytes: Rc<[u8]>), {
+    match archive::gzip_decompress(&bytes) {
+        Ok(bytes) => Ok(bytes.into()),
+        Err(message) => Err(invalid("gzip_decompress", message)),
+    }
+});
+
+// zip_read(path) lists the entries of a zip file, as dicts with their
+// "name", "size" and "compressed_size"; zip_read(path, name) gives the
+// contents of the one called name
+builtin!(
+    ZipReadBuiltin,
+    "zip_read",
+    Arity::Range(1, 2),
+    _ctx,
+    args,
+    {
+        let mut args = args.into_iter();
+        let path: String = extract("zip_read", 0, args.next().unwrap())?;
+        let data = fs::read(&path).map_err(|err| io_error(&path, err))?;
+        let failed = |message| invalid("zip_read", format!("{}: {}", path, message));
+        let entries = archive::zip_entries(&data).map_err(failed)?;
+
+        let Some(name) = args.next() else {
+            let listing = entries.iter().map(|entry| {
+                let fields = [
+                    ("name", Value::from(entry.name.as_str())),
+                    ("size", Integer::from(entry.size).into()),
+                    (
+                        "compressed_size",
+                        Integer::from(entry.compressed_size).into(),
+                    ),
+                ];
+                let dict: HashMap<_, _> = fields
+                    .into_iter()
+                    .map(|(key, value)| (ValueKey(key.into()), value))
+                    .collect();
+                Value::Dict(Rc::new(RefCell::new(Dict(dict))))
+            });
+            return Ok(listing.collect::<Vec<_>>().into());
+        };
+        let name: String = extract("zip_read", 1, name)?;
+        match entries.iter().find(|entry| entry.name == name) {
+            Some(entry) => Ok(archive::zip_extract(&data, entry).map_err(failed)?.into()),
+            None => Err(failed(format!("there is no entry `{}`", name))),
+        }
+    }
+);
@@ -1,44 +1,34 @@
-use std::{cell::RefCell, rc::Rc};
-
 use super::builtin;
-use crate::{list::List, token::Literal, value::Value};
+use crate::{
+    dict::Dict,
+    interpreter::RuntimeError,
+    list::List,
+    query,
+    report::InvalidArgument,
+    sync::{Rc, RefCell},
+    value::Value,
+};
 
-builtin!(KeysBuiltin, "keys", 1, _ctx, args, {
-    if let Some(item) = args.first() {
-        match item {
-            Value::Dict(value) => {
-                let keys = value
-                    .borrow()
-                    .0
-                    .keys()
-                    .into_iter()
-                    .map(|key| key.0.clone())
-                    .collect();
-                Ok(Value::List(Rc::new(RefCell::new(List(keys)))))
-            }
-            _ => Ok(Value::Literal(Literal::Integer(0.into()))),
-        }
-    } else {
-        Ok(Value::Nil)
-    }
+builtin!(KeysBuiltin, "keys", (dict: Rc<RefCell<Dict>>), {
+    let keys = dict.borrow().0.keys().map(|key| key.0.clone()).collect();
+    Ok(Value::List(Rc::new(RefCell::new(List(keys)))))
+});
+
+builtin!(ValuesBuiltin, "values", (dict: Rc<RefCell<Dict>>), {
+    let values = dict.borrow().0.values().cloned().collect();
+    Ok(Value::List(Rc::new(RefCell::new(List(values)))))
 });
 
-builtin!(ValuesBuiltin, "values", 1, _ctx, args, {
-    if let Some(item) = args.first() {
-        match item {
-            Value::Dict(value) => {
-                let keys = value
-                    .borrow()
-                    .0
-                    .values()
-                    .into_iter()
-                    .map(|value| value.clone())
-                    .collect();
-                Ok(Value::List(Rc::new(RefCell::new(List(keys)))))
+// what a jq-like filter, as `.users[] | select(.age > 30) | .name`, gives
+// for `value`, as a list; see src/query.rs
+builtin!(QueryBuiltin, "query", (value: Value, query: String), {
+    query::query(value, &query).map_err(|message| {
+        RuntimeError::Report(
+            InvalidArgument {
+                name: "query".to_string(),
+                message,
             }
-            _ => Ok(Value::Literal(Literal::Integer(0.into()))),
-        }
-    } else {
-        Ok(Value::Nil)
-    }
+            .into(),
+        )
+    })
 });
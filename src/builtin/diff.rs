@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use super::{builtin, style::styled};
+use crate::{
+    dict::Dict,
+    interpreter::RuntimeError,
+    list::List,
+    printer,
+    report::InvalidArgument,
+    sync::{Rc, RefCell},
+    token::Literal,
+    value::{Value, ValueKey},
+};
+
+// deep enough for real data, and a stop for values that contain themselves
+const MAX_DEPTH: usize = 512;
+
+fn invalid(name: &str, message: String) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message,
+        }
+        .into(),
+    )
+}
+
+// where two values differ: an entry or item only in the new value (`old` is
+// None), only in the old one (`new` is None), or in both but not equal
+struct Difference {
+    path: String,
+    old: Option<Value>,
+    new: Option<Value>,
+}
+
+impl Difference {
+    fn kind(&self) -> &'static str {
+        match (&self.old, &self.new) {
+            (None, _) => "added",
+            (_, None) => "removed",
+            _ => "changed",
+        }
+    }
+}
+
+fn child(path: &str, key: &Value) -> String {
+    match key {
+        Value::Literal(Literal::String(key)) => format!("{}.{}", path, key),
+        key => format!("{}[{}]", path, key),
+    }
+}
+
+// dicts are compared key by key and lists item by item, by index; anything
+// else differs when it isn't equal
+fn compare(
+    old: &Value,
+    new: &Value,
+    path: String,
+    depth: usize,
+    differences: &mut Vec<Difference>,
+) -> Result<(), String> {
+    if depth > MAX_DEPTH {
+        return Err("values are nested too deeply, or contain themselves".to_string());
+    }
+
+    match (old, new) {
+        (Value::Dict(a), Value::Dict(b)) if !Rc::ptr_eq(a, b) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            let mut keys: Vec<&ValueKey> =
+                a.0.keys()
+                    .chain(b.0.keys().filter(|key| !a.0.contains_key(key)))
+                    .collect();
+            keys.sort_by_key(|key| key.to_string());
+            for key in keys {
+                let path = child(&path, &key.0);
+                match (a.0.get(key), b.0.get(key)) {
+                    (Some(old), Some(new)) => compare(old, new, path, depth + 1, differences)?,
+                    (old, new) => differences.push(Difference {
+                        path,
+                        old: old.cloned(),
+                        new: new.cloned(),
+                    }),
+                }
+            }
+        }
+        (Value::List(a), Value::List(b)) if !Rc::ptr_eq(a, b) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            for index in 0..a.0.len().max(b.0.len()) {
+                let path = format!("{}[{}]", path, index);
+                match (a.0.get(index), b.0.get(index)) {
+                    (Some(old), Some(new)) => compare(old, new, path, depth + 1, differences)?,
+                    (old, new) => differences.push(Difference {
+                        path,
+                        old: old.cloned(),
+                        new: new.cloned(),
+                    }),
+                }
+            }
+        }
+        (old, new) if old != new => differences.push(Difference {
+            path,
+            old: Some(old.clone()),
+            new: Some(new.clone()),
+        }),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn differences(name: &str, old: &Value, new: &Value) -> Result<Vec<Difference>, RuntimeError> {
+    let mut differences = Vec::new();
+    compare(old, new, "$".to_string(), 0, &mut differences)
+        .map_err(|message| invalid(name, message))?;
+    Ok(differences)
+}
+
+// a list of {"path", "kind", "old", "new"} dicts, one for every place where
+// the values differ; "old" is missing for an added entry and "new" for a
+// removed one
+builtin!(DiffBuiltin, "diff", (old: Value, new: Value), {
+    let differences = differences("diff", &old, &new)?
+        .into_iter()
+        .map(|difference| {
+            let mut entries = HashMap::new();
+            let mut insert = |key: &str, value: Value| {
+                entries.insert(ValueKey::try_from(Value::from(key)).unwrap(), value);
+            };
+            insert("path", difference.path.as_str().into());
+            insert("kind", difference.kind().into());
+            if let Some(old) = difference.old {
+                insert("old", old);
+            }
+            if let Some(new) = difference.new {
+                insert("new", new);
+            }
+            Value::Dict(Rc::new(RefCell::new(Dict(entries))))
+        })
+        .collect();
+    Ok(Value::List(Rc::new(RefCell::new(List(differences)))))
+});
+
+// the differences as ('-', "path: old") and ('+', "path: new") lines
+pub(super) fn lines(
+    name: &str,
+    old: &Value,
+    new: &Value,
+) -> Result<Vec<(char, String)>, RuntimeError> {
+    let mut lines = Vec::new();
+    for difference in differences(name, old, new)? {
+        if let Some(old) = &difference.old {
+            lines.push(('-', format!("{}: {}", difference.path, printer::value(old))));
+        }
+        if let Some(new) = &difference.new {
+            lines.push(('+', format!("{}: {}", difference.path, printer::value(new))));
+        }
+    }
+    Ok(lines)
+}
+
+// the differences as "- path: old" and "+ path: new" lines, in red and green
+builtin!(PrintDiffBuiltin, "print_diff", ctx, (old: Value, new: Value), {
+    for (sign, line) in lines("print_diff", &old, &new)? {
+        let color = if sign == '-' { 31 } else { 32 };
+        let line = styled(ctx, format!("{} {}", sign, line).into(), &[color]);
+        ctx.borrow().runtime().print(&format!("{}\n", line));
+    }
+    Ok(Value::Nil)
+});
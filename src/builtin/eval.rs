@@ -0,0 +1,130 @@
+use miette::{NamedSource, Report};
+
+use super::{builtin, extract};
+use crate::{
+    ast::{self, Builder},
+    context::Ctx,
+    expr::{Stmt, StmtKind},
+    function::{Arity, Purity, SimpleFunction},
+    interpreter::{interpret, RuntimeError},
+    lexer::Lexer,
+    list::List,
+    parser::{self, Parser},
+    report::{EvalDenied, InvalidArgument},
+    resolver::Resolver,
+    sync::{Rc, RefCell},
+    token::Span,
+    value::Value,
+};
+
+fn invalid(name: &str, message: String) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message,
+        }
+        .into(),
+    )
+}
+
+// errors in the code are reported against it, not the script calling
+fn attach(report: Report, code: &str) -> RuntimeError {
+    RuntimeError::Report(report.with_source_code(NamedSource::new("eval", code.to_string())))
+}
+
+fn parse(code: &str) -> Result<Vec<Stmt>, RuntimeError> {
+    let mut lexer = Lexer::new(code);
+    let tokens = lexer.scan_tokens().map_err(|report| attach(report, code))?;
+    Parser::new(tokens)
+        .parse()
+        .map_err(|report| attach(report, code))
+}
+
+// runs `code` in the scope it's called from, which sees its variables and
+// gets its declarations, and returns the value of its last statement, whose
+// `;` can be left out
+builtin!(EvalBuiltin, "eval", ctx, (code: String), {
+    if !ctx.borrow().runtime().config.allow_eval {
+        return Err(RuntimeError::Report(EvalDenied { name: "eval" }.into()));
+    }
+
+    let code = parser::terminated(&code);
+    let statements = parse(&code)?;
+    let mut resolver = Resolver::within(ctx.borrow().env());
+    resolver
+        .resolve(&statements)
+        .map_err(|report| attach(report, &code))?;
+    for warning in resolver.warnings.drain(..) {
+        let warning = warning.with_source_code(NamedSource::new("eval", code.clone()));
+        ctx.borrow().runtime().warn(warning);
+    }
+
+    let ctx = Ctx::with_resolver(ctx, Rc::new(resolver));
+    // a value thrown and not caught in the code is reported in it too
+    interpret(&ctx, &statements)
+        .or_else(RuntimeError::unwind)
+        .map_err(|report| attach(report, &code))
+});
+
+// the statements of `code` as the dicts described in `ast.rs`
+builtin!(ParseBuiltin, "parse", (code: String), {
+    Ok(ast::stmts(&parse(&code)?))
+});
+
+// compile(tree) or compile(tree, params): a function with the parameters
+// named in `params` running the statements of `tree`, a list of statement or
+// expression nodes as `quote` and `parse` make them, or a single node. Its
+// names are looked up where `compile` is called, as if it were defined there.
+builtin!(CompileBuiltin, "compile", Arity::Range(1, 2), ctx, args, {
+    if !ctx.borrow().runtime().config.allow_eval {
+        return Err(RuntimeError::Report(EvalDenied { name: "compile" }.into()));
+    }
+
+    let mut args = args.into_iter();
+    let tree = args.next().unwrap();
+    let params = match args.next() {
+        Some(params) => {
+            let params: Rc<RefCell<List>> = extract("compile", 1, params)?;
+            let params = params.borrow().0.clone();
+            params
+                .into_iter()
+                .map(|param| extract::<String>("compile", 1, param).map(Rc::from))
+                .collect::<Result<_, _>>()?
+        }
+        None => Vec::new(),
+    };
+
+    let mut builder = Builder::default();
+    let body = match &tree {
+        Value::List(_) => builder.stmts(&tree),
+        _ => builder.stmt(&tree).map(|stmt| vec![stmt]),
+    };
+    let body = body.map_err(|message| invalid("compile", message))?;
+    let (params, body) = (Rc::new(params), Rc::new(body));
+
+    // resolved as the declaration of a function where `compile` is called
+    let declaration = Stmt {
+        kind: StmtKind::Function {
+            name: "compiled".into(),
+            params: params.clone(),
+            body: body.clone(),
+            annotations: Vec::new(),
+        },
+        span: Span::default(),
+        id: builder.id(),
+    };
+    let mut resolver = Resolver::within(ctx.borrow().env());
+    resolver.resolve(&[declaration])?;
+    for warning in resolver.warnings.drain(..) {
+        ctx.borrow().runtime().warn(warning);
+    }
+
+    Ok(SimpleFunction {
+        name: "compiled".into(),
+        params,
+        body,
+        closure: Ctx::with_resolver(ctx, Rc::new(resolver)),
+        purity: Purity::Impure,
+    }
+    .into())
+});
@@ -0,0 +1,76 @@
+use rug::{Float, Integer, Rational};
+
+use super::builtin;
+use crate::{
+    exact::Exact,
+    interpreter::RuntimeError,
+    report::{ArgumentTypeError, InvalidArgument},
+    token::Literal,
+    value::Value,
+};
+
+fn invalid(name: &str, message: impl Into<String>) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message: message.into(),
+        }
+        .into(),
+    )
+}
+
+fn not_a_number(name: &str, value: &Value) -> RuntimeError {
+    RuntimeError::Report(
+        ArgumentTypeError {
+            name: name.to_string(),
+            position: 1,
+            expected: "integer, float or exact",
+            found: value.type_name(),
+        }
+        .into(),
+    )
+}
+
+// the value of `pi`
+pub(crate) fn pi() -> Value {
+    Exact::Pi.into_value()
+}
+
+// exact for integers and exacts, so that sqrt(2) stays √2 until `approx`
+// or a float operand collapses it; a float for floats
+builtin!(SqrtBuiltin, "sqrt", (x: Value), {
+    if let Value::Literal(Literal::Float(f)) = &x {
+        return match **f < 0 {
+            true => Err(invalid("sqrt", format!("{} is negative", f))),
+            false => Ok(Float::with_val(f.prec(), f.sqrt_ref()).into()),
+        };
+    }
+    let exact = Exact::from_value(&x).ok_or_else(|| not_a_number("sqrt", &x))?;
+    match exact.sqrt() {
+        Some(root) => Ok(root.into_value()),
+        None => Err(invalid("sqrt", format!("{} is negative", x))),
+    }
+});
+
+// the exact rational `numer / denom`, an integer when it is one
+builtin!(RationalBuiltin, "rational", (numer: Integer, denom: Integer), {
+    if denom == 0 {
+        return Err(invalid("rational", "the denominator is 0"));
+    }
+    Ok(Exact::Rational(Rational::from((numer, denom))).into_value())
+});
+
+// a float with at least `digits` correct decimal digits
+builtin!(ApproxBuiltin, "approx", (x: Value, digits: Integer), {
+    let digits = match digits.to_u32() {
+        Some(digits @ 1..=1_000_000) => digits,
+        _ => return Err(invalid("approx", "digits must be between 1 and 1000000")),
+    };
+    let precision = (f64::from(digits) * std::f64::consts::LOG2_10).ceil() as u32;
+    match &x {
+        Value::Exact(exact) => Ok(exact.approx(precision).into()),
+        Value::Literal(Literal::Integer(i)) => Ok(Float::with_val(precision, &**i).into()),
+        Value::Literal(Literal::Float(f)) => Ok(Float::with_val(precision, &**f).into()),
+        _ => Err(not_a_number("approx", &x)),
+    }
+});
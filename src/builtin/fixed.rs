@@ -0,0 +1,152 @@
+use rug::Integer;
+
+use super::{builtin, extract};
+use crate::{
+    fixed::{self, Fixed, Op, Overflow, KINDS},
+    interpreter::RuntimeError,
+    report::InvalidArgument,
+    value::Value,
+};
+
+fn invalid(name: &str, message: impl Into<String>) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message: message.into(),
+        }
+        .into(),
+    )
+}
+
+// integers and machine integers of any kind are cast by keeping their low
+// bits, like `as` in Rust
+fn cast(name: &str, value: Value) -> Result<Value, RuntimeError> {
+    let (_, kind) = KINDS.iter().find(|(kind, _)| *kind == name).unwrap();
+    let integer = match value {
+        Value::Fixed(value) => value.to_integer(),
+        other => extract::<Integer>(name, 0, other)?,
+    };
+    Ok(Value::Fixed(kind.wrap(&integer)))
+}
+
+builtin!(I8Builtin, "i8", (value: Value), {
+    cast("i8", value)
+});
+
+builtin!(I16Builtin, "i16", (value: Value), {
+    cast("i16", value)
+});
+
+builtin!(I32Builtin, "i32", (value: Value), {
+    cast("i32", value)
+});
+
+builtin!(I64Builtin, "i64", (value: Value), {
+    cast("i64", value)
+});
+
+builtin!(U8Builtin, "u8", (value: Value), {
+    cast("u8", value)
+});
+
+builtin!(U16Builtin, "u16", (value: Value), {
+    cast("u16", value)
+});
+
+builtin!(U32Builtin, "u32", (value: Value), {
+    cast("u32", value)
+});
+
+builtin!(U64Builtin, "u64", (value: Value), {
+    cast("u64", value)
+});
+
+builtin!(IntegerBuiltin, "integer", (value: Fixed), {
+    Ok(value.to_integer().into())
+});
+
+fn operands(name: &str, lhs: Value, rhs: Value) -> Result<(Fixed, Fixed), RuntimeError> {
+    let message = format!("cannot combine {} and {}", lhs, rhs);
+    fixed::operands(lhs, rhs).map_err(|_| invalid(name, message))
+}
+
+// nil when a checked operation overflows or divides by zero
+fn apply(
+    name: &str,
+    op: Op,
+    overflow: Overflow,
+    lhs: Value,
+    rhs: Value,
+) -> Result<Value, RuntimeError> {
+    let (lhs, rhs) = operands(name, lhs, rhs)?;
+    match lhs.apply(op, rhs, overflow) {
+        Some(result) => Ok(Value::Fixed(result)),
+        None if overflow == Overflow::Check => Ok(Value::Nil),
+        None => Err(invalid(name, "division by zero")),
+    }
+}
+
+builtin!(WrappingAddBuiltin, "wrapping_add", (lhs: Value, rhs: Value), {
+    apply("wrapping_add", Op::Add, Overflow::Wrap, lhs, rhs)
+});
+
+builtin!(WrappingSubBuiltin, "wrapping_sub", (lhs: Value, rhs: Value), {
+    apply("wrapping_sub", Op::Sub, Overflow::Wrap, lhs, rhs)
+});
+
+builtin!(WrappingMulBuiltin, "wrapping_mul", (lhs: Value, rhs: Value), {
+    apply("wrapping_mul", Op::Mul, Overflow::Wrap, lhs, rhs)
+});
+
+builtin!(CheckedAddBuiltin, "checked_add", (lhs: Value, rhs: Value), {
+    apply("checked_add", Op::Add, Overflow::Check, lhs, rhs)
+});
+
+builtin!(CheckedSubBuiltin, "checked_sub", (lhs: Value, rhs: Value), {
+    apply("checked_sub", Op::Sub, Overflow::Check, lhs, rhs)
+});
+
+builtin!(CheckedMulBuiltin, "checked_mul", (lhs: Value, rhs: Value), {
+    apply("checked_mul", Op::Mul, Overflow::Check, lhs, rhs)
+});
+
+builtin!(CheckedDivBuiltin, "checked_div", (lhs: Value, rhs: Value), {
+    apply("checked_div", Op::Div, Overflow::Check, lhs, rhs)
+});
+
+builtin!(SaturatingAddBuiltin, "saturating_add", (lhs: Value, rhs: Value), {
+    apply("saturating_add", Op::Add, Overflow::Saturate, lhs, rhs)
+});
+
+builtin!(SaturatingSubBuiltin, "saturating_sub", (lhs: Value, rhs: Value), {
+    apply("saturating_sub", Op::Sub, Overflow::Saturate, lhs, rhs)
+});
+
+builtin!(SaturatingMulBuiltin, "saturating_mul", (lhs: Value, rhs: Value), {
+    apply("saturating_mul", Op::Mul, Overflow::Saturate, lhs, rhs)
+});
+
+// `|` is the operator; and, xor and the shifts are builtins
+builtin!(BitAndBuiltin, "bit_and", (lhs: Value, rhs: Value), {
+    let (lhs, rhs) = operands("bit_and", lhs, rhs)?;
+    Ok(Value::Fixed(lhs.bits(rhs, |a, b| a & b)))
+});
+
+builtin!(BitXorBuiltin, "bit_xor", (lhs: Value, rhs: Value), {
+    let (lhs, rhs) = operands("bit_xor", lhs, rhs)?;
+    Ok(Value::Fixed(lhs.bits(rhs, |a, b| a ^ b)))
+});
+
+fn shift(value: Fixed, amount: Integer) -> Value {
+    let bits = Integer::from(value.kind.bits);
+    let amount = amount.clamp(&-bits.clone(), &bits).to_i32().unwrap();
+    Value::Fixed(value.shift(amount))
+}
+
+builtin!(ShlBuiltin, "shl", (value: Fixed, amount: Integer), {
+    Ok(shift(value, amount))
+});
+
+builtin!(ShrBuiltin, "shr", (value: Fixed, amount: Integer), {
+    Ok(shift(value, -amount))
+});
@@ -0,0 +1,212 @@
+use rug::{Integer, Rational};
+
+use super::{builtin, extract};
+use crate::{
+    dict::Dict,
+    function::Arity,
+    interpreter::RuntimeError,
+    report::{ArgumentTypeError, InvalidArgument},
+    sync::{Rc, RefCell},
+    token::Literal,
+    value::{Value, ValueKey},
+};
+
+// the separator of thousands and the decimal point of a locale, and whether
+// it writes currency symbols after the amount
+const LOCALES: [(&str, &str, &str, bool); 4] = [
+    ("de", ".", ",", true),
+    ("en", ",", ".", false),
+    ("fr", " ", ",", true),
+    ("it", ".", ",", true),
+];
+
+struct Format {
+    group: String,
+    point: String,
+    decimals: Option<usize>,
+    symbol_after: bool,
+}
+
+fn invalid(name: &str, message: impl Into<String>) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message: message.into(),
+        }
+        .into(),
+    )
+}
+
+fn locale(name: &str, locale: &str) -> Result<Format, RuntimeError> {
+    match LOCALES.iter().find(|(code, ..)| *code == locale) {
+        Some(&(_, group, point, symbol_after)) => Ok(Format {
+            group: group.to_string(),
+            point: point.to_string(),
+            decimals: None,
+            symbol_after,
+        }),
+        None => Err(invalid(
+            name,
+            format!(
+                "unknown locale `{}`, expected one of {}",
+                locale,
+                LOCALES.map(|(code, ..)| code).join(", ")
+            ),
+        )),
+    }
+}
+
+// {"locale": "de", "group": ",", "point": ".", "decimals": 2}, where the
+// locale, "en" when it's missing, gives the separators the others don't
+fn options(name: &str, index: usize, options: Option<Value>) -> Result<Format, RuntimeError> {
+    let Some(options) = options else {
+        return locale(name, "en");
+    };
+    let options: Rc<RefCell<Dict>> = extract(name, index, options)?;
+    let options = options.borrow();
+    let string = |option: &str, value: &Value| match value {
+        Value::Literal(Literal::String(string)) => Ok(string.to_string()),
+        other => Err(invalid(
+            name,
+            format!("`{}` must be a string, found {}", option, other.type_name()),
+        )),
+    };
+
+    let mut format = match options.0.get(&ValueKey(Value::from("locale"))) {
+        Some(value) => locale(name, &string("locale", value)?)?,
+        None => locale(name, "en")?,
+    };
+    for (key, value) in options.0.iter() {
+        let option = match &key.0 {
+            Value::Literal(Literal::String(option)) => option.as_ref(),
+            other => return Err(invalid(name, format!("unknown option {}", other))),
+        };
+        match option {
+            "locale" => {}
+            "group" => format.group = string(option, value)?,
+            "point" => format.point = string(option, value)?,
+            "decimals" => {
+                let decimals = match value {
+                    Value::Literal(Literal::Integer(decimals)) => decimals.to_usize(),
+                    _ => None,
+                };
+                match decimals {
+                    Some(decimals) => format.decimals = Some(decimals),
+                    None => return Err(invalid(name, "`decimals` must be a non-negative integer")),
+                }
+            }
+            other => return Err(invalid(name, format!("unknown option `{}`", other))),
+        }
+    }
+    Ok(format)
+}
+
+// the exact value of a number, and whether it's a float
+fn number(name: &str, value: &Value) -> Result<(Rational, bool), RuntimeError> {
+    let number = match value {
+        Value::Literal(Literal::Integer(i)) => Some((Rational::from(&**i), false)),
+        Value::Literal(Literal::Float(f)) => f.to_rational().map(|r| (r, true)),
+        Value::Fixed(fixed) => Some((Rational::from(fixed.to_integer()), false)),
+        _ => None,
+    };
+    number.ok_or_else(|| {
+        RuntimeError::Report(
+            ArgumentTypeError {
+                name: name.to_string(),
+                position: 1,
+                expected: "finite number",
+                found: value.type_name(),
+            }
+            .into(),
+        )
+    })
+}
+
+// `number` rounded half away from zero to `decimals` digits after the
+// point, with its integer digits in groups of three
+fn format(number: Rational, decimals: usize, format: &Format) -> (bool, String) {
+    let scaled = (number * Integer::from(Integer::u_pow_u(10, decimals as u32))).round();
+    let scaled = scaled.into_numer_denom().0;
+    let negative = scaled < 0;
+    let digits = scaled.abs().to_string();
+    let digits = format!("{:0>width$}", digits, width = decimals + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - decimals);
+
+    let mut output = String::new();
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            output.push_str(&format.group);
+        }
+        output.push(digit);
+    }
+    if decimals > 0 {
+        output.push_str(&format.point);
+        output.push_str(fraction);
+    }
+    (negative, output)
+}
+
+fn signed((negative, digits): (bool, String)) -> String {
+    match negative {
+        true => format!("-{}", digits),
+        false => digits,
+    }
+}
+
+// format_number(x) or format_number(x, options): 1234567.891 as
+// "1,234,567.89"; floats have 2 decimals unless told otherwise, integers none
+builtin!(
+    FormatNumberBuiltin,
+    "format_number",
+    Arity::Range(1, 2),
+    _ctx,
+    args,
+    {
+        let mut args = args.into_iter();
+        let (number, float) = number("format_number", &args.next().unwrap())?;
+        let options = options("format_number", 1, args.next())?;
+        let decimals = options.decimals.unwrap_or(if float { 2 } else { 0 });
+        Ok(signed(format(number, decimals, &options)).into())
+    }
+);
+
+// format_percent(x) or format_percent(x, options): 0.125 as "13%", or as
+// "12.5%" with {"decimals": 1}
+builtin!(
+    FormatPercentBuiltin,
+    "format_percent",
+    Arity::Range(1, 2),
+    _ctx,
+    args,
+    {
+        let mut args = args.into_iter();
+        let (number, _) = number("format_percent", &args.next().unwrap())?;
+        let options = options("format_percent", 1, args.next())?;
+        let decimals = options.decimals.unwrap_or(0);
+        let percent = signed(format(number * 100, decimals, &options));
+        Ok(format!("{}%", percent).into())
+    }
+);
+
+// format_currency(x, symbol) or format_currency(x, symbol, options): -5 and
+// "$" as "-$5.00", or as "-5,00 $" in a locale that writes the symbol after
+builtin!(
+    FormatCurrencyBuiltin,
+    "format_currency",
+    Arity::Range(2, 3),
+    _ctx,
+    args,
+    {
+        let mut args = args.into_iter();
+        let (number, _) = number("format_currency", &args.next().unwrap())?;
+        let symbol: String = extract("format_currency", 1, args.next().unwrap())?;
+        let options = options("format_currency", 2, args.next())?;
+        let decimals = options.decimals.unwrap_or(2);
+        let (negative, digits) = format(number, decimals, &options);
+        let amount = match options.symbol_after {
+            true => format!("{} {}", digits, symbol),
+            false => format!("{}{}", symbol, digits),
+        };
+        Ok(signed((negative, amount)).into())
+    }
+);
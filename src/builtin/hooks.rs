@@ -0,0 +1,55 @@
+use super::builtin;
+use crate::{
+    function::Function,
+    interpreter::{run_event_handlers, RuntimeError},
+    report::InvalidArgument,
+    sync::Rc,
+    value::Value,
+};
+
+pub(crate) fn handler(
+    name: &str,
+    handler: &Rc<dyn Function>,
+    args: usize,
+) -> Result<(), RuntimeError> {
+    if handler.arity().accepts(args) {
+        return Ok(());
+    }
+    Err(RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message: format!("the handler must take {}, not {}", args, handler.arity()),
+        }
+        .into(),
+    ))
+}
+
+// `handler()` runs when the script (or the REPL) ends without an error
+builtin!(OnExitBuiltin, "on_exit", ctx, (handler: Rc<dyn Function>), {
+    self::handler("on_exit", &handler, 0)?;
+    ctx.borrow().runtime().hooks.exit.borrow_mut().push(handler);
+    Ok(Value::Nil)
+});
+
+// `handler(error)` runs before an uncaught runtime error is reported, with
+// the error as {"code", "message"}
+builtin!(OnErrorBuiltin, "on_error", ctx, (handler: Rc<dyn Function>), {
+    self::handler("on_error", &handler, 1)?;
+    ctx.borrow().runtime().hooks.error.borrow_mut().push(handler);
+    Ok(Value::Nil)
+});
+
+// `handler(payload)` runs on every `emit(event, payload)`, from the script or
+// from the program embedding it
+builtin!(OnBuiltin, "on", ctx, (event: String, handler: Rc<dyn Function>), {
+    self::handler("on", &handler, 1)?;
+    let runtime = ctx.borrow().runtime().clone();
+    runtime.hooks.events.borrow_mut().entry(event).or_default().push(handler);
+    Ok(Value::Nil)
+});
+
+// calls the handlers of `event` in the order they were added
+builtin!(EmitBuiltin, "emit", ctx, (event: String, payload: Value), {
+    run_event_handlers(ctx, &event, payload)?;
+    Ok(Value::Nil)
+});
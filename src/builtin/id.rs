@@ -0,0 +1,103 @@
+use std::{
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rug::Integer;
+
+use super::{builtin, extract};
+use crate::{
+    function::Arity,
+    interpreter::RuntimeError,
+    report::{InvalidArgument, IoError},
+    value::Value,
+};
+
+// the 64 characters nanoids are made of, all safe in URLs and file names
+const NANOID_ALPHABET: &[u8; 64] =
+    b"_-0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+const NANOID_LEN: usize = 21;
+
+fn random<const N: usize>(name: &str) -> Result<[u8; N], RuntimeError> {
+    let mut bytes = [0; N];
+    match getrandom::getrandom(&mut bytes) {
+        Ok(()) => Ok(bytes),
+        Err(err) => Err(RuntimeError::Report(
+            IoError {
+                message: format!("{}: {}", name, err),
+            }
+            .into(),
+        )),
+    }
+}
+
+// `bytes` stamped with a UUID version and the RFC 9562 variant
+fn stamp(mut bytes: [u8; 16], version: u8) -> [u8; 16] {
+    bytes[6] = (bytes[6] & 0x0f) | (version << 4);
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    bytes
+}
+
+// the 8-4-4-4-12 hex form of a UUID
+fn uuid(bytes: [u8; 16]) -> Value {
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+    .into()
+}
+
+// a random UUID
+builtin!(Uuid4Builtin, "uuid4", (), {
+    Ok(uuid(stamp(random("uuid4")?, 4)))
+});
+
+// a UUID that starts with the milliseconds since the epoch and is random
+// after that; one made within the same millisecond as the last is that one
+// plus one, so that each sorts after the ones before it
+builtin!(Uuid7Builtin, "uuid7", (), {
+    static LAST: Mutex<u128> = Mutex::new(0);
+
+    let mut bytes = random::<16>("uuid7")?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    bytes[..6].copy_from_slice(&millis.to_be_bytes()[2..]);
+    let mut last = LAST.lock().unwrap_or_else(|err| err.into_inner());
+    let id = u128::from_be_bytes(stamp(bytes, 7)).max(*last + 1);
+    *last = id;
+    Ok(uuid(id.to_be_bytes()))
+});
+
+// nanoid() or nanoid(len): `len` random characters, 21 when it's missing,
+// which makes collisions as unlikely as they are between UUIDs
+builtin!(NanoidBuiltin, "nanoid", Arity::Range(0, 1), _ctx, args, {
+    let len = match args.into_iter().next() {
+        Some(len) => extract::<Integer>("nanoid", 0, len)?
+            .to_usize()
+            .filter(|len| (1..=1024).contains(len))
+            .ok_or_else(|| {
+                RuntimeError::Report(
+                    InvalidArgument {
+                        name: "nanoid".to_string(),
+                        message: "the length must be between 1 and 1024".to_string(),
+                    }
+                    .into(),
+                )
+            })?,
+        None => NANOID_LEN,
+    };
+    let bytes = random::<1024>("nanoid")?;
+    let id = bytes[..len]
+        .iter()
+        .map(|&byte| NANOID_ALPHABET[(byte & 63) as usize] as char)
+        .collect::<String>();
+    Ok(id.into())
+});
@@ -1,16 +1,178 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::atomic::{AtomicU32, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
 use super::builtin;
-use crate::value::Value;
+use crate::{
+    file::File,
+    function::{Arity, Function},
+    interpreter::RuntimeError,
+    report::IoError,
+    sync::{Rc, RefCell},
+    value::Value,
+};
 
-builtin!(PrintBuiltin, "print", 0, _ctx, args, {
+builtin!(PrintBuiltin, "print", Arity::AtLeast(0), ctx, args, {
     let strings = args.into_iter().map(|v| v.to_string()).collect::<Vec<_>>();
-    print!("{}", strings.join(" "));
+    ctx.borrow().runtime().print(&strings.join(" "));
 
     Ok(Value::Nil)
 });
 
-builtin!(PrintlnBuiltin, "println", 0, _ctx, args, {
+builtin!(PrintlnBuiltin, "println", Arity::AtLeast(0), ctx, args, {
     let strings = args.into_iter().map(|v| v.to_string()).collect::<Vec<_>>();
-    println!("{}", strings.join(" "));
+    ctx.borrow().runtime().print(&(strings.join(" ") + "\n"));
+
+    Ok(Value::Nil)
+});
+
+builtin!(ReadFileBytesBuiltin, "read_file_bytes", (path: String), {
+    match fs::read(&path) {
+        Ok(bytes) => Ok(bytes.into()),
+        Err(err) => Err(io_error(&path, err)),
+    }
+});
+
+builtin!(WriteFileBytesBuiltin, "write_file_bytes", (path: String, bytes: Rc<[u8]>), {
+    match fs::write(&path, &bytes) {
+        Ok(()) => Ok(Value::Nil),
+        Err(err) => Err(io_error(&path, err)),
+    }
+});
 
+fn open(path: &str) -> Result<File, RuntimeError> {
+    File::open(path).map_err(|err| io_error(path, err))
+}
+
+// a file to read a line at a time with `read_line`
+builtin!(OpenBuiltin, "open", (path: String), {
+    Ok(Value::File(Rc::new(RefCell::new(open(&path)?))))
+});
+
+// the next line without its line ending, or nil at the end of the file
+builtin!(ReadLineBuiltin, "read_line", (file: Rc<RefCell<File>>), {
+    let mut file = file.borrow_mut();
+    match file.read_line() {
+        Ok(Some(line)) => Ok(line.into()),
+        Ok(None) => Ok(Value::Nil),
+        Err(err) => Err(io_error(file.path(), err)),
+    }
+});
+
+builtin!(CloseBuiltin, "close", (file: Rc<RefCell<File>>), {
+    file.borrow_mut().close();
     Ok(Value::Nil)
 });
+
+// calls `function` with every line of the file in turn, holding only the
+// one it's at
+builtin!(ForEachLineBuiltin, "for_each_line", ctx, (path: String, function: Rc<dyn Function>), {
+    let mut file = open(&path)?;
+    loop {
+        match file.read_line() {
+            Ok(Some(line)) => function.call(ctx, vec![line.into()])?,
+            Ok(None) => return Ok(Value::Nil),
+            Err(err) => return Err(io_error(&path, err)),
+        };
+    }
+});
+
+// A path in the system temp directory that no other process will pick:
+// `create` gets called on candidates until one doesn't exist yet.
+fn create_temp(create: fn(&PathBuf) -> io::Result<()>) -> io::Result<PathBuf> {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    loop {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos();
+        let name = format!(
+            "xi-{}-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            nanos
+        );
+        let path = std::env::temp_dir().join(name);
+        match create(&path) {
+            Ok(()) => return Ok(path),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn temp(create: fn(&PathBuf) -> io::Result<()>) -> Result<Value, RuntimeError> {
+    match create_temp(create) {
+        Ok(path) => Ok(path.to_string_lossy().into_owned().into()),
+        Err(err) => Err(io_error(&std::env::temp_dir().to_string_lossy(), err)),
+    }
+}
+
+// an empty file, left for the script to remove
+builtin!(TempFileBuiltin, "temp_file", (), {
+    temp(|path| {
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map(|_| ())
+    })
+});
+
+builtin!(TempDirBuiltin, "temp_dir", (), {
+    temp(|path| fs::create_dir(path))
+});
+
+// creates the directory and any missing parents
+builtin!(MkdirsBuiltin, "mkdirs", (path: String), {
+    match fs::create_dir_all(&path) {
+        Ok(()) => Ok(Value::Nil),
+        Err(err) => Err(io_error(&path, err)),
+    }
+});
+
+// removes a file, or a directory with everything in it
+builtin!(RemoveBuiltin, "remove", (path: String), {
+    let result = fs::symlink_metadata(&path).and_then(|metadata| match metadata.is_dir() {
+        true => fs::remove_dir_all(&path),
+        false => fs::remove_file(&path),
+    });
+    match result {
+        Ok(()) => Ok(Value::Nil),
+        Err(err) => Err(io_error(&path, err)),
+    }
+});
+
+builtin!(CopyFileBuiltin, "copy_file", (src: String, dst: String), {
+    match fs::copy(&src, &dst) {
+        Ok(_) => Ok(Value::Nil),
+        Err(err) => Err(io_error(&src, err)),
+    }
+});
+
+// renames when it can, and copies then removes across file systems
+builtin!(MoveFileBuiltin, "move_file", (src: String, dst: String), {
+    let result = match fs::rename(&src, &dst) {
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+            fs::copy(&src, &dst).and_then(|_| fs::remove_file(&src))
+        }
+        result => result,
+    };
+    match result {
+        Ok(()) => Ok(Value::Nil),
+        Err(err) => Err(io_error(&src, err)),
+    }
+});
+
+pub(crate) fn io_error(path: &str, err: std::io::Error) -> RuntimeError {
+    RuntimeError::Report(
+        IoError {
+            message: format!("{}: {}", path, err),
+        }
+        .into(),
+    )
+}
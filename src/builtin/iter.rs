@@ -0,0 +1,88 @@
+use rug::Integer;
+
+use super::{builtin, extract};
+use crate::{
+    function::{Arity, Function},
+    interpreter::RuntimeError,
+    iterator::{collect, Filter, Iter, Map, Range},
+    report::ArgumentTypeError,
+    sync::{Rc, RefCell},
+    token::Literal,
+    value::Value,
+};
+
+// range(end), range(start, end) or range(start, end, step)
+builtin!(RangeBuiltin, "range", Arity::Range(1, 3), _ctx, args, {
+    let mut bounds = args
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| extract::<Integer>("range", index, value))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let step = match bounds.len() {
+        3 => bounds.pop().unwrap(),
+        _ => Integer::from(1),
+    };
+    let end = bounds.pop().unwrap();
+    let current = bounds.pop().unwrap_or_default();
+
+    if step == 0 {
+        return Err(RuntimeError::Report(
+            ArgumentTypeError {
+                name: "range".to_string(),
+                position: 3,
+                expected: "non-zero integer",
+                found: "zero",
+            }
+            .into(),
+        ));
+    }
+
+    Ok(Value::Iterator(Rc::new(RefCell::new(Range {
+        current,
+        end,
+        step,
+    }))))
+});
+
+builtin!(MapBuiltin, "map", (seq: Iter, function: Rc<dyn Function>), {
+    Ok(Value::Iterator(Rc::new(RefCell::new(Map {
+        inner: seq,
+        function,
+    }))))
+});
+
+builtin!(FilterBuiltin, "filter", (seq: Iter, predicate: Rc<dyn Function>), {
+    Ok(Value::Iterator(Rc::new(RefCell::new(Filter {
+        inner: seq,
+        predicate,
+    }))))
+});
+
+builtin!(CollectBuiltin, "collect", ctx, (seq: Iter), {
+    Ok(Value::List(Rc::new(RefCell::new(collect(&seq, ctx)?))))
+});
+
+builtin!(SumBuiltin, "sum", ctx, (seq: Iter), {
+    let mut total = Value::from(Integer::new());
+    loop {
+        let item = seq.borrow_mut().next(ctx)?;
+        match item {
+            Some(item @ Value::Literal(Literal::Integer(_) | Literal::Float(_))) => {
+                total = (total + item).expect("numbers can be added")
+            }
+            Some(item) => {
+                return Err(RuntimeError::Report(
+                    ArgumentTypeError {
+                        name: "sum".to_string(),
+                        position: 1,
+                        expected: "iterable of numbers",
+                        found: item.type_name(),
+                    }
+                    .into(),
+                ))
+            }
+            None => return Ok(total),
+        }
+    }
+});
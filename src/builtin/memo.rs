@@ -0,0 +1,77 @@
+use std::{collections::HashMap, fmt::Display};
+
+use super::builtin;
+use crate::{
+    context::Ctx,
+    function::{Arity, Function, Purity},
+    interpreter::RuntimeError,
+    sync::{Rc, RefCell},
+    value::{Value, ValueKey},
+};
+
+// Caches the results of `function` by its arguments. Calls with arguments
+// that can't be dict keys are passed through uncached.
+#[derive(Debug)]
+pub struct MemoFunction {
+    function: Rc<dyn Function>,
+    cache: RefCell<HashMap<Vec<ValueKey>, Value>>,
+}
+
+impl MemoFunction {
+    pub fn new(function: Rc<dyn Function>) -> Self {
+        Self {
+            function,
+            cache: RefCell::default(),
+        }
+    }
+}
+
+impl Display for MemoFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(memo {})", self.function)
+    }
+}
+
+impl Function for MemoFunction {
+    fn run(&self, ctx: &Rc<RefCell<Ctx>>, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let key = args
+            .iter()
+            .cloned()
+            .map(ValueKey::try_from)
+            .collect::<Result<Vec<_>, _>>();
+
+        let Ok(key) = key else {
+            return self.function.call(ctx, args);
+        };
+
+        if let Some(value) = self.cache.borrow().get(&key) {
+            return Ok(value.clone());
+        }
+
+        // the cache isn't borrowed during the call, which usually recurses
+        let value = self.function.call(ctx, args)?;
+        self.cache.borrow_mut().insert(key, value.clone());
+        Ok(value)
+    }
+
+    fn arity(&self) -> Arity {
+        self.function.arity()
+    }
+
+    // the cache doesn't change what a call gives
+    fn purity(&self) -> Purity {
+        self.function.purity()
+    }
+
+    fn referenced(&self) -> Vec<Value> {
+        let cache = self.cache.borrow();
+        let cached = cache.values().cloned();
+        std::iter::once(Value::Function(self.function.clone()))
+            .chain(cached)
+            .collect()
+    }
+}
+
+builtin!(MemoBuiltin, "memo", (function: Rc<dyn Function>), {
+    Ok(Value::Function(Rc::new(MemoFunction::new(function))))
+});
@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use super::{builtin, io_error};
+use crate::{
+    alloc,
+    dict::Dict,
+    heap,
+    sync::{Rc, RefCell},
+    value::{Value, ValueKey},
+};
+
+// {"bytes", "allocations"} live on the heap, "peak_bytes" so far and
+// "total_allocations" ever made
+builtin!(MemStatsBuiltin, "mem_stats", (), {
+    let stats = alloc::stats();
+    let entries = [
+        ("bytes", stats.bytes),
+        ("allocations", stats.allocations),
+        ("peak_bytes", stats.peak_bytes),
+        ("total_allocations", stats.total_allocations),
+    ];
+    let dict = entries
+        .into_iter()
+        .map(|(name, count)| {
+            let key = ValueKey::try_from(Value::from(name)).unwrap();
+            (key, rug::Integer::from(count).into())
+        })
+        .collect::<HashMap<_, _>>();
+    Ok(Value::Dict(Rc::new(RefCell::new(Dict(dict)))))
+});
+
+// writes everything reachable from the calling scope to `path` as JSON (see
+// `heap::dump`)
+builtin!(HeapDumpBuiltin, "heap_dump", ctx, (path: String), {
+    let dump = heap::dump(ctx.borrow().env());
+    match std::fs::write(&path, dump.to_string()) {
+        Ok(()) => Ok(Value::Nil),
+        Err(err) => Err(io_error(&path, err)),
+    }
+});
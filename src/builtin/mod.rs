@@ -1,14 +1,454 @@
+use rug::{Float, Integer};
+
+use crate::{
+    datetime::{DateTime, Duration},
+    dict::Dict,
+    file::File,
+    fixed::Fixed,
+    function::{Function, Purity},
+    interpreter::RuntimeError,
+    iterator::{iterate, Iter},
+    lexer::DEFAULT_FLOAT_PRECISION,
+    list::List,
+    process::Process,
+    progress::Progress,
+    report::ArgumentTypeError,
+    set::Set,
+    sync::{Rc, RefCell},
+    table::Table,
+    token::Literal,
+    value::{Value, WeakRef},
+};
+
+mod assert;
+mod bytes;
+mod cfg;
+mod checksum;
+mod compress;
 mod dict;
+mod diff;
+mod eval;
+mod exact;
+mod fixed;
+mod format;
+mod hooks;
+mod id;
 mod io;
+mod iter;
+mod memo;
+mod memory;
+mod msgpack;
+mod pack;
+mod plot;
+mod process;
+mod progress;
+mod prompt;
+mod repr;
+mod semver;
 mod seq;
+mod set;
+mod string;
+mod style;
+mod table;
 mod time;
+mod timer;
+mod url;
+mod validate;
+mod weak;
 
+pub use assert::*;
+pub use bytes::*;
+pub use cfg::*;
+pub use checksum::*;
+pub use compress::*;
 pub use dict::*;
+pub use diff::*;
+pub use eval::*;
+pub use exact::*;
+pub use fixed::*;
+pub use format::*;
+pub use hooks::*;
+pub use id::*;
 pub use io::*;
+pub use iter::*;
+pub use memo::*;
+pub use memory::*;
+pub use msgpack::*;
+pub use pack::*;
+pub use plot::*;
+pub use process::*;
+pub use progress::*;
+pub use prompt::*;
+pub use repr::*;
+pub use semver::*;
 pub use seq::*;
+pub use set::*;
+pub use string::*;
+pub use style::*;
+pub use table::*;
 pub use time::*;
+pub use timer::*;
+pub use url::*;
+pub use validate::*;
+pub use weak::*;
+
+// Types a typed builtin parameter can be declared with
+pub trait FromArg: Sized {
+    const TYPE: &'static str;
+
+    fn from_arg(value: Value) -> Option<Self>;
+}
+
+impl FromArg for Value {
+    const TYPE: &'static str = "any";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        Some(value)
+    }
+}
+
+impl FromArg for bool {
+    const TYPE: &'static str = "bool";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::True => Some(true),
+            Value::False => Some(false),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for Integer {
+    const TYPE: &'static str = "integer";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::Literal(Literal::Integer(i)) => Some(Rc::unwrap_or_clone(i)),
+            _ => None,
+        }
+    }
+}
+
+// integers are accepted too, converted exactly
+impl FromArg for Float {
+    const TYPE: &'static str = "float";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::Literal(Literal::Float(f)) => Some(Rc::unwrap_or_clone(f)),
+            Value::Literal(Literal::Integer(i)) => {
+                let precision = DEFAULT_FLOAT_PRECISION.max(i.significant_bits());
+                Some(Float::with_val(precision, &*i))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for String {
+    const TYPE: &'static str = "string";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::Literal(Literal::String(s)) => Some(s.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for Rc<[u8]> {
+    const TYPE: &'static str = "bytes";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for Duration {
+    const TYPE: &'static str = "duration";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::Duration(duration) => Some(duration),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for DateTime {
+    const TYPE: &'static str = "datetime";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::DateTime(datetime) => Some(datetime),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for Fixed {
+    const TYPE: &'static str = "machine integer";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::Fixed(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for Rc<RefCell<List>> {
+    const TYPE: &'static str = "list";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::List(list) => Some(list),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for Rc<RefCell<Dict>> {
+    const TYPE: &'static str = "dict";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for Rc<Set> {
+    const TYPE: &'static str = "set";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::Set(set) => Some(set),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for Rc<RefCell<Process>> {
+    const TYPE: &'static str = "process";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::Process(process) => Some(process),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for Rc<RefCell<File>> {
+    const TYPE: &'static str = "file";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::File(file) => Some(file),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for Rc<RefCell<Progress>> {
+    const TYPE: &'static str = "progress";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::Progress(progress) => Some(progress),
+            _ => None,
+        }
+    }
+}
 
+impl FromArg for Rc<Table> {
+    const TYPE: &'static str = "table";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::Table(table) => Some(table),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for Rc<dyn Function> {
+    const TYPE: &'static str = "function";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::Function(function) => Some(function),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for WeakRef {
+    const TYPE: &'static str = "weak";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        match value {
+            Value::Weak(weak) => Some(weak),
+            _ => None,
+        }
+    }
+}
+
+impl FromArg for Iter {
+    const TYPE: &'static str = "iterable";
+
+    fn from_arg(value: Value) -> Option<Self> {
+        iterate(value).ok()
+    }
+}
+
+pub(crate) fn extract<T: FromArg>(
+    name: &str,
+    index: usize,
+    value: Value,
+) -> Result<T, RuntimeError> {
+    let found = value.type_name();
+    T::from_arg(value).ok_or_else(|| {
+        RuntimeError::Report(
+            ArgumentTypeError {
+                name: name.to_string(),
+                position: index + 1,
+                expected: T::TYPE,
+                found,
+            }
+            .into(),
+        )
+    })
+}
+
+// The builtins whose results depend on their arguments only, and the ones
+// that only print; every other builtin may change something.
+const PURE: [&str; 82] = [
+    "adler32",
+    "agg",
+    "approx",
+    "as_ms",
+    "as_ns",
+    "as_s",
+    "bit_and",
+    "bit_xor",
+    "bold",
+    "bytes",
+    "bytes_to_int",
+    "cfg",
+    "checked_add",
+    "checked_div",
+    "checked_mul",
+    "checked_sub",
+    "color",
+    "contains",
+    "crc32",
+    "date",
+    "decode",
+    "diff",
+    "difference",
+    "duration_ms",
+    "duration_ns",
+    "duration_s",
+    "format_currency",
+    "format_date",
+    "format_number",
+    "format_percent",
+    "from_base64",
+    "from_hex",
+    "group_by",
+    "gzip_compress",
+    "gzip_decompress",
+    "i16",
+    "i32",
+    "i64",
+    "i8",
+    "in_zone",
+    "int_to_bytes",
+    "integer",
+    "intersection",
+    "keys",
+    "len",
+    "msgpack_decode",
+    "msgpack_encode",
+    "pack",
+    "pack_size",
+    "query",
+    "query_string",
+    "rational",
+    "repr",
+    "rows",
+    "saturating_add",
+    "saturating_mul",
+    "saturating_sub",
+    "semver_cmp",
+    "semver_parse",
+    "shl",
+    "shr",
+    "sort_by",
+    "sqrt",
+    "style",
+    "table",
+    "template",
+    "to_base64",
+    "to_csv",
+    "to_hex",
+    "u16",
+    "u32",
+    "u64",
+    "u8",
+    "union",
+    "unpack",
+    "url_decode",
+    "url_encode",
+    "url_parse",
+    "values",
+    "wrapping_add",
+    "wrapping_mul",
+    "wrapping_sub",
+];
+const OUTPUT: [&str; 4] = ["plot", "print", "print_diff", "println"];
+
+pub(crate) fn purity(name: &str) -> Purity {
+    match name {
+        _ if PURE.contains(&name) => Purity::Pure,
+        _ if OUTPUT.contains(&name) => Purity::Output,
+        _ => Purity::Impure,
+    }
+}
+
+// `builtin!(Name, "name", (x: Float, y: Integer), { ... })` binds each
+// parameter to its argument after checking its type;
+// `builtin!(Name, "name", Arity::AtLeast(1), ctx, args, { ... })` gets the
+// raw arguments. Argument counts are checked against the arity by the caller.
 macro_rules! builtin {
+    ($name:ident, $fn_name:expr, ($($param:ident : $ty:ty),* $(,)?), $body:expr) => {
+        builtin!($name, $fn_name, _ctx, ($($param: $ty),*), $body);
+    };
+    ($name:ident, $fn_name:expr, $ctx:ident, ($($param:ident : $ty:ty),* $(,)?), $body:expr) => {
+        builtin!(
+            $name,
+            $fn_name,
+            crate::function::Arity::Exact(<[&str]>::len(&[$(stringify!($param)),*])),
+            $ctx,
+            args,
+            {
+                #[allow(unused_mut, unused_variables)]
+                let mut args = args.into_iter().chain(std::iter::repeat(crate::value::Value::Nil)).enumerate();
+                $(
+                    let (index, value) = args.next().unwrap();
+                    let $param: $ty = crate::builtin::extract($fn_name, index, value)?;
+                )*
+                $body
+            }
+        );
+    };
     ($name:ident, $fn_name:expr, $arity:expr, $ctx:ident, $args:ident, $body:expr) => {
         #[derive(Debug)]
         pub struct $name;
@@ -16,15 +456,23 @@ macro_rules! builtin {
         impl crate::function::Function for $name {
             fn run(
                 &self,
-                $ctx: &std::rc::Rc<std::cell::RefCell<crate::context::Ctx>>,
+                $ctx: &crate::sync::Rc<crate::sync::RefCell<crate::context::Ctx>>,
                 $args: Vec<crate::value::Value>,
             ) -> Result<crate::value::Value, crate::interpreter::RuntimeError> {
                 $body
             }
 
-            fn arity(&self) -> usize {
+            fn arity(&self) -> crate::function::Arity {
                 $arity
             }
+
+            fn purity(&self) -> crate::function::Purity {
+                crate::builtin::purity($fn_name)
+            }
+
+            fn builtin(&self) -> Option<&'static str> {
+                Some($fn_name)
+            }
         }
 
         impl std::fmt::Display for $name {
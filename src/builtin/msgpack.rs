@@ -0,0 +1,23 @@
+use super::builtin;
+use crate::{interpreter::RuntimeError, msgpack, report::InvalidArgument, sync::Rc, value::Value};
+
+fn invalid(name: &str, message: String) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message,
+        }
+        .into(),
+    )
+}
+
+builtin!(MsgpackEncodeBuiltin, "msgpack_encode", (value: Value), {
+    match msgpack::encode(&value) {
+        Ok(bytes) => Ok(bytes.into()),
+        Err(message) => Err(invalid("msgpack_encode", message)),
+    }
+});
+
+builtin!(MsgpackDecodeBuiltin, "msgpack_decode", (bytes: Rc<[u8]>), {
+    msgpack::decode(&bytes).map_err(|message| invalid("msgpack_decode", message))
+});
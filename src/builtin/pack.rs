@@ -0,0 +1,241 @@
+use rug::{Float, Integer};
+
+use super::{builtin, extract};
+use crate::{
+    function::Arity,
+    interpreter::RuntimeError,
+    list::List,
+    report::InvalidArgument,
+    sync::{Rc, RefCell},
+    value::Value,
+};
+
+// Formats work like Python's `struct` module without alignment: an optional
+// byte order (`<` little, `>` or `!` big, `=` native, the default) followed
+// by codes, each optionally preceded by a count:
+//
+//     x pad byte            b B   8-bit integer, signed and unsigned
+//     ? bool                h H  16-bit
+//     s bytes (count=size)  i I l L  32-bit
+//     f 32-bit float        q Q  64-bit
+//     d 64-bit float
+
+fn invalid(name: &str, message: impl Into<String>) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message: message.into(),
+        }
+        .into(),
+    )
+}
+
+struct Format {
+    big_endian: bool,
+    items: Vec<(char, usize)>,
+}
+
+impl Format {
+    fn parse(name: &str, format: &str) -> Result<Self, RuntimeError> {
+        let mut chars = format.chars().filter(|c| !c.is_whitespace()).peekable();
+        let big_endian = match chars.next_if(|c| "<>!=".contains(*c)) {
+            Some('>' | '!') => true,
+            Some('<') => false,
+            _ => cfg!(target_endian = "big"),
+        };
+
+        let mut items = Vec::new();
+        while chars.peek().is_some() {
+            let mut count = None;
+            while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                let digit = digit.to_digit(10).unwrap() as usize;
+                count = count
+                    .unwrap_or(0usize)
+                    .checked_mul(10)
+                    .and_then(|c| c.checked_add(digit));
+                if count.is_none() {
+                    return Err(invalid(name, "count too large"));
+                }
+            }
+
+            match chars.next() {
+                Some(code) if "xbB?hHiIlLqQfds".contains(code) => {
+                    items.push((code, count.unwrap_or(1)))
+                }
+                Some(code) => return Err(invalid(name, format!("unknown format code `{}`", code))),
+                None => return Err(invalid(name, "format ends with a count")),
+            }
+        }
+
+        Ok(Self { big_endian, items })
+    }
+
+    fn width(code: char) -> usize {
+        match code {
+            'x' | 'b' | 'B' | '?' | 's' => 1,
+            'h' | 'H' => 2,
+            'i' | 'I' | 'l' | 'L' | 'f' => 4,
+            _ => 8,
+        }
+    }
+
+    // every value the format packs or unpacks, as (code, size in bytes)
+    fn fields(&self) -> impl Iterator<Item = (char, usize)> + '_ {
+        self.items.iter().flat_map(|&(code, count)| match code {
+            's' => vec![(code, count)],
+            _ => vec![(code, Self::width(code)); count],
+        })
+    }
+
+    fn size(&self) -> usize {
+        self.fields().map(|(_, size)| size).sum()
+    }
+
+    fn order<const N: usize>(&self, mut bytes: [u8; N]) -> [u8; N] {
+        if self.big_endian != cfg!(target_endian = "big") {
+            bytes.reverse();
+        }
+        bytes
+    }
+}
+
+fn pack_integer(
+    format: &Format,
+    (code, size): (char, usize),
+    (index, value): (usize, Value),
+    output: &mut Vec<u8>,
+) -> Result<(), RuntimeError> {
+    let integer: Integer = extract("pack", index, value)?;
+    let signed = code.is_ascii_lowercase();
+    let bits = size as u32 * 8;
+    let (min, max) = match signed {
+        true => (
+            -(Integer::from(1) << (bits - 1)),
+            (Integer::from(1) << (bits - 1)) - 1,
+        ),
+        false => (Integer::new(), (Integer::from(1) << bits) - 1),
+    };
+    if integer < min || integer > max {
+        return Err(invalid(
+            "pack",
+            format!("{} does not fit format code `{}`", integer, code),
+        ));
+    }
+
+    // two's complement of the value, on 64 bits
+    let wrapped = match signed {
+        true => integer.to_i64().unwrap() as u64,
+        false => integer.to_u64().unwrap(),
+    };
+    let bytes = format.order(wrapped.to_ne_bytes());
+    match format.big_endian {
+        true => output.extend_from_slice(&bytes[8 - size..]),
+        false => output.extend_from_slice(&bytes[..size]),
+    }
+    Ok(())
+}
+
+builtin!(PackBuiltin, "pack", Arity::AtLeast(1), _ctx, args, {
+    let mut args = args.into_iter().enumerate();
+    let (_, format) = args.next().unwrap();
+    let format: String = extract("pack", 0, format)?;
+    let format = Format::parse("pack", &format)?;
+
+    let mut output = Vec::with_capacity(format.size());
+    for (code, size) in format.fields() {
+        if code == 'x' {
+            output.push(0);
+            continue;
+        }
+
+        let Some((index, value)) = args.next() else {
+            return Err(invalid("pack", "not enough values for the format"));
+        };
+        match code {
+            '?' => output.push(value.is_truthy() as u8),
+            's' => {
+                let bytes: Rc<[u8]> = extract("pack", index, value)?;
+                // truncated or zero-padded to the given size
+                output.extend(bytes.iter().copied().chain(std::iter::repeat(0)).take(size));
+            }
+            'f' => {
+                let float: Float = extract("pack", index, value)?;
+                output.extend_from_slice(&format.order(float.to_f32().to_ne_bytes()));
+            }
+            'd' => {
+                let float: Float = extract("pack", index, value)?;
+                output.extend_from_slice(&format.order(float.to_f64().to_ne_bytes()));
+            }
+            _ => pack_integer(&format, (code, size), (index, value), &mut output)?,
+        }
+    }
+
+    if args.next().is_some() {
+        return Err(invalid("pack", "too many values for the format"));
+    }
+
+    Ok(output.into())
+});
+
+builtin!(UnpackBuiltin, "unpack", (format: String, bytes: Rc<[u8]>), {
+    let format = Format::parse("unpack", &format)?;
+    if bytes.len() != format.size() {
+        return Err(invalid(
+            "unpack",
+            format!(
+                "the format needs {} bytes, found {}",
+                format.size(),
+                bytes.len()
+            ),
+        ));
+    }
+
+    let mut values = Vec::new();
+    let mut rest = &bytes[..];
+    for (code, size) in format.fields() {
+        let (field, tail) = rest.split_at(size);
+        rest = tail;
+
+        let value = match code {
+            'x' => continue,
+            '?' => (field[0] != 0).into(),
+            's' => field.to_vec().into(),
+            'f' => {
+                let float = f32::from_ne_bytes(format.order(field.try_into().unwrap()));
+                Float::with_val(24, float).into()
+            }
+            'd' => {
+                let float = f64::from_ne_bytes(format.order(field.try_into().unwrap()));
+                Float::with_val(53, float).into()
+            }
+            _ => {
+                let mut wide = [0u8; 8];
+                let negative = code.is_ascii_lowercase()
+                    && match format.big_endian {
+                        true => field[0] & 0x80 != 0,
+                        false => field[size - 1] & 0x80 != 0,
+                    };
+                if negative {
+                    wide = [0xff; 8];
+                }
+                match format.big_endian {
+                    true => wide[8 - size..].copy_from_slice(field),
+                    false => wide[..size].copy_from_slice(field),
+                }
+                let wrapped = u64::from_ne_bytes(format.order(wide));
+                match code.is_ascii_lowercase() {
+                    true => Integer::from(wrapped as i64).into(),
+                    false => Integer::from(wrapped).into(),
+                }
+            }
+        };
+        values.push(value);
+    }
+
+    Ok(Value::List(Rc::new(RefCell::new(List(values)))))
+});
+
+builtin!(PackSizeBuiltin, "pack_size", (format: String), {
+    let format = Format::parse("pack_size", &format)?;
+    Ok(Integer::from(format.size()).into())
+});
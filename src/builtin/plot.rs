@@ -0,0 +1,200 @@
+use std::fs;
+
+use super::{builtin, extract, io_error};
+use crate::{
+    dict::Dict,
+    function::Arity,
+    interpreter::RuntimeError,
+    plot::{self, Options, Series},
+    report::InvalidArgument,
+    sync::{Rc, RefCell},
+    token::Literal,
+    value::{Value, ValueKey},
+};
+
+fn invalid(name: &str, message: impl Into<String>) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message: message.into(),
+        }
+        .into(),
+    )
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Literal(Literal::Integer(i)) => Some(i.to_f64()),
+        Value::Literal(Literal::Float(f)) => Some(f.to_f64()),
+        Value::Fixed(fixed) => Some(fixed.value as f64),
+        Value::Exact(exact) => Some(exact.approx(53).to_f64()),
+        _ => None,
+    }
+}
+
+// the items of a list of numbers, called `what` in errors
+fn numbers(name: &str, what: &str, list: &Value) -> Result<Vec<f64>, RuntimeError> {
+    let Value::List(list) = list else {
+        return Err(invalid(
+            name,
+            format!("{} must be a list, found {}", what, list.type_name()),
+        ));
+    };
+    list.borrow()
+        .0
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            number(item).ok_or_else(|| {
+                let found = item.type_name();
+                invalid(
+                    name,
+                    format!("item {} of {} is a {}, not a number", index, what, found),
+                )
+            })
+        })
+        .collect()
+}
+
+fn points(name: &str, xs: &[f64], ys: &[f64]) -> Result<Vec<(f64, f64)>, RuntimeError> {
+    match xs.len() == ys.len() {
+        true => Ok(xs.iter().copied().zip(ys.iter().copied()).collect()),
+        false => Err(invalid(
+            name,
+            format!("there are {} xs and {} ys", xs.len(), ys.len()),
+        )),
+    }
+}
+
+// {"width": 60, "height": 15, "title": "..."}; sizes are in characters for
+// `plot` and in pixels for `plot_svg`
+fn options(
+    name: &str,
+    index: usize,
+    options: Option<Value>,
+    size: (usize, usize),
+) -> Result<Options, RuntimeError> {
+    let mut parsed = Options {
+        width: size.0,
+        height: size.1,
+        title: None,
+    };
+    let Some(options) = options else {
+        return Ok(parsed);
+    };
+    let options: Rc<RefCell<Dict>> = extract(name, index, options)?;
+    for (key, value) in options.borrow().0.iter() {
+        match (&key.0, value) {
+            (Value::Literal(Literal::String(key)), Value::Literal(Literal::Integer(size)))
+                if matches!(key.as_ref(), "width" | "height") =>
+            {
+                let size = size
+                    .to_usize()
+                    .filter(|size| (1..=10_000).contains(size))
+                    .ok_or_else(|| {
+                        invalid(name, format!("`{}` must be between 1 and 10000", key))
+                    })?;
+                match key.as_ref() {
+                    "width" => parsed.width = size,
+                    _ => parsed.height = size,
+                }
+            }
+            (Value::Literal(Literal::String(key)), Value::Literal(Literal::String(title)))
+                if key.as_ref() == "title" =>
+            {
+                parsed.title = Some(title.to_string());
+            }
+            (key, _) => return Err(invalid(name, format!("unknown or invalid option {}", key))),
+        }
+    }
+    Ok(parsed)
+}
+
+// plot(xs, ys) or plot(xs, ys, options): prints the points joined by lines
+builtin!(PlotBuiltin, "plot", Arity::Range(2, 3), ctx, args, {
+    let mut args = args.into_iter();
+    let xs = numbers("plot", "xs", &args.next().unwrap())?;
+    let ys = numbers("plot", "ys", &args.next().unwrap())?;
+    let points = points("plot", &xs, &ys)?;
+    if points.is_empty() {
+        return Err(invalid("plot", "there is nothing to plot"));
+    }
+    let options = options("plot", 2, args.next(), (plot::WIDTH, plot::HEIGHT))?;
+
+    let mut chart = String::new();
+    if let Some(title) = &options.title {
+        chart.push_str(title);
+        chart.push('\n');
+    }
+    chart.push_str(&plot::braille(&points, options.width, options.height));
+    ctx.borrow().runtime().print(&chart);
+    Ok(Value::Nil)
+});
+
+// one series, {"y": [...]} with an "x" list as long, 0, 1, 2... when it's
+// missing, and a "name" for the legend
+fn series(name: &str, value: &Value) -> Result<Series, RuntimeError> {
+    let Value::Dict(dict) = value else {
+        return Err(invalid(
+            name,
+            format!("a series must be a dict, found {}", value.type_name()),
+        ));
+    };
+    let dict = dict.borrow();
+    let get = |key: &str| dict.0.get(&ValueKey(Value::from(key)));
+    let ys = match get("y") {
+        Some(ys) => numbers(name, "y", ys)?,
+        None => return Err(invalid(name, "a series needs a \"y\" list")),
+    };
+    let xs = match get("x") {
+        Some(xs) => numbers(name, "x", xs)?,
+        None => (0..ys.len()).map(|x| x as f64).collect(),
+    };
+    let label = match get("name") {
+        Some(Value::Literal(Literal::String(label))) => Some(label.to_string()),
+        Some(other) => Some(other.to_string()),
+        None => None,
+    };
+    Ok(Series {
+        name: label,
+        points: points(name, &xs, &ys)?,
+    })
+}
+
+// every series of a list of them, or the one a dict is
+fn all_series(name: &str, value: Value) -> Result<Vec<Series>, RuntimeError> {
+    let series = match value {
+        Value::List(list) => list
+            .borrow()
+            .0
+            .iter()
+            .map(|value| series(name, value))
+            .collect::<Result<Vec<_>, _>>()?,
+        value => vec![series(name, &value)?],
+    };
+    match series.iter().all(|series| series.points.is_empty()) {
+        true => Err(invalid(name, "there is nothing to plot")),
+        false => Ok(series),
+    }
+}
+
+// plot_svg(series, path) or plot_svg(series, path, options): writes a chart
+// of one series, or of a list of them, to `path`
+builtin!(
+    PlotSvgBuiltin,
+    "plot_svg",
+    Arity::Range(2, 3),
+    _ctx,
+    args,
+    {
+        let mut args = args.into_iter();
+        let series = all_series("plot_svg", args.next().unwrap())?;
+        let path: String = extract("plot_svg", 1, args.next().unwrap())?;
+        let size = (plot::SVG_WIDTH, plot::SVG_HEIGHT);
+        let options = options("plot_svg", 2, args.next(), size)?;
+        match fs::write(&path, plot::svg(&series, &options)) {
+            Ok(()) => Ok(Value::Nil),
+            Err(err) => Err(io_error(&path, err)),
+        }
+    }
+);
@@ -0,0 +1,91 @@
+use rug::Integer;
+
+use super::{builtin, extract, io_error};
+use crate::{
+    function::Arity,
+    interpreter::RuntimeError,
+    list::List,
+    process::Process,
+    report::ArgumentTypeError,
+    sync::{Rc, RefCell},
+    token::Literal,
+    value::Value,
+};
+
+// spawn(program) or spawn(program, args); arguments that aren't strings are
+// passed as they print
+builtin!(SpawnBuiltin, "spawn", Arity::Range(1, 2), _ctx, args, {
+    let mut args = args.into_iter();
+    let program: String = extract("spawn", 0, args.next().unwrap())?;
+    let arguments = match args.next() {
+        Some(list) => {
+            let list: Rc<RefCell<List>> = extract("spawn", 1, list)?;
+            let list = list.borrow();
+            list.0.iter().map(|arg| arg.to_string()).collect()
+        }
+        None => Vec::new(),
+    };
+
+    match Process::spawn(&program, &arguments) {
+        Ok(process) => Ok(Value::Process(Rc::new(RefCell::new(process)))),
+        Err(err) => Err(io_error(&program, err)),
+    }
+});
+
+// writes a string or bytes, as is: add the "\n" yourself
+builtin!(WriteStdinBuiltin, "write_stdin", (process: Rc<RefCell<Process>>, data: Value), {
+    let data = match data {
+        Value::Literal(Literal::String(string)) => string.as_bytes().to_vec(),
+        Value::Bytes(bytes) => bytes.to_vec(),
+        other => {
+            return Err(RuntimeError::Report(
+                ArgumentTypeError {
+                    name: "write_stdin".to_string(),
+                    position: 2,
+                    expected: "string or bytes",
+                    found: other.type_name(),
+                }
+                .into(),
+            ))
+        }
+    };
+
+    let mut process = process.borrow_mut();
+    match process.write_stdin(&data) {
+        Ok(()) => Ok(Value::Nil),
+        Err(err) => Err(io_error(process.program(), err)),
+    }
+});
+
+builtin!(CloseStdinBuiltin, "close_stdin", (process: Rc<RefCell<Process>>), {
+    process.borrow_mut().close_stdin();
+    Ok(Value::Nil)
+});
+
+// nil once the process has closed its output
+builtin!(ReadStdoutLineBuiltin, "read_stdout_line", (process: Rc<RefCell<Process>>), {
+    let mut process = process.borrow_mut();
+    match process.read_stdout_line() {
+        Ok(Some(line)) => Ok(line.into()),
+        Ok(None) => Ok(Value::Nil),
+        Err(err) => Err(io_error(process.program(), err)),
+    }
+});
+
+// the exit code, or nil when the process was killed by a signal
+builtin!(WaitBuiltin, "wait", (process: Rc<RefCell<Process>>), {
+    let mut process = process.borrow_mut();
+    match process.wait() {
+        Ok(Some(code)) => Ok(Integer::from(code).into()),
+        Ok(None) => Ok(Value::Nil),
+        Err(err) => Err(io_error(process.program(), err)),
+    }
+});
+
+builtin!(KillBuiltin, "kill", (process: Rc<RefCell<Process>>), {
+    let mut process = process.borrow_mut();
+    match process.kill() {
+        Ok(()) => Ok(Value::Nil),
+        Err(err) => Err(io_error(process.program(), err)),
+    }
+});
@@ -0,0 +1,41 @@
+use rug::Integer;
+
+use super::{builtin, extract};
+use crate::{
+    function::Arity,
+    interpreter::RuntimeError,
+    progress::Progress,
+    report::InvalidArgument,
+    sync::{Rc, RefCell},
+    value::Value,
+};
+
+builtin!(ProgressBuiltin, "progress", (total: Integer), {
+    match total.to_u64() {
+        Some(total) => Ok(Value::Progress(Rc::new(RefCell::new(Progress::new(total))))),
+        None => Err(RuntimeError::Report(
+            InvalidArgument {
+                name: "progress".to_string(),
+                message: format!("total {} is negative or too large", total),
+            }
+            .into(),
+        )),
+    }
+});
+
+// tick(bar) advances by one step, tick(bar, n) by n
+builtin!(TickBuiltin, "tick", Arity::Range(1, 2), _ctx, args, {
+    let mut args = args.into_iter();
+    let progress: Rc<RefCell<Progress>> = extract("tick", 0, args.next().unwrap())?;
+    let steps = match args.next() {
+        Some(steps) => extract::<Integer>("tick", 1, steps)?.to_u64().unwrap_or(0),
+        None => 1,
+    };
+    progress.borrow_mut().tick(steps);
+    Ok(Value::Nil)
+});
+
+builtin!(FinishBuiltin, "finish", (progress: Rc<RefCell<Progress>>), {
+    progress.borrow_mut().finish();
+    Ok(Value::Nil)
+});
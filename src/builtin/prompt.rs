@@ -0,0 +1,139 @@
+use std::io::{BufRead, IsTerminal, Write};
+
+use rustyline::{error::ReadlineError, DefaultEditor};
+
+use super::{builtin, extract, select_columns};
+use crate::{
+    interpreter::RuntimeError,
+    list::List,
+    report::{InvalidArgument, IoError},
+    sync::{Rc, RefCell},
+    value::Value,
+};
+
+fn prompt_error(name: &str, err: impl std::fmt::Display) -> RuntimeError {
+    RuntimeError::Report(
+        IoError {
+            message: format!("{}: {}", name, err),
+        }
+        .into(),
+    )
+}
+
+// one line of input, or None at the end of it
+fn read_line(name: &str, prompt: &str) -> Result<Option<String>, RuntimeError> {
+    let mut editor = DefaultEditor::new().map_err(|err| prompt_error(name, err))?;
+    match editor.readline(prompt) {
+        Ok(line) => Ok(Some(line.trim().to_string())),
+        Err(ReadlineError::Eof) => Ok(None),
+        Err(err) => Err(prompt_error(name, err)),
+    }
+}
+
+// asks until the answer is yes or no; the end of input counts as no
+builtin!(ConfirmBuiltin, "confirm", (message: String), {
+    let prompt = format!("{} [y/n] ", message);
+    loop {
+        let answer = read_line("confirm", &prompt)?.map(|line| line.to_lowercase());
+        match answer.as_deref() {
+            Some("y" | "yes") => return Ok(Value::True),
+            Some("n" | "no") | None => return Ok(Value::False),
+            Some(_) => continue,
+        }
+    }
+});
+
+// lists the options numbered from 1 and returns the chosen one, or nil at
+// the end of input; select(table, columns) picks columns of a table instead
+builtin!(SelectBuiltin, "select", (message: Value, options: Value), {
+    if let Value::Table(table) = &message {
+        return select_columns(table, options);
+    }
+    let message: String = extract("select", 0, message)?;
+    let options: Rc<RefCell<List>> = extract("select", 1, options)?;
+    let options = options.borrow().0.clone();
+    if options.is_empty() {
+        return Err(RuntimeError::Report(
+            InvalidArgument {
+                name: "select".to_string(),
+                message: "there are no options to select from".to_string(),
+            }
+            .into(),
+        ));
+    }
+
+    println!("{}", message);
+    for (index, option) in options.iter().enumerate() {
+        println!("  {}) {}", index + 1, option);
+    }
+    let prompt = format!("[1-{}] ", options.len());
+    loop {
+        let Some(answer) = read_line("select", &prompt)? else {
+            return Ok(Value::Nil);
+        };
+        match answer.parse::<usize>() {
+            Ok(choice) if (1..=options.len()).contains(&choice) => {
+                return Ok(options[choice - 1].clone())
+            }
+            _ => continue,
+        }
+    }
+});
+
+// Turns terminal echo off until dropped
+#[cfg(unix)]
+struct NoEcho(libc::termios);
+
+#[cfg(unix)]
+impl NoEcho {
+    fn new() -> Option<Self> {
+        // SAFETY: termios is plain data, filled in by tcgetattr
+        unsafe {
+            let mut termios = std::mem::zeroed::<libc::termios>();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut termios) != 0 {
+                return None;
+            }
+            let saved = termios;
+            termios.c_lflag &= !libc::ECHO;
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios);
+            Some(Self(saved))
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for NoEcho {
+    fn drop(&mut self) {
+        // SAFETY: restores the settings read in `new`
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.0);
+        }
+    }
+}
+
+// reads a line without echoing it; nil at the end of input
+builtin!(PasswordBuiltin, "password", (message: String), {
+    print!("{} ", message);
+    std::io::stdout()
+        .flush()
+        .map_err(|err| prompt_error("password", err))?;
+
+    let stdin = std::io::stdin();
+    let terminal = stdin.is_terminal();
+    #[cfg(unix)]
+    let no_echo = terminal.then(NoEcho::new);
+
+    let mut line = String::new();
+    let read = stdin.lock().read_line(&mut line);
+
+    #[cfg(unix)]
+    drop(no_echo);
+    if terminal {
+        println!();
+    }
+
+    match read.map_err(|err| prompt_error("password", err))? {
+        0 => Ok(Value::Nil),
+        _ => Ok(line.trim_end_matches(['\n', '\r']).to_string().into()),
+    }
+});
@@ -0,0 +1,19 @@
+use super::builtin;
+use crate::{interpreter::RuntimeError, report::InvalidArgument, repr, value::Value};
+
+fn invalid(name: &str, message: String) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message,
+        }
+        .into(),
+    )
+}
+
+builtin!(ReprBuiltin, "repr", (value: Value), {
+    match repr::repr(&value) {
+        Ok(repr) => Ok(repr.into()),
+        Err(message) => Err(invalid("repr", message)),
+    }
+});
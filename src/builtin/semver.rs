@@ -0,0 +1,59 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use rug::Integer;
+
+use super::builtin;
+use crate::{
+    dict::Dict,
+    interpreter::RuntimeError,
+    report::InvalidArgument,
+    semver::{Identifier, Version},
+    sync::{Rc, RefCell},
+    value::{Value, ValueKey},
+};
+
+fn parse(name: &str, text: &str) -> Result<Version, RuntimeError> {
+    Version::parse(text).map_err(|message| {
+        RuntimeError::Report(
+            InvalidArgument {
+                name: name.to_string(),
+                message: format!("{}: {}", text, message),
+            }
+            .into(),
+        )
+    })
+}
+
+// {"major", "minor", "patch", "pre", "build"}, with the pre-release and the
+// build as lists of their dot-separated parts, numbers where they're digits
+builtin!(SemverParseBuiltin, "semver_parse", (text: String), {
+    let version = parse("semver_parse", &text)?;
+    let pre = version.pre.into_iter().map(|part| match part {
+        Identifier::Numeric(number) => Value::from(number),
+        Identifier::Alphanumeric(word) => Value::from(word),
+    });
+    let build = version.build.into_iter().map(Value::from);
+    let fields = [
+        ("major", Value::from(version.major)),
+        ("minor", version.minor.into()),
+        ("patch", version.patch.into()),
+        ("pre", pre.collect::<Vec<_>>().into()),
+        ("build", build.collect::<Vec<_>>().into()),
+    ];
+    let dict: HashMap<_, _> = fields
+        .into_iter()
+        .map(|(key, value)| (ValueKey(key.into()), value))
+        .collect();
+    Ok(Value::Dict(Rc::new(RefCell::new(Dict(dict)))))
+});
+
+// -1, 0 or 1 as `a` comes before, with or after `b`
+builtin!(SemverCmpBuiltin, "semver_cmp", (a: String, b: String), {
+    let ordering = parse("semver_cmp", &a)?.precedence(&parse("semver_cmp", &b)?);
+    let sign = match ordering {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    };
+    Ok(Integer::from(sign).into())
+});
@@ -1,18 +1,25 @@
+use rug::Integer;
+
 use super::builtin;
-use crate::{token::Literal, value::Value};
+use crate::{interpreter::RuntimeError, report::ArgumentTypeError, token::Literal, value::Value};
 
-builtin!(LenBuiltin, "len", 1, _ctx, args, {
-    if let Some(item) = args.first() {
-        match item {
-            Value::List(value) => Ok(Value::Literal(Literal::Integer(
-                value.borrow().0.len().into(),
-            ))),
-            Value::Dict(value) => Ok(Value::Literal(Literal::Integer(
-                value.borrow().0.len().into(),
-            ))),
-            _ => Ok(Value::Literal(Literal::Integer(0.into()))),
-        }
-    } else {
-        Ok(Value::Nil)
+// strings are counted in characters, as they are indexed
+builtin!(LenBuiltin, "len", (item: Value), {
+    match item {
+        Value::List(value) => Ok(Integer::from(value.borrow().0.len()).into()),
+        Value::Dict(value) => Ok(Integer::from(value.borrow().0.len()).into()),
+        Value::Set(value) => Ok(Integer::from(value.0.len()).into()),
+        Value::Bytes(value) => Ok(Integer::from(value.len()).into()),
+        Value::Table(value) => Ok(Integer::from(value.len()).into()),
+        Value::Literal(Literal::String(value)) => Ok(Integer::from(value.chars().count()).into()),
+        other => Err(RuntimeError::Report(
+            ArgumentTypeError {
+                name: "len".to_string(),
+                position: 1,
+                expected: "list, dict, set, string, bytes or table",
+                found: other.type_name(),
+            }
+            .into(),
+        )),
     }
 });
@@ -0,0 +1,44 @@
+use super::builtin;
+use crate::{
+    interpreter::RuntimeError,
+    iterator::{collect, Iter},
+    report::InvalidArgument,
+    set::Set,
+    sync::Rc,
+    value::Value,
+};
+
+// the distinct items of a list, iterator or anything else iterable
+builtin!(SetBuiltin, "set", ctx, (items: Iter), {
+    let items = collect(&items, ctx)?;
+    match Set::new(items.0) {
+        Ok(set) => Ok(Value::Set(Rc::new(set))),
+        Err(_) => Err(RuntimeError::Report(
+            InvalidArgument {
+                name: "set".to_string(),
+                message: "its items must be hashable, as dict keys are".to_string(),
+            }
+            .into(),
+        )),
+    }
+});
+
+builtin!(ContainsBuiltin, "contains", (set: Rc<Set>, item: Value), {
+    Ok(set.contains(item).into())
+});
+
+builtin!(UnionBuiltin, "union", (a: Rc<Set>, b: Rc<Set>), {
+    let items = a.0.union(&b.0).cloned().collect();
+    Ok(Value::Set(Rc::new(Set(items))))
+});
+
+builtin!(IntersectionBuiltin, "intersection", (a: Rc<Set>, b: Rc<Set>), {
+    let items = a.0.intersection(&b.0).cloned().collect();
+    Ok(Value::Set(Rc::new(Set(items))))
+});
+
+// the items of `a` that aren't in `b`
+builtin!(DifferenceBuiltin, "difference", (a: Rc<Set>, b: Rc<Set>), {
+    let items = a.0.difference(&b.0).cloned().collect();
+    Ok(Value::Set(Rc::new(Set(items))))
+});
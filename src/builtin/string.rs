@@ -0,0 +1,92 @@
+use miette::{NamedSource, Report};
+
+use super::builtin;
+use crate::{
+    dict::Dict,
+    interpreter::RuntimeError,
+    report::{TemplateKeyError, TemplateSyntaxError},
+    sync::{Rc, RefCell},
+    value::{Value, ValueKey},
+};
+
+// Replaces every `{key}` in `template` with `dict[key]`; `{{` and `}}` stand
+// for literal braces. Errors point into the template.
+fn fill(template: &str, dict: &Dict) -> Result<String, RuntimeError> {
+    let error = |report: Report| {
+        let source = NamedSource::new("template", template.to_string());
+        RuntimeError::Report(report.with_source_code(source))
+    };
+    let syntax_error = |problem, start: usize, end: usize, help: &str| {
+        error(
+            TemplateSyntaxError {
+                problem,
+                span: (start..end).into(),
+                help: help.to_string(),
+            }
+            .into(),
+        )
+    };
+
+    let mut output = String::with_capacity(template.len());
+    let mut chars = template.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '{' if chars.next_if(|&(_, c)| c == '{').is_some() => output.push('{'),
+            '}' if chars.next_if(|&(_, c)| c == '}').is_some() => output.push('}'),
+            '}' => {
+                return Err(syntax_error(
+                    "a `}` without a `{`",
+                    offset,
+                    offset + 1,
+                    "write `}}` for a literal `}`",
+                ))
+            }
+            '{' => {
+                let mut key = String::new();
+                let end =
+                    loop {
+                        match chars.next() {
+                            Some((end, '}')) => break end + 1,
+                            Some((nested, '{')) => {
+                                return Err(syntax_error(
+                                    "a nested placeholder",
+                                    offset,
+                                    nested + 1,
+                                    "placeholders can't be nested",
+                                ))
+                            }
+                            Some((_, c)) => key.push(c),
+                            None => return Err(syntax_error(
+                                "an unclosed placeholder",
+                                offset,
+                                template.len(),
+                                "close the placeholder with `}`, or write `{{` for a literal `{`",
+                            )),
+                        }
+                    };
+
+                let key = key.trim();
+                match dict.0.get(&ValueKey(Value::from(key))) {
+                    Some(value) => output.push_str(&value.to_string()),
+                    None => {
+                        return Err(error(
+                            TemplateKeyError {
+                                key: key.to_string(),
+                                span: (offset..end).into(),
+                            }
+                            .into(),
+                        ))
+                    }
+                }
+            }
+            c => output.push(c),
+        }
+    }
+
+    Ok(output)
+}
+
+builtin!(TemplateBuiltin, "template", (template: String, dict: Rc<RefCell<Dict>>), {
+    let output = fill(&template, &dict.borrow())?;
+    Ok(output.into())
+});
@@ -0,0 +1,108 @@
+use std::{io::IsTerminal, sync::OnceLock};
+
+use super::builtin;
+use crate::{
+    context::Ctx,
+    dict::Dict,
+    interpreter::RuntimeError,
+    report::InvalidArgument,
+    runtime::ColorChoice,
+    sync::{Rc, RefCell},
+    token::Literal,
+    value::Value,
+};
+
+const COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+// Styling is skipped when stdout isn't a terminal or NO_COLOR is set
+// (https://no-color.org), so styled text can be piped as is, unless
+// `--color` says otherwise.
+fn enabled(ctx: &Rc<RefCell<Ctx>>) -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    match ctx.borrow().runtime().config.color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => *ENABLED.get_or_init(|| {
+            let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+            std::io::stdout().is_terminal() && !no_color
+        }),
+    }
+}
+
+fn invalid(name: &str, message: impl Into<String>) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message: message.into(),
+        }
+        .into(),
+    )
+}
+
+// the SGR code of a foreground color, "red" or "bright_red"; background
+// codes are 10 higher
+fn color_code(name: &str, color: &str) -> Result<u32, RuntimeError> {
+    let (base, color) = match color.strip_prefix("bright_") {
+        Some(color) => (90, color),
+        None => (30, color),
+    };
+    match COLORS.iter().position(|&c| c == color) {
+        Some(index) => Ok(base + index as u32),
+        None => Err(invalid(
+            name,
+            format!(
+                "unknown color `{}`, expected one of {} (optionally `bright_`)",
+                color,
+                COLORS.join(", ")
+            ),
+        )),
+    }
+}
+
+pub(super) fn styled(ctx: &Rc<RefCell<Ctx>>, text: Value, codes: &[u32]) -> Value {
+    let text = text.to_string();
+    if !enabled(ctx) || codes.is_empty() {
+        return text.into();
+    }
+    let codes = codes.iter().map(u32::to_string).collect::<Vec<_>>();
+    format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text).into()
+}
+
+builtin!(ColorBuiltin, "color", ctx, (text: Value, color: String), {
+    Ok(styled(ctx, text, &[color_code("color", &color)?]))
+});
+
+builtin!(BoldBuiltin, "bold", ctx, (text: Value), {
+    Ok(styled(ctx, text, &[1]))
+});
+
+// style(text, {"color": "red", "background": "white", "bold": true,
+// "dim": true, "italic": true, "underline": true})
+builtin!(StyleBuiltin, "style", ctx, (text: Value, options: Rc<RefCell<Dict>>), {
+    let mut codes = Vec::new();
+    let options = options.borrow();
+    for (key, value) in options.0.iter() {
+        let option = match &key.0 {
+            Value::Literal(Literal::String(option)) => option.as_ref(),
+            other => return Err(invalid("style", format!("unknown option {}", other))),
+        };
+        let color = |value: &Value| match value {
+            Value::Literal(Literal::String(color)) => color_code("style", color),
+            other => Err(invalid("style", format!("{} is not a color", other))),
+        };
+        match option {
+            "color" => codes.push(color(value)?),
+            "background" => codes.push(color(value)? + 10),
+            "bold" | "dim" | "italic" | "underline" if !value.is_truthy() => {}
+            "bold" => codes.push(1),
+            "dim" => codes.push(2),
+            "italic" => codes.push(3),
+            "underline" => codes.push(4),
+            other => return Err(invalid("style", format!("unknown option `{}`", other))),
+        }
+    }
+    codes.sort_unstable();
+    Ok(styled(ctx, text, &codes))
+});
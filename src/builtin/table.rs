@@ -0,0 +1,146 @@
+use super::{builtin, extract};
+use crate::{
+    dict::Dict,
+    function::{Arity, Function},
+    interpreter::RuntimeError,
+    list::List,
+    report::InvalidArgument,
+    sync::{Rc, RefCell},
+    table::{Aggregate, Table},
+    token::Literal,
+    value::Value,
+};
+
+fn invalid(name: &str, message: impl Into<String>) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message: message.into(),
+        }
+        .into(),
+    )
+}
+
+fn table(name: &str, table: Result<Table, String>) -> Result<Value, RuntimeError> {
+    match table {
+        Ok(table) => Ok(Value::Table(Rc::new(table))),
+        Err(message) => Err(invalid(name, message)),
+    }
+}
+
+// a column name, or a list of them
+fn names(name: &str, value: Value) -> Result<Vec<String>, RuntimeError> {
+    let items = match value {
+        Value::List(list) => list.borrow().0.clone(),
+        value => vec![value],
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            Value::Literal(Literal::String(column)) => Ok(column.to_string()),
+            other => Err(invalid(
+                name,
+                format!(
+                    "a column name must be a string, found {}",
+                    other.type_name()
+                ),
+            )),
+        })
+        .collect()
+}
+
+// table([{"name": "apple", "price": 3}, ...]): a column for every key of
+// the rows, nil where a row doesn't have it
+builtin!(TableBuiltin, "table", (rows: Rc<RefCell<List>>), {
+    let table = Table::from_rows(&rows.borrow().0);
+    self::table("table", table)
+});
+
+// `select(table, columns)`, which `select` hands tables to
+pub(crate) fn select_columns(table: &Table, columns: Value) -> Result<Value, RuntimeError> {
+    let columns = names("select", columns)?;
+    self::table("select", table.select(&columns))
+}
+
+// where(table, predicate) keeps the rows, as dicts, that the predicate is
+// truthy for; where(table, column, value) the ones whose column is value
+builtin!(WhereBuiltin, "where", Arity::Range(2, 3), ctx, args, {
+    let mut args = args.into_iter();
+    let source: Rc<Table> = extract("where", 0, args.next().unwrap())?;
+    let test = args.next().unwrap();
+    if let Some(value) = args.next() {
+        let column: String = extract("where", 1, test)?;
+        return table("where", source.matching(&column, &value));
+    }
+    let predicate: Rc<dyn Function> = extract("where", 1, test)?;
+    let mut rows = Vec::new();
+    for row in 0..source.len() {
+        if predicate.call(ctx, vec![source.row(row)])?.is_truthy() {
+            rows.push(row);
+        }
+    }
+    Ok(Value::Table(Rc::new(source.take(&rows))))
+});
+
+builtin!(GroupByBuiltin, "group_by", (source: Rc<Table>, columns: Value), {
+    let columns = names("group_by", columns)?;
+    table("group_by", source.group_by(&columns))
+});
+
+// agg(table, {"total": ["sum", "price"], "price": "max"}): a row per group
+// with a column per aggregate, taken of the column named next to the
+// operation or of the one with the same name; nils are left out of all
+builtin!(AggBuiltin, "agg", (source: Rc<Table>, spec: Rc<RefCell<Dict>>), {
+    let operation = |name: &Value| match name {
+        Value::Literal(Literal::String(name)) => Aggregate::from_name(name),
+        _ => None,
+    };
+    let mut aggregates = Vec::new();
+    for (key, value) in spec.borrow().0.iter() {
+        let Value::Literal(Literal::String(output)) = &key.0 else {
+            return Err(invalid("agg", format!("a column name must be a string, found {}", key)));
+        };
+        let (aggregate, column) = match value {
+            Value::List(pair) => match pair.borrow().0.as_slice() {
+                [aggregate, Value::Literal(Literal::String(column))] => {
+                    (operation(aggregate), column.to_string())
+                }
+                _ => (None, String::new()),
+            },
+            aggregate => (operation(aggregate), output.to_string()),
+        };
+        let Some(aggregate) = aggregate else {
+            return Err(invalid(
+                "agg",
+                format!(
+                    "`{}` must be an operation or [operation, column], with operations {}",
+                    output,
+                    Aggregate::NAMES.join(", ")
+                ),
+            ));
+        };
+        aggregates.push((output.to_string(), aggregate, column));
+    }
+    aggregates.sort_by(|lhs, rhs| lhs.0.cmp(&rhs.0));
+    table("agg", source.agg(&aggregates))
+});
+
+// sort_by(table, columns) or sort_by(table, columns, descending); nils go
+// last either way
+builtin!(SortByBuiltin, "sort_by", Arity::Range(2, 3), _ctx, args, {
+    let mut args = args.into_iter();
+    let source: Rc<Table> = extract("sort_by", 0, args.next().unwrap())?;
+    let columns = names("sort_by", args.next().unwrap())?;
+    let descending = args.next().is_some_and(|value| value.is_truthy());
+    table("sort_by", source.sort_by(&columns, descending))
+});
+
+builtin!(ToCsvBuiltin, "to_csv", (source: Rc<Table>), {
+    Ok(source.to_csv().into())
+});
+
+// the rows of a table as dicts
+builtin!(RowsBuiltin, "rows", (source: Rc<Table>), {
+    let rows = (0..source.len()).map(|row| source.row(row)).collect::<Vec<_>>();
+    Ok(rows.into())
+});
@@ -1,13 +1,106 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use super::builtin;
-use crate::{token::Literal, value::Value};
+use rug::{Float, Integer};
 
-builtin!(TimeBuiltin, "time", 0, _ctx, _args, {
+use super::{builtin, extract};
+use crate::{
+    datetime::{DateTime, Duration},
+    function::Arity,
+    interpreter::RuntimeError,
+    lexer::DEFAULT_FLOAT_PRECISION,
+    report::InvalidArgument,
+    value::Value,
+};
+
+fn invalid(name: &str, message: impl Into<String>) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message: message.into(),
+        }
+        .into(),
+    )
+}
+
+builtin!(TimeBuiltin, "time", (), {
     let time = SystemTime::now();
     let epoch = time
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards");
 
-    Ok(Value::Literal(Literal::Integer(epoch.as_nanos().into())))
+    Ok(Integer::from(epoch.as_nanos()).into())
+});
+
+fn duration(name: &str, amount: Integer, unit: i128) -> Result<Value, RuntimeError> {
+    amount
+        .to_i128()
+        .and_then(|amount| Duration::from_unit(amount, unit))
+        .map(Value::Duration)
+        .ok_or_else(|| invalid(name, "duration out of range"))
+}
+
+builtin!(DurationNsBuiltin, "duration_ns", (amount: Integer), {
+    duration("duration_ns", amount, 1)
+});
+
+builtin!(DurationMsBuiltin, "duration_ms", (amount: Integer), {
+    duration("duration_ms", amount, 1_000_000)
+});
+
+builtin!(DurationSBuiltin, "duration_s", (amount: Integer), {
+    duration("duration_s", amount, 1_000_000_000)
+});
+
+builtin!(AsNsBuiltin, "as_ns", (duration: Duration), {
+    Ok(Integer::from(duration.0).into())
+});
+
+// whole milliseconds, rounded towards zero
+builtin!(AsMsBuiltin, "as_ms", (duration: Duration), {
+    Ok(Integer::from(duration.0 / 1_000_000).into())
+});
+
+builtin!(AsSBuiltin, "as_s", (duration: Duration), {
+    let nanos = Float::with_val(DEFAULT_FLOAT_PRECISION, duration.0);
+    Ok(Value::from(nanos / 1_000_000_000))
+});
+
+// date(year, month, day) or date(year, month, day, hour, minute, second), in UTC
+builtin!(DateBuiltin, "date", Arity::Range(3, 6), _ctx, args, {
+    let mut parts = args
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| extract::<Integer>("date", index, value))
+        .collect::<Result<Vec<_>, _>>()?;
+    parts.resize(6, Integer::new());
+
+    let year = parts[0]
+        .to_i64()
+        .filter(|year| year.abs() <= 1_000_000)
+        .ok_or_else(|| invalid("date", format!("year {} is out of range", parts[0])))?;
+    let [month, day, hour, minute, second] =
+        std::array::from_fn(|i| parts[i + 1].to_u32().unwrap_or(u32::MAX));
+
+    DateTime::new(year, month, day, hour, minute, second)
+        .map(Value::DateTime)
+        .map_err(|message| invalid("date", message))
+});
+
+builtin!(NowBuiltin, "now", (), {
+    Ok(Value::DateTime(DateTime::now()))
+});
+
+// the same instant at another offset: "UTC", "local", or "+HH:MM"
+builtin!(InZoneBuiltin, "in_zone", (datetime: DateTime, zone: String), {
+    datetime
+        .in_zone(&zone)
+        .map(Value::DateTime)
+        .map_err(|message| invalid("in_zone", message))
+});
+
+builtin!(FormatDateBuiltin, "format_date", (datetime: DateTime, format: String), {
+    datetime
+        .format(&format)
+        .map(Value::from)
+        .map_err(|message| invalid("format_date", message))
 });
@@ -0,0 +1,76 @@
+use rug::Integer;
+
+use super::{builtin, extract};
+use crate::{
+    context::Ctx,
+    datetime::Duration,
+    function::Function,
+    interpreter::RuntimeError,
+    report::InvalidArgument,
+    sync::{Rc, RefCell},
+    value::Value,
+};
+
+// a delay in milliseconds, or a duration
+fn delay(name: &str, value: Value) -> Result<std::time::Duration, RuntimeError> {
+    let nanos = match value {
+        Value::Duration(Duration(nanos)) => Some(nanos),
+        other => extract::<Integer>(name, 1, other)?
+            .to_i128()
+            .and_then(|ms| ms.checked_mul(1_000_000)),
+    };
+    match nanos.and_then(|nanos| u64::try_from(nanos).ok()) {
+        Some(nanos) => Ok(std::time::Duration::from_nanos(nanos)),
+        None => Err(RuntimeError::Report(
+            InvalidArgument {
+                name: name.to_string(),
+                message: "the delay must not be negative".to_string(),
+            }
+            .into(),
+        )),
+    }
+}
+
+fn schedule(
+    ctx: &Rc<RefCell<Ctx>>,
+    name: &str,
+    function: Rc<dyn Function>,
+    delay: Value,
+    repeat: bool,
+) -> Result<Value, RuntimeError> {
+    let delay = self::delay(name, delay)?;
+    let id = ctx
+        .borrow()
+        .runtime()
+        .scheduler
+        .schedule(function, delay, repeat);
+    Ok(Integer::from(id).into())
+}
+
+// `f()` once, in `delay` milliseconds (or a duration) of `run_loop`; gives
+// the id `clear_timer` takes
+builtin!(SetTimeoutBuiltin, "set_timeout", ctx, (function: Rc<dyn Function>, delay: Value), {
+    schedule(ctx, "set_timeout", function, delay, false)
+});
+
+// `f()` every `delay` milliseconds (or duration), until cleared
+builtin!(SetIntervalBuiltin, "set_interval", ctx, (function: Rc<dyn Function>, delay: Value), {
+    schedule(ctx, "set_interval", function, delay, true)
+});
+
+// whether there was such a timer still to run
+builtin!(ClearTimerBuiltin, "clear_timer", ctx, (id: Integer), {
+    let id = id.to_u64().unwrap_or(0);
+    Ok(ctx.borrow().runtime().scheduler.cancel(id).into())
+});
+
+// runs timers as they come due, until there are none left
+builtin!(RunLoopBuiltin, "run_loop", ctx, (), {
+    loop {
+        let function = ctx.borrow().runtime().scheduler.next();
+        match function {
+            Some(function) => function.call(ctx, vec![])?,
+            None => return Ok(Value::Nil),
+        };
+    }
+});
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use rug::Integer;
+
+use super::builtin;
+use crate::{
+    dict::Dict,
+    interpreter::RuntimeError,
+    report::InvalidArgument,
+    sync::{Rc, RefCell},
+    token::Literal,
+    url::{self, Url},
+    value::{Value, ValueKey},
+};
+
+fn invalid(name: &str, message: String) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message,
+        }
+        .into(),
+    )
+}
+
+fn dict(entries: Vec<(Value, Value)>) -> Value {
+    let entries: HashMap<_, _> = entries
+        .into_iter()
+        .map(|(key, value)| (ValueKey(key), value))
+        .collect();
+    Value::Dict(Rc::new(RefCell::new(Dict(entries))))
+}
+
+fn optional(text: Option<String>) -> Value {
+    text.map_or(Value::Nil, Value::from)
+}
+
+// the parameters of a query, a list of values for the ones that come more
+// than once
+fn params(query: &str) -> Result<Value, String> {
+    let mut params: Vec<(String, Vec<Value>)> = Vec::new();
+    for (key, value) in url::parse_query(query)? {
+        match params.iter_mut().find(|(name, _)| *name == key) {
+            Some((_, values)) => values.push(value.into()),
+            None => params.push((key, vec![value.into()])),
+        }
+    }
+    let params = params.into_iter().map(|(key, mut values)| {
+        let value = match values.len() {
+            1 => values.pop().unwrap(),
+            _ => values.into(),
+        };
+        (Value::from(key), value)
+    });
+    Ok(dict(params.collect()))
+}
+
+// {"scheme", "user", "host", "port", "path", "query", "fragment"}, with the
+// query as a dict of its parameters and nil for the parts that are missing
+builtin!(UrlParseBuiltin, "url_parse", (url: String), {
+    let failed = |message| invalid("url_parse", format!("{}: {}", url, message));
+    let parsed = Url::parse(&url).map_err(failed)?;
+    let query = match &parsed.query {
+        Some(query) => params(query).map_err(failed)?,
+        None => dict(Vec::new()),
+    };
+    Ok(dict(vec![
+        ("scheme".into(), parsed.scheme.into()),
+        ("user".into(), optional(parsed.user)),
+        ("host".into(), parsed.host.into()),
+        (
+            "port".into(),
+            parsed.port.map_or(Value::Nil, |port| Integer::from(port).into()),
+        ),
+        ("path".into(), parsed.path.into()),
+        ("query".into(), query),
+        ("fragment".into(), optional(parsed.fragment)),
+    ]))
+});
+
+builtin!(UrlEncodeBuiltin, "url_encode", (text: String), {
+    Ok(url::encode(&text).into())
+});
+
+builtin!(UrlDecodeBuiltin, "url_decode", (text: String), {
+    match url::decode(&text) {
+        Ok(text) => Ok(text.into()),
+        Err(message) => Err(invalid("url_decode", message)),
+    }
+});
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::Literal(Literal::String(text)) => text.to_string(),
+        value => value.to_string(),
+    }
+}
+
+// `a=1&b=x%20y` from {"a": 1, "b": "x y"}, sorted by key; a list gives its
+// key once per item and nil leaves the key out
+builtin!(QueryStringBuiltin, "query_string", (params: Rc<RefCell<Dict>>), {
+    let mut pairs = Vec::new();
+    for (key, value) in params.borrow().0.iter() {
+        let values = match value {
+            Value::Nil => Vec::new(),
+            Value::List(list) => list.borrow().0.iter().map(text).collect(),
+            value => vec![text(value)],
+        };
+        pairs.extend(values.into_iter().map(|value| (text(&key.0), value)));
+    }
+    // stable, so the items of a list keep their order
+    pairs.sort_by(|lhs, rhs| lhs.0.cmp(&rhs.0));
+    Ok(url::query_string(&pairs).into())
+});
@@ -0,0 +1,336 @@
+use super::builtin;
+use crate::{
+    dict::Dict,
+    fixed::KINDS,
+    interpreter::RuntimeError,
+    list::List,
+    printer,
+    report::InvalidArgument,
+    sync::{Rc, RefCell},
+    token::Literal,
+    value::{Value, ValueKey},
+};
+
+// A schema is a dict of any of:
+//
+//     "type"        a type name ("integer", "string", ...), "number" for
+//                   integers and floats, or a list of them
+//     "one_of"      the list of values allowed
+//     "min", "max"  bounds, inclusive, for numbers
+//     "min_length", "max_length"  for strings, bytes, lists and dicts
+//     "items"       the schema of every item of a list
+//     "keys"        a dict of the schemas of a dict's entries
+//     "required"    the list of keys a dict must have
+//     "extra"       whether a dict may have keys not in "keys" (true)
+//     "values"      the schema of every value of a dict
+const SCHEMA_KEYS: [&str; 11] = [
+    "type",
+    "one_of",
+    "min",
+    "max",
+    "min_length",
+    "max_length",
+    "items",
+    "keys",
+    "required",
+    "extra",
+    "values",
+];
+
+// what "type" can name, besides the fixed-width integers
+const TYPES: [&str; 24] = [
+    "any",
+    "number",
+    "bool",
+    "nil",
+    "identifier",
+    "string",
+    "integer",
+    "float",
+    "bytes",
+    "duration",
+    "datetime",
+    "exact",
+    "function",
+    "list",
+    "dict",
+    "set",
+    "iterator",
+    "process",
+    "file",
+    "progress",
+    "table",
+    "class",
+    "instance",
+    "weak",
+];
+
+fn invalid(name: &str, message: String) -> RuntimeError {
+    RuntimeError::Report(
+        InvalidArgument {
+            name: name.to_string(),
+            message,
+        }
+        .into(),
+    )
+}
+
+fn entry(schema: &Dict, name: &str) -> Option<Value> {
+    let key = ValueKey::try_from(Value::from(name)).unwrap();
+    schema.0.get(&key).cloned()
+}
+
+fn schema(value: Value, path: &str) -> Result<Rc<RefCell<Dict>>, String> {
+    match value {
+        Value::Dict(dict) => Ok(dict),
+        other => Err(format!(
+            "the schema at {} is a {}, not a dict",
+            path,
+            other.type_name()
+        )),
+    }
+}
+
+fn list(value: Value, name: &str, path: &str) -> Result<Rc<RefCell<List>>, String> {
+    match value {
+        Value::List(list) => Ok(list),
+        other => Err(format!(
+            "\"{}\" at {} is a {}, not a list",
+            name,
+            path,
+            other.type_name()
+        )),
+    }
+}
+
+fn child(path: &str, key: &Value) -> String {
+    match key {
+        Value::Literal(Literal::String(key)) => format!("{}.{}", path, key),
+        key => format!("{}[{}]", path, key),
+    }
+}
+
+fn type_matches(value: &Value, name: &str) -> bool {
+    match name {
+        "any" => true,
+        "number" => matches!(
+            value,
+            Value::Literal(Literal::Integer(_) | Literal::Float(_))
+        ),
+        name => value.type_name() == name,
+    }
+}
+
+fn length(value: &Value) -> Option<usize> {
+    match value {
+        Value::Literal(Literal::String(s)) => Some(s.chars().count()),
+        Value::Bytes(bytes) => Some(bytes.len()),
+        Value::List(list) => Some(list.borrow().0.len()),
+        Value::Dict(dict) => Some(dict.borrow().0.len()),
+        _ => None,
+    }
+}
+
+// the mistakes in a schema, found before any value is checked against it,
+// so that a broken schema isn't passed off as bad data; `path` names the
+// schema by the keys leading to it, as `$.keys.age`
+fn well_formed(schema: &Dict, path: &str) -> Result<(), String> {
+    if let Some(key) = schema
+        .0
+        .keys()
+        .find(|key| !SCHEMA_KEYS.iter().any(|name| key.0 == Value::from(*name)))
+    {
+        return Err(format!(
+            "unknown schema key {} at {}",
+            printer::value(&key.0),
+            path
+        ));
+    }
+
+    if let Some(types) = entry(schema, "type") {
+        let names = match types {
+            Value::List(list) => list.borrow().0.clone(),
+            other => vec![other],
+        };
+        for name in names {
+            match name {
+                Value::Literal(Literal::String(name))
+                    if TYPES.contains(&&*name) || KINDS.iter().any(|(kind, _)| **kind == *name) => {
+                }
+                Value::Literal(Literal::String(name)) => {
+                    return Err(format!("unknown type `{}` in the schema at {}", name, path))
+                }
+                other => return Err(format!("the type at {} is a {}, not a string", path, other)),
+            }
+        }
+    }
+    for name in ["one_of", "required"] {
+        if let Some(value) = entry(schema, name) {
+            self::list(value, name, path)?;
+        }
+    }
+    for name in ["items", "values"] {
+        if let Some(value) = entry(schema, name) {
+            let path = format!("{}.{}", path, name);
+            well_formed(&self::schema(value, &path)?.borrow(), &path)?;
+        }
+    }
+    if let Some(keys) = entry(schema, "keys") {
+        let path = format!("{}.keys", path);
+        let keys = self::schema(keys, &path)?;
+        let keys = keys.borrow();
+        let mut entries: Vec<_> = keys.0.iter().collect();
+        entries.sort_by_key(|(key, _)| key.to_string());
+        for (key, value) in entries {
+            let path = child(&path, &key.0);
+            well_formed(&self::schema(value.clone(), &path)?.borrow(), &path)?;
+        }
+    }
+
+    Ok(())
+}
+
+// appends the violations of `value` to `violations`; errors are mistakes in
+// the schema itself
+fn check(
+    value: &Value,
+    schema: &Dict,
+    path: &str,
+    violations: &mut Vec<String>,
+) -> Result<(), String> {
+    if let Some(types) = entry(schema, "type") {
+        let names = match types {
+            Value::List(list) => list.borrow().0.clone(),
+            other => vec![other],
+        };
+        let names = names
+            .into_iter()
+            .map(|name| match name {
+                Value::Literal(Literal::String(name)) => Ok(name),
+                other => Err(format!("the type at {} is a {}, not a string", path, other)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if !names.iter().any(|name| type_matches(value, name)) {
+            violations.push(format!(
+                "{}: expected {}, found {}",
+                path,
+                names.join(" or "),
+                value.type_name()
+            ));
+            // nothing else makes sense to check on a value of the wrong type
+            return Ok(());
+        }
+    }
+
+    if let Some(allowed) = entry(schema, "one_of") {
+        let allowed = self::list(allowed, "one_of", path)?;
+        let allowed = allowed.borrow();
+        if !allowed.0.contains(value) {
+            let allowed: Vec<_> = allowed.0.iter().map(printer::value).collect();
+            violations.push(format!(
+                "{}: {} is not one of {}",
+                path,
+                printer::value(value),
+                allowed.join(", ")
+            ));
+        }
+    }
+
+    if let Some(min) = entry(schema, "min") {
+        if value.partial_cmp(&min) == Some(std::cmp::Ordering::Less) {
+            violations.push(format!("{}: {} is less than {}", path, value, min));
+        }
+    }
+    if let Some(max) = entry(schema, "max") {
+        if value.partial_cmp(&max) == Some(std::cmp::Ordering::Greater) {
+            violations.push(format!("{}: {} is more than {}", path, value, max));
+        }
+    }
+
+    if let Some(len) = length(value) {
+        let len = Value::from(rug::Integer::from(len));
+        if let Some(min) = entry(schema, "min_length") {
+            if len < min {
+                violations.push(format!("{}: length {} is less than {}", path, len, min));
+            }
+        }
+        if let Some(max) = entry(schema, "max_length") {
+            if len > max {
+                violations.push(format!("{}: length {} is more than {}", path, len, max));
+            }
+        }
+    }
+
+    if let (Value::List(list), Some(items)) = (value, entry(schema, "items")) {
+        let items = self::schema(items, path)?;
+        for (index, item) in list.borrow().0.iter().enumerate() {
+            let path = format!("{}[{}]", path, index);
+            check(item, &items.borrow(), &path, violations)?;
+        }
+    }
+
+    if let Value::Dict(dict) = value {
+        let dict = dict.borrow();
+        if let Some(required) = entry(schema, "required") {
+            for key in self::list(required, "required", path)?.borrow().0.iter() {
+                let present = ValueKey::try_from(key.clone())
+                    .map(|key| dict.0.contains_key(&key))
+                    .unwrap_or(false);
+                if !present {
+                    violations.push(format!(
+                        "{}: missing required key {}",
+                        path,
+                        printer::value(key)
+                    ));
+                }
+            }
+        }
+
+        let keys = match entry(schema, "keys") {
+            Some(keys) => Some(self::schema(keys, path)?),
+            None => None,
+        };
+        let values = match entry(schema, "values") {
+            Some(values) => Some(self::schema(values, path)?),
+            None => None,
+        };
+        let extra = entry(schema, "extra").is_none_or(|extra| extra.is_truthy());
+        let mut entries: Vec<_> = dict.0.iter().collect();
+        entries.sort_by_key(|(key, _)| key.to_string());
+        for (key, item) in entries {
+            let path = child(path, &key.0);
+            let known = keys
+                .as_ref()
+                .and_then(|keys| keys.borrow().0.get(key).cloned());
+            match known {
+                Some(known) => check(
+                    item,
+                    &self::schema(known, &path)?.borrow(),
+                    &path,
+                    violations,
+                )?,
+                None if !extra && keys.is_some() => {
+                    violations.push(format!("{}: unexpected key", path))
+                }
+                None => {}
+            }
+            if let Some(values) = &values {
+                check(item, &values.borrow(), &path, violations)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// the list of the ways `value` doesn't match `schema`, as "path: problem"
+// strings; empty when it matches
+builtin!(ValidateBuiltin, "validate", (value: Value, schema: Rc<RefCell<Dict>>), {
+    let mut violations = Vec::new();
+    well_formed(&schema.borrow(), "$")
+        .and_then(|()| check(&value, &schema.borrow(), "$", &mut violations))
+        .map_err(|message| invalid("validate", message))?;
+
+    let violations = violations.into_iter().map(Value::from).collect();
+    Ok(Value::List(Rc::new(RefCell::new(List(violations)))))
+});
@@ -0,0 +1,28 @@
+use super::builtin;
+use crate::{
+    interpreter::RuntimeError,
+    report::ArgumentTypeError,
+    value::{Value, WeakRef},
+};
+
+// a reference to a list, dict or instance that doesn't keep it alive, for
+// caches
+builtin!(WeakBuiltin, "weak", (value: Value), {
+    match WeakRef::new(&value) {
+        Some(weak) => Ok(Value::Weak(weak)),
+        None => Err(RuntimeError::Report(
+            ArgumentTypeError {
+                name: "weak".to_string(),
+                position: 1,
+                expected: "list, dict or instance",
+                found: value.type_name(),
+            }
+            .into(),
+        )),
+    }
+});
+
+// the referenced value, or nil once it has been dropped
+builtin!(UpgradeBuiltin, "upgrade", (weak: WeakRef), {
+    Ok(weak.upgrade().unwrap_or(Value::Nil))
+});
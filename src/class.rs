@@ -0,0 +1,111 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    context::Ctx,
+    function::{Arity, Function, SimpleFunction},
+    sync::{Rc, RefCell},
+    value::Value,
+};
+
+// Classes and their instances. Calling a class makes an instance with no
+// fields and runs the class's `init` method on it, if it has one, with the
+// call's arguments. An instance's properties are what the getters of its
+// class give first, then its fields, then the methods of its class, which
+// come bound: `this` in them is the instance. Setting a property with a
+// setter calls it, even from inside the setter, instead of making a field,
+// and one with only a getter can't be set. A class without a method, or an
+// accessor, looks for it in its superclass, and so on up the chain;
+// `super.method` in a method starts looking from the superclass of the
+// class the method belongs to.
+
+pub const INITIALIZER: &str = "init";
+
+#[derive(Debug)]
+pub struct Class {
+    pub name: Rc<str>,
+    pub superclass: Option<Rc<Class>>,
+    pub methods: Functions,
+    pub getters: Functions,
+    pub setters: Functions,
+}
+
+type Functions = HashMap<Rc<str>, Rc<SimpleFunction>>;
+
+impl Class {
+    // the function `name` in `table` of the class or of the closest
+    // superclass with one
+    fn find(&self, table: fn(&Class) -> &Functions, name: &str) -> Option<&Rc<SimpleFunction>> {
+        match table(self).get(name) {
+            Some(function) => Some(function),
+            None => self.superclass.as_ref()?.find(table, name),
+        }
+    }
+
+    pub fn find_method(&self, name: &str) -> Option<&Rc<SimpleFunction>> {
+        self.find(|class| &class.methods, name)
+    }
+
+    pub fn find_getter(&self, name: &str) -> Option<&Rc<SimpleFunction>> {
+        self.find(|class| &class.getters, name)
+    }
+
+    pub fn find_setter(&self, name: &str) -> Option<&Rc<SimpleFunction>> {
+        self.find(|class| &class.setters, name)
+    }
+
+    // the arguments calling the class takes, the ones of `init`
+    pub fn arity(&self) -> Arity {
+        match self.find_method(INITIALIZER) {
+            Some(init) => init.arity(),
+            None => Arity::Exact(0),
+        }
+    }
+}
+
+impl Display for Class {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(class {})", self.name)
+    }
+}
+
+#[derive(Debug)]
+pub struct Instance {
+    pub class: Rc<Class>,
+    pub fields: HashMap<Rc<str>, Value>,
+}
+
+impl Instance {
+    pub fn new(class: Rc<Class>) -> Self {
+        Self {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+
+    // the field `name` of `instance`, or else the method bound to it
+    pub fn get(instance: &Rc<RefCell<Instance>>, name: &str) -> Option<Value> {
+        let this = instance.borrow();
+        if let Some(value) = this.fields.get(name) {
+            return Some(value.clone());
+        }
+        let method = this.class.find_method(name)?;
+        Some(bind(method, Value::Instance(instance.clone())).into())
+    }
+}
+
+impl Display for Instance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({} instance)", self.class.name)
+    }
+}
+
+// `method` in a scope of its own where `this` is `instance`, which the
+// resolver put between the method and the scope the class was declared in
+pub fn bind(method: &SimpleFunction, instance: Value) -> SimpleFunction {
+    let closure = Ctx::with_parent(&method.closure);
+    closure.borrow_mut().define("this", instance);
+    SimpleFunction {
+        closure,
+        ..method.clone()
+    }
+}
@@ -1,36 +1,87 @@
-use std::{cell::RefCell, rc::Rc};
+use std::collections::HashMap;
 
 use crate::{
-    env::{Env, EnvError},
+    env::{Env, EnvError, Slot},
     expr::Identifiable,
     resolver::Resolver,
+    runtime::Runtime,
+    sync::{Rc, RefCell},
     value::Value,
 };
 
 #[derive(Debug)]
 pub struct Ctx {
     env: Rc<RefCell<Env>>,
+    // where names the resolver left unresolved live
+    globals: Rc<RefCell<Env>>,
     resolver: Rc<Resolver>,
-    size: usize,
+    runtime: Rc<Runtime>,
+    // the values of the loop-invariant expressions of the loops being run,
+    // by expression id
+    hoisted: Option<Rc<HashMap<usize, Value>>>,
 }
 
 impl Ctx {
-    pub fn new(env: &Rc<RefCell<Env>>, resolver: Rc<Resolver>) -> Self {
+    pub fn new(env: &Rc<RefCell<Env>>, resolver: Rc<Resolver>, runtime: Rc<Runtime>) -> Self {
         Self {
             env: env.clone(),
+            globals: env.clone(),
             resolver,
-            size: 0,
+            runtime,
+            hoisted: None,
         }
     }
 
     pub fn with_parent(ctx: &Rc<RefCell<Self>>) -> Rc<RefCell<Self>> {
         Rc::new(RefCell::new(Self {
             env: Env::with_parent(&ctx.borrow().env),
+            globals: ctx.borrow().globals.clone(),
             resolver: ctx.borrow().resolver.clone(),
-            size: ctx.borrow().size + 1,
+            runtime: ctx.borrow().runtime.clone(),
+            hoisted: ctx.borrow().hoisted.clone(),
         }))
     }
 
+    // the same scope, for code resolved on its own, like `eval`'s
+    pub fn with_resolver(ctx: &Rc<RefCell<Self>>, resolver: Rc<Resolver>) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            env: ctx.borrow().env.clone(),
+            globals: ctx.borrow().globals.clone(),
+            resolver,
+            runtime: ctx.borrow().runtime.clone(),
+            // its ids aren't the ones of the code the values were hoisted from
+            hoisted: None,
+        }))
+    }
+
+    // the same scope, with `values` hoisted out of a loop
+    pub fn with_hoisted(
+        ctx: &Rc<RefCell<Self>>,
+        values: HashMap<usize, Value>,
+    ) -> Rc<RefCell<Self>> {
+        let mut hoisted = ctx.borrow().hoisted.as_deref().cloned().unwrap_or_default();
+        hoisted.extend(values);
+        Rc::new(RefCell::new(Self {
+            env: ctx.borrow().env.clone(),
+            globals: ctx.borrow().globals.clone(),
+            resolver: ctx.borrow().resolver.clone(),
+            runtime: ctx.borrow().runtime.clone(),
+            hoisted: Some(Rc::new(hoisted)),
+        }))
+    }
+
+    pub fn hoisted(&self, id: usize) -> Option<Value> {
+        self.hoisted.as_ref()?.get(&id).cloned()
+    }
+
+    pub fn env(&self) -> &Rc<RefCell<Env>> {
+        &self.env
+    }
+
+    pub fn runtime(&self) -> &Rc<Runtime> {
+        &self.runtime
+    }
+
     pub fn define(&mut self, name: &str, value: Value) {
         self.env.borrow_mut().define(name, value);
     }
@@ -41,12 +92,45 @@ impl Ctx {
         name: &str,
         value: Value,
     ) -> Result<(), EnvError> {
-        let distance = *self.resolver.locals.get(i.id()).unwrap_or(&self.size); // TODO: ??
-        self.env.borrow_mut().assign(distance, name, value)
+        let cached = self.resolver.slots.borrow().get(i.id()).copied();
+        let result = match self.resolver.locals.get(i.id()) {
+            Some(&distance) => Env::assign(&self.env, distance, name, value, cached),
+            None => Env::assign(&self.globals, 0, name, value, cached),
+        };
+        match result {
+            Ok(slot) => {
+                self.cache(i, cached, slot);
+                Ok(())
+            }
+            Err(e) => Err(e.with_suggestion(&self.env, name)),
+        }
     }
 
     pub fn get<I: Identifiable>(&self, i: &I, name: &str) -> Result<Value, EnvError> {
-        let distance = *self.resolver.locals.get(i.id()).unwrap_or(&self.size); // TODO: ??
-        self.env.borrow().get(distance, name)
+        let cached = self.resolver.slots.borrow().get(i.id()).copied();
+        let result = match self.resolver.locals.get(i.id()) {
+            Some(&distance) => Env::get(&self.env, distance, name, cached),
+            None => Env::get(&self.globals, 0, name, cached),
+        };
+        match result {
+            Ok((value, slot)) => {
+                self.cache(i, cached, slot);
+                Ok(value)
+            }
+            Err(e) => Err(e.with_suggestion(&self.env, name)),
+        }
+    }
+
+    // the instance of a `super.method`: `bind` puts the scope with `this`
+    // right inside the one with `super`
+    pub fn this_of_super<I: Identifiable>(&self, i: &I) -> Result<Value, EnvError> {
+        let distance = self.resolver.locals.get(i.id()).copied().unwrap_or(1);
+        Env::get(&self.env, distance.saturating_sub(1), "this", None).map(|(value, _)| value)
+    }
+
+    fn cache<I: Identifiable>(&self, i: &I, cached: Option<Slot>, slot: Slot) {
+        if cached != Some(slot) {
+            self.resolver.slots.borrow_mut().insert(*i.id(), slot);
+        }
     }
 }
@@ -0,0 +1,287 @@
+use std::fmt::{Display, Write};
+
+// A signed span of time, in nanoseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration(pub i128);
+
+// An instant, in nanoseconds since the Unix epoch, and the UTC offset (in
+// seconds) it is displayed at. Two datetimes are equal when they are the same
+// instant, whatever their offsets.
+#[derive(Debug, Clone, Copy)]
+pub struct DateTime {
+    pub nanos: i128,
+    pub offset: i32,
+}
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+const SECONDS_PER_DAY: i128 = 86_400;
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+// days since 1970-01-01 of a proleptic Gregorian date, and back
+// (http://howardhinnant.github.io/date_algorithms.html)
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Duration {
+    pub fn from_unit(amount: i128, unit: i128) -> Option<Self> {
+        amount.checked_mul(unit).map(Self)
+    }
+}
+
+impl Display for Duration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let nanos = self.0.unsigned_abs();
+        match u64::try_from(nanos / NANOS_PER_SECOND as u128) {
+            Ok(seconds) => {
+                let duration =
+                    std::time::Duration::new(seconds, (nanos % NANOS_PER_SECOND as u128) as u32);
+                write!(f, "{}{:?}", sign, duration)
+            }
+            Err(_) => write!(f, "{}{}ns", sign, nanos),
+        }
+    }
+}
+
+impl DateTime {
+    pub fn new(
+        year: i64,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+        second: u32,
+    ) -> Result<Self, String> {
+        if !(1..=12).contains(&month) {
+            return Err(format!("month {} is not between 1 and 12", month));
+        }
+        if day == 0 || day > days_in_month(year, month) {
+            return Err(format!("day {} is not in month {}", day, month));
+        }
+        if hour > 23 || minute > 59 || second > 59 {
+            return Err(format!("{}:{}:{} is not a time", hour, minute, second));
+        }
+
+        let days = days_from_civil(year, month, day) as i128;
+        let seconds = days * SECONDS_PER_DAY + (hour * 3600 + minute * 60 + second) as i128;
+        Ok(Self {
+            nanos: seconds * NANOS_PER_SECOND,
+            offset: 0,
+        })
+    }
+
+    pub fn now() -> Self {
+        let since_epoch = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        Self {
+            nanos: since_epoch.as_nanos() as i128,
+            offset: 0,
+        }
+    }
+
+    // the same instant, displayed at another offset: "UTC", "local" or
+    // "+HH:MM" / "-HH:MM"
+    pub fn in_zone(self, zone: &str) -> Result<Self, String> {
+        let offset = match zone {
+            "UTC" | "utc" | "Z" => 0,
+            "local" => self.local_offset(),
+            _ => parse_offset(zone).ok_or_else(|| format!("unknown time zone `{}`", zone))?,
+        };
+        Ok(Self { offset, ..self })
+    }
+
+    #[cfg(unix)]
+    fn local_offset(&self) -> i32 {
+        let seconds = self.nanos.div_euclid(NANOS_PER_SECOND) as libc::time_t;
+        // SAFETY: both pointers are valid for the duration of the call
+        unsafe {
+            let mut tm = std::mem::zeroed::<libc::tm>();
+            match libc::localtime_r(&seconds, &mut tm).is_null() {
+                true => 0,
+                false => tm.tm_gmtoff as i32,
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn local_offset(&self) -> i32 {
+        0
+    }
+
+    // (year, month, day, hour, minute, second, nanosecond, weekday) at the
+    // datetime's offset
+    fn parts(&self) -> (i64, u32, u32, u32, u32, u32, u32, usize) {
+        let local = self.nanos + self.offset as i128 * NANOS_PER_SECOND;
+        let seconds = local.div_euclid(NANOS_PER_SECOND);
+        let nanos = local.rem_euclid(NANOS_PER_SECOND) as u32;
+        let days = seconds.div_euclid(SECONDS_PER_DAY) as i64;
+        let time = seconds.rem_euclid(SECONDS_PER_DAY) as u32;
+        let (year, month, day) = civil_from_days(days);
+        let weekday = (days + 4).rem_euclid(7) as usize;
+        (
+            year,
+            month,
+            day,
+            time / 3600,
+            time / 60 % 60,
+            time % 60,
+            nanos,
+            weekday,
+        )
+    }
+
+    fn write_offset(&self, output: &mut String, colon: bool) -> std::fmt::Result {
+        let sign = if self.offset < 0 { '-' } else { '+' };
+        let offset = self.offset.unsigned_abs();
+        let separator = if colon { ":" } else { "" };
+        write!(
+            output,
+            "{}{:02}{}{:02}",
+            sign,
+            offset / 3600,
+            separator,
+            offset / 60 % 60
+        )
+    }
+
+    // strftime-like: %Y %m %d %H %M %S %f (nanoseconds) %j (day of the year)
+    // %a %A %b %B (names) %z (+0200) %:z (+02:00) %Z (UTC or the offset)
+    // %s (Unix seconds) and %%
+    pub fn format(&self, format: &str) -> Result<String, String> {
+        let (year, month, day, hour, minute, second, nanos, weekday) = self.parts();
+        let mut output = String::new();
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                output.push(c);
+                continue;
+            }
+
+            let _ = match chars.next() {
+                Some('Y') => write!(output, "{:04}", year),
+                Some('m') => write!(output, "{:02}", month),
+                Some('d') => write!(output, "{:02}", day),
+                Some('H') => write!(output, "{:02}", hour),
+                Some('M') => write!(output, "{:02}", minute),
+                Some('S') => write!(output, "{:02}", second),
+                Some('f') => write!(output, "{:09}", nanos),
+                Some('j') => {
+                    let first = days_from_civil(year, 1, 1);
+                    let today = days_from_civil(year, month, day);
+                    write!(output, "{:03}", today - first + 1)
+                }
+                Some('a') => write!(output, "{}", &WEEKDAYS[weekday][..3]),
+                Some('A') => write!(output, "{}", WEEKDAYS[weekday]),
+                Some('b') => write!(output, "{}", &MONTHS[month as usize - 1][..3]),
+                Some('B') => write!(output, "{}", MONTHS[month as usize - 1]),
+                Some('z') => self.write_offset(&mut output, false),
+                Some(':') if chars.next() == Some('z') => self.write_offset(&mut output, true),
+                Some('Z') if self.offset == 0 => write!(output, "UTC"),
+                Some('Z') => self.write_offset(&mut output, true),
+                Some('s') => write!(output, "{}", self.nanos.div_euclid(NANOS_PER_SECOND)),
+                Some('%') => write!(output, "%"),
+                Some(other) => return Err(format!("unknown format specifier `%{}`", other)),
+                None => return Err("format ends with `%`".to_string()),
+            };
+        }
+
+        Ok(output)
+    }
+}
+
+fn parse_offset(zone: &str) -> Option<i32> {
+    let (sign, rest) = match zone.as_bytes().first()? {
+        b'+' => (1, &zone[1..]),
+        b'-' => (-1, &zone[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let (hours, minutes) = (hours.parse::<i32>().ok()?, minutes.parse::<i32>().ok()?);
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+// ISO 8601, with fractional seconds only when there are any
+impl Display for DateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (_, _, _, _, _, _, nanos, _) = self.parts();
+        let mut output = self.format("%Y-%m-%dT%H:%M:%S").unwrap();
+        if nanos != 0 {
+            let fraction = format!("{:09}", nanos);
+            let _ = write!(output, ".{}", fraction.trim_end_matches('0'));
+        }
+        match self.offset {
+            0 => output.push('Z'),
+            _ => self.write_offset(&mut output, true)?,
+        }
+        f.write_str(&output)
+    }
+}
+
+impl PartialEq for DateTime {
+    fn eq(&self, other: &Self) -> bool {
+        self.nanos == other.nanos
+    }
+}
+
+impl PartialOrd for DateTime {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.nanos.partial_cmp(&other.nanos)
+    }
+}
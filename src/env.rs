@@ -1,90 +1,387 @@
 use std::{
-    cell::RefCell,
-    collections::{hash_map::Entry, HashMap},
-    rc::Rc,
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 use miette::Report;
 use thiserror::Error;
 
-use crate::{builtin::*, report::UndefinedValue, resolver::Resolver, token::Span, value::Value};
+use crate::{
+    builtin::*,
+    function::Function,
+    report::UndefinedValue,
+    resolver::Resolver,
+    sync::{Rc, RefCell},
+    token::Span,
+    value::Value,
+};
 
-#[derive(Default, Clone, Debug)]
+#[derive(Debug)]
 pub struct Env {
-    values: HashMap<String, Value>,
+    // the slot of each name; a name keeps its slot once defined
+    values: HashMap<String, usize>,
+    slots: Vec<Value>,
+    // tells this env from every other one, for the `Slot`s cached by
+    // expression
+    generation: u64,
+    // the global env, where the builtins not looked up yet are
+    builtins: bool,
     pub enclosing: Option<Rc<RefCell<Env>>>,
     resolver: Rc<RefCell<Resolver>>,
 }
 
+// Where a name was found, kept by the resolver for the expression that
+// looked it up: the next lookup from it goes straight to the slot, without
+// hashing the name, when it ends up in the same env.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slot {
+    generation: u64,
+    index: usize,
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+        Self {
+            values: HashMap::default(),
+            slots: Vec::new(),
+            generation: GENERATION.fetch_add(1, Ordering::Relaxed),
+            builtins: false,
+            enclosing: None,
+            resolver: Rc::default(),
+        }
+    }
+}
+
 impl Env {
     pub fn with_parent(enclosing: &Rc<RefCell<Self>>) -> Rc<RefCell<Self>> {
         Rc::new(RefCell::new(Self {
-            values: HashMap::default(),
             resolver: enclosing.borrow().resolver.clone(),
             enclosing: Some(enclosing.clone()),
+            ..Default::default()
         }))
     }
 
+    // the builtins are put in it the first time they are looked up
     pub fn global() -> Rc<RefCell<Self>> {
-        let mut global = Self::default();
-        global.define("time", Value::Function(Rc::new(TimeBuiltin {})));
-        global.define("print", Value::Function(Rc::new(PrintBuiltin {})));
-        global.define("println", Value::Function(Rc::new(PrintlnBuiltin {})));
-        global.define("len", Value::Function(Rc::new(LenBuiltin {})));
-        global.define("keys", Value::Function(Rc::new(KeysBuiltin {})));
-        global.define("values", Value::Function(Rc::new(ValuesBuiltin {})));
-        Rc::new(RefCell::new(global))
+        Rc::new(RefCell::new(Self {
+            builtins: true,
+            ..Default::default()
+        }))
+    }
+
+    // defines the builtin `name`, when there's one and this env has them
+    fn load(&mut self, name: &str) -> Option<usize> {
+        if !self.builtins {
+            return None;
+        }
+        let value = match BUILTINS.binary_search_by(|(builtin, _)| builtin.cmp(&name)) {
+            Ok(index) => Value::Function(BUILTINS[index].1()),
+            Err(_) => CONSTANTS.iter().find(|(constant, _)| *constant == name)?.1(),
+        };
+        self.define(name, value);
+        self.values.get(name).copied()
     }
 }
 
+type Builtin = fn() -> Rc<dyn Function>;
+
+// Sorted by name, for the binary search. Making every builtin up front got
+// slower with each one added, and most programs only use a few.
+const BUILTINS: [(&str, Builtin); 147] = [
+    ("adler32", || Rc::new(Adler32Builtin {})),
+    ("agg", || Rc::new(AggBuiltin {})),
+    ("approx", || Rc::new(ApproxBuiltin {})),
+    ("as_ms", || Rc::new(AsMsBuiltin {})),
+    ("as_ns", || Rc::new(AsNsBuiltin {})),
+    ("as_s", || Rc::new(AsSBuiltin {})),
+    ("assert", || Rc::new(AssertBuiltin {})),
+    ("assert_close", || Rc::new(AssertCloseBuiltin {})),
+    ("assert_eq", || Rc::new(AssertEqBuiltin {})),
+    ("assert_ne", || Rc::new(AssertNeBuiltin {})),
+    ("assert_raises", || Rc::new(AssertRaisesBuiltin {})),
+    ("bit_and", || Rc::new(BitAndBuiltin {})),
+    ("bit_xor", || Rc::new(BitXorBuiltin {})),
+    ("bold", || Rc::new(BoldBuiltin {})),
+    ("bytes", || Rc::new(BytesBuiltin {})),
+    ("bytes_to_int", || Rc::new(BytesToIntBuiltin {})),
+    ("cfg", || Rc::new(CfgBuiltin {})),
+    ("checked_add", || Rc::new(CheckedAddBuiltin {})),
+    ("checked_div", || Rc::new(CheckedDivBuiltin {})),
+    ("checked_mul", || Rc::new(CheckedMulBuiltin {})),
+    ("checked_sub", || Rc::new(CheckedSubBuiltin {})),
+    ("clear_timer", || Rc::new(ClearTimerBuiltin {})),
+    ("close", || Rc::new(CloseBuiltin {})),
+    ("close_stdin", || Rc::new(CloseStdinBuiltin {})),
+    ("collect", || Rc::new(CollectBuiltin {})),
+    ("color", || Rc::new(ColorBuiltin {})),
+    ("compile", || Rc::new(CompileBuiltin {})),
+    ("confirm", || Rc::new(ConfirmBuiltin {})),
+    ("contains", || Rc::new(ContainsBuiltin {})),
+    ("copy_file", || Rc::new(CopyFileBuiltin {})),
+    ("crc32", || Rc::new(Crc32Builtin {})),
+    ("date", || Rc::new(DateBuiltin {})),
+    ("decode", || Rc::new(DecodeBuiltin {})),
+    ("diff", || Rc::new(DiffBuiltin {})),
+    ("difference", || Rc::new(DifferenceBuiltin {})),
+    ("duration_ms", || Rc::new(DurationMsBuiltin {})),
+    ("duration_ns", || Rc::new(DurationNsBuiltin {})),
+    ("duration_s", || Rc::new(DurationSBuiltin {})),
+    ("emit", || Rc::new(EmitBuiltin {})),
+    ("eval", || Rc::new(EvalBuiltin {})),
+    ("file_sha256", || Rc::new(FileSha256Builtin {})),
+    ("filter", || Rc::new(FilterBuiltin {})),
+    ("finish", || Rc::new(FinishBuiltin {})),
+    ("for_each_line", || Rc::new(ForEachLineBuiltin {})),
+    ("format_currency", || Rc::new(FormatCurrencyBuiltin {})),
+    ("format_date", || Rc::new(FormatDateBuiltin {})),
+    ("format_number", || Rc::new(FormatNumberBuiltin {})),
+    ("format_percent", || Rc::new(FormatPercentBuiltin {})),
+    ("from_base64", || Rc::new(FromBase64Builtin {})),
+    ("from_hex", || Rc::new(FromHexBuiltin {})),
+    ("group_by", || Rc::new(GroupByBuiltin {})),
+    ("gzip_compress", || Rc::new(GzipCompressBuiltin {})),
+    ("gzip_decompress", || Rc::new(GzipDecompressBuiltin {})),
+    ("heap_dump", || Rc::new(HeapDumpBuiltin {})),
+    ("i16", || Rc::new(I16Builtin {})),
+    ("i32", || Rc::new(I32Builtin {})),
+    ("i64", || Rc::new(I64Builtin {})),
+    ("i8", || Rc::new(I8Builtin {})),
+    ("in_zone", || Rc::new(InZoneBuiltin {})),
+    ("int_to_bytes", || Rc::new(IntToBytesBuiltin {})),
+    ("integer", || Rc::new(IntegerBuiltin {})),
+    ("intersection", || Rc::new(IntersectionBuiltin {})),
+    ("keys", || Rc::new(KeysBuiltin {})),
+    ("kill", || Rc::new(KillBuiltin {})),
+    ("len", || Rc::new(LenBuiltin {})),
+    ("map", || Rc::new(MapBuiltin {})),
+    ("mem_stats", || Rc::new(MemStatsBuiltin {})),
+    ("memo", || Rc::new(MemoBuiltin {})),
+    ("mkdirs", || Rc::new(MkdirsBuiltin {})),
+    ("move_file", || Rc::new(MoveFileBuiltin {})),
+    ("msgpack_decode", || Rc::new(MsgpackDecodeBuiltin {})),
+    ("msgpack_encode", || Rc::new(MsgpackEncodeBuiltin {})),
+    ("nanoid", || Rc::new(NanoidBuiltin {})),
+    ("now", || Rc::new(NowBuiltin {})),
+    ("on", || Rc::new(OnBuiltin {})),
+    ("on_error", || Rc::new(OnErrorBuiltin {})),
+    ("on_exit", || Rc::new(OnExitBuiltin {})),
+    ("open", || Rc::new(OpenBuiltin {})),
+    ("pack", || Rc::new(PackBuiltin {})),
+    ("pack_size", || Rc::new(PackSizeBuiltin {})),
+    ("parse", || Rc::new(ParseBuiltin {})),
+    ("password", || Rc::new(PasswordBuiltin {})),
+    ("plot", || Rc::new(PlotBuiltin {})),
+    ("plot_svg", || Rc::new(PlotSvgBuiltin {})),
+    ("print", || Rc::new(PrintBuiltin {})),
+    ("print_diff", || Rc::new(PrintDiffBuiltin {})),
+    ("println", || Rc::new(PrintlnBuiltin {})),
+    ("progress", || Rc::new(ProgressBuiltin {})),
+    ("query", || Rc::new(QueryBuiltin {})),
+    ("query_string", || Rc::new(QueryStringBuiltin {})),
+    ("range", || Rc::new(RangeBuiltin {})),
+    ("rational", || Rc::new(RationalBuiltin {})),
+    ("read_file_bytes", || Rc::new(ReadFileBytesBuiltin {})),
+    ("read_line", || Rc::new(ReadLineBuiltin {})),
+    ("read_stdout_line", || Rc::new(ReadStdoutLineBuiltin {})),
+    ("remove", || Rc::new(RemoveBuiltin {})),
+    ("repr", || Rc::new(ReprBuiltin {})),
+    ("rows", || Rc::new(RowsBuiltin {})),
+    ("run_loop", || Rc::new(RunLoopBuiltin {})),
+    ("saturating_add", || Rc::new(SaturatingAddBuiltin {})),
+    ("saturating_mul", || Rc::new(SaturatingMulBuiltin {})),
+    ("saturating_sub", || Rc::new(SaturatingSubBuiltin {})),
+    ("select", || Rc::new(SelectBuiltin {})),
+    ("semver_cmp", || Rc::new(SemverCmpBuiltin {})),
+    ("semver_parse", || Rc::new(SemverParseBuiltin {})),
+    ("set", || Rc::new(SetBuiltin {})),
+    ("set_interval", || Rc::new(SetIntervalBuiltin {})),
+    ("set_timeout", || Rc::new(SetTimeoutBuiltin {})),
+    ("shl", || Rc::new(ShlBuiltin {})),
+    ("shr", || Rc::new(ShrBuiltin {})),
+    ("sort_by", || Rc::new(SortByBuiltin {})),
+    ("spawn", || Rc::new(SpawnBuiltin {})),
+    ("sqrt", || Rc::new(SqrtBuiltin {})),
+    ("style", || Rc::new(StyleBuiltin {})),
+    ("sum", || Rc::new(SumBuiltin {})),
+    ("table", || Rc::new(TableBuiltin {})),
+    ("temp_dir", || Rc::new(TempDirBuiltin {})),
+    ("temp_file", || Rc::new(TempFileBuiltin {})),
+    ("template", || Rc::new(TemplateBuiltin {})),
+    ("tick", || Rc::new(TickBuiltin {})),
+    ("time", || Rc::new(TimeBuiltin {})),
+    ("to_base64", || Rc::new(ToBase64Builtin {})),
+    ("to_csv", || Rc::new(ToCsvBuiltin {})),
+    ("to_hex", || Rc::new(ToHexBuiltin {})),
+    ("u16", || Rc::new(U16Builtin {})),
+    ("u32", || Rc::new(U32Builtin {})),
+    ("u64", || Rc::new(U64Builtin {})),
+    ("u8", || Rc::new(U8Builtin {})),
+    ("union", || Rc::new(UnionBuiltin {})),
+    ("unpack", || Rc::new(UnpackBuiltin {})),
+    ("upgrade", || Rc::new(UpgradeBuiltin {})),
+    ("url_decode", || Rc::new(UrlDecodeBuiltin {})),
+    ("url_encode", || Rc::new(UrlEncodeBuiltin {})),
+    ("url_parse", || Rc::new(UrlParseBuiltin {})),
+    ("uuid4", || Rc::new(Uuid4Builtin {})),
+    ("uuid7", || Rc::new(Uuid7Builtin {})),
+    ("validate", || Rc::new(ValidateBuiltin {})),
+    ("values", || Rc::new(ValuesBuiltin {})),
+    ("wait", || Rc::new(WaitBuiltin {})),
+    ("weak", || Rc::new(WeakBuiltin {})),
+    ("where", || Rc::new(WhereBuiltin {})),
+    ("wrapping_add", || Rc::new(WrappingAddBuiltin {})),
+    ("wrapping_mul", || Rc::new(WrappingMulBuiltin {})),
+    ("wrapping_sub", || Rc::new(WrappingSubBuiltin {})),
+    ("write_file_bytes", || Rc::new(WriteFileBytesBuiltin {})),
+    ("write_stdin", || Rc::new(WriteStdinBuiltin {})),
+    ("zip_read", || Rc::new(ZipReadBuiltin {})),
+];
+
+type Constant = fn() -> Value;
+
+// the builtins that aren't functions
+const CONSTANTS: [(&str, Constant); 1] = [("pi", pi)];
+
 #[derive(Error, Debug)]
 pub enum EnvError {
     #[error("undefined variable")]
-    UndefinedValue,
+    UndefinedValue { suggestion: Option<String> },
 }
 
 impl EnvError {
     pub fn into_report(self, span: &Span) -> Report {
         match self {
-            EnvError::UndefinedValue => UndefinedValue {
+            EnvError::UndefinedValue { suggestion } => UndefinedValue {
                 span: (*span).into(),
+                help: suggestion.map(|name| format!("did you mean `{}`?", name)),
             }
             .into(),
         }
     }
+
+    pub fn with_suggestion(self, env: &Rc<RefCell<Env>>, name: &str) -> Self {
+        match self {
+            EnvError::UndefinedValue { .. } => EnvError::UndefinedValue {
+                suggestion: Env::suggest(env, name),
+            },
+        }
+    }
 }
 
 impl Env {
     pub fn define(&mut self, name: &str, value: Value) {
-        self.values.insert(name.to_string(), value);
+        match self.values.get(name) {
+            Some(&index) => self.slots[index] = value,
+            None => {
+                self.values.insert(name.to_string(), self.slots.len());
+                self.slots.push(value);
+            }
+        }
     }
 
-    pub fn assign(&mut self, distance: usize, name: &str, value: Value) -> Result<(), EnvError> {
-        if distance == 0 {
-            if let Entry::Occupied(mut e) = self.values.entry(name.to_string()) {
-                e.insert(value);
-                return Ok(());
-            }
+    // the env `distance` levels up, and the slot of `name` in it
+    fn find(
+        env: &Rc<RefCell<Env>>,
+        distance: usize,
+        name: &str,
+        cached: Option<Slot>,
+    ) -> Result<(Rc<RefCell<Env>>, Slot), EnvError> {
+        let mut env = env.clone();
+        for _ in 0..distance {
+            let enclosing = env.borrow().enclosing.clone();
+            env = enclosing.ok_or(EnvError::UndefinedValue { suggestion: None })?;
         }
 
-        if let Some(enclosing) = &mut self.enclosing {
-            enclosing.borrow_mut().assign(distance - 1, name, value)
-        } else {
-            Err(EnvError::UndefinedValue)
+        let generation = env.borrow().generation;
+        let slot = match cached {
+            Some(slot) if slot.generation == generation => slot,
+            _ => {
+                let index = env.borrow().values.get(name).copied();
+                let index = index.or_else(|| env.borrow_mut().load(name));
+                Slot {
+                    generation,
+                    index: index.ok_or(EnvError::UndefinedValue { suggestion: None })?,
+                }
+            }
+        };
+        Ok((env, slot))
+    }
+
+    pub fn assign(
+        env: &Rc<RefCell<Env>>,
+        distance: usize,
+        name: &str,
+        value: Value,
+        cached: Option<Slot>,
+    ) -> Result<Slot, EnvError> {
+        let (env, slot) = Self::find(env, distance, name, cached)?;
+        env.borrow_mut().slots[slot.index] = value;
+        Ok(slot)
+    }
+
+    pub fn get(
+        env: &Rc<RefCell<Env>>,
+        distance: usize,
+        name: &str,
+        cached: Option<Slot>,
+    ) -> Result<(Value, Slot), EnvError> {
+        let (env, slot) = Self::find(env, distance, name, cached)?;
+        let value = env.borrow().slots[slot.index].clone();
+        Ok((value, slot))
+    }
+}
+
+impl Env {
+    // the closest name visible from `env`, if any is a plausible typo of `name`
+    pub fn suggest(env: &Rc<RefCell<Env>>, name: &str) -> Option<String> {
+        let mut names = Vec::new();
+        let mut current = Some(env.clone());
+        while let Some(env) = current {
+            names.extend(env.borrow().names().map(str::to_string));
+            current = env.borrow().enclosing.clone();
         }
+
+        closest(name, names.iter())
     }
 
-    pub fn get(&self, distance: usize, name: &str) -> Result<Value, EnvError> {
-        if distance == 0 {
-            if let Some(value) = self.values.get(name) {
-                return Ok(value.clone());
-            }
+    // the builtins included, looked up or not
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        let builtins = BUILTINS
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(CONSTANTS.iter().map(|(name, _)| *name))
+            .filter(|name| self.builtins && !self.values.contains_key(*name));
+        self.values.keys().map(String::as_str).chain(builtins)
+    }
+
+    pub fn bindings(&self) -> impl Iterator<Item = (&String, &Value)> {
+        self.values
+            .iter()
+            .map(|(name, &index)| (name, &self.slots[index]))
+    }
+}
+
+// the candidate that is a plausible typo of `name`, if any
+pub fn closest<'a>(name: &str, candidates: impl Iterator<Item = &'a String>) -> Option<String> {
+    let max_distance = 2.min(name.chars().count().saturating_sub(1));
+
+    let mut best: Option<(usize, &String)> = None;
+    for candidate in candidates {
+        let distance = strsim::levenshtein(name, candidate);
+        if distance == 0 || distance > max_distance {
+            continue;
         }
 
-        if let Some(enclosing) = &self.enclosing {
-            enclosing.borrow().get(distance - 1, name)
-        } else {
-            Err(EnvError::UndefinedValue)
+        let better = match &best {
+            Some((best_distance, best_name)) => (distance, candidate) < (*best_distance, best_name),
+            None => true,
+        };
+        if better {
+            best = Some((distance, candidate));
         }
     }
+
+    best.map(|(_, name)| name.clone())
 }
@@ -0,0 +1,453 @@
+use std::{
+    fmt::{Display, Write},
+    ops::{Add, Mul, Neg},
+};
+
+use rug::{float::Constant, ops::Pow, Float, Integer, Rational};
+
+use crate::{
+    fixed::Op,
+    token::Literal,
+    value::{Value, ValueError},
+};
+
+// An exact number: a rational, π, a square root, or sums and products of
+// them. Arithmetic on exact numbers and integers stays exact, so that
+// `sqrt(2) * sqrt(2)` is 2 and `rational(1, 3) * 3` is 1; a float operand,
+// or `approx`, collapses one into a float, with all the digits of the
+// float's precision.
+//
+// Results are simplified as they are made: like terms of a sum are added up,
+// rationals are folded together, square roots of rationals are taken out of
+// their squares, and an exact that is an integer is given back as one.
+#[derive(Debug, Clone)]
+pub enum Exact {
+    // never an integer as a value, which is an `Integer` instead
+    Rational(Rational),
+    Pi,
+    Sqrt(Box<Exact>),
+    // at least two terms, none of them a sum, and in the order they came in
+    Sum(Vec<Exact>),
+    // a coefficient other than 0, and at least one factor that is neither a
+    // rational nor a product; a lone factor has a coefficient other than 1
+    Product(Rational, Vec<Exact>),
+    Inverse(Box<Exact>),
+}
+
+// how far square roots of rationals look for square factors to take out of
+// them; bigger ones are left in
+const SQUARE_FACTORS: u32 = 10_000;
+
+impl Exact {
+    // the integer, exact rational or symbolic value of an exact
+    pub fn into_value(self) -> Value {
+        match self {
+            Self::Rational(rational) if *rational.denom() == 1 => {
+                rational.into_numer_denom().0.into()
+            }
+            exact => Value::Exact(exact.into()),
+        }
+    }
+
+    // `value` as an exact, when it's an integer or one already
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Literal(Literal::Integer(i)) => Some(Self::Rational(Rational::from(&**i))),
+            Value::Exact(exact) => Some((**exact).clone()),
+            _ => None,
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        matches!(self, Self::Rational(r) if *r == 0)
+    }
+
+    // the coefficient of a term, and what it multiplies
+    fn term(self) -> (Rational, Vec<Exact>) {
+        match self {
+            Self::Rational(rational) => (rational, Vec::new()),
+            Self::Product(coefficient, factors) => (coefficient, factors),
+            other => (Rational::from(1), vec![other]),
+        }
+    }
+
+    fn from_term(coefficient: Rational, mut factors: Vec<Exact>) -> Self {
+        match factors.len() {
+            _ if coefficient == 0 => Self::Rational(coefficient),
+            0 => Self::Rational(coefficient),
+            1 if coefficient == 1 => factors.pop().unwrap(),
+            _ => Self::Product(coefficient, factors),
+        }
+    }
+
+    pub fn inverse(self) -> Result<Self, ValueError> {
+        Ok(match self {
+            Self::Rational(r) if r == 0 => return Err(ValueError::DivisionByZero),
+            Self::Rational(r) => Self::Rational(r.recip()),
+            Self::Inverse(inner) => *inner,
+            Self::Sqrt(inner) => match *inner {
+                Self::Rational(r) => {
+                    let (root, inner) = sqrt_rational(r.recip());
+                    Self::from_term(root, inner.into_iter().collect())
+                }
+                inner => Self::Inverse(Self::Sqrt(inner.into()).into()),
+            },
+            Self::Product(coefficient, factors) => {
+                let mut inverse = Self::Rational(coefficient.recip());
+                for factor in factors {
+                    inverse = inverse * factor.inverse()?;
+                }
+                inverse
+            }
+            other => Self::Inverse(other.into()),
+        })
+    }
+
+    // the square root; nothing of a negative rational
+    pub fn sqrt(self) -> Option<Self> {
+        match self {
+            Self::Rational(r) if r < 0 => None,
+            Self::Rational(r) => {
+                let (root, inner) = sqrt_rational(r);
+                Some(Self::from_term(root, inner.into_iter().collect()))
+            }
+            other => Some(Self::Sqrt(other.into())),
+        }
+    }
+
+    // the value rounded to `precision` bits, computed with enough more of
+    // them for the rounding of every step to stay below the last one
+    pub fn approx(&self, precision: u32) -> Float {
+        let mut float = self.float(precision + 64);
+        float.set_prec(precision);
+        float
+    }
+
+    fn float(&self, precision: u32) -> Float {
+        match self {
+            Self::Rational(r) => Float::with_val(precision, r),
+            Self::Pi => Float::with_val(precision, Constant::Pi),
+            Self::Sqrt(inner) => inner.float(precision).sqrt(),
+            Self::Sum(terms) => terms.iter().fold(Float::new(precision), |sum, term| {
+                sum + term.float(precision)
+            }),
+            Self::Product(coefficient, factors) => factors.iter().fold(
+                Float::with_val(precision, coefficient),
+                |product, factor| product * factor.float(precision),
+            ),
+            Self::Inverse(inner) => inner.float(precision).recip(),
+        }
+    }
+
+    // written as the source that makes it again: `pi`, `sqrt(2)` and
+    // `rational(1, 3)`
+    pub fn source(&self) -> String {
+        let mut output = String::new();
+        let _ = self.write(&mut output, true);
+        output
+    }
+
+    fn write(&self, f: &mut impl Write, source: bool) -> std::fmt::Result {
+        match self {
+            Self::Rational(r) if source && *r.denom() == 1 => write!(f, "{}", r.numer()),
+            Self::Rational(r) if source => write!(f, "rational({}, {})", r.numer(), r.denom()),
+            Self::Rational(r) => write!(f, "{}", r),
+            Self::Pi if source => write!(f, "pi"),
+            Self::Pi => write!(f, "π"),
+            Self::Sqrt(inner) if source => {
+                write!(f, "sqrt(")?;
+                inner.write(f, source)?;
+                write!(f, ")")
+            }
+            Self::Sqrt(inner) => {
+                write!(f, "√")?;
+                match **inner {
+                    Self::Rational(ref r) if *r.denom() == 1 && *r >= 0 => inner.write(f, source),
+                    Self::Pi => inner.write(f, source),
+                    _ => parenthesized(f, inner, source),
+                }
+            }
+            Self::Sum(terms) => {
+                for (index, term) in terms.iter().enumerate() {
+                    let negative =
+                        matches!(term, Self::Rational(r) | Self::Product(r, _) if *r < 0);
+                    match (index, negative) {
+                        (0, _) => term.write(f, source)?,
+                        (_, true) => {
+                            write!(f, " - ")?;
+                            (-term.clone()).write(f, source)?;
+                        }
+                        (_, false) => {
+                            write!(f, " + ")?;
+                            term.write(f, source)?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Self::Product(coefficient, factors) => {
+                // `2π/(3√5)`, or `2 * pi / (3 * sqrt(5))`
+                let separator = if source { " * " } else { "" };
+                let (numer, denom) = (coefficient.numer(), coefficient.denom());
+                let (inverses, factors): (Vec<_>, Vec<_>) = factors
+                    .iter()
+                    .partition(|factor| matches!(factor, Self::Inverse(_)));
+
+                let mut top = Vec::new();
+                if factors.is_empty() || numer.clone().abs() != 1 {
+                    top.push(numer.clone().abs().to_string());
+                }
+                for factor in factors {
+                    top.push(piece(factor, source, matches!(factor, Self::Sum(_))));
+                }
+                let mut bottom = Vec::new();
+                if *denom != 1 {
+                    bottom.push(denom.to_string());
+                }
+                for inverse in inverses {
+                    if let Self::Inverse(inner) = inverse {
+                        let grouped = matches!(**inner, Self::Sum(_) | Self::Product(..));
+                        bottom.push(piece(inner, source, grouped));
+                    }
+                }
+
+                if *numer < 0 {
+                    write!(f, "-")?;
+                }
+                write!(f, "{}", top.join(separator))?;
+                let over = if source { " / " } else { "/" };
+                match bottom.len() {
+                    0 => Ok(()),
+                    1 => write!(f, "{}{}", over, bottom[0]),
+                    _ => write!(f, "{}({})", over, bottom.join(separator)),
+                }
+            }
+            Self::Inverse(inner) => {
+                write!(f, "{}", if source { "1 / " } else { "1/" })?;
+                match **inner {
+                    Self::Pi | Self::Sqrt(_) => inner.write(f, source),
+                    _ => parenthesized(f, inner, source),
+                }
+            }
+        }
+    }
+}
+
+impl Add for Exact {
+    type Output = Exact;
+
+    fn add(self, rhs: Self) -> Self {
+        let mut terms: Vec<(Rational, Vec<Exact>)> = Vec::new();
+        for term in [self, rhs] {
+            let summands = match term {
+                Self::Sum(summands) => summands,
+                other => vec![other],
+            };
+            for summand in summands {
+                let (coefficient, factors) = summand.term();
+                match terms.iter_mut().find(|(_, other)| same(other, &factors)) {
+                    Some((sum, _)) => *sum += coefficient,
+                    None => terms.push((coefficient, factors)),
+                }
+            }
+        }
+
+        terms.retain(|(coefficient, _)| *coefficient != 0);
+        let mut terms: Vec<_> = terms
+            .into_iter()
+            .map(|(coefficient, factors)| Self::from_term(coefficient, factors))
+            .collect();
+        match terms.len() {
+            0 => Self::Rational(Rational::new()),
+            1 => terms.pop().unwrap(),
+            _ => Self::Sum(terms),
+        }
+    }
+}
+
+impl Mul for Exact {
+    type Output = Exact;
+
+    fn mul(self, rhs: Self) -> Self {
+        // a rational times a sum is the sum of it times every term
+        let (lhs, rhs) = match (self, rhs) {
+            (Self::Rational(r), Self::Sum(terms)) | (Self::Sum(terms), Self::Rational(r)) => {
+                let zero = Self::Rational(Rational::new());
+                return terms
+                    .into_iter()
+                    .fold(zero, |sum, term| sum + Self::Rational(r.clone()) * term);
+            }
+            (lhs, rhs) => (lhs.term(), rhs.term()),
+        };
+        let mut coefficient = lhs.0 * rhs.0;
+        let mut radicand = Rational::from(1);
+        let mut factors: Vec<Exact> = Vec::new();
+        for factor in lhs.1.into_iter().chain(rhs.1) {
+            match factor {
+                Self::Sqrt(inner) => match *inner {
+                    Self::Rational(r) => radicand *= r,
+                    inner => factors.push(Self::Sqrt(inner.into())),
+                },
+                factor => {
+                    // `x` and `1 / x` cancel out
+                    let inverse = factors.iter().position(|other| match (other, &factor) {
+                        (Self::Inverse(a), b) | (b, Self::Inverse(a)) => same_one(a, b),
+                        _ => false,
+                    });
+                    match inverse {
+                        Some(index) => drop(factors.remove(index)),
+                        None => factors.push(factor),
+                    }
+                }
+            }
+        }
+        if radicand != 1 {
+            let (root, inner) = sqrt_rational(radicand);
+            coefficient *= root;
+            factors.extend(inner);
+        }
+        // π first and square roots after the rest, as they are written
+        factors.sort_by_key(|factor| match factor {
+            Self::Pi => 0,
+            Self::Sqrt(_) => 2,
+            _ => 1,
+        });
+        Self::from_term(coefficient, factors)
+    }
+}
+
+impl Neg for Exact {
+    type Output = Exact;
+
+    fn neg(self) -> Self {
+        Self::Rational(Rational::from(-1)) * self
+    }
+}
+
+fn parenthesized(f: &mut impl Write, exact: &Exact, source: bool) -> std::fmt::Result {
+    write!(f, "(")?;
+    exact.write(f, source)?;
+    write!(f, ")")
+}
+
+fn piece(exact: &Exact, source: bool, grouped: bool) -> String {
+    let mut output = String::new();
+    let _ = match grouped {
+        true => parenthesized(&mut output, exact, source),
+        false => exact.write(&mut output, source),
+    };
+    output
+}
+
+// the same factors, in the same order, as `mul` sorts them
+fn same(lhs: &[Exact], rhs: &[Exact]) -> bool {
+    lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(a, b)| same_one(a, b))
+}
+
+fn same_one(lhs: &Exact, rhs: &Exact) -> bool {
+    match (lhs, rhs) {
+        (Exact::Rational(a), Exact::Rational(b)) => a == b,
+        (Exact::Pi, Exact::Pi) => true,
+        (Exact::Sqrt(a), Exact::Sqrt(b)) | (Exact::Inverse(a), Exact::Inverse(b)) => same_one(a, b),
+        (Exact::Sum(a), Exact::Sum(b)) => same(a, b),
+        (Exact::Product(a, x), Exact::Product(b, y)) => a == b && same(x, y),
+        _ => false,
+    }
+}
+
+// √r as the rational outside of it, and what's left under it, if anything:
+// √(8/3) is (2/3)√6
+fn sqrt_rational(r: Rational) -> (Rational, Option<Exact>) {
+    let (numer, denom) = r.into_numer_denom();
+    // √(a/b) = √(ab) / b
+    let radicand = numer * &denom;
+    let (root, rest) = match radicand.is_perfect_square() {
+        true => (radicand.sqrt(), Integer::from(1)),
+        false => square_factors(radicand),
+    };
+    let outside = Rational::from((root, denom));
+    match rest == 1 {
+        true => (outside, None),
+        false => (
+            outside,
+            Some(Exact::Sqrt(Exact::Rational(rest.into()).into())),
+        ),
+    }
+}
+
+// `n` as root² · rest, with the square factors below `SQUARE_FACTORS`
+// taken out of rest
+fn square_factors(mut n: Integer) -> (Integer, Integer) {
+    let mut root = Integer::from(1);
+    let mut factor = 2u32;
+    while factor < SQUARE_FACTORS && Integer::from(factor).pow(2u32) <= n {
+        let square = Integer::from(factor).pow(2u32);
+        while n.is_divisible(&square) {
+            n /= &square;
+            root *= factor;
+        }
+        factor += 1;
+    }
+    if n.is_perfect_square() {
+        root *= n.clone().sqrt();
+        n = Integer::from(1);
+    }
+    (root, n)
+}
+
+impl PartialEq for Exact {
+    fn eq(&self, other: &Self) -> bool {
+        (self.clone() + -other.clone()).is_zero()
+    }
+}
+
+impl PartialOrd for Exact {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        if self == other {
+            return Some(std::cmp::Ordering::Equal);
+        }
+        self.approx(256).partial_cmp(&other.approx(256))
+    }
+}
+
+impl Display for Exact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, false)
+    }
+}
+
+// arithmetic with at least one exact operand: exact with the other one an
+// integer or exact too, and at its precision with a float, which the exact
+// one collapses into
+pub fn arithmetic(op: Op, lhs: Value, rhs: Value) -> Result<Value, ValueError> {
+    match (Exact::from_value(&lhs), Exact::from_value(&rhs)) {
+        (Some(lhs), Some(rhs)) => Ok(match op {
+            Op::Add => lhs + rhs,
+            Op::Sub => lhs + -rhs,
+            Op::Mul => lhs * rhs,
+            Op::Div => lhs * rhs.inverse()?,
+        }
+        .into_value()),
+        _ => {
+            let precision = [&lhs, &rhs].into_iter().find_map(|value| match value {
+                Value::Literal(Literal::Float(f)) => Some(f.prec()),
+                _ => None,
+            });
+            let precision = precision.ok_or(ValueError::UnsupportedOperation)?;
+            let (lhs, rhs) = (collapse(lhs, precision), collapse(rhs, precision));
+            match op {
+                Op::Add => lhs + rhs,
+                Op::Sub => lhs - rhs,
+                Op::Mul => lhs * rhs,
+                Op::Div => lhs / rhs,
+            }
+        }
+    }
+}
+
+fn collapse(value: Value, precision: u32) -> Value {
+    match value {
+        Value::Exact(exact) => exact.approx(precision).into(),
+        value => value,
+    }
+}
@@ -0,0 +1,721 @@
+// Extended descriptions for the diagnostic codes in report.rs, printed by
+// `ix explain <code>`. Codes are grouped by the stage raising them: E00xx
+// lexer, E01xx parser, E02xx resolver, E03xx runtime. Lints, which are
+// warnings unless denied, use W instead of E. Never reuse a code.
+
+pub struct Explanation {
+    pub code: &'static str,
+    pub title: &'static str,
+    pub text: &'static str,
+}
+
+pub const EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: "E0001",
+        title: "unexpected character",
+        text: "\
+The lexer found a character that cannot start any token.
+
+Identifiers must start with an ASCII letter and may contain ASCII letters,
+digits and underscores. Comments start with `#`.
+
+    let é = 1;    # error: `é` is not a valid identifier character
+    let e = 1;    # ok
+",
+    },
+    Explanation {
+        code: "E0002",
+        title: "malformed float precision",
+        text: "\
+A float literal carries a precision suffix that is not a valid number of bits.
+
+The digits after `_` give the precision (in bits) of the float. It must be a
+positive integer within the range supported by MPFR.
+
+    let x = 1.5_0;     # error: zero bits of precision
+    let x = 1.5_128;   # ok: 128 bits of precision
+",
+    },
+    Explanation {
+        code: "E0003",
+        title: "malformed number",
+        text: "\
+A numeric literal could not be parsed.
+
+    let x = 1e;     # error: the exponent has no digits
+    let x = 1e3;    # ok
+",
+    },
+    Explanation {
+        code: "E0004",
+        title: "unterminated sequence",
+        text: "\
+A string literal is missing its closing quote.
+
+    let s = \"hello;     # error
+    let s = \"hello\";    # ok
+",
+    },
+    Explanation {
+        code: "E0005",
+        title: "invalid escape sequence",
+        text: "\
+A backslash in a string or bytes literal is followed by something that is
+not a known escape. Both understand `\\0`, `\\n`, `\\r`, `\\t`, `\\\\` and
+`\\\"`; strings also take `\\u{...}`, the hex code of a character, and bytes
+literals `\\xNN` (two hex digits).
+
+    \"C:\\data\"       # error: `\\d` isn't an escape
+    \"C:\\\\data\"      # ok
+    \"caf\\u{e9}\"     # ok: the string café
+    b\"\\q\"          # error
+    b\"\\x41\\n\"      # ok: the bytes 0x41 and 0x0a
+",
+    },
+    Explanation {
+        code: "E0006",
+        title: "unknown number suffix",
+        text: "\
+A numeric literal is followed by letters that are not a known suffix. `f`
+makes the literal a float and `r` an exact rational, which a decimal like
+`0.1` as a float isn't; `i8` to `i64` and `u8` to `u64` make an integer
+literal a machine integer of that type. A float's precision can't be given
+along with `r`.
+
+    let x = 10q;     # error
+    let x = 10f;     # ok: the float 10.0
+    let x = 0.1r;    # ok: same as rational(1, 10)
+    let x = 255u8;   # ok: same as u8(255)
+",
+    },
+    Explanation {
+        code: "E0007",
+        title: "literal out of range",
+        text: "\
+An integer literal with a machine integer suffix does not fit in that type.
+Unlike the `u8` to `u64` builtins, which wrap, a literal must be exact.
+
+The `-` in front of a literal is an operator, not part of it, so the most
+negative value of a signed type has to be made with its builtin.
+
+    let x = 256u8;    # error: a u8 goes from 0 to 255
+    let x = u8(256);  # ok: wraps to 0u8
+    let x = -128i8;   # error: 128 doesn't fit in an i8
+    let x = i8(-128); # ok
+",
+    },
+    Explanation {
+        code: "E0100",
+        title: "unexpected token",
+        text: "\
+The parser found a token that does not fit the grammar at this point. The help
+message says which token was expected.
+
+    let = 3;       # error: wanted an identifier after `let`
+    let x = 3;     # ok
+",
+    },
+    Explanation {
+        code: "E0101",
+        title: "expression nested too deeply",
+        text: "\
+Expressions, blocks and statements can be nested at most 256 levels deep.
+Deeper programs are rejected up front rather than overflowing the stack.
+
+Split the expression using intermediate `let` bindings or helper functions.
+",
+    },
+    Explanation {
+        code: "E0102",
+        title: "unexpected eof",
+        text: "\
+The source ended in the middle of a construct, usually because of a missing
+closing `)`, `]` or `}`.
+
+    fn f(a) { return a;     # error: the body is never closed
+    fn f(a) { return a; }   # ok
+",
+    },
+    Explanation {
+        code: "E0103",
+        title: "expected token",
+        text: "\
+A specific token was required but not found.
+",
+    },
+    Explanation {
+        code: "E0104",
+        title: "invalid assignment target",
+        text: "\
+The left-hand side of `=` must be a variable, an index (`a[i]`) or a property
+(`a.b`).
+
+    1 = x;         # error
+    (a) = 3;       # error: parentheses make this a value, not a place
+    a = 3;         # ok
+    list[0] = 3;   # ok
+",
+    },
+    Explanation {
+        code: "E0105",
+        title: "`else` without a matching `if`",
+        text: "\
+An `else` was found where a statement starts. It must directly follow the
+then branch of an `if`; anything in between, such as a second statement in
+an unbraced branch, ends the `if`.
+
+    if (a) x = 1; y = 2; else x = 0;      # error: `y = 2;` ended the if
+    if (a) { x = 1; y = 2; } else x = 0;  # ok
+",
+    },
+    Explanation {
+        code: "E0106",
+        title: "missing semicolon",
+        text: "\
+Expression statements, `let`, `return`, `throw` and `import` end with `;`,
+wherever they are: before a `}` and at the end of a file too. Statements
+ending in a block (`fn`, `class`, `if`, `while`, `for`, `try` and blocks
+themselves) don't need one.
+
+    let x = 1       # error
+    let x = 1;      # ok
+    fn f() { return x }    # error
+    fn f() { return x; }   # ok
+
+The REPL, `xi -c` and `eval` add the semicolon when their code doesn't end
+with one.
+",
+    },
+    Explanation {
+        code: "E0107",
+        title: "`unquote` outside of `quote`",
+        text: "\
+`unquote(value)` only means something inside `quote { ... }`: it evaluates
+`value` when the quote is, and puts it in the tree in its place. Inside the
+parentheses of an `unquote` the code is back outside the quote, so unquotes
+don't nest unless quotes do.
+
+    let n = 3;
+    quote { x * unquote(n); }    # the tree of `x * 3;`
+    unquote(n);                  # error
+",
+    },
+    Explanation {
+        code: "E0108",
+        title: "invalid annotation",
+        text: "\
+Annotations go in front of a `fn` or a `let`, and there are four of them:
+`@pure`, `@memo` and `@test` for functions, tests taking no arguments, and
+`@deprecated`, with the reason as a string or without one, for both.
+
+    @memo fn fibo(n) { ... }
+    @deprecated(\"use fibo\") let fib = fibo;
+    @test fn sums() { assert_eq(1 + 1, 2); }
+    @memo let cache = {};                        # error
+    @cached fn area(r) { ... }                   # error
+",
+    },
+    Explanation {
+        code: "E0200",
+        title: "illegal to read local variable in its own initializer",
+        text: "\
+A local variable was used inside the expression that initializes it. The new
+variable does not exist yet while its initializer runs.
+
+    {
+        let a = a + 1;    # error
+    }
+
+Give the new variable a different name, or assign to the outer one instead.
+",
+    },
+    Explanation {
+        code: "E0201",
+        title: "assignment to undeclared variable",
+        text: "\
+Assignment only changes variables that already exist; new ones are declared
+with `let`. A name that is assigned but never declared, in any scope around
+the assignment or at the top level of the program, is usually a typo.
+
+    let total = 0;
+    totl = total + 1;     # error: did you mean `total`?
+    let count = 1;        # declares `count`
+
+Top-level declarations count wherever they appear, so a function may assign
+to a global declared after it.
+",
+    },
+    Explanation {
+        code: "E0202",
+        title: "`this` outside of a class",
+        text: "\
+`this` is the instance a method was called on, so it only exists in the
+methods of a class declaration.
+
+    fn area() { return this.w * this.h; }          # error
+    class Rect { area() { return this.w * this.h; } }   # ok
+",
+    },
+    Explanation {
+        code: "E0203",
+        title: "`super` outside of a class",
+        text: "\
+`super.method` is `method` as the superclass of the class has it, bound to
+the same instance as `this`, so it only exists in the methods of a class
+declaration.
+
+    fn describe() { return super.describe(); }     # error
+",
+    },
+    Explanation {
+        code: "E0204",
+        title: "`super` in a class without a superclass",
+        text: "\
+`super` looks methods up in the superclass, which a class only has when it
+is declared with `<` and the class it inherits from.
+
+    class Shape { area() { return super.area(); } }             # error
+    class Square < Rect { area() { return super.area(); } }     # ok
+",
+    },
+    Explanation {
+        code: "E0205",
+        title: "class inherits from itself",
+        text: "\
+The superclass of a class is another class, declared before it.
+
+    class Node < Node { }       # error
+",
+    },
+    Explanation {
+        code: "E0300",
+        title: "unsupported operation",
+        text: "\
+An operator was applied to operands it is not defined for.
+
+    [1] - 1        # error: lists don't support `-`
+    \"ab\" * -1      # error: strings repeat a non-negative number of times
+    \"ab\" * 3       # ok: \"ababab\"
+",
+    },
+    Explanation {
+        code: "E0301",
+        title: "division by zero",
+        text: "\
+An integer was divided by zero. Integer division has no sensible result here;
+float division by zero produces `inf` instead.
+
+    1 / 0          # error
+    1.0 / 0        # inf
+",
+    },
+    Explanation {
+        code: "E0302",
+        title: "unhashable value",
+        text: "\
+Only nil, booleans, strings, integers, bytes, durations, datetimes,
+machine integers (`u8`, `i64`, ...) and functions can be hashed, which dict
+keys and set items must be; a function stands for that very function, not
+for functions that behave the same. Floats, lists, dicts and every other
+value cannot.
+
+    let d = {[1, 2]: 3};    # error
+    let s = #{[1, 2]};      # error
+    let d = {\"12\": 3};      # ok
+",
+    },
+    Explanation {
+        code: "E0303",
+        title: "undefined value",
+        text: "\
+A variable was read or assigned before being declared with `let`.
+
+    x = 1;         # error
+    let x = 1;     # ok
+    x = 2;         # ok
+",
+    },
+    Explanation {
+        code: "E0304",
+        title: "undefined property",
+        text: "\
+A property was read that the object does not have. A dict's properties are
+its string keys: `d.name` is `d[\"name\"]`, and `d.name(a)` calls that entry.
+
+A method call `x.f(a)` on a value without an `f` property calls the function
+`f` as `f(x, a)` instead, so this error also means no such function exists.
+
+    [1, 2].len()     # ok: same as len([1, 2])
+    [1, 2].size()    # error
+",
+    },
+    Explanation {
+        code: "E0305",
+        title: "only functions and classes can be called",
+        text: "\
+A value that is not callable was used with call syntax.
+
+    let x = 3;
+    x();           # error
+",
+    },
+    Explanation {
+        code: "E0306",
+        title: "only instances have properties",
+        text: "\
+Property access (`a.b`) was used on a value that has no properties. Only
+instances of classes, with their fields and methods, and dicts, with one per
+string key, have properties; calls like `[1, 2].len()` still work because
+they fall back to `len([1, 2])`.
+",
+    },
+    Explanation {
+        code: "E0307",
+        title: "only lists, dicts, strings and bytes can be indexed",
+        text: "\
+Index syntax (`a[i]`) was used on a value that is not a list, a dict, a
+string or bytes. All but dicts can also be sliced (`a[i:j]`).
+
+    let x = 3;
+    x[0];          # error
+",
+    },
+    Explanation {
+        code: "E0308",
+        title: "invalid index",
+        text: "\
+Lists, strings and bytes can only be indexed with non-negative integers.
+
+    let l = [1, 2, 3];
+    l[\"0\"];        # error
+    l[-1];         # error
+    l[0];          # ok
+",
+    },
+    Explanation {
+        code: "E0309",
+        title: "index out of bounds",
+        text: "\
+The index is past the end of the list, string or bytes. Valid indices go
+from 0 to one less than the length; a string's are counted in characters.
+
+    let l = [1, 2, 3];
+    l[3];          # error
+    l[len(l) - 1]; # ok
+",
+    },
+    Explanation {
+        code: "E0310",
+        title: "dict key does not exist",
+        text: "\
+The dict has no entry for the key being read. Assigning to a missing key
+inserts it instead.
+
+    let d = {\"a\": 1};
+    d[\"b\"];        # error
+    d[\"b\"] = 2;    # ok
+",
+    },
+    Explanation {
+        code: "E0311",
+        title: "native imports are disabled",
+        text: "\
+`import native` loads a shared library into the interpreter, which runs its
+code with the full privileges of the process. It is refused unless the
+interpreter was started with `--allow-native`.
+
+    import native \"./libfast_math.so\";    # error without --allow-native
+",
+    },
+    Explanation {
+        code: "E0312",
+        title: "native extension could not be loaded",
+        text: "\
+The shared library named by `import native` could not be opened, or does not
+export both `XI_ABI_VERSION` and `xi_register`. Paths without a `/` are
+searched for like any other shared library; use `./` for the current
+directory.
+",
+    },
+    Explanation {
+        code: "E0313",
+        title: "native extension ABI mismatch",
+        text: "\
+The plugin was compiled against a different version of the native extension
+interface (`include/xi.h`) than the one this interpreter implements. Rebuild
+it against the header shipped with this version.
+",
+    },
+    Explanation {
+        code: "E0314",
+        title: "native function failed",
+        text: "\
+A function provided by a native extension returned an error, or was called
+with arguments it cannot receive. Only nil, booleans, 64-bit integers,
+floats and strings can be passed to native functions.
+",
+    },
+    Explanation {
+        code: "E0315",
+        title: "wrong number of arguments",
+        text: "\
+A function was called with more or fewer arguments than it takes. Functions
+declared with `fn` take exactly one argument per parameter; some builtins,
+like `print`, take any number.
+
+    fn add(a, b) { return a + b; }
+    add(1)               # error: expected 2 arguments, found 1
+    keys()               # error: expected 1 argument, found 0
+    add(1, 2)            # ok
+",
+    },
+    Explanation {
+        code: "E0316",
+        title: "argument has the wrong type",
+        text: "\
+A builtin was passed an argument of a type it does not accept. The message
+names the position of the argument and the type that was expected. Integers
+are accepted where a float is expected.
+
+    keys([1, 2])         # error: argument 1 of `keys` must be dict
+",
+    },
+    Explanation {
+        code: "E0317",
+        title: "value is not iterable",
+        text: "\
+`for (x in ...)` and builtins like `map` and `sum` take an iterable: a list,
+a dict (its keys), a string (its characters) or an iterator such as the one
+returned by `range`. `for (k, v in ...)` takes the same ones, and binds the
+key and value of a dict's entries, or the index and item of the others.
+
+    for (x in 3) {}              # error
+    for (x in range(3)) {}       # ok: 0, 1, 2
+    for (k, v in {\"a\": 1}) {}  # ok: \"a\", 1
+
+Iterators are lazy: `map` and `filter` produce their items one at a time as
+they are consumed, and `collect` turns an iterator into a list.
+",
+    },
+    Explanation {
+        code: "E0318",
+        title: "template has no value for a placeholder",
+        text: "\
+`template(text, dict)` replaces each `{key}` in `text` with the value of the
+string key `key` in `dict`. Every placeholder must have an entry.
+
+    template(\"hi {name}\", {})                  # error
+    template(\"hi {name}\", {\"name\": \"ada\"})     # ok: \"hi ada\"
+",
+    },
+    Explanation {
+        code: "E0319",
+        title: "malformed template",
+        text: "\
+A template has an unclosed `{`, a stray `}` or a placeholder nested in
+another one. Double a brace to write it literally.
+
+    template(\"{a\", d)          # error: unclosed placeholder
+    template(\"{{a}}\", d)       # ok: \"{a}\"
+",
+    },
+    Explanation {
+        code: "E0320",
+        title: "input/output error",
+        text: "\
+Reading or writing a file failed. The message comes from the operating
+system, for example because the file does not exist or cannot be written.
+",
+    },
+    Explanation {
+        code: "E0321",
+        title: "invalid argument",
+        text: "\
+A builtin was given an argument of the right type but with a value it cannot
+work with, such as malformed hex or base64 text, or an integer that does not
+fit in the requested number of bytes.
+
+    from_hex(\"zz\")                # error: not hex digits
+    int_to_bytes(256, 1, \"big\")   # error: 256 needs 2 bytes
+",
+    },
+    Explanation {
+        code: "E0322",
+        title: "integer overflow",
+        text: "\
+Arithmetic on machine integers (made with `i8` to `i64` and `u8` to `u64`)
+produced a result outside the range of their type. Integers mixed in are
+converted to the machine integer's type, and must fit in it too. Use the
+`wrapping_`, `checked_` or `saturating_` builtins to pick what should happen
+instead.
+
+    u8(200) + 100                  # error: 300 doesn't fit in a u8
+    wrapping_add(u8(200), 100)     # 44u8
+    saturating_add(u8(200), 100)   # 255u8
+    checked_add(u8(200), 100)      # nil
+",
+    },
+    Explanation {
+        code: "E0323",
+        title: "assertion failed",
+        text: "\
+A call to `assert`, `assert_eq`, `assert_ne`, `assert_close` or
+`assert_raises` found that what it checks doesn't hold. The help shows the
+values involved, and for lists and dicts where they differ.
+
+    assert(1 > 2)                      # error: assertion failed
+    assert_eq([1, 2], [1, 3])          # error: $[1] is 2 on the left, 3 on the right
+    assert_close(0.1 + 0.2, 0.3, 1e-9) # ok
+",
+    },
+    Explanation {
+        code: "E0324",
+        title: "eval is disabled",
+        text: "\
+`eval` runs code from a string, and `compile` turns a tree from `parse` or
+`quote` into a function, with the same access as the script itself: a
+script that runs text it reads or receives can be made to do anything. Both
+are refused unless the interpreter was started with `--allow-eval`. `parse`
+and `quote` only build trees and are always allowed.
+
+    eval(\"1 + 2;\")                 # error without --allow-eval
+    compile(quote { x * x; }, [\"x\"])  # error without --allow-eval
+",
+    },
+    Explanation {
+        code: "E0325",
+        title: "item is not a pair",
+        text: "\
+`for (k, v in ...)` over an iterator binds the two items of each of its
+items, which must be lists of two. Dicts and lists don't have this problem:
+they give their entries, and their indices and items, as pairs themselves.
+
+    fn square(x) { return x * x; }
+    fn pair(x) { return [x, x * x]; }
+    for (k, v in map([1, 2], square)) {}   # error
+    for (k, v in map([1, 2], pair)) {}     # ok: 1, 1 and 2, 4
+",
+    },
+    Explanation {
+        code: "E0326",
+        title: "interrupted",
+        text: "\
+An `interrupt` request to `xi serve` stopped the evaluation that was running,
+at the expression it was about to evaluate. What it had already done, such
+as the variables it defined, is kept in the session; send `reset` to start
+over.
+",
+    },
+    Explanation {
+        code: "E0327",
+        title: "superclass is not a class",
+        text: "\
+`class Child < Parent` inherits the methods of `Parent`, so `Parent` must be a
+class when the declaration runs.
+
+    let Shape = nil;
+    class Square < Shape { }    # error
+
+    class Shape { area() { return 0; } }
+    class Square < Shape { }    # ok
+",
+    },
+    Explanation {
+        code: "E0328",
+        title: "uncaught throw",
+        text: "\
+`throw` stops the code and unwinds to the closest enclosing `try`, in the
+function or in the ones that called it. The program ends with this error
+when it finds none.
+
+    throw \"no config\";                 # error
+    try {
+        throw \"no config\";
+    } catch (error) {
+        println(error);                  # prints \"no config\"
+    }
+
+A `catch` gets other runtime errors too, as a {\"code\", \"message\"} dict.
+",
+    },
+    Explanation {
+        code: "E0329",
+        title: "no arm matches the value",
+        text: "\
+A `match` tries its arms in order and evaluates to the body of the first
+whose pattern matches. It is an error when none does; a last arm of `_`
+matches anything.
+
+    match (3) { 1 => \"one\", 2 => \"two\" }             # error
+    match (3) { 1 => \"one\", 2 => \"two\", _ => \"many\" }  # \"many\"
+",
+    },
+    Explanation {
+        code: "E0330",
+        title: "property only has a getter",
+        text: "\
+A class's `get name() { ... }` computes the property `name` of its
+instances whenever it is read, so there is no field to assign to. Setting
+the property takes a `set name(value) { ... }` as well, which is called with
+the value instead.
+
+    class Circle {
+        init(r) { this.r = r; }
+        get area() { return 3.14 * this.r * this.r; }
+    }
+    let c = Circle(2);
+    c.area;              # 12.56
+    c.area = 1;          # error
+",
+    },
+    Explanation {
+        code: "W0300",
+        title: "integer operand is rounded to the float's precision",
+        text: "\
+Arithmetic mixing an integer and a float is carried out at the float's
+precision, so an integer with more significant bits than that is silently
+rounded first. This lint is off by default; enable it with
+`--precision-loss warn`, or make it an error with `--precision-loss deny`.
+
+    let n = 1267650600228229401496703205377;   # 2^100 + 1
+    n + 1.0                                   # the `+ 1` is lost at 64 bits
+    n + 1.0_101                               # exact
+",
+    },
+    Explanation {
+        code: "W0301",
+        title: "assignment used as a condition",
+        text: "\
+An assignment is an expression whose value is the assigned value, so
+`if (x = 1)` assigns to `x` and always takes the branch. This is almost
+always a typo for `==`. When the assignment is intended, put it in another
+pair of parentheses to silence the warning, or bind a new variable with
+`let`, which also stops at nil rather than at any false value:
+
+    while ((line = read_stdout_line(p))) { println(line); }
+    while (let line = read_stdout_line(p)) { println(line); }
+",
+    },
+    Explanation {
+        code: "W0302",
+        title: "deprecated name used",
+        text: "\
+The function or variable was declared with `@deprecated`, and the warning
+gives the reason when the annotation has one. It's still there, and works as
+before, but is on its way out.
+
+    @deprecated(\"use area\") fn size(r) { return area(r); }
+    size(2);                                    # warning
+",
+    },
+];
+
+pub fn explain(code: &str) -> Option<&'static Explanation> {
+    let code = code.trim().to_ascii_uppercase();
+    let (prefix, number) = match code.strip_prefix(['E', 'W']) {
+        Some(number) => (&code[..1], number),
+        None => ("E", code.as_str()),
+    };
+    let code = format!("{}{:0>4}", prefix, number);
+    EXPLANATIONS.iter().find(|e| e.code == code)
+}
@@ -1,8 +1,7 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-
+use crate::annotation::Annotation;
 use crate::env::Env;
-use crate::token::{Span, Token};
+use crate::sync::{Rc, RefCell};
+use crate::token::{Operator, Span};
 use crate::value::Value;
 
 pub trait Identifiable {
@@ -12,12 +11,12 @@ pub trait Identifiable {
 #[derive(Debug)]
 pub enum ExprKind {
     Assign {
-        name: String,
+        name: Rc<str>,
         value: Box<Expr>,
     },
     Binary {
         left: Box<Expr>,
-        op: Token,
+        op: Operator,
         right: Box<Expr>,
     },
     Call {
@@ -28,6 +27,15 @@ pub enum ExprKind {
         obj: Box<Expr>,
         index: Box<Expr>,
     },
+    // `...value` in a list literal or call arguments
+    Spread {
+        value: Box<Expr>,
+    },
+    Slice {
+        obj: Box<Expr>,
+        start: Option<Box<Expr>>,
+        end: Option<Box<Expr>>,
+    },
     SetIndex {
         obj: Box<Expr>,
         index: Box<Expr>,
@@ -39,9 +47,13 @@ pub enum ExprKind {
     Dict {
         items: Vec<(Expr, Expr)>,
     },
+    // `#{items}`, which may be spread like a list's
+    SetLiteral {
+        items: Vec<Expr>,
+    },
     Get {
         obj: Box<Expr>,
-        name: String,
+        name: Rc<str>,
     },
     Grouping {
         value: Box<Expr>,
@@ -51,21 +63,102 @@ pub enum ExprKind {
     },
     Logical {
         left: Box<Expr>,
-        op: Token,
+        op: Operator,
         right: Box<Expr>,
     },
+    // `cond ? then_branch : else_branch`, which evaluates only the branch it
+    // takes
+    Conditional {
+        cond: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+    },
     Set {
         obj: Box<Expr>,
-        name: String,
+        name: Rc<str>,
         value: Box<Expr>,
     },
     Unary {
-        op: Token,
+        op: Operator,
         right: Box<Expr>,
     },
     Variable {
-        name: String,
+        name: Rc<str>,
+    },
+    // the instance a method was called on
+    This,
+    // `super.method`, the method of the superclass bound to `this`
+    Super {
+        method: Rc<str>,
+    },
+    // `quote { ... }`, the statements as a tree of dicts rather than code to
+    // run; `unquote(value)` in them is evaluated and spliced in
+    Quote {
+        body: Vec<Stmt>,
     },
+    Unquote {
+        value: Box<Expr>,
+    },
+    // `match (scrutinee) { pattern => body, ... }`, the body of the first arm
+    // whose pattern matches
+    Match {
+        scrutinee: Box<Expr>,
+        arms: Vec<Arm>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Arm {
+    pub pattern: Pattern,
+    pub body: Box<Expr>,
+}
+
+#[derive(Debug)]
+pub enum PatternKind {
+    // `_`, anything
+    Wildcard,
+    Literal {
+        value: Value,
+    },
+    // anything, bound to the name in the arm
+    Binding {
+        name: Rc<str>,
+    },
+    // `[a, b]`, lists of as many items, or `[a, b, rest...]`, of at least as
+    // many, the rest a wildcard or bound to a list of the items left
+    List {
+        items: Vec<Pattern>,
+        rest: Option<Box<Pattern>>,
+    },
+    // `{"key": pattern}`, dicts with at least these keys
+    Dict {
+        entries: Vec<(Value, Pattern)>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub span: Span,
+}
+
+impl Pattern {
+    // the names the pattern binds, in order
+    pub fn bindings(&self) -> Vec<&Rc<str>> {
+        match &self.kind {
+            PatternKind::Wildcard | PatternKind::Literal { .. } => vec![],
+            PatternKind::Binding { name } => vec![name],
+            PatternKind::List { items, rest } => items
+                .iter()
+                .chain(rest.as_deref())
+                .flat_map(Pattern::bindings)
+                .collect(),
+            PatternKind::Dict { entries } => entries
+                .iter()
+                .flat_map(|(_, pattern)| pattern.bindings())
+                .collect(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -86,15 +179,45 @@ pub enum StmtKind {
     Block {
         statements: Vec<Stmt>,
     },
+    // `class Name < Superclass { ... }`, its methods all `StmtKind::Function`s
+    // and so its `get name() { ... }` and `set name(value) { ... }` too
+    Class {
+        name: Rc<str>,
+        superclass: Option<Rc<str>>,
+        methods: Vec<Stmt>,
+        getters: Vec<Stmt>,
+        setters: Vec<Stmt>,
+    },
     Expression {
         expr: Box<Expr>,
     },
+    Throw {
+        value: Box<Expr>,
+    },
+    // `try { ... } catch (name) { ... }`, both blocks
+    Try {
+        body: Box<Stmt>,
+        name: Rc<str>,
+        handler: Box<Stmt>,
+    },
     Function {
-        name: String,
-        params: Rc<Vec<String>>,
+        name: Rc<str>,
+        params: Rc<Vec<Rc<str>>>,
         body: Rc<Vec<Stmt>>,
+        annotations: Vec<Annotation>,
     },
+    // `for (name in iterable)`, or `for (name, value in iterable)` over the
+    // key and value of every entry
+    ForIn {
+        name: Rc<str>,
+        value: Option<Rc<str>>,
+        iterable: Box<Expr>,
+        body: Box<Stmt>,
+    },
+    // `if (let x = e)` and `while (let x = e)` bind the value of `e` to `x`
+    // in the branch or body, and take it when it isn't nil
     If {
+        binding: Option<Rc<str>>,
         cond: Box<Expr>,
         then_branch: Box<Stmt>,
         else_branch: Option<Box<Stmt>>,
@@ -103,10 +226,15 @@ pub enum StmtKind {
         expr: Option<Box<Expr>>,
     },
     Let {
-        name: String,
+        name: Rc<str>,
         initializer: Option<Box<Expr>>,
+        annotations: Vec<Annotation>,
+    },
+    NativeImport {
+        path: String,
     },
     While {
+        binding: Option<Rc<str>>,
         cond: Box<Expr>,
         body: Box<Stmt>,
     },
@@ -0,0 +1,61 @@
+use std::{
+    fmt::Display,
+    fs,
+    io::{self, BufRead, BufReader},
+};
+
+// A file open for reading a line at a time, so that a script can go through
+// one that doesn't fit in memory.
+#[derive(Debug)]
+pub struct File {
+    path: String,
+    // None once closed
+    reader: Option<BufReader<fs::File>>,
+}
+
+impl File {
+    pub fn open(path: &str) -> io::Result<Self> {
+        Ok(Self {
+            path: path.to_string(),
+            reader: Some(BufReader::new(fs::File::open(path)?)),
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // None at the end of the file, and from then on once it's closed
+    pub fn read_line(&mut self) -> io::Result<Option<String>> {
+        match &mut self.reader {
+            Some(reader) => read_line(reader),
+            None => Ok(None),
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.reader = None;
+    }
+}
+
+// the next line without its line ending, or None at the end of the input
+pub fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    match reader.read_line(&mut line)? {
+        0 => Ok(None),
+        _ => {
+            let trimmed = line.trim_end_matches(['\n', '\r']).len();
+            line.truncate(trimmed);
+            Ok(Some(line))
+        }
+    }
+}
+
+impl Display for File {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.reader {
+            Some(_) => write!(f, "(file {})", self.path),
+            None => write!(f, "(file {}, closed)", self.path),
+        }
+    }
+}
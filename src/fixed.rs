@@ -0,0 +1,187 @@
+use std::fmt::Display;
+
+use rug::Integer;
+
+use crate::{
+    token::Literal,
+    value::{Value, ValueError},
+};
+
+// The width and signedness of a machine integer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Kind {
+    pub bits: u32,
+    pub signed: bool,
+}
+
+// A machine integer: `value` is always within the range of `kind`.
+// Arithmetic between two of them, or one and an integer, is checked; the
+// wrapping and saturating variants are builtins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fixed {
+    pub value: i128,
+    pub kind: Kind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+// what happens when the exact result doesn't fit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overflow {
+    Wrap,
+    Check,
+    Saturate,
+}
+
+pub const KINDS: [(&str, Kind); 8] = [
+    ("i8", Kind::new(8, true)),
+    ("i16", Kind::new(16, true)),
+    ("i32", Kind::new(32, true)),
+    ("i64", Kind::new(64, true)),
+    ("u8", Kind::new(8, false)),
+    ("u16", Kind::new(16, false)),
+    ("u32", Kind::new(32, false)),
+    ("u64", Kind::new(64, false)),
+];
+
+impl Kind {
+    pub const fn new(bits: u32, signed: bool) -> Self {
+        Self { bits, signed }
+    }
+
+    pub fn name(&self) -> &'static str {
+        KINDS
+            .iter()
+            .find(|(_, kind)| kind == self)
+            .map(|(name, _)| *name)
+            .expect("every kind is listed in KINDS")
+    }
+
+    pub fn min(&self) -> i128 {
+        match self.signed {
+            true => -(1 << (self.bits - 1)),
+            false => 0,
+        }
+    }
+
+    pub fn max(&self) -> i128 {
+        match self.signed {
+            true => (1 << (self.bits - 1)) - 1,
+            false => (1 << self.bits) - 1,
+        }
+    }
+
+    // `value` if it is in range
+    pub fn exact(&self, value: &Integer) -> Option<Fixed> {
+        value
+            .to_i128()
+            .filter(|value| (self.min()..=self.max()).contains(value))
+            .map(|value| Fixed { value, kind: *self })
+    }
+
+    // the low `bits` bits of `value`, like a cast between machine integers
+    pub fn wrap(&self, value: &Integer) -> Fixed {
+        let modulus = Integer::from(1) << self.bits;
+        let mut value = Integer::from(value % &modulus);
+        if value < 0 {
+            value += &modulus;
+        }
+        if self.signed && value > self.max() {
+            value -= modulus;
+        }
+        Fixed {
+            value: value.to_i128().expect("wrapped into at most 64 bits"),
+            kind: *self,
+        }
+    }
+
+    pub fn saturate(&self, value: &Integer) -> Fixed {
+        let value = match value.to_i128() {
+            Some(value) => value.clamp(self.min(), self.max()),
+            None if *value < 0 => self.min(),
+            None => self.max(),
+        };
+        Fixed { value, kind: *self }
+    }
+}
+
+impl Fixed {
+    pub fn to_integer(self) -> Integer {
+        Integer::from(self.value)
+    }
+
+    // `None` when the result overflows and `overflow` is `Check`, or when
+    // dividing by zero
+    pub fn apply(self, op: Op, rhs: Fixed, overflow: Overflow) -> Option<Fixed> {
+        let (lhs, rhs) = (self.to_integer(), rhs.to_integer());
+        let exact = match op {
+            Op::Add => lhs + rhs,
+            Op::Sub => lhs - rhs,
+            Op::Mul => lhs * rhs,
+            Op::Div if rhs == 0 => return None,
+            Op::Div => lhs / rhs,
+        };
+        match overflow {
+            Overflow::Wrap => Some(self.kind.wrap(&exact)),
+            Overflow::Check => self.kind.exact(&exact),
+            Overflow::Saturate => Some(self.kind.saturate(&exact)),
+        }
+    }
+
+    // a bitwise operation on the two's complement bits, kept to the width
+    pub fn bits(self, rhs: Fixed, op: impl Fn(i128, i128) -> i128) -> Fixed {
+        self.kind.wrap(&Integer::from(op(self.value, rhs.value)))
+    }
+
+    // the bits shifted by `amount` (left when positive), dropping those that
+    // fall off either end; right shifts of signed values keep the sign
+    pub fn shift(self, amount: i32) -> Fixed {
+        let amount = amount.clamp(-(self.kind.bits as i32), self.kind.bits as i32);
+        let value = match amount >= 0 {
+            true => Integer::from(self.value) << amount as u32,
+            false => Integer::from(self.value) >> amount.unsigned_abs(),
+        };
+        self.kind.wrap(&value)
+    }
+}
+
+// the operands of arithmetic with at least one machine integer: both of the
+// same kind, or one and an integer that fits in it
+pub fn operands(lhs: Value, rhs: Value) -> Result<(Fixed, Fixed), ValueError> {
+    match (lhs, rhs) {
+        (Value::Fixed(lhs), Value::Fixed(rhs)) if lhs.kind == rhs.kind => Ok((lhs, rhs)),
+        (Value::Fixed(lhs), Value::Literal(Literal::Integer(rhs))) => {
+            let rhs = lhs.kind.exact(&rhs).ok_or(ValueError::Overflow)?;
+            Ok((lhs, rhs))
+        }
+        (Value::Literal(Literal::Integer(lhs)), Value::Fixed(rhs)) => {
+            let lhs = rhs.kind.exact(&lhs).ok_or(ValueError::Overflow)?;
+            Ok((lhs, rhs))
+        }
+        _ => Err(ValueError::UnsupportedOperation),
+    }
+}
+
+// checked arithmetic, as the operators do it
+pub fn arithmetic(op: Op, lhs: Value, rhs: Value) -> Result<Value, ValueError> {
+    let (lhs, rhs) = operands(lhs, rhs)?;
+    if op == Op::Div && rhs.value == 0 {
+        return Err(ValueError::DivisionByZero);
+    }
+    match lhs.apply(op, rhs, Overflow::Check) {
+        Some(result) => Ok(Value::Fixed(result)),
+        None => Err(ValueError::Overflow),
+    }
+}
+
+impl Display for Fixed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.value, self.kind.name())
+    }
+}
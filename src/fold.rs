@@ -0,0 +1,172 @@
+use crate::{
+    env::Env,
+    expr::{Expr, ExprKind, Stmt, StmtKind},
+    runtime::Config,
+    sync::{Rc, RefCell},
+    token::{Literal, Token, TokenKind},
+    value::Value,
+};
+
+// Dead-branch elimination for `cfg`: with the `--define`s known before a
+// program runs, an `if` whose condition only depends on `cfg("name")` calls,
+// negated, compared with literals or combined with `and` and `or`, is
+// replaced by the branch it takes before the program is resolved. Code for
+// a define that wasn't given costs nothing when the program runs, however
+// often it would have been reached.
+//
+// Other conditions are left alone, even constant ones like `if (false)`, and
+// so is code in quotes, which isn't run. A program that may mean some other
+// `cfg`, one it declares or assigns itself, isn't folded at all.
+
+// whether `cfg` in the program is the builtin: every `cfg` the program
+// mentions is a call rather than a declaration, and the global is. The global
+// is only looked up for a program that mentions `cfg`, as the lookup defines
+// the builtin in the globals.
+pub fn applies(tokens: &[Token], globals: &Rc<RefCell<Env>>) -> bool {
+    let mut mentions = false;
+    for (index, token) in tokens.iter().enumerate() {
+        if !matches!(&token.literal, Some(Literal::Identifier(name)) if name == "cfg") {
+            continue;
+        }
+        let call = tokens.get(index + 1).map(|next| next.kind) == Some(TokenKind::LeftParen)
+            && !matches!(
+                index.checked_sub(1).map(|i| tokens[i].kind),
+                Some(TokenKind::Fn)
+            );
+        if !call {
+            return false;
+        }
+        mentions = true;
+    }
+    // without a `cfg` call there's nothing to fold
+    !mentions
+        || match Env::get(globals, 0, "cfg", None) {
+            Ok((Value::Function(function), _)) => function.builtin() == Some("cfg"),
+            _ => false,
+        }
+}
+
+pub fn eliminate(statements: &mut [Stmt], config: &Config) {
+    for statement in statements {
+        stmt(statement, config);
+    }
+}
+
+fn stmt(stmt: &mut Stmt, config: &Config) {
+    let taken = match &stmt.kind {
+        StmtKind::If {
+            binding: None,
+            cond,
+            ..
+        } => constant(cond, config, false).map(|value| value.is_truthy()),
+        _ => None,
+    };
+    if let Some(taken) = taken {
+        // an `if` without the branch it takes does nothing, as an empty block
+        let empty = StmtKind::Block {
+            statements: Vec::new(),
+        };
+        let StmtKind::If {
+            then_branch,
+            else_branch,
+            ..
+        } = std::mem::replace(&mut stmt.kind, empty)
+        else {
+            unreachable!()
+        };
+        match (taken, else_branch) {
+            (true, _) => *stmt = *then_branch,
+            (false, Some(else_branch)) => *stmt = *else_branch,
+            (false, None) => {}
+        }
+        // the branch taken, an `else if` say, may fold further
+        return self::stmt(stmt, config);
+    }
+
+    match &mut stmt.kind {
+        StmtKind::Block { statements } => eliminate(statements, config),
+        StmtKind::Class {
+            methods,
+            getters,
+            setters,
+            ..
+        } => {
+            for functions in [methods, getters, setters] {
+                eliminate(functions, config);
+            }
+        }
+        // a body is only shared once the function is declared
+        StmtKind::Function { body, .. } => {
+            if let Some(body) = Rc::get_mut(body) {
+                eliminate(body, config);
+            }
+        }
+        StmtKind::If {
+            then_branch,
+            else_branch,
+            ..
+        } => {
+            self::stmt(then_branch, config);
+            if let Some(else_branch) = else_branch {
+                self::stmt(else_branch, config);
+            }
+        }
+        StmtKind::ForIn { body, .. } | StmtKind::While { body, .. } => self::stmt(body, config),
+        StmtKind::Try { body, handler, .. } => {
+            self::stmt(body, config);
+            self::stmt(handler, config);
+        }
+        StmtKind::Expression { .. }
+        | StmtKind::Let { .. }
+        | StmtKind::NativeImport { .. }
+        | StmtKind::Return { .. }
+        | StmtKind::Throw { .. } => {}
+    }
+}
+
+// the value of `expr` when it only depends on `cfg` calls; literals only
+// count next to one
+fn constant(expr: &Expr, config: &Config, literal: bool) -> Option<Value> {
+    match &expr.kind {
+        ExprKind::Literal { value } if literal => Some(value.clone()),
+        ExprKind::Grouping { value } => constant(value, config, literal),
+        ExprKind::Call { callee, args } => match (&callee.kind, args.as_slice()) {
+            (ExprKind::Variable { name }, [arg]) if name.as_ref() == "cfg" => match &arg.kind {
+                ExprKind::Literal {
+                    value: Value::Literal(Literal::String(name)),
+                } => Some(config.define(name)),
+                _ => None,
+            },
+            _ => None,
+        },
+        ExprKind::Unary { op, right } if op.kind == TokenKind::Bang => {
+            Some((!constant(right, config, false)?.is_truthy()).into())
+        }
+        ExprKind::Binary { left, op, right }
+            if matches!(op.kind, TokenKind::EqualEqual | TokenKind::BangEqual) =>
+        {
+            let (left, right) = operands(left, right, config)?;
+            Some(((left == right) == (op.kind == TokenKind::EqualEqual)).into())
+        }
+        ExprKind::Logical { left, op, right } => {
+            let (left, right) = operands(left, right, config)?;
+            let short = match op.kind {
+                TokenKind::And => !left.is_truthy(),
+                _ => left.is_truthy(),
+            };
+            Some(if short { left } else { right })
+        }
+        _ => None,
+    }
+}
+
+// both sides constant, one of them at least with a `cfg` call
+fn operands(left: &Expr, right: &Expr, config: &Config) -> Option<(Value, Value)> {
+    match constant(left, config, false) {
+        Some(left) => Some((left, constant(right, config, true)?)),
+        None => Some((
+            constant(left, config, true)?,
+            constant(right, config, false)?,
+        )),
+    }
+}
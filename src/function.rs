@@ -1,17 +1,55 @@
-use std::{
-    cell::RefCell,
-    fmt::Display,
-    rc::Rc,
-};
+use std::fmt::Display;
 
 use crate::{
     context::Ctx,
+    env::Env,
     expr::Stmt,
     interpreter::{interpret, RuntimeError},
+    sync::{MaybeSync, Rc, RefCell},
     value::Value,
 };
 
-pub trait Function: std::fmt::Debug + std::fmt::Display {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+    Exact(usize),
+    AtLeast(usize),
+    Range(usize, usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exact(n) => count == n,
+            Arity::AtLeast(n) => count >= n,
+            Arity::Range(min, max) => (min..=max).contains(&count),
+        }
+    }
+}
+
+impl Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        match *self {
+            Arity::Exact(n) => write!(f, "{} argument{}", n, plural(n)),
+            Arity::AtLeast(n) => write!(f, "at least {} argument{}", n, plural(n)),
+            Arity::Range(min, max) => write!(f, "{} to {} arguments", min, max),
+        }
+    }
+}
+
+// What calling a function can do besides giving its result, for the loop
+// hoisting in `hoist`: a pure call can be made once for the same arguments,
+// and neither kind changes any value the program can see
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Purity {
+    // the result depends on the arguments only
+    Pure,
+    // it only writes to the output
+    Output,
+    Impure,
+}
+
+pub trait Function: std::fmt::Debug + std::fmt::Display + MaybeSync {
     fn call(&self, env: &Rc<RefCell<Ctx>>, args: Vec<Value>) -> Result<Value, RuntimeError> {
         match self.run(env, args) {
             Ok(value) => Ok(value),
@@ -21,15 +59,36 @@ pub trait Function: std::fmt::Debug + std::fmt::Display {
     }
 
     fn run(&self, env: &Rc<RefCell<Ctx>>, args: Vec<Value>) -> Result<Value, RuntimeError>;
-    fn arity(&self) -> usize;
+    fn arity(&self) -> Arity;
+
+    // what the function keeps alive, for heap dumps: the environment it
+    // closes over and any values it holds
+    fn closure(&self) -> Option<Rc<RefCell<Env>>> {
+        None
+    }
+
+    fn referenced(&self) -> Vec<Value> {
+        Vec::new()
+    }
+
+    fn purity(&self) -> Purity {
+        Purity::Impure
+    }
+
+    // the name of the builtin the function is, if it is one
+    fn builtin(&self) -> Option<&'static str> {
+        None
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct SimpleFunction {
-    pub name: String,
-    pub params: Rc<Vec<String>>,
+    pub name: Rc<str>,
+    pub params: Rc<Vec<Rc<str>>>,
     pub body: Rc<Vec<Stmt>>,
     pub closure: Rc<RefCell<Ctx>>,
+    // Impure unless declared `@pure`, which is taken at its word
+    pub purity: Purity,
 }
 
 impl From<SimpleFunction> for Value {
@@ -49,13 +108,22 @@ impl Function for SimpleFunction {
         let new_env = Ctx::with_parent(&self.closure);
 
         for (i, name) in self.params.iter().enumerate() {
-            new_env.borrow_mut().define(name, args[i].clone())
+            let value = args.get(i).cloned().unwrap_or(Value::Nil);
+            new_env.borrow_mut().define(name, value)
         }
 
         interpret(&new_env, &self.body)
     }
 
-    fn arity(&self) -> usize {
-        self.params.len()
+    fn arity(&self) -> Arity {
+        Arity::Exact(self.params.len())
+    }
+
+    fn closure(&self) -> Option<Rc<RefCell<Env>>> {
+        Some(self.closure.borrow().env().clone())
+    }
+
+    fn purity(&self) -> Purity {
+        self.purity
     }
 }
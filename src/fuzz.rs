@@ -0,0 +1,122 @@
+//! Entry points for the `cargo fuzz` targets in `fuzz/`.
+//!
+//! Each stage of the pipeline must turn any input into either a value or a
+//! diagnostic: a panic here is a bug.
+
+use miette::Report;
+
+use crate::{
+    context::Ctx,
+    env::Env,
+    expr::Stmt,
+    interpreter::{interpret, RuntimeError},
+    lexer::Lexer,
+    parser::Parser,
+    printer,
+    resolver::Resolver,
+    runtime::Runtime,
+    session::Outcome,
+    sync::{Rc, RefCell},
+};
+
+pub fn fuzz_lex(data: &[u8]) {
+    if let Ok(source) = std::str::from_utf8(data) {
+        let _ = Lexer::new(source).scan_tokens();
+    }
+}
+
+pub fn fuzz_parse(data: &[u8]) {
+    if let Ok(source) = std::str::from_utf8(data) {
+        let mut lexer = Lexer::new(source);
+        if let Ok(tokens) = lexer.scan_tokens() {
+            if let Ok(statements) = Parser::new(tokens).parse() {
+                let _ = Resolver::default().resolve(&statements);
+            }
+        }
+    }
+}
+
+pub fn fuzz_eval(data: &[u8]) {
+    if let Ok(source) = std::str::from_utf8(data) {
+        let mut lexer = Lexer::new(source);
+        let Ok(tokens) = lexer.scan_tokens() else {
+            return;
+        };
+        let Ok(statements) = Parser::new(tokens).parse() else {
+            return;
+        };
+
+        let mut resolver = Resolver::default();
+        if resolver.resolve(&statements).is_err() {
+            return;
+        }
+
+        let context = Ctx::new(&Env::global(), Rc::new(resolver), Rc::default());
+        let context = Rc::new(RefCell::new(context));
+        let _ = interpret(&context, &statements);
+    }
+}
+
+// A way to run a program: what it printed, and its value or error.
+pub type Backend = fn(&[Stmt]) -> (String, Outcome);
+
+fn front_end(source: &str) -> Result<Vec<Stmt>, Report> {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.scan_tokens()?;
+    Parser::new(tokens).parse()
+}
+
+pub fn tree_walker(statements: &[Stmt]) -> (String, Outcome) {
+    let output = Rc::new(RefCell::new(String::new()));
+    let mut resolver = Resolver::default();
+    let result = match resolver.resolve(statements) {
+        Ok(()) => {
+            let runtime = Runtime::default().with_output(Some(output.clone()));
+            let context = Ctx::new(&Env::global(), Rc::new(resolver), Rc::new(runtime));
+            let context = Rc::new(RefCell::new(context));
+            interpret(&context, statements).or_else(RuntimeError::unwind)
+        }
+        Err(report) => Err(report),
+    };
+    (output.take(), Outcome::new(&result))
+}
+
+// The tree-walker again, on the program as the printer writes it back: a
+// second backend until there is a bytecode VM to compare against, and a check
+// that printing keeps a program's meaning.
+pub fn reprinted(statements: &[Stmt]) -> (String, Outcome) {
+    let source: Vec<_> = statements.iter().map(printer::stmt).collect();
+    match front_end(&source.join("\n")) {
+        Ok(statements) => tree_walker(&statements),
+        Err(report) => panic!("the printed program doesn't parse: {}", report),
+    }
+}
+
+pub const BACKENDS: [(&str, Backend); 2] = [("tree-walker", tree_walker), ("reprinted", reprinted)];
+
+// Every backend must print the same and end with the same value or error,
+// for any program that makes it through the front end. Mutating the scripts
+// in tests/ makes a good corpus: `cargo fuzz run differential tests`.
+pub fn fuzz_differential(data: &[u8]) {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(statements) = front_end(source) else {
+        return;
+    };
+
+    let mut results = BACKENDS.iter().map(|(name, run)| (name, run(&statements)));
+    let (first, expected) = results.next().unwrap();
+    // a program that doesn't agree with itself, as one printing a dict in
+    // its unspecified order, can't be compared
+    if BACKENDS[0].1(&statements) != expected {
+        return;
+    }
+    for (name, result) in results {
+        assert_eq!(
+            expected, result,
+            "{} and {} disagree on {:?}",
+            first, name, source
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    mem::size_of,
+};
+
+use crate::{
+    env::Env,
+    json::Json,
+    sync::{Rc, RefCell},
+    token::Literal,
+    value::Value,
+};
+
+// Something a heap dump has a node for: environments and the values that
+// live behind an `Rc`. Plain values are counted in the size of whatever
+// holds them.
+enum Node {
+    Env(Rc<RefCell<Env>>),
+    Value(Value),
+}
+
+fn address<T: ?Sized>(rc: &Rc<T>) -> usize {
+    Rc::as_ptr(rc).cast::<()>() as usize
+}
+
+impl Node {
+    fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Function(_)
+            | Value::List(_)
+            | Value::Dict(_)
+            | Value::Set(_)
+            | Value::Iterator(_)
+            | Value::Process(_)
+            | Value::File(_)
+            | Value::Progress(_)
+            | Value::Table(_)
+            | Value::Class(_)
+            | Value::Instance(_) => Some(Self::Value(value.clone())),
+            _ => None,
+        }
+    }
+
+    fn id(&self) -> usize {
+        match self {
+            Node::Env(env) => address(env),
+            Node::Value(Value::Function(f)) => address(f),
+            Node::Value(Value::List(l)) => address(l),
+            Node::Value(Value::Dict(d)) => address(d),
+            Node::Value(Value::Set(s)) => address(s),
+            Node::Value(Value::Iterator(i)) => address(i),
+            Node::Value(Value::Process(p)) => address(p),
+            Node::Value(Value::File(f)) => address(f),
+            Node::Value(Value::Progress(p)) => address(p),
+            Node::Value(Value::Table(t)) => address(t),
+            Node::Value(Value::Class(c)) => address(c),
+            Node::Value(Value::Instance(i)) => address(i),
+            Node::Value(_) => unreachable!("`Node::of` only wraps values behind an Rc"),
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Node::Env(_) => "env",
+            Node::Value(value) => value.type_name(),
+        }
+    }
+
+    // (size in bytes, edges to other nodes), the size being a rough count
+    // of what this node owns directly
+    fn visit(&self) -> (usize, Vec<(String, Node)>) {
+        let mut size = 0;
+        let mut edges = Vec::new();
+        let mut children = Vec::new();
+
+        match self {
+            Node::Env(env) => {
+                let env = env.borrow();
+                for (name, value) in env.bindings() {
+                    children.push((name.clone(), value.clone()));
+                }
+                size += env.bindings().map(|(name, _)| name.len()).sum::<usize>();
+                if let Some(enclosing) = &env.enclosing {
+                    edges.push(("(enclosing)".to_string(), Node::Env(enclosing.clone())));
+                }
+            }
+            Node::Value(Value::List(list)) => {
+                for (index, value) in list.borrow().0.iter().enumerate() {
+                    children.push((format!("[{}]", index), value.clone()));
+                }
+            }
+            Node::Value(Value::Dict(dict)) => {
+                for (key, value) in dict.borrow().0.iter() {
+                    children.push((format!("[{}]", key), value.clone()));
+                    children.push((format!("(key {})", key), key.0.clone()));
+                }
+            }
+            Node::Value(Value::Set(set)) => {
+                for item in set.0.iter() {
+                    children.push((format!("(item {})", item), item.0.clone()));
+                }
+            }
+            Node::Value(Value::Function(function)) => {
+                size += std::mem::size_of_val(&**function);
+                if let Some(closure) = function.closure() {
+                    edges.push(("(closure)".to_string(), Node::Env(closure)));
+                }
+                for (index, value) in function.referenced().into_iter().enumerate() {
+                    children.push((format!("(ref {})", index), value));
+                }
+            }
+            Node::Value(Value::Table(table)) => {
+                for column in &table.columns {
+                    size += column.name.len();
+                    for (index, value) in column.values.iter().enumerate() {
+                        children.push((format!("[{}][{}]", column.name, index), value.clone()));
+                    }
+                }
+            }
+            Node::Value(Value::Class(class)) => {
+                if let Some(superclass) = &class.superclass {
+                    edges.push((
+                        "(superclass)".to_string(),
+                        Node::Value(Value::Class(superclass.clone())),
+                    ));
+                }
+                for (name, method) in &class.methods {
+                    children.push((format!(".{}", name), Value::Function(method.clone())));
+                }
+                for (kind, functions) in [("get", &class.getters), ("set", &class.setters)] {
+                    for (name, function) in functions {
+                        let label = format!("({} {})", kind, name);
+                        children.push((label, Value::Function(function.clone())));
+                    }
+                }
+            }
+            Node::Value(Value::Instance(instance)) => {
+                let instance = instance.borrow();
+                edges.push((
+                    "(class)".to_string(),
+                    Node::Value(Value::Class(instance.class.clone())),
+                ));
+                for (name, value) in &instance.fields {
+                    size += name.len();
+                    children.push((format!(".{}", name), value.clone()));
+                }
+            }
+            Node::Value(_) => {}
+        }
+
+        for (label, value) in children {
+            size += size_of::<Value>() + inline_size(&value);
+            if let Some(node) = Node::of(&value) {
+                edges.push((label, node));
+            }
+        }
+
+        (size, edges)
+    }
+}
+
+// the heap bytes a plain value owns itself
+fn inline_size(value: &Value) -> usize {
+    match value {
+        Value::Literal(Literal::String(s)) => s.len(),
+        Value::Literal(Literal::Identifier(s)) => s.len(),
+        Value::Literal(Literal::Integer(i)) => i.significant_bits() as usize / 8,
+        Value::Literal(Literal::Float(f)) => f.prec() as usize / 8,
+        Value::Bytes(bytes) => bytes.len(),
+        _ => 0,
+    }
+}
+
+fn id(id: usize) -> Json {
+    format!("{:#x}", id).into()
+}
+
+// Every node reachable from `root`, as
+//
+//     {"roots": [id], "nodes": [{"id", "type", "size", "edges": [{"label", "to"}]}]}
+//
+// Weak references are not followed.
+pub fn dump(root: &Rc<RefCell<Env>>) -> Json {
+    let root = Node::Env(root.clone());
+    let roots = vec![id(root.id())];
+
+    let mut seen = HashSet::from([root.id()]);
+    let mut queue = VecDeque::from([root]);
+    let mut nodes = Vec::new();
+    while let Some(node) = queue.pop_front() {
+        let (size, edges) = node.visit();
+        let edges = edges
+            .into_iter()
+            .map(|(label, to)| {
+                let to_id = to.id();
+                if seen.insert(to_id) {
+                    queue.push_back(to);
+                }
+                Json::Object(vec![
+                    ("label".into(), label.into()),
+                    ("to".into(), id(to_id)),
+                ])
+            })
+            .collect();
+        nodes.push(Json::Object(vec![
+            ("id".into(), id(node.id())),
+            ("type".into(), node.type_name().into()),
+            ("size".into(), size.into()),
+            ("edges".into(), Json::Array(edges)),
+        ]));
+    }
+
+    Json::Object(vec![
+        ("roots".into(), Json::Array(roots)),
+        ("nodes".into(), Json::Array(nodes)),
+    ])
+}
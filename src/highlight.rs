@@ -0,0 +1,260 @@
+use std::fmt::Write;
+
+use clap::ValueEnum;
+
+use crate::{
+    ast::{BINARY, UNARY},
+    env::Env,
+    fixed::KINDS,
+    json::Json,
+    lexer::KEYWORDS,
+    printer,
+    token::TokenKind,
+};
+
+// Syntax definitions for editors, generated from the lexer's own tables (the
+// keywords, the operators and the number suffixes) and the builtins, so they
+// follow the language as it changes.
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    // a TextMate grammar, for VS Code, Sublime Text and others
+    Tmlanguage,
+    Vim,
+    Pygments,
+}
+
+enum Class {
+    Keyword,
+    Constant,
+    // `and`, `or`
+    Operator,
+}
+
+fn class(kind: TokenKind) -> Class {
+    match kind {
+        TokenKind::True | TokenKind::False | TokenKind::Nil => Class::Constant,
+        TokenKind::And | TokenKind::Or => Class::Operator,
+        _ => Class::Keyword,
+    }
+}
+
+fn keywords(wanted: fn(&Class) -> bool) -> Vec<&'static str> {
+    let mut keywords: Vec<_> = KEYWORDS
+        .iter()
+        .filter(|(_, kind)| wanted(&class(*kind)))
+        .map(|(keyword, _)| *keyword)
+        .collect();
+    keywords.sort();
+    keywords
+}
+
+fn builtins() -> Vec<String> {
+    let mut names: Vec<_> = Env::global().borrow().names().map(str::to_string).collect();
+    names.sort();
+    names
+}
+
+// longest first, so that `<=` wins over `<`
+fn operators() -> Vec<&'static str> {
+    let mut operators: Vec<_> = BINARY
+        .iter()
+        .chain(&UNARY)
+        .map(|kind| printer::operator(*kind))
+        .collect();
+    operators.extend(["=", "..."]);
+    operators.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    operators.dedup();
+    operators
+}
+
+// a regex alternation of literal words or symbols
+fn alternation<S: AsRef<str>>(items: &[S]) -> String {
+    let escaped: Vec<String> = items
+        .iter()
+        .map(|item| {
+            item.as_ref()
+                .chars()
+                .map(|c| match c.is_ascii_alphanumeric() || c == '_' {
+                    true => c.to_string(),
+                    false => format!("\\{}", c),
+                })
+                .collect()
+        })
+        .collect();
+    escaped.join("|")
+}
+
+// digits, a fraction, an exponent, a `_precision` and a type suffix
+fn number() -> String {
+    let suffixes: Vec<_> = KINDS.iter().map(|(name, _)| *name).chain(["f"]).collect();
+    format!(
+        "\\b[0-9]+(\\.[0-9]+)?([eE][+-]?[0-9]+)?(_[0-9]+)?({})?\\b",
+        suffixes.join("|")
+    )
+}
+
+// `#{` opens a set instead
+const COMMENT: &str = "#([^{].*)?$";
+const STRING: &str = "\"[^\"]*\"";
+const BYTES: &str = "b\"(\\\\.|[^\"\\\\])*\"";
+
+pub fn generate(format: Format) -> String {
+    match format {
+        Format::Tmlanguage => tmlanguage(),
+        Format::Vim => vim(),
+        Format::Pygments => pygments(),
+    }
+}
+
+fn tmlanguage() -> String {
+    let pattern = |name: &str, regex: String| {
+        Json::Object(vec![
+            ("name".to_string(), name.into()),
+            ("match".to_string(), regex.into()),
+        ])
+    };
+    let words = |words: &[&str]| format!("\\b({})\\b", alternation(words));
+    let patterns = vec![
+        pattern("comment.line.number-sign.xi", COMMENT.to_string()),
+        pattern("string.quoted.double.bytes.xi", BYTES.to_string()),
+        pattern("string.quoted.double.xi", STRING.to_string()),
+        pattern("constant.numeric.xi", number()),
+        pattern(
+            "constant.language.xi",
+            words(&keywords(|class| matches!(class, Class::Constant))),
+        ),
+        pattern(
+            "keyword.operator.word.xi",
+            words(&keywords(|class| matches!(class, Class::Operator))),
+        ),
+        pattern(
+            "keyword.control.xi",
+            words(&keywords(|class| matches!(class, Class::Keyword))),
+        ),
+        pattern(
+            "support.function.builtin.xi",
+            format!("\\b({})\\b", alternation(&builtins())),
+        ),
+        pattern("keyword.operator.xi", alternation(&operators())),
+    ];
+
+    let grammar = Json::Object(vec![
+        ("name".to_string(), "xi".into()),
+        ("scopeName".to_string(), "source.xi".into()),
+        ("fileTypes".to_string(), Json::Array(vec!["xi".into()])),
+        ("patterns".to_string(), Json::Array(patterns)),
+    ]);
+    format!("{}\n", grammar)
+}
+
+fn vim() -> String {
+    // a vim regex: `\<word\>` and `\|` between alternatives
+    let words = |words: &[String]| format!("\\<\\({}\\)\\>", words.join("\\|"));
+    let suffixes: Vec<_> = KINDS.iter().map(|(name, _)| *name).chain(["f"]).collect();
+    let number = format!(
+        "\\<[0-9]\\+\\(\\.[0-9]\\+\\)\\=\\([eE][+-]\\=[0-9]\\+\\)\\=\\(_[0-9]\\+\\)\\=\\({}\\)\\=\\>",
+        suffixes.join("\\|")
+    );
+
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "\" Vim syntax file for xi, made by `xi highlight --format vim`"
+    );
+    let _ = writeln!(output, "if exists(\"b:current_syntax\")\n  finish\nendif\n");
+    let _ = writeln!(
+        output,
+        "syn keyword xiKeyword {}",
+        keywords(|class| matches!(class, Class::Keyword)).join(" ")
+    );
+    let _ = writeln!(
+        output,
+        "syn keyword xiConstant {}",
+        keywords(|class| matches!(class, Class::Constant)).join(" ")
+    );
+    let _ = writeln!(
+        output,
+        "syn keyword xiOperatorWord {}",
+        keywords(|class| matches!(class, Class::Operator)).join(" ")
+    );
+    // a match rather than keywords: some names, like `contains`, are
+    // arguments of `syn keyword`
+    let _ = writeln!(output, "syn match xiBuiltin \"{}\"", words(&builtins()));
+    let _ = writeln!(output, "syn match xiNumber \"{}\"", number);
+    let _ = writeln!(
+        output,
+        "syn region xiBytes start=+b\"+ skip=+\\\\.+ end=+\"+"
+    );
+    let _ = writeln!(output, "syn region xiString start=+\"+ end=+\"+");
+    let _ = writeln!(output, "syn match xiComment \"{}\"", COMMENT);
+    let _ = writeln!(output);
+    for (group, link) in [
+        ("xiKeyword", "Keyword"),
+        ("xiConstant", "Constant"),
+        ("xiOperatorWord", "Operator"),
+        ("xiBuiltin", "Function"),
+        ("xiNumber", "Number"),
+        ("xiBytes", "String"),
+        ("xiString", "String"),
+        ("xiComment", "Comment"),
+    ] {
+        let _ = writeln!(output, "hi def link {} {}", group, link);
+    }
+    let _ = writeln!(output, "\nlet b:current_syntax = \"xi\"");
+    output
+}
+
+fn pygments() -> String {
+    let words = |words: &[&str]| {
+        let quoted: Vec<_> = words.iter().map(|word| format!("'{}'", word)).collect();
+        format!("words(({},), suffix=r'\\b')", quoted.join(", "))
+    };
+    let builtins = builtins();
+    let builtins: Vec<&str> = builtins.iter().map(String::as_str).collect();
+
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "# Pygments lexer for xi, made by `xi highlight --format pygments`"
+    );
+    let _ = writeln!(output, "from pygments.lexer import RegexLexer, words");
+    let _ = writeln!(
+        output,
+        "from pygments.token import Comment, Keyword, Name, Number, Operator, Punctuation, String, Whitespace\n\n"
+    );
+    let _ = writeln!(output, "class XiLexer(RegexLexer):");
+    let _ = writeln!(output, "    name = 'xi'");
+    let _ = writeln!(output, "    aliases = ['xi', 'ix']");
+    let _ = writeln!(output, "    filenames = ['*.xi']\n");
+    let _ = writeln!(output, "    tokens = {{");
+    let _ = writeln!(output, "        'root': [");
+    for (regex, token) in [
+        ("r'\\s+'".to_string(), "Whitespace"),
+        (format!("r'{}'", COMMENT), "Comment.Single"),
+        (format!("r'{}'", BYTES), "String"),
+        (format!("r'{}'", STRING), "String"),
+        (format!("r'{}'", number()), "Number"),
+        (
+            words(&keywords(|class| matches!(class, Class::Constant))),
+            "Keyword.Constant",
+        ),
+        (
+            words(&keywords(|class| matches!(class, Class::Operator))),
+            "Operator.Word",
+        ),
+        (
+            words(&keywords(|class| matches!(class, Class::Keyword))),
+            "Keyword",
+        ),
+        (words(&builtins), "Name.Builtin"),
+        ("r'[A-Za-z][A-Za-z0-9_]*'".to_string(), "Name"),
+        (format!("r'{}'", alternation(&operators())), "Operator"),
+        ("r'[(){}\\[\\],.;:]'".to_string(), "Punctuation"),
+    ] {
+        let _ = writeln!(output, "            ({}, {}),", regex, token);
+    }
+    let _ = writeln!(output, "        ],");
+    let _ = writeln!(output, "    }}");
+    output
+}
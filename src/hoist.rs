@@ -0,0 +1,259 @@
+use std::collections::HashSet;
+
+use crate::expr::{Expr, ExprKind, Stmt, StmtKind};
+
+// Loop-invariant code motion for `while` loops, and so for `for` loops: the
+// parts of the condition and the body that give the same value on every
+// iteration, like `len(xs)` or `2 * 3`, are evaluated once before the loop.
+//
+// A loop qualifies when nothing in it can change a value behind a name's
+// back: it has no `.name =` or `[index] =` assignments, no quotes and no
+// function declarations, and it only calls names it doesn't assign. Whether
+// those names are builtins from the purity table is only known once they are
+// looked up, so that check is left to the interpreter.
+
+pub struct Plan<'a> {
+    // the names the loop calls
+    pub callees: Vec<&'a Expr>,
+    // the invariant expressions, each with the names it calls
+    pub invariants: Vec<(&'a Expr, Vec<&'a str>)>,
+}
+
+pub fn plan<'a>(cond: &'a Expr, body: &'a Stmt) -> Option<Plan<'a>> {
+    let mut scan = Scan {
+        clean: true,
+        assigned: HashSet::new(),
+        callees: Vec::new(),
+    };
+    scan.expr(cond);
+    scan.stmt(body);
+    let assigned = |callee: &&Expr| match &callee.kind {
+        ExprKind::Variable { name } => scan.assigned.contains(name.as_ref()),
+        _ => true,
+    };
+    if !scan.clean || scan.callees.iter().any(assigned) {
+        return None;
+    }
+
+    let mut invariants = Vec::new();
+    collect(cond, &scan.assigned, &mut invariants);
+    let mut stmts = vec![body];
+    while let Some(stmt) = stmts.pop() {
+        let (exprs, children) = stmt_children(stmt);
+        for expr in exprs {
+            collect(expr, &scan.assigned, &mut invariants);
+        }
+        stmts.extend(children);
+    }
+    match invariants.is_empty() {
+        true => None,
+        false => Some(Plan {
+            callees: scan.callees,
+            invariants,
+        }),
+    }
+}
+
+struct Scan<'a> {
+    clean: bool,
+    // the names declared or assigned anywhere in the loop
+    assigned: HashSet<&'a str>,
+    callees: Vec<&'a Expr>,
+}
+
+impl<'a> Scan<'a> {
+    fn stmt(&mut self, stmt: &'a Stmt) {
+        match &stmt.kind {
+            StmtKind::Function { .. } | StmtKind::Class { .. } | StmtKind::NativeImport { .. } => {
+                self.clean = false
+            }
+            StmtKind::Let { name, .. }
+            | StmtKind::ForIn { name, .. }
+            | StmtKind::Try { name, .. }
+            | StmtKind::If {
+                binding: Some(name),
+                ..
+            }
+            | StmtKind::While {
+                binding: Some(name),
+                ..
+            } => {
+                self.assigned.insert(name);
+            }
+            _ => {}
+        }
+        if let StmtKind::ForIn {
+            value: Some(value), ..
+        } = &stmt.kind
+        {
+            self.assigned.insert(value);
+        }
+        let (exprs, stmts) = stmt_children(stmt);
+        for expr in exprs {
+            self.expr(expr);
+        }
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn expr(&mut self, expr: &'a Expr) {
+        match &expr.kind {
+            ExprKind::Set { .. }
+            | ExprKind::SetIndex { .. }
+            | ExprKind::Quote { .. }
+            | ExprKind::Unquote { .. } => self.clean = false,
+            ExprKind::Assign { name, .. } => {
+                self.assigned.insert(name);
+            }
+            ExprKind::Match { arms, .. } => {
+                for arm in arms {
+                    self.assigned
+                        .extend(arm.pattern.bindings().into_iter().map(|name| &**name));
+                }
+            }
+            ExprKind::Call { callee, .. } => match callee.kind {
+                ExprKind::Variable { .. } => self.callees.push(callee),
+                // a method, or a function from an expression
+                _ => self.clean = false,
+            },
+            _ => {}
+        }
+        for child in expr_children(expr) {
+            self.expr(child);
+        }
+    }
+}
+
+// the largest invariant expressions in `expr`, leaving out the ones as cheap
+// as a lookup of the cache
+fn collect<'a>(expr: &'a Expr, assigned: &HashSet<&str>, out: &mut Vec<(&'a Expr, Vec<&'a str>)>) {
+    let mut calls = Vec::new();
+    if worth(expr) && invariant(expr, assigned, &mut calls) {
+        out.push((expr, calls));
+        return;
+    }
+    for child in expr_children(expr) {
+        collect(child, assigned, out);
+    }
+}
+
+fn worth(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Literal { .. } | ExprKind::Variable { .. } => false,
+        ExprKind::Grouping { value } => worth(value),
+        _ => true,
+    }
+}
+
+// not `.name`, which a getter may compute any way it likes
+fn invariant<'a>(expr: &'a Expr, assigned: &HashSet<&str>, calls: &mut Vec<&'a str>) -> bool {
+    let all = |exprs: &[&'a Expr], calls: &mut Vec<&'a str>| {
+        exprs.iter().all(|expr| invariant(expr, assigned, calls))
+    };
+    match &expr.kind {
+        ExprKind::Literal { .. } => true,
+        ExprKind::Variable { name } => !assigned.contains(name.as_ref()),
+        ExprKind::Grouping { value } | ExprKind::Unary { right: value, .. } => all(&[value], calls),
+        ExprKind::Binary { left, right, .. } | ExprKind::Logical { left, right, .. } => {
+            all(&[left, right], calls)
+        }
+        ExprKind::Conditional {
+            cond,
+            then_branch,
+            else_branch,
+        } => all(&[cond, then_branch, else_branch], calls),
+        ExprKind::GetIndex { obj, index } => all(&[obj, index], calls),
+        ExprKind::Slice { obj, start, end } => {
+            let mut parts = vec![obj.as_ref()];
+            parts.extend(start.as_deref());
+            parts.extend(end.as_deref());
+            all(&parts, calls)
+        }
+        ExprKind::Call { callee, args } => match &callee.kind {
+            ExprKind::Variable { name }
+                if !assigned.contains(name.as_ref())
+                    && !args
+                        .iter()
+                        .any(|arg| matches!(arg.kind, ExprKind::Spread { .. })) =>
+            {
+                calls.push(name);
+                all(&args.iter().collect::<Vec<_>>(), calls)
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn stmt_children(stmt: &Stmt) -> (Vec<&Expr>, Vec<&Stmt>) {
+    match &stmt.kind {
+        StmtKind::Block { statements } => (vec![], statements.iter().collect()),
+        StmtKind::Expression { expr } => (vec![expr], vec![]),
+        StmtKind::Function { .. } | StmtKind::Class { .. } | StmtKind::NativeImport { .. } => {
+            (vec![], vec![])
+        }
+        StmtKind::ForIn { iterable, body, .. } => (vec![iterable], vec![body]),
+        StmtKind::If {
+            cond,
+            then_branch,
+            else_branch,
+            ..
+        } => {
+            let mut stmts = vec![then_branch.as_ref()];
+            stmts.extend(else_branch.as_deref());
+            (vec![cond], stmts)
+        }
+        StmtKind::Return { expr } => (expr.as_deref().into_iter().collect(), vec![]),
+        StmtKind::Throw { value } => (vec![value], vec![]),
+        StmtKind::Try { body, handler, .. } => (vec![], vec![body, handler]),
+        StmtKind::Let { initializer, .. } => (initializer.as_deref().into_iter().collect(), vec![]),
+        StmtKind::While { cond, body, .. } => (vec![cond], vec![body]),
+    }
+}
+
+fn expr_children(expr: &Expr) -> Vec<&Expr> {
+    match &expr.kind {
+        ExprKind::Assign { value, .. }
+        | ExprKind::Spread { value }
+        | ExprKind::Grouping { value }
+        | ExprKind::Unquote { value }
+        | ExprKind::Unary { right: value, .. }
+        | ExprKind::Get { obj: value, .. } => vec![value],
+        ExprKind::Binary { left, right, .. } | ExprKind::Logical { left, right, .. } => {
+            vec![left, right]
+        }
+        ExprKind::Call { callee, args } => {
+            let mut children = vec![callee.as_ref()];
+            children.extend(args);
+            children
+        }
+        ExprKind::Conditional {
+            cond,
+            then_branch,
+            else_branch,
+        } => vec![cond, then_branch, else_branch],
+        ExprKind::GetIndex { obj, index } => vec![obj, index],
+        ExprKind::Slice { obj, start, end } => {
+            let mut children = vec![obj.as_ref()];
+            children.extend(start.as_deref());
+            children.extend(end.as_deref());
+            children
+        }
+        ExprKind::SetIndex { obj, index, value } => vec![obj, index, value],
+        ExprKind::Set { obj, value, .. } => vec![obj, value],
+        ExprKind::List { items } | ExprKind::SetLiteral { items } => items.iter().collect(),
+        ExprKind::Dict { items } => items.iter().flat_map(|(k, v)| [k, v]).collect(),
+        ExprKind::Match { scrutinee, arms } => {
+            let mut children = vec![scrutinee.as_ref()];
+            children.extend(arms.iter().map(|arm| arm.body.as_ref()));
+            children
+        }
+        // the statements of a quote aren't run
+        ExprKind::Literal { .. }
+        | ExprKind::Variable { .. }
+        | ExprKind::This
+        | ExprKind::Super { .. }
+        | ExprKind::Quote { .. } => vec![],
+    }
+}
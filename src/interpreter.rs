@@ -1,28 +1,67 @@
-use std::cell::RefCell;
 use std::collections::HashMap;
 use std::ops::Add;
+use std::ops::BitOr;
 use std::ops::Div;
 use std::ops::Mul;
 use std::ops::Neg;
 use std::ops::Sub;
-use std::rc::Rc;
 
 use miette::Report;
 
+use crate::annotation;
+use crate::ast;
+use crate::builtin;
+use crate::builtin::MemoFunction;
+use crate::class::{bind, Class, Instance, INITIALIZER};
 use crate::context::Ctx;
 use crate::dict::Dict;
+use crate::env::Env;
 use crate::expr::Expr;
 use crate::expr::ExprKind;
+use crate::expr::Pattern;
+use crate::expr::PatternKind;
 use crate::expr::Stmt;
 use crate::expr::StmtKind;
+use crate::fold;
+use crate::function::Function;
+use crate::function::Purity;
 use crate::function::SimpleFunction;
+use crate::hoist;
+use crate::iterator::collect;
+use crate::iterator::entries;
+use crate::iterator::iterate;
+use crate::iterator::pair;
+use crate::iterator::Iter;
+use crate::lexer::Lexer;
 use crate::list::List;
+use crate::native;
+use crate::parser::Parser;
+use crate::printer;
+use crate::report::ArgumentCountError;
 use crate::report::CalleeTypeError;
+use crate::report::DictKeyError;
 use crate::report::IndexTypeError;
 use crate::report::InstanceTypeError;
+use crate::report::Interrupted;
+use crate::report::InvalidArgument;
 use crate::report::ListIndexInvalidError;
 use crate::report::ListIndexOutOfBoundsError;
+use crate::report::NativeImportDenied;
+use crate::report::NoMatchingArm;
+use crate::report::NotAPair;
+use crate::report::NotIterable;
+use crate::report::PrecisionLoss;
+use crate::report::PropertyWithoutSetter;
+use crate::report::SuperclassTypeError;
+use crate::report::UncaughtThrow;
+use crate::report::UndefinedProperty;
+use crate::resolver::Resolver;
+use crate::runtime::Lint;
+use crate::runtime::Runtime;
+use crate::set::Set;
+use crate::sync::{Rc, RefCell};
 use crate::token::Literal;
+use crate::token::Span;
 use crate::token::TokenKind;
 use crate::value::Value;
 use crate::value::ValueKey;
@@ -30,6 +69,24 @@ use crate::value::ValueKey;
 pub enum RuntimeError {
     Report(Report),
     Return(Value),
+    // `throw value`, until a `try` catches it
+    Thrown(Value, Span),
+}
+
+impl RuntimeError {
+    // what a run stopped by `self` ends with: the value it returned, or the
+    // error, which a value thrown and never caught is too
+    pub fn unwind(self) -> Result<Value, Report> {
+        match self {
+            Self::Return(value) => Ok(value),
+            Self::Report(report) => Err(report),
+            Self::Thrown(value, span) => Err(UncaughtThrow {
+                span: span.into(),
+                value: printer::value(&value),
+            }
+            .into()),
+        }
+    }
 }
 
 impl From<Report> for RuntimeError {
@@ -44,7 +101,140 @@ impl From<Value> for RuntimeError {
     }
 }
 
-pub fn interpret(ctx: &Rc<RefCell<Ctx>>, statements: &Vec<Stmt>) -> Result<Value, RuntimeError> {
+// A global scope to run programs in, for embedding and for the benchmarks:
+// each program is lexed, parsed, resolved and interpreted as the binary runs
+// a file, and sees what the ones before it defined.
+pub struct Interpreter {
+    env: Rc<RefCell<Env>>,
+    runtime: Rc<Runtime>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new(&Env::global(), Rc::new(Runtime::default()))
+    }
+}
+
+impl Interpreter {
+    pub fn new(env: &Rc<RefCell<Env>>, runtime: Rc<Runtime>) -> Self {
+        Self {
+            env: env.clone(),
+            runtime,
+        }
+    }
+
+    // A fresh global scope with `prelude` run in it. The prelude is lexed,
+    // parsed and resolved once however many interpreters start from it, and
+    // the builtins are only made as each one uses them, so this is cheap
+    // enough for an interpreter per request. Nothing one of them defines or
+    // changes is seen by the others.
+    pub fn with_prelude(prelude: &Program, runtime: Rc<Runtime>) -> Result<Self, Report> {
+        let interpreter = Self::new(&Env::global(), runtime);
+        interpreter.execute(prelude)?;
+        Ok(interpreter)
+    }
+
+    pub fn env(&self) -> &Rc<RefCell<Env>> {
+        &self.env
+    }
+
+    pub fn runtime(&self) -> &Rc<Runtime> {
+        &self.runtime
+    }
+
+    // lexed, parsed and resolved against the globals, with the resolver's
+    // warnings handed to the runtime
+    pub fn compile(&self, source: &str) -> Result<Program, Report> {
+        let mut lexer = Lexer::new(source);
+        let tokens = lexer.scan_tokens()?;
+        let mut parser = Parser::new(tokens);
+        let mut statements = parser.parse()?;
+        if fold::applies(tokens, &self.env) {
+            fold::eliminate(&mut statements, &self.runtime.config);
+        }
+
+        let mut resolver = Resolver::with_globals(self.env.borrow().names());
+        resolver.resolve(&statements)?;
+        for warning in resolver.warnings.drain(..) {
+            self.runtime.warn(warning);
+        }
+
+        Ok(Program {
+            statements,
+            resolver: Rc::new(resolver),
+        })
+    }
+
+    // a scope for a program to run in, at the top level
+    pub fn context(&self, program: &Program) -> Rc<RefCell<Ctx>> {
+        let context = Ctx::new(&self.env, program.resolver.clone(), self.runtime.clone());
+        Rc::new(RefCell::new(context))
+    }
+
+    // the value of the last statement; the `on_error` hooks see a runtime
+    // error before it is returned
+    pub fn run(&self, source: &str) -> Result<Value, Report> {
+        self.execute(&self.compile(source)?)
+    }
+
+    pub fn execute(&self, program: &Program) -> Result<Value, Report> {
+        let context = self.context(program);
+        interpret(&context, &program.statements)
+            .or_else(RuntimeError::unwind)
+            .map_err(|report| self.failed(&context, report))
+    }
+
+    // has the global function `name` handle `event`, as `on(event, name)`
+    // would in the script
+    pub fn on(&self, event: &str, name: &str) -> Result<(), Report> {
+        let function = match Env::get(&self.env, 0, name, None) {
+            Ok((Value::Function(function), _)) => function,
+            _ => {
+                return Err(InvalidArgument {
+                    name: "on".to_string(),
+                    message: format!("`{}` is not a global function", name),
+                }
+                .into())
+            }
+        };
+        if let Err(err) = builtin::handler("on", &function, 1) {
+            err.unwind()?;
+        }
+        let mut events = self.runtime.hooks.events.borrow_mut();
+        events.entry(event.to_string()).or_default().push(function);
+        Ok(())
+    }
+
+    // runs the handlers of `event`, from the script or from `on`, with the
+    // payload as their argument
+    pub fn emit(&self, event: &str, payload: impl Into<Value>) -> Result<(), Report> {
+        let context = Ctx::new(&self.env, Rc::default(), self.runtime.clone());
+        let context = Rc::new(RefCell::new(context));
+        match run_event_handlers(&context, event, payload.into()) {
+            Ok(()) => Ok(()),
+            Err(err) => match err.unwind() {
+                Ok(_) => Ok(()),
+                Err(report) => Err(self.failed(&context, report)),
+            },
+        }
+    }
+
+    pub(crate) fn failed(&self, context: &Rc<RefCell<Ctx>>, report: Report) -> Report {
+        if let Err(Err(hook)) = run_error_hooks(context, &report).map_err(RuntimeError::unwind) {
+            self.runtime.warn(hook);
+        }
+        report
+    }
+}
+
+// A program ready to run, any number of times: in the interpreter that
+// compiled it, or as the prelude of fresh ones.
+pub struct Program {
+    pub statements: Vec<Stmt>,
+    resolver: Rc<Resolver>,
+}
+
+pub fn interpret(ctx: &Rc<RefCell<Ctx>>, statements: &[Stmt]) -> Result<Value, RuntimeError> {
     match statements.len() {
         0 => Ok(Value::Nil),
         1 => visit_stmt(ctx, &statements[0]),
@@ -60,13 +250,160 @@ pub fn interpret(ctx: &Rc<RefCell<Ctx>>, statements: &Vec<Stmt>) -> Result<Value
     }
 }
 
+// a runtime error as the {"code", "message"} dict scripts see
+pub fn error_value(report: &Report) -> Value {
+    let code = match report.code() {
+        Some(code) => Value::from(code.to_string()),
+        None => Value::Nil,
+    };
+    let entries = [("code", code), ("message", report.to_string().into())];
+    let error = entries
+        .into_iter()
+        .map(|(key, value)| (ValueKey::try_from(Value::from(key)).unwrap(), value))
+        .collect::<HashMap<_, _>>();
+    Value::Dict(Rc::new(RefCell::new(Dict(error))))
+}
+
+// calls the `on_error` hooks with the {"code", "message"} of a runtime error
+// that is about to end the run. A hook that fails stops the ones after it.
+pub fn run_error_hooks(ctx: &Rc<RefCell<Ctx>>, report: &Report) -> Result<(), RuntimeError> {
+    let hooks = ctx.borrow().runtime().hooks.error.borrow().clone();
+    if hooks.is_empty() {
+        return Ok(());
+    }
+
+    let error = error_value(report);
+    for hook in hooks {
+        hook.call(ctx, vec![error.clone()])?;
+    }
+    Ok(())
+}
+
+// calls the handlers `on` added for `event`; one that fails stops the ones
+// after it
+pub fn run_event_handlers(
+    ctx: &Rc<RefCell<Ctx>>,
+    event: &str,
+    payload: Value,
+) -> Result<(), RuntimeError> {
+    let hooks = ctx.borrow().runtime().hooks.clone();
+    let handlers = hooks.events.borrow().get(event).cloned();
+    for handler in handlers.unwrap_or_default() {
+        handler.call(ctx, vec![payload.clone()])?;
+    }
+    Ok(())
+}
+
+// calls the `on_exit` hooks, once, when the session ends without an error
+pub fn run_exit_hooks(ctx: &Rc<RefCell<Ctx>>) -> Result<(), RuntimeError> {
+    let hooks = ctx.borrow().runtime().hooks.exit.take();
+    for hook in hooks {
+        hook.call(ctx, vec![])?;
+    }
+    Ok(())
+}
+
 fn visit_value(_: &Rc<RefCell<Ctx>>, value: &Value) -> Result<Value, RuntimeError> {
     Ok(value.clone())
 }
 
-fn visit_expr(ctx: &Rc<RefCell<Ctx>>, expr: &Expr) -> Result<Value, RuntimeError> {
+// whether `value` matches `pattern`, adding what it binds to `bindings`
+fn matches(pattern: &Pattern, value: &Value, bindings: &mut Vec<(Rc<str>, Value)>) -> bool {
+    match (&pattern.kind, value) {
+        (PatternKind::Wildcard, _) => true,
+        (PatternKind::Literal { value: literal }, value) => literal == value,
+        (PatternKind::Binding { name }, value) => {
+            bindings.push((name.clone(), value.clone()));
+            true
+        }
+        (PatternKind::List { items, rest }, Value::List(list)) => {
+            let list = list.borrow();
+            let fits = match rest {
+                Some(_) => list.0.len() >= items.len(),
+                None => list.0.len() == items.len(),
+            };
+            if !fits
+                || !items
+                    .iter()
+                    .zip(&list.0)
+                    .all(|(item, value)| matches(item, value, bindings))
+            {
+                return false;
+            }
+            match rest {
+                Some(rest) => {
+                    let left = List(list.0[items.len()..].to_vec());
+                    matches(rest, &Value::List(Rc::new(RefCell::new(left))), bindings)
+                }
+                None => true,
+            }
+        }
+        (PatternKind::Dict { entries }, Value::Dict(dict)) => {
+            let dict = dict.borrow();
+            entries.iter().all(|(key, pattern)| {
+                let key = ValueKey::try_from(key.clone()).expect("pattern keys are hashable");
+                dict.0
+                    .get(&key)
+                    .is_some_and(|value| matches(pattern, value, bindings))
+            })
+        }
+        _ => false,
+    }
+}
+
+pub(crate) fn visit_expr(ctx: &Rc<RefCell<Ctx>>, expr: &Expr) -> Result<Value, RuntimeError> {
+    ctx.borrow().runtime().count(expr.id, expr.span);
+    if ctx.borrow().runtime().interrupted() {
+        return Err(RuntimeError::Report(
+            Interrupted {
+                span: expr.span.into(),
+            }
+            .into(),
+        ));
+    }
+    if let Some(value) = ctx.borrow().hoisted(expr.id) {
+        return Ok(value);
+    }
     match &expr.kind {
         ExprKind::Grouping { value } => visit_expr(ctx, value),
+        ExprKind::Spread { .. } => unreachable!("spreads only parse in lists and arguments"),
+        ExprKind::Quote { body } => {
+            let mut error = None;
+            let tree = ast::quote(body, &mut |value| {
+                if error.is_some() {
+                    return Value::Nil;
+                }
+                visit_expr(ctx, value).unwrap_or_else(|e| {
+                    error = Some(e);
+                    Value::Nil
+                })
+            });
+            match error {
+                Some(error) => Err(error),
+                None => Ok(tree),
+            }
+        }
+        ExprKind::Unquote { .. } => unreachable!("unquotes are evaluated with their quote"),
+        ExprKind::Match { scrutinee, arms } => {
+            let value = visit_expr(ctx, scrutinee)?;
+            for arm in arms {
+                let mut bindings = Vec::new();
+                if matches(&arm.pattern, &value, &mut bindings) {
+                    let new_env = Ctx::with_parent(ctx);
+                    for (name, value) in bindings {
+                        new_env.borrow_mut().define(&name, value);
+                    }
+                    return visit_expr(&new_env, &arm.body);
+                }
+            }
+            Err(RuntimeError::Report(
+                NoMatchingArm {
+                    span: scrutinee.span.into(),
+                    help: format!("the value is {}", printer::value(&value)),
+                }
+                .into(),
+            ))
+        }
         ExprKind::Literal { value } => visit_value(ctx, value),
         ExprKind::Unary { op, right } => {
             let value = visit_expr(ctx, right)?;
@@ -79,11 +416,34 @@ fn visit_expr(ctx: &Rc<RefCell<Ctx>>, expr: &Expr) -> Result<Value, RuntimeError
         ExprKind::Binary { left, op, right } => {
             let l = visit_expr(ctx, left)?;
             let r = visit_expr(ctx, right)?;
+
+            let runtime = ctx.borrow().runtime().clone();
+            let lint = runtime.config.precision_loss;
+            if lint != Lint::Allow
+                && matches!(
+                    op.kind,
+                    TokenKind::Minus | TokenKind::Slash | TokenKind::Star | TokenKind::Plus
+                )
+            {
+                if let Some((needed, precision)) = l.precision_loss(&r) {
+                    runtime.lint(lint, expr.id, |severity| {
+                        PrecisionLoss {
+                            span: expr.span.into(),
+                            severity,
+                            needed,
+                            precision,
+                        }
+                        .into()
+                    })?;
+                }
+            }
+
             match op.kind {
                 TokenKind::Minus => Ok(l.sub(r).map_err(|e| e.into_report(&expr.span))?),
                 TokenKind::Slash => Ok(l.div(r).map_err(|e| e.into_report(&expr.span))?),
                 TokenKind::Star => Ok(l.mul(r).map_err(|e| e.into_report(&expr.span))?),
                 TokenKind::Plus => Ok(l.add(r).map_err(|e| e.into_report(&expr.span))?),
+                TokenKind::Pipe => Ok(l.bitor(r).map_err(|e| e.into_report(&expr.span))?),
                 TokenKind::Greater => Ok((l.gt(&r)).into()),
                 TokenKind::GreaterEqual => Ok((l.ge(&r)).into()),
                 TokenKind::Less => Ok((l.lt(&r)).into()),
@@ -97,6 +457,33 @@ fn visit_expr(ctx: &Rc<RefCell<Ctx>>, expr: &Expr) -> Result<Value, RuntimeError
             .borrow()
             .get(expr, name)
             .map_err(|e| e.into_report(&expr.span))?),
+        ExprKind::This => Ok(ctx
+            .borrow()
+            .get(expr, "this")
+            .map_err(|e| e.into_report(&expr.span))?),
+        ExprKind::Super { method } => {
+            let superclass = ctx
+                .borrow()
+                .get(expr, "super")
+                .map_err(|e| e.into_report(&expr.span))?;
+            let this = ctx
+                .borrow()
+                .this_of_super(expr)
+                .map_err(|e| e.into_report(&expr.span))?;
+            let method = match &superclass {
+                Value::Class(superclass) => superclass.find_method(method),
+                _ => None,
+            };
+            match method {
+                Some(method) => Ok(bind(method, this).into()),
+                None => Err(RuntimeError::Report(
+                    UndefinedProperty {
+                        span: expr.span.into(),
+                    }
+                    .into(),
+                )),
+            }
+        }
         ExprKind::Assign { name, value } => {
             let value = visit_expr(ctx, value)?;
             ctx.borrow_mut()
@@ -115,39 +502,92 @@ fn visit_expr(ctx: &Rc<RefCell<Ctx>>, expr: &Expr) -> Result<Value, RuntimeError
                 visit_expr(ctx, right)
             }
         }
+        ExprKind::Conditional {
+            cond,
+            then_branch,
+            else_branch,
+        } => match visit_expr(ctx, cond)?.is_truthy() {
+            true => visit_expr(ctx, then_branch),
+            false => visit_expr(ctx, else_branch),
+        },
         ExprKind::Call { callee, args } => {
-            let callee = visit_expr(ctx, callee)?;
-            let args = args
-                .iter()
-                .map(|e| visit_expr(ctx, e))
-                .collect::<Result<Vec<_>, _>>()?;
-
-            match callee {
-                Value::Function(f) => Ok(f.call(ctx, args)?),
-                _ => Err(RuntimeError::Report(
-                    CalleeTypeError {
-                        span: expr.span.into(),
+            let (callee, mut receiver) = match &callee.kind {
+                // `d.f(a)` calls the dict entry "f" when there is one; any
+                // other `x.f(a)` falls back to calling the function `f` as
+                // `f(x, a)`
+                ExprKind::Get { obj, name } => {
+                    let this = visit_expr(ctx, obj)?;
+                    if let Some(entry) = property(ctx, &this, name)? {
+                        return call(ctx, expr, entry, visit_items(ctx, args)?);
                     }
-                    .into(),
-                )),
+                    let function = ctx.borrow().get(callee.as_ref(), name).map_err(|_| {
+                        RuntimeError::Report(
+                            UndefinedProperty {
+                                span: callee.span.into(),
+                            }
+                            .into(),
+                        )
+                    })?;
+                    (function, Some(this))
+                }
+                _ => (visit_expr(ctx, callee)?, None),
+            };
+            let mut args = visit_items(ctx, args)?;
+            if let Some(receiver) = receiver.take() {
+                args.insert(0, receiver);
             }
+            call(ctx, expr, callee, args)
         }
-        ExprKind::Get { obj, name: _ } => {
-            let _this = visit_expr(ctx, obj)?;
-            Err(RuntimeError::Report(
+        // instances have their fields and methods as properties, and dicts
+        // their string keys
+        ExprKind::Get { obj, name } => match visit_expr(ctx, obj)? {
+            this @ (Value::Instance(_) | Value::Dict(_)) => {
+                property(ctx, &this, name)?.ok_or_else(|| {
+                    RuntimeError::Report(
+                        UndefinedProperty {
+                            span: expr.span.into(),
+                        }
+                        .into(),
+                    )
+                })
+            }
+            _ => Err(RuntimeError::Report(
                 InstanceTypeError {
                     span: expr.span.into(),
                 }
                 .into(),
-            ))
-        }
-        ExprKind::Set {
-            obj,
-            name: _,
-            value,
-        } => {
-            let _this = visit_expr(ctx, obj)?;
-            let _value = visit_expr(ctx, value)?;
+            )),
+        },
+        ExprKind::Set { obj, name, value } => {
+            let this = visit_expr(ctx, obj)?;
+            let value = visit_expr(ctx, value)?;
+            match &this {
+                Value::Instance(instance) => {
+                    let class = instance.borrow().class.clone();
+                    if let Some(setter) = class.find_setter(name) {
+                        bind(setter, this.clone()).call(ctx, vec![value.clone()])?;
+                        return Ok(value);
+                    }
+                    if class.find_getter(name).is_some() {
+                        return Err(RuntimeError::Report(
+                            PropertyWithoutSetter {
+                                span: expr.span.into(),
+                                name: name.to_string(),
+                            }
+                            .into(),
+                        ));
+                    }
+                    let mut instance = instance.borrow_mut();
+                    instance.fields.insert(name.clone(), value.clone());
+                    return Ok(value);
+                }
+                Value::Dict(dict) => {
+                    let key = ValueKey::try_from(Value::from(name.as_ref())).unwrap();
+                    dict.borrow_mut().0.insert(key, value.clone());
+                    return Ok(value);
+                }
+                _ => {}
+            }
             Err(RuntimeError::Report(
                 InstanceTypeError {
                     span: expr.span.into(),
@@ -156,61 +596,51 @@ fn visit_expr(ctx: &Rc<RefCell<Ctx>>, expr: &Expr) -> Result<Value, RuntimeError
             ))
         }
         ExprKind::List { items } => {
-            let items = items
-                .iter()
-                .map(|expr| visit_expr(ctx, expr))
-                .collect::<Result<Vec<_>, _>>()?;
-
-            let list = List(items);
+            let list = List(visit_items(ctx, items)?);
             Ok(Value::List(Rc::new(RefCell::new(list))))
         }
+        ExprKind::SetLiteral { items } => {
+            let set = Set::new(visit_items(ctx, items)?).map_err(|e| e.into_report(&expr.span))?;
+            Ok(Value::Set(Rc::new(set)))
+        }
         ExprKind::Dict { items } => {
             let mut i = HashMap::new();
             for (left, right) in items {
-                let left = visit_expr(ctx, left)?;
+                let key = ValueKey::try_from(visit_expr(ctx, left)?)
+                    .map_err(|e| e.into_report(&left.span))?;
                 let right = visit_expr(ctx, right)?;
-                i.insert(ValueKey(left), right);
+                i.insert(key, right);
             }
 
             let list = Dict(i);
             Ok(Value::Dict(Rc::new(RefCell::new(list))))
         }
         ExprKind::GetIndex { obj, index } => {
+            let this = visit_expr(ctx, obj)?;
+            get_index(ctx, this, index, expr)
+        }
+        ExprKind::Slice { obj, start, end } => {
+            let this = visit_expr(ctx, obj)?;
+            slice(ctx, this, start, end, expr)
+        }
+        ExprKind::SetIndex { obj, index, value } => {
             let this = visit_expr(ctx, obj)?;
             match this {
                 Value::List(list) => {
-                    let index = visit_expr(ctx, index)?;
-                    match index {
-                        Value::Literal(Literal::Integer(i)) => {
-                            let index: usize = i.try_into().map_err(|_| {
-                                RuntimeError::Report(
-                                    ListIndexInvalidError {
-                                        span: expr.span.into(),
-                                    }
-                                    .into(),
-                                )
-                            })?;
-                            match list.borrow().0.get(index) {
-                                Some(value) => Ok(value.clone()),
-                                None => Err(RuntimeError::Report(
-                                    ListIndexOutOfBoundsError {
-                                        span: expr.span.into(),
-                                    }
-                                    .into(),
-                                )),
-                            }
-                        }
-                        _ => Err(RuntimeError::Report(
-                            ListIndexInvalidError {
-                                span: expr.span.into(),
-                            }
-                            .into(),
-                        )),
-                    }
+                    let index = to_index(visit_expr(ctx, index)?, expr)?;
+                    // the value may read the list, so it comes before the borrow
+                    let new = visit_expr(ctx, value)?;
+                    let mut list = list.borrow_mut();
+                    let prev = in_bounds(list.0.get_mut(index), expr)?;
+                    *prev = new.clone();
+                    Ok(new)
                 }
                 Value::Dict(dict) => {
-                    let index = ValueKey(visit_expr(ctx, index)?);
-                    Ok(dict.borrow().0.get(&index).unwrap().clone())
+                    let value = visit_expr(ctx, value)?;
+                    let index = ValueKey::try_from(visit_expr(ctx, index)?)
+                        .map_err(|e| e.into_report(&index.span))?;
+                    dict.borrow_mut().0.insert(index, value.clone());
+                    Ok(value)
                 }
                 _ => Err(RuntimeError::Report(
                     IndexTypeError {
@@ -220,64 +650,344 @@ fn visit_expr(ctx: &Rc<RefCell<Ctx>>, expr: &Expr) -> Result<Value, RuntimeError
                 )),
             }
         }
-        ExprKind::SetIndex { obj, index, value } => {
-            let this = visit_expr(ctx, obj)?;
-            match this {
-                Value::List(list) => {
-                    let index = visit_expr(ctx, index)?;
-                    match index {
-                        Value::Literal(Literal::Integer(i)) => {
-                            let index: usize = i.try_into().map_err(|_| {
-                                RuntimeError::Report(
-                                    ListIndexInvalidError {
-                                        span: expr.span.into(),
-                                    }
-                                    .into(),
-                                )
-                            })?;
-                            match list.borrow_mut().0.get_mut(index) {
-                                Some(prev) => {
-                                    let new = visit_expr(ctx, value)?;
-                                    *prev = new.clone();
-                                    Ok(new)
-                                }
-                                None => Err(RuntimeError::Report(
-                                    ListIndexOutOfBoundsError {
-                                        span: expr.span.into(),
-                                    }
-                                    .into(),
-                                )),
-                            }
+    }
+}
+
+// list items or call arguments, with the items of `...iterable` spliced in
+fn visit_items(ctx: &Rc<RefCell<Ctx>>, items: &[Expr]) -> Result<Vec<Value>, RuntimeError> {
+    let mut values = Vec::with_capacity(items.len());
+    for item in items {
+        match &item.kind {
+            ExprKind::Spread { value } => {
+                let iterator = iterate(visit_expr(ctx, value)?).map_err(|value| {
+                    RuntimeError::Report(
+                        NotIterable {
+                            span: item.span.into(),
+                            help: format!("found {}", value.type_name()),
                         }
-                        _ => Err(RuntimeError::Report(
-                            ListIndexInvalidError {
-                                span: expr.span.into(),
-                            }
-                            .into(),
-                        )),
-                    }
-                }
-                Value::Dict(dict) => {
-                    let value = visit_expr(ctx, value)?;
-                    let index = ValueKey(visit_expr(ctx, index)?);
-                    dict.borrow_mut().0.insert(index, value.clone());
-                    Ok(value)
-                }
-                _ => Err(RuntimeError::Report(
-                    IndexTypeError {
+                        .into(),
+                    )
+                })?;
+                values.extend(collect(&iterator, ctx)?.0);
+            }
+            _ => values.push(visit_expr(ctx, item)?),
+        }
+    }
+    Ok(values)
+}
+
+// the property `name` of an instance, running its getter if it has one, or
+// the entry `name` of a dict
+fn property(
+    ctx: &Rc<RefCell<Ctx>>,
+    this: &Value,
+    name: &str,
+) -> Result<Option<Value>, RuntimeError> {
+    match this {
+        Value::Instance(instance) => {
+            let getter = instance.borrow().class.find_getter(name).cloned();
+            match getter {
+                Some(getter) => bind(&getter, this.clone()).call(ctx, vec![]).map(Some),
+                None => Ok(Instance::get(instance, name)),
+            }
+        }
+        Value::Dict(dict) => {
+            let Ok(key) = ValueKey::try_from(Value::from(name)) else {
+                return Ok(None);
+            };
+            Ok(dict.borrow().0.get(&key).cloned())
+        }
+        _ => Ok(None),
+    }
+}
+
+// a scope with the loop's invariant expressions evaluated, when it has any
+// and calls only builtins that change nothing. An expression that fails is
+// left to fail in the loop, where it would have.
+pub(crate) fn hoisted(
+    ctx: &Rc<RefCell<Ctx>>,
+    cond: &Expr,
+    body: &Stmt,
+) -> Option<Rc<RefCell<Ctx>>> {
+    let plan = hoist::plan(cond, body)?;
+    let mut purity = HashMap::new();
+    for callee in plan.callees {
+        let ExprKind::Variable { name } = &callee.kind else {
+            return None;
+        };
+        let Ok(Value::Function(f)) = ctx.borrow().get(callee, name) else {
+            return None;
+        };
+        if f.purity() == Purity::Impure {
+            return None;
+        }
+        // names are resolved to the same scope everywhere in the loop
+        purity.insert(name.as_ref(), f.purity());
+    }
+
+    let mut values = HashMap::new();
+    for (expr, calls) in plan.invariants {
+        if !calls
+            .iter()
+            .all(|name| purity.get(name) == Some(&Purity::Pure))
+        {
+            continue;
+        }
+        // not the values with state, like a list, made anew every time
+        if let Ok(
+            value @ (Value::True
+            | Value::False
+            | Value::Nil
+            | Value::Literal(_)
+            | Value::Bytes(_)
+            | Value::Duration(_)
+            | Value::DateTime(_)
+            | Value::Fixed(_)
+            | Value::Exact(_)),
+        ) = visit_expr(ctx, expr)
+        {
+            values.insert(expr.id, value);
+        }
+    }
+    match values.is_empty() {
+        true => None,
+        false => Some(Ctx::with_hoisted(ctx, values)),
+    }
+}
+
+fn call(
+    ctx: &Rc<RefCell<Ctx>>,
+    expr: &Expr,
+    callee: Value,
+    args: Vec<Value>,
+) -> Result<Value, RuntimeError> {
+    match callee {
+        Value::Function(f) if !f.arity().accepts(args.len()) => Err(RuntimeError::Report(
+            ArgumentCountError {
+                span: expr.span.into(),
+                help: format!("expected {}, found {}", f.arity(), args.len()),
+            }
+            .into(),
+        )),
+        Value::Function(f) => Ok(f.call(ctx, args)?),
+        Value::Class(class) if !class.arity().accepts(args.len()) => Err(RuntimeError::Report(
+            ArgumentCountError {
+                span: expr.span.into(),
+                help: format!("expected {}, found {}", class.arity(), args.len()),
+            }
+            .into(),
+        )),
+        Value::Class(class) => {
+            let instance = Value::Instance(Rc::new(RefCell::new(Instance::new(class.clone()))));
+            if let Some(init) = class.find_method(INITIALIZER) {
+                bind(init, instance.clone()).call(ctx, args)?;
+            }
+            Ok(instance)
+        }
+        _ => Err(RuntimeError::Report(
+            CalleeTypeError {
+                span: expr.span.into(),
+            }
+            .into(),
+        )),
+    }
+}
+
+// `this[index]`, kept out of `visit_expr` so that its frame stays small
+fn get_index(
+    ctx: &Rc<RefCell<Ctx>>,
+    this: Value,
+    index: &Expr,
+    expr: &Expr,
+) -> Result<Value, RuntimeError> {
+    match this {
+        Value::List(list) => {
+            let index = to_index(visit_expr(ctx, index)?, expr)?;
+            let list = list.borrow();
+            in_bounds(list.0.get(index), expr).cloned()
+        }
+        Value::Bytes(bytes) => {
+            let index = to_index(visit_expr(ctx, index)?, expr)?;
+            let byte = in_bounds(bytes.get(index), expr)?;
+            Ok(rug::Integer::from(*byte).into())
+        }
+        // strings are indexed by character, not by byte
+        Value::Literal(Literal::String(s)) => {
+            let index = to_index(visit_expr(ctx, index)?, expr)?;
+            let c = in_bounds(s.chars().nth(index), expr)?;
+            Ok(c.to_string().into())
+        }
+        Value::Dict(dict) => {
+            let index = ValueKey::try_from(visit_expr(ctx, index)?)
+                .map_err(|e| e.into_report(&index.span))?;
+            match dict.borrow().0.get(&index) {
+                Some(value) => Ok(value.clone()),
+                None => Err(RuntimeError::Report(
+                    DictKeyError {
                         span: expr.span.into(),
                     }
                     .into(),
                 )),
             }
         }
+        _ => Err(RuntimeError::Report(
+            IndexTypeError {
+                span: expr.span.into(),
+            }
+            .into(),
+        )),
     }
 }
 
-fn visit_stmt(ctx: &Rc<RefCell<Ctx>>, stmt: &Stmt) -> Result<Value, RuntimeError> {
+// `this[start:end]`
+fn slice(
+    ctx: &Rc<RefCell<Ctx>>,
+    this: Value,
+    start: &Option<Box<Expr>>,
+    end: &Option<Box<Expr>>,
+    expr: &Expr,
+) -> Result<Value, RuntimeError> {
+    let bound = |bound: &Option<Box<Expr>>| match bound {
+        Some(bound) => visit_expr(ctx, bound)
+            .and_then(|b| to_index(b, expr))
+            .map(Some),
+        None => Ok(None),
+    };
+    let (start, end) = (bound(start)?, bound(end)?);
+
+    // out of range bounds are clamped, like in Python
+    let range = |len: usize| {
+        let end = end.unwrap_or(len).min(len);
+        start.unwrap_or(0).min(end)..end
+    };
+
+    match this {
+        Value::List(list) => {
+            let list = list.borrow();
+            let items = list.0[range(list.0.len())].to_vec();
+            Ok(Value::List(Rc::new(RefCell::new(List(items)))))
+        }
+        Value::Bytes(bytes) => Ok(Value::Bytes(bytes[range(bytes.len())].into())),
+        Value::Literal(Literal::String(s)) => {
+            let range = range(s.chars().count());
+            let s: String = s.chars().skip(range.start).take(range.len()).collect();
+            Ok(s.into())
+        }
+        _ => Err(RuntimeError::Report(
+            IndexTypeError {
+                span: expr.span.into(),
+            }
+            .into(),
+        )),
+    }
+}
+
+fn to_index(index: Value, expr: &Expr) -> Result<usize, RuntimeError> {
+    let invalid = || {
+        RuntimeError::Report(
+            ListIndexInvalidError {
+                span: expr.span.into(),
+            }
+            .into(),
+        )
+    };
+
+    match index {
+        Value::Literal(Literal::Integer(i)) => (&*i).try_into().map_err(|_| invalid()),
+        _ => Err(invalid()),
+    }
+}
+
+// the item at an index of a list, bytes or string, if it isn't past the end
+fn in_bounds<T>(item: Option<T>, expr: &Expr) -> Result<T, RuntimeError> {
+    item.ok_or_else(|| {
+        RuntimeError::Report(
+            ListIndexOutOfBoundsError {
+                span: expr.span.into(),
+            }
+            .into(),
+        )
+    })
+}
+
+// what a `for` loop goes over, the entries of it when it binds a value too
+pub(crate) fn for_in_iterator(
+    ctx: &Rc<RefCell<Ctx>>,
+    iterable: &Expr,
+    pairs: bool,
+) -> Result<Iter, RuntimeError> {
+    let value = visit_expr(ctx, iterable)?;
+    let iterator = match pairs {
+        true => entries(value),
+        false => iterate(value),
+    };
+    iterator.map_err(|value| {
+        RuntimeError::Report(
+            NotIterable {
+                span: iterable.span.into(),
+                help: format!("found {}", value.type_name()),
+            }
+            .into(),
+        )
+    })
+}
+
+// the scope of one run of the body of a `for` loop, with the item bound
+pub(crate) fn for_in_scope(
+    ctx: &Rc<RefCell<Ctx>>,
+    name: &str,
+    value: Option<&str>,
+    item: Value,
+    iterable: &Expr,
+) -> Result<Rc<RefCell<Ctx>>, RuntimeError> {
+    let new_env = Ctx::with_parent(ctx);
+    match value {
+        None => new_env.borrow_mut().define(name, item),
+        Some(value) => {
+            let (first, second) = pair(item).map_err(|item| {
+                RuntimeError::Report(
+                    NotAPair {
+                        span: iterable.span.into(),
+                        help: format!("expected a list of two, found {}", item),
+                    }
+                    .into(),
+                )
+            })?;
+            let mut scope = new_env.borrow_mut();
+            scope.define(name, first);
+            scope.define(value, second);
+        }
+    }
+    Ok(new_env)
+}
+
+pub(crate) fn visit_stmt(ctx: &Rc<RefCell<Ctx>>, stmt: &Stmt) -> Result<Value, RuntimeError> {
     match &stmt.kind {
         StmtKind::Expression { expr } => visit_expr(ctx, expr),
-        StmtKind::Let { name, initializer } => {
+        StmtKind::Throw { value } => Err(RuntimeError::Thrown(visit_expr(ctx, value)?, stmt.span)),
+        // a thrown value is caught as it is, a runtime error as its
+        // {"code", "message"}; an interrupt stops the code all the same
+        StmtKind::Try {
+            body,
+            name,
+            handler,
+        } => {
+            let error = match visit_stmt(ctx, body) {
+                Err(RuntimeError::Thrown(value, _)) => value,
+                Err(RuntimeError::Report(report))
+                    if report.downcast_ref::<Interrupted>().is_none() =>
+                {
+                    error_value(&report)
+                }
+                result => return result,
+            };
+            let new_env = Ctx::with_parent(ctx);
+            new_env.borrow_mut().define(name, error);
+            visit_stmt(&new_env, handler)
+        }
+        StmtKind::Let {
+            name, initializer, ..
+        } => {
             let value = match initializer {
                 Some(expr) => visit_expr(ctx, expr)?,
                 None => Value::Nil,
@@ -294,12 +1004,17 @@ fn visit_stmt(ctx: &Rc<RefCell<Ctx>>, stmt: &Stmt) -> Result<Value, RuntimeError
             Ok(Value::Nil)
         }
         StmtKind::If {
+            binding,
             cond,
             then_branch,
             else_branch,
         } => {
             let value = visit_expr(ctx, cond)?;
-            if value.is_truthy() {
+            if let (Some(name), false) = (binding, value == Value::Nil) {
+                let new_env = Ctx::with_parent(ctx);
+                new_env.borrow_mut().define(name, value);
+                visit_stmt(&new_env, then_branch)
+            } else if binding.is_none() && value.is_truthy() {
                 visit_stmt(ctx, then_branch)
             } else if let Some(else_branch) = else_branch {
                 visit_stmt(ctx, else_branch)
@@ -307,23 +1022,144 @@ fn visit_stmt(ctx: &Rc<RefCell<Ctx>>, stmt: &Stmt) -> Result<Value, RuntimeError
                 Ok(Value::Nil)
             }
         }
-        StmtKind::While { cond, body } => {
+        StmtKind::While {
+            binding: None,
+            cond,
+            body,
+        } => {
+            let ctx = &hoisted(ctx, cond, body).unwrap_or_else(|| ctx.clone());
             while visit_expr(ctx, cond)?.is_truthy() {
                 visit_stmt(ctx, body)?;
             }
 
             Ok(Value::Nil)
         }
-        StmtKind::Function { name, params, body } => {
-            let function = SimpleFunction {
+        StmtKind::While {
+            binding: Some(name),
+            cond,
+            body,
+        } => loop {
+            let value = visit_expr(ctx, cond)?;
+            if value == Value::Nil {
+                return Ok(Value::Nil);
+            }
+            let new_env = Ctx::with_parent(ctx);
+            new_env.borrow_mut().define(name, value);
+            visit_stmt(&new_env, body)?;
+        },
+        StmtKind::ForIn {
+            name,
+            value,
+            iterable,
+            body,
+        } => {
+            let iterator = for_in_iterator(ctx, iterable, value.is_some())?;
+
+            loop {
+                // not borrowed while the body runs, which may use it too
+                let item = iterator.borrow_mut().next(ctx)?;
+                let Some(item) = item else {
+                    break;
+                };
+
+                let new_env = for_in_scope(ctx, name, value.as_deref(), item, iterable)?;
+                visit_stmt(&new_env, body)?;
+            }
+
+            Ok(Value::Nil)
+        }
+        StmtKind::Function {
+            name,
+            params,
+            body,
+            annotations,
+        } => {
+            let function = Rc::new(SimpleFunction {
                 name: name.clone(),
                 params: params.clone(),
                 body: body.clone(),
                 closure: ctx.clone(),
+                purity: match annotation::find(annotations, "pure") {
+                    Some(_) => Purity::Pure,
+                    None => Purity::Impure,
+                },
+            });
+            let function: Rc<dyn Function> = match annotation::find(annotations, "memo") {
+                Some(_) => Rc::new(MemoFunction::new(function)),
+                None => function,
             };
 
-            ctx.borrow_mut()
-                .define(name, Value::Function(Rc::new(function)));
+            ctx.borrow_mut().define(name, Value::Function(function));
+
+            Ok(Value::Nil)
+        }
+        StmtKind::Class {
+            name,
+            superclass,
+            methods,
+            getters,
+            setters,
+        } => {
+            let superclass = match superclass {
+                Some(superclass) => match ctx
+                    .borrow()
+                    .get(stmt, superclass)
+                    .map_err(|e| e.into_report(&stmt.span))?
+                {
+                    Value::Class(superclass) => Some(superclass),
+                    value => {
+                        return Err(RuntimeError::Report(
+                            SuperclassTypeError {
+                                span: stmt.span.into(),
+                                help: format!("`{}` is {}", superclass, value.type_name()),
+                            }
+                            .into(),
+                        ))
+                    }
+                },
+                None => None,
+            };
+            // the scope the resolver put `super` in, between the methods and
+            // the scope the class is declared in
+            let closure = match &superclass {
+                Some(superclass) => {
+                    let closure = Ctx::with_parent(ctx);
+                    closure
+                        .borrow_mut()
+                        .define("super", Value::Class(superclass.clone()));
+                    closure
+                }
+                None => ctx.clone(),
+            };
+            let functions = |functions: &[Stmt]| {
+                functions
+                    .iter()
+                    .filter_map(|function| match &function.kind {
+                        StmtKind::Function {
+                            name, params, body, ..
+                        } => Some((
+                            name.clone(),
+                            Rc::new(SimpleFunction {
+                                name: name.clone(),
+                                params: params.clone(),
+                                body: body.clone(),
+                                closure: closure.clone(),
+                                purity: Purity::Impure,
+                            }),
+                        )),
+                        _ => None,
+                    })
+                    .collect()
+            };
+            let class = Class {
+                name: name.clone(),
+                superclass,
+                methods: functions(methods),
+                getters: functions(getters),
+                setters: functions(setters),
+            };
+
+            ctx.borrow_mut().define(name, Value::Class(Rc::new(class)));
 
             Ok(Value::Nil)
         }
@@ -335,5 +1171,22 @@ fn visit_stmt(ctx: &Rc<RefCell<Ctx>>, stmt: &Stmt) -> Result<Value, RuntimeError
 
             Err(RuntimeError::Return(value))
         }
+        StmtKind::NativeImport { path } => {
+            if !ctx.borrow().runtime().config.allow_native {
+                return Err(RuntimeError::Report(
+                    NativeImportDenied {
+                        span: stmt.span.into(),
+                    }
+                    .into(),
+                ));
+            }
+
+            let functions = native::load(path).map_err(|e| e.into_report(&stmt.span, path))?;
+            for (name, function) in functions {
+                ctx.borrow_mut().define(&name, function);
+            }
+
+            Ok(Value::Nil)
+        }
     }
 }
@@ -0,0 +1,197 @@
+use rug::Integer;
+
+use crate::{
+    context::Ctx,
+    function::Function,
+    interpreter::RuntimeError,
+    list::List,
+    sync::{MaybeSync, Rc, RefCell},
+    token::Literal,
+    value::Value,
+};
+
+// Lazy sequences of values. Producing an item may run user code (`map`,
+// `filter`), so it takes the context the iteration happens in.
+pub trait ValueIterator: std::fmt::Debug + MaybeSync {
+    fn next(&mut self, ctx: &Rc<RefCell<Ctx>>) -> Result<Option<Value>, RuntimeError>;
+}
+
+pub type Iter = Rc<RefCell<dyn ValueIterator>>;
+
+// Lists, dicts (their keys), sets, strings (their characters) and iterators
+// can be iterated; other values give themselves back.
+pub fn iterate(value: Value) -> Result<Iter, Value> {
+    match value {
+        Value::Iterator(iterator) => Ok(iterator),
+        Value::List(list) => Ok(Rc::new(RefCell::new(ListIter { list, index: 0 }))),
+        Value::Dict(dict) => {
+            let keys = dict.borrow().0.keys().map(|key| key.0.clone()).collect();
+            Ok(Rc::new(RefCell::new(VecIter(keys))))
+        }
+        Value::Set(set) => {
+            let items = set.0.iter().map(|item| item.0.clone()).collect();
+            Ok(Rc::new(RefCell::new(VecIter(items))))
+        }
+        Value::Bytes(bytes) => {
+            let bytes = bytes
+                .iter()
+                .rev()
+                .map(|&b| Integer::from(b).into())
+                .collect();
+            Ok(Rc::new(RefCell::new(VecIter(bytes))))
+        }
+        Value::Literal(Literal::String(string)) => {
+            let chars = string.chars().rev().map(|c| c.to_string().into()).collect();
+            Ok(Rc::new(RefCell::new(VecIter(chars))))
+        }
+        other => Err(other),
+    }
+}
+
+// What `for (k, v in value)` goes over: dicts give a key and a value per
+// entry, iterators items that must already be pairs, and everything else
+// `iterate` takes the index and the item.
+pub fn entries(value: Value) -> Result<Iter, Value> {
+    match value {
+        Value::Iterator(iterator) => Ok(iterator),
+        Value::Dict(dict) => {
+            let entries = dict
+                .borrow()
+                .0
+                .iter()
+                .map(|(key, value)| Value::from(vec![key.0.clone(), value.clone()]))
+                .collect();
+            Ok(Rc::new(RefCell::new(VecIter(entries))))
+        }
+        other => Ok(Rc::new(RefCell::new(Indexed {
+            inner: iterate(other)?,
+            index: 0,
+        }))),
+    }
+}
+
+// the two halves of an item of `entries`, or the item when it isn't a list
+// of two
+pub fn pair(item: Value) -> Result<(Value, Value), Value> {
+    if let Value::List(list) = &item {
+        if let [first, second] = list.borrow().0.as_slice() {
+            return Ok((first.clone(), second.clone()));
+        }
+    }
+    Err(item)
+}
+
+#[derive(Debug)]
+struct Indexed {
+    inner: Iter,
+    index: usize,
+}
+
+impl ValueIterator for Indexed {
+    fn next(&mut self, ctx: &Rc<RefCell<Ctx>>) -> Result<Option<Value>, RuntimeError> {
+        let item = self.inner.borrow_mut().next(ctx)?;
+        let Some(item) = item else {
+            return Ok(None);
+        };
+        let index = Integer::from(self.index).into();
+        self.index += 1;
+        Ok(Some(vec![index, item].into()))
+    }
+}
+
+pub fn collect(iterator: &Iter, ctx: &Rc<RefCell<Ctx>>) -> Result<List, RuntimeError> {
+    let mut items = Vec::new();
+    loop {
+        let item = iterator.borrow_mut().next(ctx)?;
+        match item {
+            Some(item) => items.push(item),
+            None => return Ok(List(items)),
+        }
+    }
+}
+
+// reads the list as it goes, so it sees items pushed while iterating
+#[derive(Debug)]
+struct ListIter {
+    list: Rc<RefCell<List>>,
+    index: usize,
+}
+
+impl ValueIterator for ListIter {
+    fn next(&mut self, _: &Rc<RefCell<Ctx>>) -> Result<Option<Value>, RuntimeError> {
+        let item = self.list.borrow().0.get(self.index).cloned();
+        self.index += 1;
+        Ok(item)
+    }
+}
+
+// items in reverse order, popped from the end
+#[derive(Debug)]
+struct VecIter(Vec<Value>);
+
+impl ValueIterator for VecIter {
+    fn next(&mut self, _: &Rc<RefCell<Ctx>>) -> Result<Option<Value>, RuntimeError> {
+        Ok(self.0.pop())
+    }
+}
+
+#[derive(Debug)]
+pub struct Range {
+    pub current: Integer,
+    pub end: Integer,
+    pub step: Integer,
+}
+
+impl ValueIterator for Range {
+    fn next(&mut self, _: &Rc<RefCell<Ctx>>) -> Result<Option<Value>, RuntimeError> {
+        let done = match self.step.cmp0() {
+            std::cmp::Ordering::Less => self.current <= self.end,
+            _ => self.current >= self.end,
+        };
+        if done {
+            return Ok(None);
+        }
+
+        let item = self.current.clone();
+        self.current += &self.step;
+        Ok(Some(item.into()))
+    }
+}
+
+#[derive(Debug)]
+pub struct Map {
+    pub inner: Iter,
+    pub function: Rc<dyn Function>,
+}
+
+impl ValueIterator for Map {
+    fn next(&mut self, ctx: &Rc<RefCell<Ctx>>) -> Result<Option<Value>, RuntimeError> {
+        let item = self.inner.borrow_mut().next(ctx)?;
+        match item {
+            Some(item) => Ok(Some(self.function.call(ctx, vec![item])?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Filter {
+    pub inner: Iter,
+    pub predicate: Rc<dyn Function>,
+}
+
+impl ValueIterator for Filter {
+    fn next(&mut self, ctx: &Rc<RefCell<Ctx>>) -> Result<Option<Value>, RuntimeError> {
+        loop {
+            let item = self.inner.borrow_mut().next(ctx)?;
+            match item {
+                Some(item) => {
+                    if self.predicate.call(ctx, vec![item.clone()])?.is_truthy() {
+                        return Ok(Some(item));
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+}
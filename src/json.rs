@@ -0,0 +1,367 @@
+use std::fmt::{Display, Write};
+
+use rug::{Float, Integer};
+
+use crate::{
+    dict::Dict,
+    lexer::DEFAULT_FLOAT_PRECISION,
+    sync::{Rc, RefCell},
+    token::Literal,
+    value::{Value, ValueKey},
+};
+
+// deep enough for real data, and a stop for cyclic lists and dicts
+const MAX_DEPTH: usize = 512;
+
+#[derive(Debug, Clone)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Self {
+        Self::Number(value.to_string())
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => Self::Null,
+        }
+    }
+}
+
+fn write_string(f: &mut std::fmt::Formatter<'_>, string: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for c in string.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::Number(value) => write!(f, "{}", value),
+            Self::String(value) => write_string(f, value),
+            Self::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Self::Object(items) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+impl Json {
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Self::Object(items) => items.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    // A value as JSON: dicts become objects with their keys as strings, in
+    // order, and datetimes strings; floats and exacts are written as 64-bit
+    // floats. Functions, bytes, durations and the handles have no JSON form.
+    pub fn from_value(value: &Value) -> Result<Json, String> {
+        Self::from_value_at(value, 0)
+    }
+
+    fn from_value_at(value: &Value, depth: usize) -> Result<Json, String> {
+        if depth > MAX_DEPTH {
+            return Err("value is nested too deeply, or contains itself".to_string());
+        }
+
+        Ok(match value {
+            Value::Nil => Self::Null,
+            Value::True => Self::Bool(true),
+            Value::False => Self::Bool(false),
+            Value::Literal(Literal::Integer(i)) => Self::Number(i.to_string()),
+            Value::Fixed(fixed) => Self::Number(fixed.to_integer().to_string()),
+            Value::Literal(Literal::Float(f)) if f.is_finite() => {
+                Self::Number(format!("{:?}", f.to_f64()))
+            }
+            Value::Literal(Literal::Float(f)) => {
+                return Err(format!("{} has no JSON form", f));
+            }
+            Value::Exact(exact) => Self::Number(format!("{:?}", exact.approx(53).to_f64())),
+            Value::Literal(literal @ (Literal::String(_) | Literal::Identifier(_))) => {
+                Self::String(literal.to_string())
+            }
+            Value::DateTime(datetime) => Self::String(datetime.to_string()),
+            Value::List(list) => Self::Array(
+                list.borrow()
+                    .0
+                    .iter()
+                    .map(|item| Self::from_value_at(item, depth + 1))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Dict(dict) => {
+                let mut items = dict
+                    .borrow()
+                    .0
+                    .iter()
+                    .map(|(key, value)| {
+                        Ok((key.0.to_string(), Self::from_value_at(value, depth + 1)?))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                items.sort_by(|(a, _), (b, _)| a.cmp(b));
+                Self::Object(items)
+            }
+            other => return Err(format!("a {} has no JSON form", other.type_name())),
+        })
+    }
+
+    // the other way: numbers with a fraction or an exponent are floats
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::Null => Value::Nil,
+            Self::Bool(value) => (*value).into(),
+            Self::Number(number) => match number.parse::<Integer>() {
+                Ok(integer) => integer.into(),
+                Err(_) => match Float::parse(number) {
+                    Ok(float) => Float::with_val(DEFAULT_FLOAT_PRECISION, float).into(),
+                    Err(_) => Value::Nil,
+                },
+            },
+            Self::String(value) => value.as_str().into(),
+            Self::Array(items) => items.iter().map(Json::to_value).collect::<Vec<_>>().into(),
+            Self::Object(items) => {
+                let entries = items
+                    .iter()
+                    .map(|(key, value)| {
+                        let key = ValueKey::try_from(Value::from(key.as_str())).unwrap();
+                        (key, value.to_value())
+                    })
+                    .collect();
+                Value::Dict(Rc::new(RefCell::new(Dict(entries))))
+            }
+        }
+    }
+
+    // a whole document; numbers are kept as they are written
+    pub fn parse(source: &str) -> Result<Json, String> {
+        let mut reader = Reader {
+            source,
+            position: 0,
+        };
+        let value = reader.value()?;
+        reader.whitespace();
+        match reader.position == source.len() {
+            true => Ok(value),
+            false => Err(reader.error("trailing characters")),
+        }
+    }
+}
+
+struct Reader<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("{} at offset {}", message, self.position)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.whitespace();
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(self.error(&format!("expected `{}`", expected))),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        match self.source[self.position..].starts_with(keyword) {
+            true => {
+                self.position += keyword.len();
+                Ok(value)
+            }
+            false => Err(self.error("unexpected character")),
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.whitespace();
+        match self.peek() {
+            Some('n') => self.keyword("null", Json::Null),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('[') => {
+                self.position += 1;
+                let mut items = Vec::new();
+                self.whitespace();
+                if self.peek() == Some(']') {
+                    self.position += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.whitespace();
+                    match self.next() {
+                        Some(',') => continue,
+                        Some(']') => return Ok(Json::Array(items)),
+                        _ => return Err(self.error("expected `,` or `]`")),
+                    }
+                }
+            }
+            Some('{') => {
+                self.position += 1;
+                let mut items = Vec::new();
+                self.whitespace();
+                if self.peek() == Some('}') {
+                    self.position += 1;
+                    return Ok(Json::Object(items));
+                }
+                loop {
+                    self.whitespace();
+                    if self.peek() != Some('"') {
+                        return Err(self.error("expected a key"));
+                    }
+                    let key = self.string()?;
+                    self.expect(':')?;
+                    items.push((key, self.value()?));
+                    self.whitespace();
+                    match self.next() {
+                        Some(',') => continue,
+                        Some('}') => return Ok(Json::Object(items)),
+                        _ => return Err(self.error("expected `,` or `}`")),
+                    }
+                }
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.position;
+                while matches!(self.peek(), Some('-' | '+' | '.' | 'e' | 'E' | '0'..='9')) {
+                    self.position += 1;
+                }
+                Ok(Json::Number(self.source[start..self.position].to_string()))
+            }
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.position += 1;
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => string.push(match self.next() {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('/') => '/',
+                    Some('b') => '\u{8}',
+                    Some('f') => '\u{c}',
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('t') => '\t',
+                    Some('u') => self.unicode()?,
+                    _ => return Err(self.error("invalid escape")),
+                }),
+                Some(c) => string.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    // `\uXXXX`, with the second half of a surrogate pair when there is one
+    fn unicode(&mut self) -> Result<char, String> {
+        let first = self.hex()?;
+        if (0xd800..0xdc00).contains(&first) && self.source[self.position..].starts_with("\\u") {
+            self.position += 2;
+            let second = self.hex()?;
+            let code = 0x10000 + ((first - 0xd800) << 10) + (second.wrapping_sub(0xdc00) & 0x3ff);
+            return char::from_u32(code).ok_or_else(|| self.error("invalid surrogate pair"));
+        }
+        char::from_u32(first).ok_or_else(|| self.error("invalid code point"))
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let digits = self
+            .source
+            .get(self.position..self.position + 4)
+            .ok_or_else(|| self.error("expected four hex digits"))?;
+        let code =
+            u32::from_str_radix(digits, 16).map_err(|_| self.error("expected four hex digits"))?;
+        self.position += 4;
+        Ok(code)
+    }
+}
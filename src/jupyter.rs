@@ -0,0 +1,599 @@
+use std::{
+    collections::BTreeSet,
+    fmt::Write as _,
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use miette::Report;
+
+use crate::{checksum::Sha256, datetime::DateTime, json::Json, serve::Control, value::Value};
+
+// `xi --jupyter-kernel connection.json`: a kernel for Jupyter frontends,
+// speaking version 5.3 of its messaging protocol over the sockets the
+// connection file names. The frontend's ZeroMQ sockets are spoken to in
+// ZMTP 3.0 over TCP, with the NULL mechanism, so that no library is needed:
+//
+//   shell, control  ROUTER  requests, each answered on its own connection
+//   iopub           PUB     what the requests print and give, to everyone
+//   hb              REP     every message sent back as it came
+//
+// A message is its frames after a `<IDS|MSG>` delimiter: the HMAC-SHA256
+// signature of the four that follow, then the header, the parent header,
+// the metadata and the content, as JSON. Messages with a wrong signature are
+// dropped. `interrupt_request`s on the control socket are answered at once
+// and stop the evaluation running, if any; every other request comes out of
+// `Kernel::requests`, one at a time, in the order it arrives. The stdin
+// socket isn't listened on: cells can't read input.
+
+const PROTOCOL_VERSION: &str = "5.3";
+
+const DELIMITER: &[u8] = b"<IDS|MSG>";
+
+// frame flags
+const MORE: u8 = 0x01;
+const LONG: u8 = 0x02;
+const COMMAND: u8 = 0x04;
+
+// big enough for any cell, and a stop for a peer that isn't speaking ZMTP
+const MAX_FRAME: u64 = 1 << 30;
+
+// the ports and key of a connection file
+pub struct Connection {
+    ip: String,
+    key: Vec<u8>,
+    shell_port: u16,
+    control_port: u16,
+    iopub_port: u16,
+    hb_port: u16,
+}
+
+impl Connection {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let json = Json::parse(source)?;
+        let text = |field: &str| match json.get(field).and_then(Json::as_str) {
+            Some(text) => Ok(text),
+            None => Err(format!("\"{}\" must be a string", field)),
+        };
+        let port = |field: &str| match json.get(field) {
+            Some(Json::Number(number)) => number
+                .parse()
+                .map_err(|_| format!("\"{}\" must be a port", field)),
+            _ => Err(format!("\"{}\" must be a number", field)),
+        };
+        if let Ok(transport) = text("transport") {
+            if transport != "tcp" {
+                return Err(format!(
+                    "transport `{}` isn't supported, use tcp",
+                    transport
+                ));
+            }
+        }
+        let key = text("key")?;
+        if !key.is_empty() {
+            match text("signature_scheme")? {
+                "hmac-sha256" => {}
+                scheme => {
+                    return Err(format!(
+                        "signature scheme `{}` isn't supported, use hmac-sha256",
+                        scheme
+                    ))
+                }
+            }
+        }
+        Ok(Self {
+            ip: text("ip")?.to_string(),
+            key: key.as_bytes().to_vec(),
+            shell_port: port("shell_port")?,
+            control_port: port("control_port")?,
+            iopub_port: port("iopub_port")?,
+            hb_port: port("hb_port")?,
+        })
+    }
+
+    fn bind(&self, port: u16) -> io::Result<TcpListener> {
+        TcpListener::bind((self.ip.as_str(), port))
+    }
+}
+
+// the greeting both ends of a ZMTP connection start with
+fn greeting() -> [u8; 64] {
+    let mut greeting = [0; 64];
+    greeting[0] = 0xff;
+    greeting[9] = 0x7f;
+    greeting[10] = 3;
+    greeting[12..16].copy_from_slice(b"NULL");
+    greeting
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// greetings, then READY commands saying what kind of socket each end is
+fn handshake(stream: &mut TcpStream, socket_type: &str) -> io::Result<()> {
+    stream.write_all(&greeting())?;
+    let mut peer = [0; 64];
+    stream.read_exact(&mut peer)?;
+    if peer[0] != 0xff || peer[9] != 0x7f || peer[10] < 3 {
+        return Err(invalid("the peer doesn't speak ZMTP 3"));
+    }
+    if peer[12..32] != greeting()[12..32] {
+        return Err(invalid(
+            "the peer wants a security mechanism other than NULL",
+        ));
+    }
+
+    let mut ready = vec![5];
+    ready.extend_from_slice(b"READY");
+    ready.push(11);
+    ready.extend_from_slice(b"Socket-Type");
+    ready.extend_from_slice(&(socket_type.len() as u32).to_be_bytes());
+    ready.extend_from_slice(socket_type.as_bytes());
+    write_frame(stream, COMMAND, &ready)?;
+    stream.flush()?;
+
+    match read_frame(stream)? {
+        (flags, body) if flags & COMMAND != 0 && body.starts_with(b"\x05READY") => Ok(()),
+        _ => Err(invalid("the peer didn't send READY")),
+    }
+}
+
+fn read_frame(stream: &mut TcpStream) -> io::Result<(u8, Vec<u8>)> {
+    let mut flags = [0];
+    stream.read_exact(&mut flags)?;
+    let size = match flags[0] & LONG {
+        0 => {
+            let mut size = [0];
+            stream.read_exact(&mut size)?;
+            size[0] as u64
+        }
+        _ => {
+            let mut size = [0; 8];
+            stream.read_exact(&mut size)?;
+            u64::from_be_bytes(size)
+        }
+    };
+    if size > MAX_FRAME {
+        return Err(invalid("frame too large"));
+    }
+    let mut body = vec![0; size as usize];
+    stream.read_exact(&mut body)?;
+    Ok((flags[0], body))
+}
+
+fn write_frame(stream: &mut impl Write, flags: u8, body: &[u8]) -> io::Result<()> {
+    match u8::try_from(body.len()) {
+        Ok(size) => stream.write_all(&[flags, size])?,
+        Err(_) => {
+            stream.write_all(&[flags | LONG])?;
+            stream.write_all(&(body.len() as u64).to_be_bytes())?;
+        }
+    }
+    stream.write_all(body)
+}
+
+// the frames of the next message, past any commands
+fn read_message(stream: &mut TcpStream) -> io::Result<Vec<Vec<u8>>> {
+    let mut frames = Vec::new();
+    loop {
+        let (flags, body) = read_frame(stream)?;
+        if flags & COMMAND != 0 {
+            continue;
+        }
+        frames.push(body);
+        if flags & MORE == 0 {
+            return Ok(frames);
+        }
+    }
+}
+
+// in one write, so that messages sent from several threads don't interleave
+fn write_message(stream: &mut TcpStream, frames: &[Vec<u8>]) -> io::Result<()> {
+    let mut buffer = Vec::new();
+    for (i, frame) in frames.iter().enumerate() {
+        let flags = if i + 1 < frames.len() { MORE } else { 0 };
+        write_frame(&mut buffer, flags, frame)?;
+    }
+    stream.write_all(&buffer)?;
+    stream.flush()
+}
+
+// HMAC-SHA256 of `parts`, in hex, or nothing without a key
+fn sign(key: &[u8], parts: &[Vec<u8>]) -> String {
+    if key.is_empty() {
+        return String::new();
+    }
+    let mut block = [0; 64];
+    match key.len() > block.len() {
+        true => {
+            let mut hash = Sha256::new();
+            hash.update(key);
+            block[..32].copy_from_slice(&hash.finish());
+        }
+        false => block[..key.len()].copy_from_slice(key),
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(&block.map(|byte| byte ^ 0x36));
+    for part in parts {
+        inner.update(part);
+    }
+    let mut outer = Sha256::new();
+    outer.update(&block.map(|byte| byte ^ 0x5c));
+    outer.update(&inner.finish());
+
+    let mut hex = String::new();
+    for byte in outer.finish() {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    hex
+}
+
+// an object from its fields, in order
+pub fn object<const N: usize>(fields: [(&str, Json); N]) -> Json {
+    Json::Object(
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    )
+}
+
+pub struct Message {
+    identities: Vec<Vec<u8>>,
+    pub header: Json,
+    pub content: Json,
+}
+
+impl Message {
+    // the frames of a message, unless it isn't one or isn't signed by `key`
+    fn decode(mut frames: Vec<Vec<u8>>, key: &[u8]) -> Result<Self, String> {
+        let delimiter = frames
+            .iter()
+            .position(|frame| frame == DELIMITER)
+            .ok_or("a message must have a delimiter")?;
+        let mut parts = frames.split_off(delimiter + 1);
+        frames.pop();
+        if parts.len() < 5 {
+            return Err(
+                "a message must have a header, a parent header, metadata and content".into(),
+            );
+        }
+        let signature = parts.remove(0);
+        if signature != sign(key, &parts[..4]).as_bytes() {
+            return Err("a message has the wrong signature".into());
+        }
+        let json = |part: &[u8]| {
+            let text = std::str::from_utf8(part).map_err(|err| err.to_string())?;
+            Json::parse(text)
+        };
+        Ok(Self {
+            identities: frames,
+            header: json(&parts[0])?,
+            content: json(&parts[3])?,
+        })
+    }
+
+    pub fn kind(&self) -> &str {
+        self.header
+            .get("msg_type")
+            .and_then(Json::as_str)
+            .unwrap_or("")
+    }
+}
+
+// what signs the messages the kernel sends, and gives them their ids
+struct Signer {
+    key: Vec<u8>,
+    session: String,
+    sent: AtomicUsize,
+}
+
+impl Signer {
+    fn frames(
+        &self,
+        identities: &[Vec<u8>],
+        parent: &Json,
+        kind: &str,
+        content: Json,
+    ) -> Vec<Vec<u8>> {
+        let number = self.sent.fetch_add(1, Ordering::Relaxed);
+        let header = object([
+            ("msg_id", format!("{}-{}", self.session, number).into()),
+            ("session", self.session.as_str().into()),
+            ("username", "xi".into()),
+            ("date", DateTime::now().to_string().into()),
+            ("msg_type", kind.into()),
+            ("version", PROTOCOL_VERSION.into()),
+        ]);
+        let parts =
+            [header, parent.clone(), object([]), content].map(|part| part.to_string().into_bytes());
+        let mut frames = identities.to_vec();
+        frames.push(DELIMITER.to_vec());
+        frames.push(sign(&self.key, &parts).into_bytes());
+        frames.extend(parts);
+        frames
+    }
+}
+
+// a request to answer, and the connection to answer it on
+pub struct Incoming {
+    pub message: Message,
+    stream: Arc<Mutex<TcpStream>>,
+    signer: Arc<Signer>,
+}
+
+impl Incoming {
+    // a connection that went away is no longer answered
+    pub fn reply(&self, kind: &str, content: Json) {
+        let message = &self.message;
+        let frames = self
+            .signer
+            .frames(&message.identities, &message.header, kind, content);
+        let _ = write_message(&mut self.stream.lock().unwrap(), &frames);
+    }
+}
+
+pub struct Kernel {
+    requests: Receiver<Incoming>,
+    subscribers: Arc<Mutex<Vec<TcpStream>>>,
+    signer: Arc<Signer>,
+}
+
+impl Kernel {
+    // binds every socket, and listens on them in the background
+    pub fn start(connection: &Connection, control: Arc<Control>) -> io::Result<Self> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos());
+        let signer = Arc::new(Signer {
+            key: connection.key.clone(),
+            session: format!("{:x}-{:x}", nanos, std::process::id()),
+            sent: AtomicUsize::new(0),
+        });
+        let shell = connection.bind(connection.shell_port)?;
+        let control_socket = connection.bind(connection.control_port)?;
+        let iopub = connection.bind(connection.iopub_port)?;
+        let heartbeat = connection.bind(connection.hb_port)?;
+
+        let (sender, requests) = mpsc::channel();
+        accept(shell, "ROUTER", {
+            let (signer, sender) = (signer.clone(), sender.clone());
+            move |stream| client(stream, &signer, &sender, None)
+        });
+        accept(control_socket, "ROUTER", {
+            let signer = signer.clone();
+            move |stream| client(stream, &signer, &sender, Some(&control))
+        });
+        accept(heartbeat, "REP", |mut stream| {
+            while let Ok(frames) = read_message(&mut stream) {
+                write_message(&mut stream, &frames)?;
+            }
+            Ok(())
+        });
+        // subscriptions aren't kept: every subscriber gets every message
+        let subscribers = Arc::new(Mutex::new(Vec::new()));
+        accept(iopub, "PUB", {
+            let subscribers = subscribers.clone();
+            move |mut stream| {
+                subscribers.lock().unwrap().push(stream.try_clone()?);
+                while read_message(&mut stream).is_ok() {}
+                Ok(())
+            }
+        });
+
+        Ok(Self {
+            requests,
+            subscribers,
+            signer,
+        })
+    }
+
+    pub fn requests(&self) -> impl Iterator<Item = Incoming> + '_ {
+        self.requests.iter()
+    }
+
+    // sends to every subscriber, under the kind of the message as its topic,
+    // dropping the ones that went away
+    pub fn publish(&self, parent: &Message, kind: &str, content: Json) {
+        let topic = [kind.as_bytes().to_vec()];
+        let frames = self.signer.frames(&topic, &parent.header, kind, content);
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain_mut(|stream| write_message(stream, &frames).is_ok());
+    }
+}
+
+// accepts connections in the background, each with a thread of its own
+// that shakes hands and then runs `serve` until the connection fails
+fn accept<F>(listener: TcpListener, socket_type: &'static str, serve: F)
+where
+    F: Fn(TcpStream) -> io::Result<()> + Clone + Send + 'static,
+{
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let serve = serve.clone();
+            thread::spawn(move || {
+                handshake(&mut stream, socket_type)?;
+                serve(stream)
+            });
+        }
+    });
+}
+
+// reads the requests of one connection until it closes
+fn client(
+    mut stream: TcpStream,
+    signer: &Arc<Signer>,
+    requests: &Sender<Incoming>,
+    control: Option<&Control>,
+) -> io::Result<()> {
+    let writer = Arc::new(Mutex::new(stream.try_clone()?));
+    loop {
+        let Ok(message) = Message::decode(read_message(&mut stream)?, &signer.key) else {
+            continue;
+        };
+        let incoming = Incoming {
+            message,
+            stream: writer.clone(),
+            signer: signer.clone(),
+        };
+        match control {
+            Some(control) if incoming.message.kind() == "interrupt_request" => {
+                control.raise();
+                incoming.reply("interrupt_reply", object([("status", "ok".into())]));
+            }
+            _ => {
+                if requests.send(incoming).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+// what `kernel_info_request` is answered with
+pub fn info() -> Json {
+    let version = env!("CARGO_PKG_VERSION");
+    object([
+        ("status", "ok".into()),
+        ("protocol_version", PROTOCOL_VERSION.into()),
+        ("implementation", "xi".into()),
+        ("implementation_version", version.into()),
+        (
+            "language_info",
+            object([
+                ("name", "xi".into()),
+                ("version", version.into()),
+                ("mimetype", "text/x-xi".into()),
+                ("file_extension", ".xi".into()),
+            ]),
+        ),
+        ("banner", format!("xi {}", version).into()),
+        ("help_links", Json::Array(Vec::new())),
+    ])
+}
+
+// a value as the frontend shows it: as printed, and as an HTML table too
+// when it's a table, a dict or a list
+pub fn display(value: &Value) -> Json {
+    let mut data = vec![("text/plain".to_string(), value.to_string().into())];
+    if let Some(html) = html(value) {
+        data.push(("text/html".to_string(), html.into()));
+    }
+    Json::Object(data)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// the columns and cells of a value shown as a table: a table's own, a dict's
+// keys and values, in the order of the keys, or a list's items, each a row
+// of the keys its dict has when they are all dicts, or else an index and the
+// item
+fn cells(value: &Value) -> Option<(Vec<String>, Vec<Vec<String>>)> {
+    let entries = |value: &Value| match value {
+        Value::Dict(dict) => {
+            let mut entries: Vec<_> = dict
+                .borrow()
+                .0
+                .iter()
+                .map(|(key, value)| (key.0.to_string(), value.to_string()))
+                .collect();
+            entries.sort();
+            Some(entries)
+        }
+        _ => None,
+    };
+
+    match value {
+        Value::Table(table) => {
+            let names = table.columns.iter().map(|column| column.name.to_string());
+            let rows = (0..table.len()).map(|row| {
+                table
+                    .columns
+                    .iter()
+                    .map(|column| column.values[row].to_string())
+                    .collect()
+            });
+            Some((names.collect(), rows.collect()))
+        }
+        Value::Dict(_) => {
+            let rows = entries(value)?
+                .into_iter()
+                .map(|(key, value)| vec![key, value]);
+            Some((vec!["key".into(), "value".into()], rows.collect()))
+        }
+        Value::List(list) => {
+            let items = &list.borrow().0;
+            if items.is_empty() {
+                return None;
+            }
+            let Some(dicts) = items.iter().map(entries).collect::<Option<Vec<_>>>() else {
+                let rows = items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| vec![i.to_string(), item.to_string()]);
+                return Some((vec!["".into(), "value".into()], rows.collect()));
+            };
+            let names: BTreeSet<_> = dicts.iter().flatten().map(|(key, _)| key.clone()).collect();
+            let names: Vec<_> = names.into_iter().collect();
+            let rows = dicts.iter().map(|entries| {
+                names
+                    .iter()
+                    .map(|name| match entries.iter().find(|(key, _)| key == name) {
+                        Some((_, value)) => value.clone(),
+                        None => String::new(),
+                    })
+                    .collect()
+            });
+            let rows = rows.collect();
+            Some((names, rows))
+        }
+        _ => None,
+    }
+}
+
+fn html(value: &Value) -> Option<String> {
+    let (names, rows) = cells(value)?;
+    let mut html = String::from("<table>\n<thead><tr>");
+    for name in names {
+        let _ = write!(html, "<th>{}</th>", escape(&name));
+    }
+    html.push_str("</tr></thead>\n<tbody>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            let _ = write!(html, "<td>{}</td>", escape(&cell));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</tbody>\n</table>");
+    Some(html)
+}
+
+// the fields an error is shown with, its traceback drawn by miette
+pub fn error(report: &Report) -> Vec<(String, Json)> {
+    let name = match report.code() {
+        Some(code) => format!("error[{}]", code),
+        None => "error".to_string(),
+    };
+    let traceback = format!("{:?}", report).lines().map(Json::from).collect();
+    vec![
+        ("ename".to_string(), name.into()),
+        ("evalue".to_string(), report.to_string().into()),
+        ("traceback".to_string(), Json::Array(traceback)),
+    ]
+}
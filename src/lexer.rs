@@ -2,10 +2,15 @@ use std::str::Chars;
 
 use miette::Result;
 use peekmore::{PeekMore, PeekMoreIterator};
-use rug::{Assign, Float, Integer};
+use rug::{Assign, Float, Integer, Rational};
 
 use crate::{
-    report::{MalformedFloatPrecision, MalformedNumber, UnexpectedCharacter, UnterminatedSequence},
+    fixed::KINDS,
+    report::{
+        InvalidEscape, LiteralOutOfRange, MalformedFloatPrecision, MalformedNumber,
+        UnexpectedCharacter, UnknownNumberSuffix, UnterminatedSequence,
+    },
+    sync::Rc,
     token::{Literal, Span, Token, TokenKind},
 };
 
@@ -17,7 +22,68 @@ pub struct Lexer<'a> {
     current: usize,
 }
 
-const DEFAULT_FLOAT_PRECISION: u32 = 64;
+pub(crate) const DEFAULT_FLOAT_PRECISION: u32 = 64;
+
+pub const KEYWORDS: [(&str, TokenKind); 23] = [
+    ("and", TokenKind::And),
+    ("catch", TokenKind::Catch),
+    ("class", TokenKind::Class),
+    ("else", TokenKind::Else),
+    ("false", TokenKind::False),
+    ("fn", TokenKind::Fn),
+    ("for", TokenKind::For),
+    ("if", TokenKind::If),
+    ("import", TokenKind::Import),
+    ("in", TokenKind::In),
+    ("nil", TokenKind::Nil),
+    ("or", TokenKind::Or),
+    ("quote", TokenKind::Quote),
+    ("return", TokenKind::Return),
+    ("super", TokenKind::Super),
+    ("this", TokenKind::This),
+    ("throw", TokenKind::Throw),
+    ("true", TokenKind::True),
+    ("try", TokenKind::Try),
+    ("unquote", TokenKind::Unquote),
+    ("let", TokenKind::Let),
+    ("match", TokenKind::Match),
+    ("while", TokenKind::While),
+];
+
+fn unknown_suffix(suffix: &str, span: Span) -> miette::Report {
+    let kinds: Vec<_> = KINDS.iter().map(|(name, _)| *name).collect();
+    UnknownNumberSuffix {
+        span: span.into(),
+        suffix: suffix.to_string(),
+        help: format!(
+            "use `f` for a float, `r` for a rational, or one of {}",
+            kinds.join(", ")
+        ),
+    }
+    .into()
+}
+
+// the most digits a rational literal's exponent adds to it or takes off it
+const MAX_RATIONAL_SCALE: u32 = 10_000;
+
+// the exact value of a decimal like `1.25` or `5e-3`, when its exponent isn't
+// too big to write out
+fn rational(literal: &str) -> Option<Rational> {
+    let (mantissa, exponent) = match literal.find(['e', 'E']) {
+        Some(index) => (&literal[..index], literal[index + 1..].parse::<i32>().ok()?),
+        None => (literal, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = Integer::from_str_radix(&format!("{}{}", whole, fraction), 10).ok()?;
+    let scale = exponent
+        .checked_sub(i32::try_from(fraction.len()).ok()?)
+        .filter(|scale| scale.unsigned_abs() <= MAX_RATIONAL_SCALE)?;
+    let power = Integer::from(Integer::u_pow_u(10, scale.unsigned_abs()));
+    Some(match scale < 0 {
+        true => Rational::from((digits, power)),
+        false => Rational::from(digits * power),
+    })
+}
 
 impl<'a> Lexer<'a> {
     pub fn new(source: &'a str) -> Self {
@@ -66,46 +132,132 @@ impl<'a> Lexer<'a> {
 
     fn matches(&mut self, c: char) -> bool {
         match self.peek() {
-            None => false,
-            Some(&other) => {
-                if c == other {
-                    self.next();
-                    true
-                } else {
-                    false
-                }
+            Some(&other) if c == other => {
+                self.next();
+                true
             }
+            _ => false,
         }
     }
 
     fn next(&mut self) -> Option<char> {
         let next = self.chars.next();
-        if next.is_some() {
-            self.current += 1;
+        if let Some(c) = next {
+            self.current += c.len_utf8();
         }
 
         next
     }
 
+    // after `"`: `\n`, `\r`, `\t`, `\0`, `\\`, `\"` and `\u{...}`, with
+    // the hex code of any character but a surrogate, are escapes
     fn scan_string(&mut self) -> Result<()> {
+        let mut string = String::new();
         loop {
-            match self.peek() {
-                Some('"') | None => break,
-                _ => self.next(),
-            };
+            let escape_start = self.current;
+            match self.next() {
+                Some('"') => break,
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some('n') => Some('\n'),
+                        Some('r') => Some('\r'),
+                        Some('t') => Some('\t'),
+                        Some('0') => Some('\0'),
+                        Some('\\') => Some('\\'),
+                        Some('"') => Some('"'),
+                        Some('u') => self.scan_unicode_escape(),
+                        _ => None,
+                    };
+                    match c {
+                        Some(c) => string.push(c),
+                        None => {
+                            return Err(InvalidEscape {
+                                span: Span::new_range(escape_start, self.current).into(),
+                            }
+                            .into())
+                        }
+                    }
+                }
+                Some(c) => string.push(c),
+                None => {
+                    return Err(UnterminatedSequence {
+                        span: self.span().into(),
+                    }
+                    .into())
+                }
+            }
         }
 
-        if self.peek().is_some() {
-            self.next(); // "
-            let literal = self.source[self.start + 1..self.current - 1].to_string();
-            self.emit(TokenKind::String, Some(Literal::String(literal)))?;
-            Ok(())
-        } else {
-            Err(UnterminatedSequence {
-                span: self.span().into(),
+        self.emit(TokenKind::String, Some(Literal::String(string.into())))
+    }
+
+    // after `\u`: `{`, one to six hex digits and `}`
+    fn scan_unicode_escape(&mut self) -> Option<char> {
+        if !self.matches('{') {
+            return None;
+        }
+        let mut code = 0u32;
+        for digits in 0.. {
+            match self.next()? {
+                '}' if digits > 0 => return char::from_u32(code),
+                c if digits < 6 => code = code * 16 + c.to_digit(16)?,
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    // after `b"`: bytes may be written as `\xNN`, `\0`, `\n`, `\r`, `\t`, `\\`
+    // and `\"`, everything else stands for its UTF-8 encoding
+    fn scan_bytes(&mut self) -> Result<()> {
+        let mut bytes = Vec::new();
+        loop {
+            let escape_start = self.current;
+            match self.next() {
+                Some('"') => break,
+                Some('\\') => {
+                    let byte = match self.next() {
+                        Some('x') => {
+                            let digits = [self.next(), self.next()];
+                            match digits {
+                                [Some(high), Some(low)] => {
+                                    match (high.to_digit(16), low.to_digit(16)) {
+                                        (Some(high), Some(low)) => Some((high * 16 + low) as u8),
+                                        _ => None,
+                                    }
+                                }
+                                _ => None,
+                            }
+                        }
+                        Some('0') => Some(b'\0'),
+                        Some('n') => Some(b'\n'),
+                        Some('r') => Some(b'\r'),
+                        Some('t') => Some(b'\t'),
+                        Some('\\') => Some(b'\\'),
+                        Some('"') => Some(b'"'),
+                        _ => None,
+                    };
+                    match byte {
+                        Some(byte) => bytes.push(byte),
+                        None => {
+                            return Err(InvalidEscape {
+                                span: Span::new_range(escape_start, self.current).into(),
+                            }
+                            .into())
+                        }
+                    }
+                }
+                Some(c) => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                None => {
+                    return Err(UnterminatedSequence {
+                        span: self.span().into(),
+                    }
+                    .into())
+                }
             }
-            .into())
         }
+
+        self.emit(TokenKind::Bytes, Some(Literal::Bytes(bytes.into())))
     }
 
     fn scan_number_as_float(&mut self) -> Result<()> {
@@ -118,18 +270,38 @@ impl<'a> Lexer<'a> {
                 self.next();
             }
             let literal = self.source[end + 1..self.current].to_string();
-            precision = literal.parse().map_err(|_| MalformedFloatPrecision {
-                span: Span::new(end, 1).into(),
-            })?;
+            precision = literal
+                .parse()
+                .ok()
+                .filter(|p| (rug::float::prec_min()..=rug::float::prec_max()).contains(p))
+                .ok_or(MalformedFloatPrecision {
+                    span: Span::new(end, 1).into(),
+                })?;
         }
 
-        let literal = self.source[self.start..end].to_string();
-        let parse = Float::parse(literal);
+        let literal = &self.source[self.start..end];
+        let exact = self.current == end;
+        match self.suffix() {
+            ("" | "f", _) => {}
+            // a rational has no precision to give
+            ("r", _) if exact => {
+                return match rational(literal) {
+                    Some(rational) => {
+                        self.emit(TokenKind::Float, Some(Literal::Rational(Rc::new(rational))))
+                    }
+                    None => Err(MalformedNumber {
+                        span: self.span().into(),
+                    }
+                    .into()),
+                };
+            }
+            (suffix, span) => return Err(unknown_suffix(suffix, span)),
+        }
 
-        if let Ok(src) = parse {
+        if let Ok(src) = Float::parse(literal) {
             let mut float = Float::new(precision);
             float.assign(src);
-            self.emit(TokenKind::Float, Some(Literal::Float(float)))
+            self.emit(TokenKind::Float, Some(float.into()))
         } else {
             Err(MalformedNumber {
                 span: self.span().into(),
@@ -138,14 +310,48 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // the letters and digits right after a number: `f` in `10f`, `u8` in `255u8`
+    fn suffix(&mut self) -> (&'a str, Span) {
+        let start = self.current;
+        if self.peek_is(|c| c.is_ascii_alphabetic()) {
+            while self.peek_is(|c| c.is_ascii_alphanumeric()) {
+                self.next();
+            }
+        }
+        let span = Span::new(start, self.current - start);
+        (&self.source[start..self.current], span)
+    }
+
     fn scan_number_as_integer(&mut self) -> Result<()> {
         let literal = self.source[self.start..self.current].to_string();
         let parse = Integer::parse(literal);
+        let (suffix, span) = self.suffix();
 
         if let Ok(src) = parse {
             let mut integer = Integer::new();
             integer.assign(src);
-            self.emit(TokenKind::Integer, Some(Literal::Integer(integer)))
+            match suffix {
+                // an integer is already exact
+                "" | "r" => self.emit(TokenKind::Integer, Some(integer.into())),
+                "f" => {
+                    let precision = DEFAULT_FLOAT_PRECISION.max(integer.significant_bits());
+                    let float = Float::with_val(precision, integer);
+                    self.emit(TokenKind::Float, Some(float.into()))
+                }
+                suffix => match KINDS.iter().find(|(name, _)| *name == suffix) {
+                    Some((_, kind)) => match kind.exact(&integer) {
+                        Some(fixed) => self.emit(TokenKind::Integer, Some(Literal::Fixed(fixed))),
+                        None => Err(LiteralOutOfRange {
+                            span: self.span().into(),
+                            kind: suffix.to_string(),
+                            min: kind.min(),
+                            max: kind.max(),
+                        }
+                        .into()),
+                    },
+                    None => Err(unknown_suffix(suffix, span)),
+                },
+            }
         } else {
             Err(MalformedNumber {
                 span: self.span().into(),
@@ -154,59 +360,60 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn scan_number(&mut self) -> Result<()> {
-        while let Some(c) = self.peek() {
-            if !c.is_ascii_digit() {
-                break;
-            }
+    // `e` or `E`, an optional sign and at least one digit, starting `n`
+    // characters ahead
+    fn exponent_at(&mut self, n: usize) -> bool {
+        self.peek_nth_is(n, |c| c == 'e' || c == 'E')
+            && (self.peek_nth_is(n + 1, |c| c.is_ascii_digit())
+                || (self.peek_nth_is(n + 1, |c| c == '+' || c == '-')
+                    && self.peek_nth_is(n + 2, |c| c.is_ascii_digit())))
+    }
+
+    fn skip_digits(&mut self) {
+        while self.peek_is(|c| c.is_ascii_digit()) {
             self.next();
         }
+    }
 
-        if self.peek_is(|c| c == '.') {
-            if self.peek_nth_is(1, |c| c.is_ascii_digit()) {
-                self.next(); // .
-
-                while self.peek_is(|c| c.is_ascii_digit()) {
-                    self.next();
-                }
-
-                if self.peek_is(|c| c == 'e') {
-                    if self.peek_nth_is(1, |c| c == '+' || c == '-')
-                        && self.peek_nth_is(2, |c| c.is_ascii_digit())
-                    {
-                        self.next(); // e
-                    }
-
-                    if self.peek_nth_is(1, |c| c.is_ascii_digit()) {
-                        self.next(); // e (or + / - if e already removed)
-                        while self.peek_is(|c| c.is_ascii_digit()) {
-                            self.next();
-                        }
-                    }
-                }
+    // digits, then an optional fraction (`1.5`, `2.`), an optional exponent
+    // (`1e10`, `1E-3`) and an optional precision (`1.5_128`). The `.` only
+    // belongs to the number when a digit or an exponent follows it, so `2.f()`
+    // still calls `f`.
+    fn scan_number(&mut self) -> Result<()> {
+        self.skip_digits();
+        let mut float = false;
+
+        if self.peek_is(|c| c == '.')
+            && (self.peek_nth_is(1, |c| c.is_ascii_digit()) || self.exponent_at(1))
+        {
+            self.next(); // .
+            self.skip_digits();
+            float = true;
+        }
 
-                return self.scan_number_as_float();
+        if self.exponent_at(0) {
+            self.next(); // e
+            if self.peek_is(|c| c == '+' || c == '-') {
+                self.next();
             }
-        } else if self.peek_is(|c| c == 'e') {
-            if self.peek_nth_is(1, |c| c == '+' || c == '-')
-                && self.peek_nth_is(2, |c| c.is_ascii_digit())
-            {
-                self.next(); // e
+            self.skip_digits();
+            float = true;
+        } else if self.peek_is(|c| c == 'e' || c == 'E') {
+            // an exponent without digits
+            while self.peek_is(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-') {
+                self.next();
             }
-
-            if self.peek_nth_is(1, |c| c.is_ascii_digit()) {
-                self.next(); // e (or + / - if e already removed)
-                while self.peek_is(|c| c.is_ascii_digit()) {
-                    self.next();
-                }
-
-                return self.scan_number_as_float();
+            return Err(MalformedNumber {
+                span: self.span().into(),
             }
-        } else if self.peek_is(|c| c == '_') {
-            return self.scan_number_as_float();
+            .into());
         }
 
-        self.scan_number_as_integer()
+        if float || self.peek_is(|c| c == '_') {
+            self.scan_number_as_float()
+        } else {
+            self.scan_number_as_integer()
+        }
     }
 
     fn scan_identifier(&mut self) -> Result<()> {
@@ -219,22 +426,11 @@ impl<'a> Lexer<'a> {
 
         let literal = &self.source[self.start..self.current];
 
-        match literal {
-            "and" => self.emit(TokenKind::And, None),
-            "else" => self.emit(TokenKind::Else, None),
-            "false" => self.emit(TokenKind::False, None),
-            "fn" => self.emit(TokenKind::Fn, None),
-            "for" => self.emit(TokenKind::For, None),
-            "if" => self.emit(TokenKind::If, None),
-            "nil" => self.emit(TokenKind::Nil, None),
-            "or" => self.emit(TokenKind::Or, None),
-            "return" => self.emit(TokenKind::Return, None),
-            "true" => self.emit(TokenKind::True, None),
-            "let" => self.emit(TokenKind::Let, None),
-            "while" => self.emit(TokenKind::While, None),
-            other => self.emit(
+        match KEYWORDS.iter().find(|(keyword, _)| *keyword == literal) {
+            Some((_, kind)) => self.emit(*kind, None),
+            None => self.emit(
                 TokenKind::Identifier,
-                Some(Literal::Identifier(other.to_string())),
+                Some(Literal::Identifier(literal.to_string())),
             ),
         }
     }
@@ -248,6 +444,11 @@ impl<'a> Lexer<'a> {
             '[' => self.emit(TokenKind::LeftSquare, None),
             ']' => self.emit(TokenKind::RightSquare, None),
             ',' => self.emit(TokenKind::Comma, None),
+            '.' if self.peek_is(|c| c == '.') && self.peek_nth_is(1, |c| c == '.') => {
+                self.next(); // .
+                self.next(); // .
+                self.emit(TokenKind::DotDotDot, None)
+            }
             '.' => self.emit(TokenKind::Dot, None),
             '-' => self.emit(TokenKind::Minus, None),
             '+' => self.emit(TokenKind::Plus, None),
@@ -256,7 +457,17 @@ impl<'a> Lexer<'a> {
             '*' => self.emit(TokenKind::Star, None),
             '/' => self.emit(TokenKind::Slash, None),
             '|' => self.emit(TokenKind::Pipe, None),
+            '?' => self.emit(TokenKind::Question, None),
+            '@' => self.emit(TokenKind::At, None),
+            // names don't start with `_`, so a lone one is a wildcard
+            '_' if !self.peek_is(|c| c.is_ascii_alphanumeric() || c == '_') => {
+                self.emit(TokenKind::Underscore, None)
+            }
             '"' => self.scan_string(),
+            'b' if self.peek_is(|c| c == '"') => {
+                self.next(); // "
+                self.scan_bytes()
+            }
             '!' => {
                 if self.matches('=') {
                     self.emit(TokenKind::BangEqual, None)
@@ -267,6 +478,8 @@ impl<'a> Lexer<'a> {
             '=' => {
                 if self.matches('=') {
                     self.emit(TokenKind::EqualEqual, None)
+                } else if self.matches('>') {
+                    self.emit(TokenKind::FatArrow, None)
                 } else {
                     self.emit(TokenKind::Equal, None)
                 }
@@ -285,6 +498,8 @@ impl<'a> Lexer<'a> {
                     self.emit(TokenKind::Less, None)
                 }
             }
+            // `#{` opens a set, and can't start a comment
+            '#' if self.matches('{') => self.emit(TokenKind::HashLeftBrace, None),
             '#' => {
                 loop {
                     match self.next() {
@@ -0,0 +1,54 @@
+pub mod alloc;
+pub mod annotation;
+pub mod archive;
+pub mod ast;
+pub mod astdiff;
+pub mod builtin;
+pub mod checksum;
+pub mod class;
+pub mod context;
+pub mod datetime;
+pub mod dict;
+pub mod env;
+pub mod exact;
+pub mod explain;
+pub mod expr;
+pub mod file;
+pub mod fixed;
+pub mod fold;
+pub mod function;
+pub mod fuzz;
+pub mod heap;
+pub mod highlight;
+pub mod hoist;
+pub mod interpreter;
+pub mod iterator;
+pub mod json;
+pub mod jupyter;
+pub mod lexer;
+pub mod list;
+pub mod msgpack;
+pub mod native;
+pub mod notebook;
+pub mod parser;
+pub mod plot;
+pub mod printer;
+pub mod process;
+pub mod progress;
+pub mod query;
+pub mod repl;
+pub mod report;
+pub mod repr;
+pub mod resolver;
+pub mod runtime;
+pub mod scheduler;
+pub mod semver;
+pub mod serve;
+pub mod session;
+pub mod set;
+pub mod step;
+pub mod sync;
+pub mod table;
+pub mod token;
+pub mod url;
+pub mod value;
@@ -1,86 +1,467 @@
-mod builtin;
-mod context;
-mod dict;
-mod env;
-mod expr;
-mod function;
-mod interpreter;
-mod lexer;
-mod list;
-mod parser;
-mod report;
-mod resolver;
-mod token;
-mod value;
-
 use std::{
-    cell::RefCell,
     fs,
+    io::{Read, Write},
     path::{Path, PathBuf},
-    rc::Rc,
-    time::SystemTime,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
-use anyhow::Context;
-use clap::Parser as CliParser;
-use env::Env;
-use miette::Result;
-use rustyline::{error::ReadlineError, DefaultEditor};
-
-use crate::{
+use anyhow::{bail, Context};
+use clap::{Parser as CliParser, Subcommand, ValueEnum};
+use miette::{Report, Result};
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    history::DefaultHistory, validate::Validator, Context as LineContext, DefaultEditor, Editor,
+    Helper,
+};
+use xi::{
+    alloc::{self, human_bytes, CountingAllocator},
+    annotation, astdiff,
     context::Ctx,
-    interpreter::{interpret, RuntimeError},
+    env::Env,
+    explain::{self, EXPLANATIONS},
+    expr::{Stmt, StmtKind},
+    heap, highlight,
+    interpreter::{run_error_hooks, run_exit_hooks, Interpreter, RuntimeError},
+    json::Json,
+    jupyter::{self, Connection, Kernel},
     lexer::Lexer,
+    notebook::{self, Piece},
+    parser::{self, Parser},
+    repl::{self, ReplConfig},
+    report::{line_column, to_json},
     resolver::Resolver,
+    runtime::{ColorChoice, Config, Hooks, Lint, Runtime},
+    serve::{self, Client, Control, Listener, Request},
+    session::{Outcome, Session},
+    step::{Execution, StepResult},
+    sync::{Rc, RefCell},
+    token::Literal,
+    value::Value,
 };
-use crate::{parser::Parser, value::Value};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
 
 #[derive(CliParser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
     file: Option<PathBuf>,
+    /// Arguments passed, as strings, to the entry function
+    #[arg(
+        requires = "entry",
+        trailing_var_arg = true,
+        allow_hyphen_values = true
+    )]
+    args: Vec<String>,
+    /// Function to call after running the script's top-level statements
+    #[arg(long, requires = "file")]
+    entry: Option<String>,
+    /// Call the script's `@test` functions after its top-level statements,
+    /// and exit with 1 if any fails
+    #[arg(long, requires = "file", conflicts_with = "entry")]
+    test: bool,
     #[arg(short, long)]
     code: Option<String>,
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Human)]
+    error_format: ErrorFormat,
+    /// Read stdin before running the script or --code, into the global
+    /// `lines` (a list of strings), `text` or `data` (parsed as JSON)
+    #[arg(long, value_enum, value_name = "NAME")]
+    stdin: Option<Stdin>,
+    /// How to print the value of the script or of --code
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    output: OutputFormat,
+    /// Lint integers rounded when mixed with lower-precision floats
+    #[arg(long, value_enum, default_value_t = Lint::Allow)]
+    precision_loss: Lint,
+    /// Whether styled text gets ANSI escapes; `auto` skips them when stdout
+    /// isn't a terminal or NO_COLOR is set
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+    /// Allow `import native` to load shared libraries
+    #[arg(long)]
+    allow_native: bool,
+    /// Allow `eval` to run code from strings
+    #[arg(long)]
+    allow_eval: bool,
+    /// Give `cfg("NAME")` a value, true without one; `if`s on it are
+    /// decided before the script runs
+    #[arg(short = 'D', long = "define", value_name = "NAME[=VALUE]")]
+    defines: Vec<String>,
+    /// Print heap usage to stderr at exit
+    #[arg(long)]
+    mem_stats: bool,
+    /// Write the values reachable from the globals to a JSON file at exit
+    #[arg(long, value_name = "PATH")]
+    heap_dump: Option<PathBuf>,
+    /// Print the N most evaluated expressions to stderr at exit
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "10"
+    )]
+    counts: Option<usize>,
+    /// REPL settings file [default: $XDG_CONFIG_HOME/xi/repl.conf]
+    #[arg(long, value_name = "PATH")]
+    repl_config: Option<PathBuf>,
+    /// REPL prompt; the placeholders in braces, `n` (the input number), `time`,
+    /// `dir`, `version` and `env:NAME`, are replaced
+    #[arg(long)]
+    prompt: Option<String>,
+    /// REPL startup banner, with the same placeholders as the prompt
+    #[arg(long)]
+    banner: Option<String>,
+    /// Start the REPL without a banner
+    #[arg(short, long)]
+    quiet: bool,
+    /// Save every REPL input, with when it was entered and its result
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["file", "code", "replay"])]
+    record: Option<PathBuf>,
+    /// Run the inputs of a recorded session again
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["file", "code"])]
+    replay: Option<PathBuf>,
+    /// Wait for Enter before each replayed input
+    #[arg(long, requires = "replay")]
+    step: bool,
+    /// Run as a Jupyter kernel on the sockets of a connection file, as the
+    /// kernel spec in jupyter/xi has Jupyter do
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["file", "code", "record", "replay"])]
+    jupyter_kernel: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Describe a diagnostic code in detail, or list all codes
+    Explain { code: Option<String> },
+    /// Compare the structure of two scripts, ignoring formatting
+    AstDiff { old: PathBuf, new: PathBuf },
+    /// Print the grammar of the language as EBNF
+    Grammar,
+    /// Run the scripts of a directory and compare what they print with the
+    /// .out file next to each; a first line `# flags: ...` gives a script
+    /// flags such as --allow-eval
+    Selftest {
+        #[arg(default_value = "tests")]
+        dir: PathBuf,
+        /// Write what the scripts print as their .out files
+        #[arg(long)]
+        bless: bool,
+    },
+    /// Print a syntax highlighting definition for an editor
+    Highlight {
+        #[arg(long, value_enum)]
+        format: highlight::Format,
+    },
+    /// Run the code cells of Markdown files
+    Notebook {
+        #[command(subcommand)]
+        command: NotebookCommand,
+    },
+    /// Keep one session running for other processes, which send it
+    /// newline-delimited JSON requests over a socket
+    Serve {
+        /// The Unix socket to listen on
+        #[arg(long, value_name = "PATH", required_unless_present = "tcp")]
+        socket: Option<PathBuf>,
+        /// The TCP address to listen on instead, as HOST:PORT
+        #[arg(long, value_name = "ADDRESS", conflicts_with = "socket")]
+        tcp: Option<String>,
+    },
+    /// A REPL running its lines in the session of `xi serve`
+    Attach {
+        /// The Unix socket the server listens on
+        #[arg(value_name = "PATH", required_unless_present = "tcp")]
+        socket: Option<PathBuf>,
+        /// Its TCP address instead, as HOST:PORT
+        #[arg(long, value_name = "ADDRESS", conflicts_with = "socket")]
+        tcp: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum NotebookCommand {
+    /// Run the ```ix fences of a Markdown file in one session, and write a
+    /// copy with what each printed below it
+    Run {
+        file: PathBuf,
+        /// Where to write the copy [default: FILE with an .out.md extension]
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum ErrorFormat {
+    Human,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Stdin {
+    Lines,
+    Text,
+    Data,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    Human,
+    // the value alone, as one line of JSON
+    Json,
+}
+
+impl Options {
+    fn runtime(&self) -> Runtime {
+        Runtime::new(self.config.clone())
+            .with_hooks(self.hooks.clone())
+            .with_output(self.output.clone())
+            .with_interrupt(self.interrupt.clone())
+    }
 }
 
-const PROMPT: &str = "ix >> ";
+struct Options {
+    error_format: ErrorFormat,
+    output_format: OutputFormat,
+    stdin: Option<Stdin>,
+    config: Config,
+    entry: Option<(String, Vec<String>)>,
+    test: bool,
+    heap_dump: Option<PathBuf>,
+    hooks: Rc<Hooks>,
+    repl: ReplConfig,
+    record: Option<PathBuf>,
+    // what `print` writes, instead of stdout
+    output: Option<Rc<RefCell<String>>>,
+    // what stops a run from another thread
+    interrupt: Option<Arc<AtomicBool>>,
+}
 
-fn run(source: String, env: &Rc<RefCell<Env>>) -> Result<()> {
-    fn inner(source: &str, env: &Rc<RefCell<Env>>) -> Result<Value> {
-        let mut lexer = Lexer::new(source);
-        let tokens = lexer.scan_tokens()?;
-        let mut parser = Parser::new(tokens);
-        let statements = parser.parse()?;
+fn evaluate(source: &str, env: &Rc<RefCell<Env>>, options: &Options) -> Result<Value> {
+    let runtime = Rc::new(options.runtime());
+    let result = Interpreter::new(env, runtime.clone()).run(source);
+    finish(result, &runtime, source, options)
+}
+
+// Calls the global function `name` of a script that has already been
+// evaluated in `env`.
+fn call(
+    name: &str,
+    args: &[String],
+    source: &str,
+    env: &Rc<RefCell<Env>>,
+    options: &Options,
+) -> Result<Value> {
+    let function = match Env::get(env, 0, name, None) {
+        Ok((Value::Function(function), _)) => function,
+        Ok(_) => miette::bail!("entry point `{}` is not a function", name),
+        Err(_) => miette::bail!("entry point `{}` is not defined", name),
+    };
+    if !function.arity().accepts(args.len()) {
+        miette::bail!(
+            "entry point `{}` takes {}, found {}",
+            name,
+            function.arity(),
+            args.len()
+        );
+    }
+    let args = args.iter().map(|arg| Value::from(arg.as_str())).collect();
 
-        let mut resolver = Resolver::default();
-        resolver.resolve(&statements)?;
+    let runtime = Rc::new(options.runtime());
+    let context = Ctx::new(env, Rc::new(Resolver::default()), runtime.clone());
+    let context = Rc::new(RefCell::new(context));
 
-        let context = Rc::new(RefCell::new(Ctx::new(env, Rc::new(resolver))));
+    let result = function
+        .call(&context, args)
+        .or_else(RuntimeError::unwind)
+        .map_err(|report| error_hooks(&context, report));
+    finish(result, &runtime, source, options)
+}
+
+// runs the `on_error` hooks for an error ending the run; one failing is
+// reported along with it
+fn error_hooks(context: &Rc<RefCell<Ctx>>, report: Report) -> Report {
+    if let Err(Err(hook)) = run_error_hooks(context, &report).map_err(RuntimeError::unwind) {
+        context.borrow().runtime().warn(hook);
+    }
+    report
+}
 
-        let result = interpret(&context, &statements);
-        match result {
-            Ok(value) => Ok(value),
-            Err(RuntimeError::Return(value)) => Ok(value),
-            Err(RuntimeError::Report(report)) => Err(report),
+// runs the `on_exit` hooks once the session ended without an error
+fn exit_hooks(source: &str, env: &Rc<RefCell<Env>>, options: &Options) {
+    let runtime = Rc::new(options.runtime());
+    let context = Ctx::new(env, Rc::new(Resolver::default()), runtime.clone());
+    let context = Rc::new(RefCell::new(context));
+
+    let result = match run_exit_hooks(&context) {
+        Ok(()) => Ok(Value::Nil),
+        Err(err) => err.unwind().map(|_| Value::Nil),
+    };
+    if let Err(err) = finish(result, &runtime, source, options) {
+        report(&err, source, options.error_format);
+    }
+}
+
+fn finish(
+    result: Result<Value>,
+    runtime: &Runtime,
+    source: &str,
+    options: &Options,
+) -> Result<Value> {
+    for warning in runtime.take_warnings() {
+        match &options.output {
+            Some(output) => output
+                .borrow_mut()
+                .push_str(&diagnostic("warning", &warning)),
+            None => report(&with_source(warning, source), source, options.error_format),
         }
     }
+    if options.config.counts.is_some() {
+        print_counts(runtime, source);
+    }
 
-    let result = inner(&source, env).map_err(|error| error.with_source_code(source.clone()))?;
+    result.map_err(|error| with_source(error, source))
+}
 
-    println!("{}", result);
+// the source a diagnostic points into, unless it already has its own, as
+// for code run by `eval`
+fn with_source(report: Report, source: &str) -> Report {
+    match report.source_code() {
+        Some(_) => report,
+        None => report.with_source_code(source.to_string()),
+    }
+}
 
+// one line per expression: its count, where it starts and its first line
+fn print_counts(runtime: &Runtime, source: &str) {
+    for (span, count) in runtime.hot_spots() {
+        let (line, column) = line_column(source, span.offset);
+        let text = source
+            .get(span.offset..span.offset + span.length)
+            .unwrap_or("");
+        let text = text.lines().next().unwrap_or("");
+        eprintln!("{:>10}  {}:{}  {}", count, line, column, text);
+    }
+}
+
+fn run(source: &str, env: &Rc<RefCell<Env>>, options: &Options) -> Result<()> {
+    let result = evaluate(source, env, options)?;
+    print_value(&result, options)
+}
+
+fn print_value(value: &Value, options: &Options) -> Result<()> {
+    match options.output_format {
+        OutputFormat::Human => println!("{}", value),
+        OutputFormat::Json => match Json::from_value(value) {
+            Ok(json) => println!("{}", json),
+            Err(message) => miette::bail!("the value can't be written as JSON: {}", message),
+        },
+    }
     Ok(())
 }
 
-fn repl() -> anyhow::Result<()> {
+#[cfg(unix)]
+fn cpu_time() -> Duration {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `time` is a valid timespec to write the clock into
+    unsafe { libc::clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID, &mut time) };
+    Duration::new(time.tv_sec as u64, time.tv_nsec as u32)
+}
+
+#[cfg(not(unix))]
+fn cpu_time() -> Duration {
+    Duration::ZERO
+}
+
+// `:command argument` lines typed in the REPL. The argument, when it's an
+// expression, is reported against as its own source.
+fn command(line: &str, env: &Rc<RefCell<Env>>, options: &Options) {
+    let (name, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let argument = &parser::terminated(argument);
+
+    let result = match name {
+        ":type" => evaluate(argument, env, options).map(|value| match &value {
+            Value::Literal(Literal::Integer(i)) => {
+                println!("{} ({} bits)", value.type_name(), i.significant_bits())
+            }
+            Value::Literal(Literal::Float(f)) => {
+                println!("{} ({} bits of precision)", value.type_name(), f.prec())
+            }
+            _ => println!("{}", value.type_name()),
+        }),
+        ":time" => {
+            let (wall, cpu) = (Instant::now(), cpu_time());
+            let result = evaluate(argument, env, options);
+            let (wall, cpu) = (wall.elapsed(), cpu_time() - cpu);
+            result.map(|value| {
+                println!("{}", value);
+                println!("wall {:?}, cpu {:?}", wall, cpu);
+            })
+        }
+        _ => {
+            println!("unknown command `{}`, try :type or :time", name);
+            Ok(())
+        }
+    };
+
+    if let Err(err) = result {
+        report(&err, argument, options.error_format)
+    }
+}
+
+fn report(error: &Report, source: &str, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Human => println!("{:?}", error),
+        ErrorFormat::Json => println!("{}", to_json(error.as_ref(), source)),
+    }
+}
+
+// runs a line typed in the REPL, a `:` command or code, printing what it
+// gives
+fn input(line: &str, env: &Rc<RefCell<Env>>, options: &Options) -> Outcome {
+    if line.trim_start().starts_with(':') {
+        command(line.trim(), env, options);
+        return Outcome::Command;
+    }
+
+    let line = parser::terminated(line);
+    let result = evaluate(&line, env, options);
+    match &result {
+        Ok(value) => println!("{}", value),
+        Err(err) => report(err, &line, options.error_format),
+    }
+    Outcome::new(&result)
+}
+
+fn repl(options: &Options) -> anyhow::Result<()> {
     let mut rl = DefaultEditor::new()?;
     rl.load_history("history.txt").ok();
     let env = Env::global();
-    loop {
-        let result = match rl.readline(PROMPT) {
+    let config = &options.repl;
+    if !config.quiet {
+        println!(
+            "{}",
+            repl::expand(&config.banner, 1).map_err(anyhow::Error::msg)?
+        );
+    }
+    let mut session = Session::default();
+    for number in 1.. {
+        let prompt = repl::expand(&config.prompt, number).map_err(anyhow::Error::msg)?;
+        let line = match rl.readline(&prompt) {
             Ok(line) => {
                 rl.add_history_entry(line.as_str())?;
-                run(line, &env)
+                line
             }
             Err(ReadlineError::Interrupted) => {
                 println!("CTRL-C");
@@ -92,47 +473,915 @@ fn repl() -> anyhow::Result<()> {
             }
             Err(err) => Err(err).context("readline error")?,
         };
+        let outcome = input(&line, &env, options);
         rl.save_history("history.txt")?;
-        if let Err(err) = result {
-            println!("{:?}", err)
+        if let Some(path) = &options.record {
+            session.record(&line, outcome);
+            fs::write(path, session.to_json().to_string())
+                .with_context(|| format!("cannot write session to {}", path.display()))?;
+        }
+    }
+
+    exit_hooks("", &env, options);
+    dump_heap(&env, options)
+}
+
+// runs a recorded session again in a new one, showing each input after the
+// prompt, and tells where a result differs from the recorded one; with
+// `step`, waits for Enter before each input
+fn replay(path: &Path, step: bool, options: &Options) -> anyhow::Result<()> {
+    let source =
+        fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    let session = Json::parse(&source)
+        .and_then(|json| Session::from_json(&json))
+        .map_err(anyhow::Error::msg)
+        .with_context(|| format!("invalid session {}", path.display()))?;
+
+    let env = Env::global();
+    let mut differences = 0;
+    for (index, entry) in session.entries.iter().enumerate() {
+        let prompt = repl::expand(&options.repl.prompt, index + 1).map_err(anyhow::Error::msg)?;
+        print!("{}{}", prompt, entry.input);
+        if step {
+            std::io::stdout().flush()?;
+            let mut enter = String::new();
+            if std::io::stdin().read_line(&mut enter)? == 0 {
+                break;
+            }
+        } else {
+            println!();
+        }
+
+        let outcome = input(&entry.input, &env, options);
+        if outcome != entry.outcome && entry.outcome != Outcome::Command {
+            differences += 1;
+            match &entry.outcome {
+                Outcome::Value(value) => println!("(recorded at {}: {})", entry.time, value),
+                Outcome::Error { message, .. } => {
+                    println!("(recorded at {}: error: {})", entry.time, message)
+                }
+                Outcome::Command => {}
+            }
         }
     }
 
+    exit_hooks("", &env, options);
+    if differences > 0 {
+        bail!(
+            "{} of {} inputs gave a different result than recorded",
+            differences,
+            session.entries.len()
+        );
+    }
+    dump_heap(&env, options)
+}
+
+fn dump_heap(env: &Rc<RefCell<Env>>, options: &Options) -> anyhow::Result<()> {
+    if let Some(path) = &options.heap_dump {
+        fs::write(path, heap::dump(env).to_string())
+            .with_context(|| format!("cannot write heap dump to {}", path.display()))?;
+    }
+
     Ok(())
 }
 
-fn file(path: &Path) -> anyhow::Result<()> {
+fn file(path: &Path, options: &Options) -> anyhow::Result<()> {
     let source = fs::read_to_string(path)?;
 
     let start = SystemTime::now();
-    let result = run(source, &Env::global());
+    let env = Env::global();
+    bind_stdin(&env, options)?;
+    let result = match &options.entry {
+        Some((name, args)) => evaluate(&source, &env, options)
+            .and_then(|_| call(name, args, &source, &env, options))
+            .and_then(|value| print_value(&value, options)),
+        None if options.test => {
+            evaluate(&source, &env, options).and_then(|_| tests(&source, &env, options))
+        }
+        None => run(&source, &env, options),
+    };
+    let failed = result.is_err();
     let end = SystemTime::now();
     let duration = end.duration_since(start).unwrap();
-    println!("Execution {} ms", duration.as_millis());
+    // only the value goes to stdout, for the program reading it
+    if options.output_format == OutputFormat::Human {
+        println!("Execution {} ms", duration.as_millis());
+    }
 
-    if let Err(err) = result {
-        println!("{:?}", err);
+    match result {
+        Ok(()) => exit_hooks(&source, &env, options),
+        Err(err) => report(&err, &source, options.error_format),
     }
 
+    dump_heap(&env, options)?;
+    if options.test && failed {
+        std::process::exit(1);
+    }
     Ok(())
 }
 
-fn immediate(code: String) -> anyhow::Result<()> {
-    let result = run(code, &Env::global());
-    if let Err(err) = result {
-        println!("{:?}", err);
+// calls the top-level `@test` functions of a script that has already been
+// evaluated in `env`, in order, each whatever the others did
+fn tests(source: &str, env: &Rc<RefCell<Env>>, options: &Options) -> Result<()> {
+    let statements = Parser::new(Lexer::new(source).scan_tokens()?).parse()?;
+    let names: Vec<_> = statements
+        .iter()
+        .filter_map(|stmt| match &stmt.kind {
+            StmtKind::Function {
+                name, annotations, ..
+            } if annotation::find(annotations, "test").is_some() => Some(name),
+            _ => None,
+        })
+        .collect();
+
+    let mut failed = 0;
+    for name in &names {
+        match call(name, &[], source, env, options) {
+            Ok(_) => println!("test {} ... ok", name),
+            Err(err) => {
+                failed += 1;
+                println!("test {} ... FAILED", name);
+                report(&err, source, options.error_format);
+            }
+        }
     }
+    println!("{} passed, {} failed", names.len() - failed, failed);
+    match failed {
+        0 => Ok(()),
+        _ => miette::bail!("{} of {} tests failed", failed, names.len()),
+    }
+}
 
+fn immediate(code: String, options: &Options) -> anyhow::Result<()> {
+    let code = parser::terminated(&code);
+    let env = Env::global();
+    bind_stdin(&env, options)?;
+    let result = run(&code, &env, options);
+    match result {
+        Ok(()) => exit_hooks(&code, &env, options),
+        Err(err) => report(&err, &code, options.error_format),
+    }
+
+    dump_heap(&env, options)
+}
+
+// what `--stdin` asks for, defined before the program runs
+fn bind_stdin(env: &Rc<RefCell<Env>>, options: &Options) -> anyhow::Result<()> {
+    let Some(binding) = options.stdin else {
+        return Ok(());
+    };
+    let mut text = String::new();
+    std::io::stdin()
+        .read_to_string(&mut text)
+        .context("cannot read stdin")?;
+
+    let (name, value) = match binding {
+        Stdin::Lines => {
+            let lines: Vec<Value> = text.lines().map(Value::from).collect();
+            ("lines", lines.into())
+        }
+        Stdin::Text => ("text", text.into()),
+        Stdin::Data => {
+            let json = Json::parse(&text)
+                .map_err(anyhow::Error::msg)
+                .context("stdin is not valid JSON")?;
+            ("data", json.to_value())
+        }
+    };
+    env.borrow_mut().define(name, value);
     Ok(())
 }
 
+// the config file, when there is one, under the flags given
+fn repl_config(cli: &Cli) -> anyhow::Result<ReplConfig> {
+    let mut config = ReplConfig::default();
+    let path = match &cli.repl_config {
+        Some(path) => Some(path.clone()),
+        None => repl::default_path().filter(|path| path.exists()),
+    };
+    if let Some(path) = path {
+        let source =
+            fs::read_to_string(&path).with_context(|| format!("cannot read {}", path.display()))?;
+        config
+            .load(&source)
+            .map_err(anyhow::Error::msg)
+            .with_context(|| format!("invalid REPL config {}", path.display()))?;
+    }
+
+    if let Some(prompt) = &cli.prompt {
+        config.prompt = prompt.clone();
+    }
+    if let Some(banner) = &cli.banner {
+        config.banner = banner.clone();
+    }
+    config.quiet |= cli.quiet;
+
+    // a mistake in either is reported before the session starts
+    for template in [&config.prompt, &config.banner] {
+        repl::expand(template, 1)
+            .map_err(anyhow::Error::msg)
+            .with_context(|| format!("invalid REPL template `{}`", template))?;
+    }
+    Ok(config)
+}
+
+// for runs that write what they print, and their warnings, to `printed`
+fn captured_options(format: ErrorFormat, printed: &Rc<RefCell<String>>) -> Options {
+    Options {
+        error_format: format,
+        output_format: OutputFormat::Human,
+        stdin: None,
+        config: Config::default(),
+        entry: None,
+        test: false,
+        heap_dump: None,
+        hooks: Rc::default(),
+        repl: ReplConfig::default(),
+        record: None,
+        output: Some(printed.clone()),
+        interrupt: None,
+    }
+}
+
+// `error[E0303]: undefined value`
+fn diagnostic(kind: &str, report: &Report) -> String {
+    match report.code() {
+        Some(code) => format!("{}[{}]: {}\n", kind, code, report),
+        None => format!("{}: {}\n", kind, report),
+    }
+}
+
+// runs `source` with captured options: what it printed, then its value
+// unless it's nil, or its error, which is also returned
+fn captured(
+    source: &str,
+    env: &Rc<RefCell<Env>>,
+    options: &Options,
+    printed: &Rc<RefCell<String>>,
+) -> (String, Option<Report>) {
+    settled(evaluate(source, env, options), printed)
+}
+
+// what a run printed, followed by its value or error
+fn settled(result: Result<Value>, printed: &Rc<RefCell<String>>) -> (String, Option<Report>) {
+    let mut output = printed.take();
+    match result {
+        Ok(Value::Nil) => (output, None),
+        Ok(value) => {
+            output.push_str(&format!("{}\n", value));
+            (output, None)
+        }
+        Err(err) => {
+            if !output.is_empty() && !output.ends_with('\n') {
+                output.push('\n');
+            }
+            output.push_str(&diagnostic("error", &err));
+            (output, Some(err))
+        }
+    }
+}
+
+// runs a whole script with a config, returning what it printed
+type Backend = fn(&str, &Config) -> String;
+
+// the ways a program can be run, which must all print the same
+const BACKENDS: [(&str, Backend); 2] = [("tree-walker", tree_walker), ("stepped", stepped)];
+
+// a whole script, and its exit hooks, as a captured run
+fn tree_walker(source: &str, config: &Config) -> String {
+    let printed = Rc::new(RefCell::new(String::new()));
+    let mut options = captured_options(ErrorFormat::Human, &printed);
+    options.config = config.clone();
+    let env = Env::global();
+    let (output, error) = captured(source, &env, &options, &printed);
+    with_exit_hooks(output, error, source, &env, &options, &printed)
+}
+
+// the same, one step at a time
+fn stepped(source: &str, config: &Config) -> String {
+    let printed = Rc::new(RefCell::new(String::new()));
+    let mut options = captured_options(ErrorFormat::Human, &printed);
+    options.config = config.clone();
+    let env = Env::global();
+    let runtime = Rc::new(options.runtime());
+    let interpreter = Interpreter::new(&env, runtime.clone());
+    let result = interpreter.compile(source).and_then(|program| {
+        let mut execution = Execution::new(&interpreter, &program);
+        loop {
+            match execution.step() {
+                StepResult::Running => {}
+                StepResult::Done(value) => return Ok(value),
+                StepResult::Error(report) => return Err(report),
+            }
+        }
+    });
+    let result = finish(result, &runtime, source, &options);
+    let (output, error) = settled(result, &printed);
+    with_exit_hooks(output, error, source, &env, &options, &printed)
+}
+
+fn with_exit_hooks(
+    mut output: String,
+    error: Option<Report>,
+    source: &str,
+    env: &Rc<RefCell<Env>>,
+    options: &Options,
+    printed: &Rc<RefCell<String>>,
+) -> String {
+    if error.is_none() {
+        exit_hooks(source, env, options);
+        output.push_str(&printed.take());
+    }
+    output
+}
+
+// a script's first line may give the flags it's run with, of the ones that
+// change how programs run: `# flags: --allow-eval -D debug`
+fn script_config(source: &str) -> anyhow::Result<Config> {
+    let first = source.lines().next().unwrap_or("");
+    let flags = first.strip_prefix("# flags:").unwrap_or("");
+    let cli = Cli::try_parse_from(std::iter::once("xi").chain(flags.split_whitespace()))?;
+    Ok(config(&cli))
+}
+
+// runs every script of `dir` with each backend, and compares what it printed
+// with the script's .out file next to it; `bless` writes them instead
+fn selftest(dir: &Path, bless: bool) -> anyhow::Result<()> {
+    let mut scripts: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("cannot read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "xi"))
+        .collect();
+    scripts.sort();
+
+    let (mut failed, mut skipped) = (0, 0);
+    for script in &scripts {
+        let source = fs::read_to_string(script)
+            .with_context(|| format!("cannot read {}", script.display()))?;
+        let expected_path = script.with_extension("out");
+        let config =
+            script_config(&source).with_context(|| format!("bad flags in {}", script.display()))?;
+        let outputs: Vec<_> = BACKENDS
+            .iter()
+            .map(|(name, run)| (*name, run(&source, &config)))
+            .collect();
+
+        if bless {
+            fs::write(&expected_path, &outputs[0].1)
+                .with_context(|| format!("cannot write {}", expected_path.display()))?;
+            println!("blessed {}", script.display());
+            continue;
+        }
+        let Ok(expected) = fs::read_to_string(&expected_path) else {
+            println!(
+                "skipped {} (no {})",
+                script.display(),
+                expected_path.display()
+            );
+            skipped += 1;
+            continue;
+        };
+
+        let mismatch = outputs.iter().find(|(_, output)| *output != expected);
+        match mismatch {
+            None => println!("ok      {}", script.display()),
+            Some((backend, output)) => {
+                failed += 1;
+                println!("FAILED  {} ({})", script.display(), backend);
+                let (expected, output): (Vec<_>, Vec<_>) =
+                    (expected.lines().collect(), output.lines().collect());
+                let line = (0..expected.len().max(output.len()))
+                    .find(|&line| expected.get(line) != output.get(line))
+                    .unwrap_or(0);
+                let missing = "(no line)";
+                println!(
+                    "  line {}:\n  - {}\n  + {}",
+                    line + 1,
+                    expected.get(line).unwrap_or(&missing),
+                    output.get(line).unwrap_or(&missing)
+                );
+            }
+        }
+    }
+
+    println!(
+        "{} passed, {} failed, {} skipped",
+        scripts.len() - failed - skipped,
+        failed,
+        skipped
+    );
+    if failed > 0 {
+        bail!("{} of {} scripts failed", failed, scripts.len());
+    }
+    Ok(())
+}
+
+// each cell's output is what it printed, then its value unless it's nil,
+// or its error
+fn run_notebook(path: &Path, output: Option<PathBuf>, format: ErrorFormat) -> anyhow::Result<()> {
+    let markdown =
+        fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    let pieces = notebook::parse(&markdown);
+
+    let printed = Rc::new(RefCell::new(String::new()));
+    let options = captured_options(format, &printed);
+    let env = Env::global();
+    let mut outputs = Vec::new();
+    let mut errors = 0;
+    for piece in &pieces {
+        let Piece::Cell { code, .. } = piece else {
+            continue;
+        };
+        let code = &parser::terminated(code);
+        let (output, error) = captured(code, &env, &options, &printed);
+        if let Some(err) = error {
+            errors += 1;
+            report(&err, code, format);
+        }
+        outputs.push(output);
+    }
+    exit_hooks("", &env, &options);
+    print!("{}", printed.take());
+
+    let output = output.unwrap_or_else(|| path.with_extension("out.md"));
+    fs::write(&output, notebook::render(&pieces, &outputs))
+        .with_context(|| format!("cannot write {}", output.display()))?;
+    if errors > 0 {
+        bail!("{} of {} cells failed", errors, outputs.len());
+    }
+
+    Ok(())
+}
+
+// answers the requests of every client in one session, the way a REPL
+// line is run, until the listener fails
+fn serve(socket: Option<PathBuf>, tcp: Option<String>) -> anyhow::Result<()> {
+    let listener = match (socket, tcp) {
+        (_, Some(address)) => {
+            Listener::tcp(&address).with_context(|| format!("cannot listen on {}", address))?
+        }
+        #[cfg(unix)]
+        (Some(path), None) => {
+            Listener::unix(&path).with_context(|| format!("cannot listen on {}", path.display()))?
+        }
+        _ => bail!("--socket needs Unix sockets, use --tcp"),
+    };
+    let control = Arc::new(Control::default());
+    let requests = serve::listen(listener, control.clone());
+
+    let printed = Rc::new(RefCell::new(String::new()));
+    let session = || {
+        let mut options = captured_options(ErrorFormat::Json, &printed);
+        options.interrupt = Some(control.interrupt.clone());
+        (Env::global(), options)
+    };
+    let (mut env, mut options) = session();
+    for incoming in requests {
+        let response = match &incoming.request {
+            Request::Evaluate(code) => {
+                let code = parser::terminated(code);
+                control.start();
+                let result = evaluate(&code, &env, &options);
+                control.finish();
+                let (value, error) = match result {
+                    Ok(Value::Nil) => (Json::Null, Json::Null),
+                    Ok(value) => (value.to_string().into(), Json::Null),
+                    Err(err) => (Json::Null, to_json(err.as_ref(), &code)),
+                };
+                vec![
+                    ("output".to_string(), printed.take().into()),
+                    ("value".to_string(), value),
+                    ("error".to_string(), error),
+                ]
+            }
+            Request::Complete(prefix) => {
+                let names = serve::completions(&env.borrow(), prefix);
+                let names = names.into_iter().map(Json::from).collect();
+                vec![("completions".to_string(), Json::Array(names))]
+            }
+            Request::Reset => {
+                (env, options) = session();
+                vec![("reset".to_string(), true.into())]
+            }
+        };
+        incoming.reply(response);
+    }
+
+    Ok(())
+}
+
+// runs the cells a Jupyter frontend sends in one session, until it asks
+// the kernel to shut down; every request is bracketed by busy and idle
+// statuses on iopub
+fn kernel(path: &Path, mut options: Options) -> anyhow::Result<()> {
+    let source =
+        fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    let connection = Connection::parse(&source)
+        .map_err(anyhow::Error::msg)
+        .with_context(|| format!("invalid connection file {}", path.display()))?;
+    let control = Arc::new(Control::default());
+    let kernel = Kernel::start(&connection, control.clone()).context("cannot listen")?;
+
+    let printed = Rc::new(RefCell::new(String::new()));
+    options.output = Some(printed.clone());
+    options.interrupt = Some(control.interrupt.clone());
+    let env = Env::global();
+    let mut count = 0;
+    for incoming in kernel.requests() {
+        let request = &incoming.message;
+        let status = |state: &str| jupyter::object([("execution_state", state.into())]);
+        kernel.publish(request, "status", status("busy"));
+        let text = |field: &str| {
+            let text = request.content.get(field).and_then(Json::as_str);
+            text.unwrap_or("").to_string()
+        };
+        match request.kind() {
+            "kernel_info_request" => incoming.reply("kernel_info_reply", jupyter::info()),
+            "execute_request" => {
+                let code = text("code");
+                let silent = matches!(request.content.get("silent"), Some(Json::Bool(true)));
+                if !silent {
+                    count += 1;
+                }
+                kernel.publish(
+                    request,
+                    "execute_input",
+                    jupyter::object([
+                        ("code", code.as_str().into()),
+                        ("execution_count", count.into()),
+                    ]),
+                );
+
+                let code = parser::terminated(&code);
+                control.start();
+                let result = evaluate(&code, &env, &options);
+                control.finish();
+                let output = printed.take();
+                if !output.is_empty() {
+                    let stream = [("name", "stdout".into()), ("text", output.into())];
+                    kernel.publish(request, "stream", jupyter::object(stream));
+                }
+                let mut reply = vec![("execution_count".to_string(), count.into())];
+                match result {
+                    Ok(Value::Nil) => {}
+                    Ok(value) if silent => drop(value),
+                    Ok(value) => {
+                        let content = jupyter::object([
+                            ("execution_count", count.into()),
+                            ("data", jupyter::display(&value)),
+                            ("metadata", jupyter::object([])),
+                        ]);
+                        kernel.publish(request, "execute_result", content);
+                    }
+                    Err(err) => {
+                        let error = jupyter::error(&err);
+                        kernel.publish(request, "error", Json::Object(error.clone()));
+                        reply.push(("status".to_string(), "error".into()));
+                        reply.extend(error);
+                    }
+                }
+                if reply.len() == 1 {
+                    reply.push(("status".to_string(), "ok".into()));
+                    reply.push(("user_expressions".to_string(), jupyter::object([])));
+                    reply.push(("payload".to_string(), Json::Array(Vec::new())));
+                }
+                incoming.reply("execute_reply", Json::Object(reply));
+            }
+            "complete_request" => {
+                let code: Vec<char> = text("code").chars().collect();
+                let end = match request.content.get("cursor_pos") {
+                    Some(Json::Number(number)) => number.parse().unwrap_or(code.len()),
+                    _ => code.len(),
+                };
+                let end = end.min(code.len());
+                let start = code[..end]
+                    .iter()
+                    .rposition(|&c| !(c.is_alphanumeric() || c == '_'))
+                    .map_or(0, |i| i + 1);
+                let prefix: String = code[start..end].iter().collect();
+                let names = serve::completions(&env.borrow(), &prefix);
+                incoming.reply(
+                    "complete_reply",
+                    jupyter::object([
+                        ("status", "ok".into()),
+                        (
+                            "matches",
+                            Json::Array(names.into_iter().map(Json::from).collect()),
+                        ),
+                        ("cursor_start", start.into()),
+                        ("cursor_end", end.into()),
+                        ("metadata", jupyter::object([])),
+                    ]),
+                );
+            }
+            "is_complete_request" => {
+                incoming.reply(
+                    "is_complete_reply",
+                    jupyter::object([("status", "unknown".into())]),
+                );
+            }
+            "shutdown_request" => {
+                let restart = request.content.get("restart").cloned();
+                let content = [
+                    ("status", "ok".into()),
+                    ("restart", restart.unwrap_or(Json::Bool(false))),
+                ];
+                incoming.reply("shutdown_reply", jupyter::object(content));
+                kernel.publish(request, "status", status("idle"));
+                break;
+            }
+            // requests the kernel has no answer for are left unanswered, which
+            // frontends take as not supported
+            _ => {}
+        }
+        kernel.publish(request, "status", status("idle"));
+    }
+    exit_hooks("", &env, &options);
+
+    Ok(())
+}
+
+// set by Ctrl-C while `attach` waits for a line to run
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+fn forward_interrupts() {
+    extern "C" fn interrupted(_: libc::c_int) {
+        INTERRUPTED.store(true, Ordering::Relaxed);
+    }
+    // SAFETY: the handler only stores to an atomic
+    unsafe {
+        libc::signal(
+            libc::SIGINT,
+            interrupted as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        )
+    };
+}
+
+#[cfg(not(unix))]
+fn forward_interrupts() {}
+
+// Tab completes the name before the cursor with the ones the server knows
+struct Completion {
+    client: Rc<RefCell<Client>>,
+}
+
+impl Completer for Completion {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &LineContext<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos]
+            .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .map_or(0, |index| index + 1);
+        let request = vec![
+            ("op".to_string(), "complete".into()),
+            ("prefix".to_string(), line[start..pos].into()),
+        ];
+        let names = match self.client.borrow_mut().request(request) {
+            Ok(response) => match response.get("completions") {
+                Some(Json::Array(names)) => names
+                    .iter()
+                    .filter_map(|name| name.as_str().map(str::to_string))
+                    .collect(),
+                _ => Vec::new(),
+            },
+            Err(_) => Vec::new(),
+        };
+        Ok((start, names))
+    }
+}
+
+impl Hinter for Completion {
+    type Hint = String;
+}
+
+impl Highlighter for Completion {}
+
+impl Validator for Completion {}
+
+impl Helper for Completion {}
+
+// what an `evaluate` response says, as the REPL would print it
+fn print_response(response: &Json) {
+    if let Some(output) = response.get("output").and_then(Json::as_str) {
+        print!("{}", output);
+    }
+    if let Some(value) = response.get("value").and_then(Json::as_str) {
+        println!("{}", value);
+    }
+    if let Some(error @ Json::Object(_)) = response.get("error") {
+        let message = error.get("message").and_then(Json::as_str).unwrap_or("");
+        match error.get("code").and_then(Json::as_str) {
+            Some(code) => println!("error[{}]: {}", code, message),
+            None => println!("error: {}", message),
+        }
+        if let Some(help) = error.get("help").and_then(Json::as_str) {
+            println!("  help: {}", help);
+        }
+    }
+}
+
+fn attach(socket: Option<PathBuf>, tcp: Option<String>, cli: &Cli) -> anyhow::Result<()> {
+    let client = match (socket, tcp) {
+        (_, Some(address)) => {
+            Client::tcp(&address).with_context(|| format!("cannot connect to {}", address))?
+        }
+        #[cfg(unix)]
+        (Some(path), None) => {
+            Client::unix(&path).with_context(|| format!("cannot connect to {}", path.display()))?
+        }
+        _ => bail!("a socket path needs Unix sockets, use --tcp"),
+    };
+    let client = Rc::new(RefCell::new(client));
+    let config = repl_config(cli)?;
+
+    let mut rl = Editor::<Completion, DefaultHistory>::new()?;
+    rl.set_helper(Some(Completion {
+        client: client.clone(),
+    }));
+    rl.load_history("history.txt").ok();
+    forward_interrupts();
+    if !config.quiet {
+        println!(
+            "{}",
+            repl::expand(&config.banner, 1).map_err(anyhow::Error::msg)?
+        );
+    }
+    for number in 1.. {
+        let prompt = repl::expand(&config.prompt, number).map_err(anyhow::Error::msg)?;
+        let line = match rl.readline(&prompt) {
+            Ok(line) => {
+                rl.add_history_entry(line.as_str())?;
+                line
+            }
+            Err(ReadlineError::Interrupted) => {
+                println!("CTRL-C");
+                break;
+            }
+            Err(ReadlineError::Eof) => {
+                println!("CTRL-D");
+                break;
+            }
+            Err(err) => Err(err).context("readline error")?,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request = vec![
+            ("op".to_string(), "evaluate".into()),
+            ("code".to_string(), line.into()),
+        ];
+        INTERRUPTED.store(false, Ordering::Relaxed);
+        let response = client
+            .borrow_mut()
+            .request_or_interrupt(request, || INTERRUPTED.swap(false, Ordering::Relaxed))
+            .context("lost the server")?;
+        print_response(&response);
+        rl.save_history("history.txt")?;
+    }
+
+    Ok(())
+}
+
+fn explain(code: Option<String>) -> anyhow::Result<()> {
+    match code {
+        Some(code) => match explain::explain(&code) {
+            Some(explanation) => {
+                println!("{}: {}\n", explanation.code, explanation.title);
+                print!("{}", explanation.text);
+            }
+            None => bail!("no extended information for `{}`", code),
+        },
+        None => {
+            for explanation in EXPLANATIONS {
+                println!("{}  {}", explanation.code, explanation.title);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_file(path: &Path, format: ErrorFormat) -> anyhow::Result<Vec<Stmt>> {
+    let source =
+        fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    let result = Lexer::new(&source)
+        .scan_tokens()
+        .and_then(|tokens| Parser::new(tokens).parse());
+    match result {
+        Ok(statements) => Ok(statements),
+        Err(err) => {
+            report(&err.with_source_code(source.clone()), &source, format);
+            bail!("cannot parse {}", path.display())
+        }
+    }
+}
+
+// prints the changes from `old` to `new` and, like diff, exits with 1 when
+// there are any
+fn ast_diff(old: &Path, new: &Path, format: ErrorFormat) -> anyhow::Result<()> {
+    let changes = astdiff::diff(&parse_file(old, format)?, &parse_file(new, format)?);
+    for change in &changes {
+        println!("{}", change);
+    }
+    if !changes.is_empty() {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+// what the flags change about how programs run
+fn config(cli: &Cli) -> Config {
+    Config {
+        precision_loss: cli.precision_loss,
+        color: cli.color,
+        allow_native: cli.allow_native,
+        allow_eval: cli.allow_eval,
+        counts: cli.counts,
+        defines: cli
+            .defines
+            .iter()
+            .map(|define| match define.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (define.clone(), None),
+            })
+            .collect(),
+    }
+}
+
 fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+
+    if let Some(command) = cli.command.take() {
+        return match command {
+            Command::Explain { code } => explain(code),
+            Command::AstDiff { old, new } => ast_diff(&old, &new, cli.error_format),
+            Command::Selftest { dir, bless } => selftest(&dir, bless),
+            Command::Grammar => {
+                print!("{}", parser::ebnf());
+                Ok(())
+            }
+            Command::Highlight { format } => {
+                print!("{}", highlight::generate(format));
+                Ok(())
+            }
+            Command::Notebook {
+                command: NotebookCommand::Run { file, output },
+            } => run_notebook(&file, output, cli.error_format),
+            Command::Serve { socket, tcp } => serve(socket, tcp),
+            Command::Attach { socket, tcp } => attach(socket, tcp, &cli),
+        };
+    }
 
-    match (cli.file, cli.code) {
-        (Some(path), None) => file(&path),
-        (None, Some(code)) => immediate(code),
-        (None, None) => repl(),
+    let repl_settings = match (&cli.file, &cli.code) {
+        _ if cli.jupyter_kernel.is_some() => ReplConfig::default(),
+        _ if cli.replay.is_some() => repl_config(&cli)?,
+        (None, None) => repl_config(&cli)?,
+        _ => ReplConfig::default(),
+    };
+    let options = Options {
+        error_format: cli.error_format,
+        output_format: cli.output,
+        stdin: cli.stdin,
+        config: config(&cli),
+        entry: cli.entry.map(|entry| (entry, cli.args)),
+        test: cli.test,
+        heap_dump: cli.heap_dump,
+        hooks: Rc::default(),
+        repl: repl_settings,
+        record: cli.record,
+        output: None,
+        interrupt: None,
+    };
+
+    let result = match (cli.file, cli.code) {
+        _ if cli.jupyter_kernel.is_some() => {
+            kernel(cli.jupyter_kernel.as_deref().unwrap(), options)
+        }
+        _ if cli.replay.is_some() => replay(cli.replay.as_deref().unwrap(), cli.step, &options),
+        (Some(path), None) => file(&path, &options),
+        (None, Some(code)) => immediate(code, &options),
+        (None, None) => repl(&options),
         (Some(_), Some(_)) => unimplemented!(),
+    };
+
+    if cli.mem_stats {
+        let stats = alloc::stats();
+        eprintln!(
+            "memory: {} live in {} allocations, peak {}, {} allocations in total",
+            human_bytes(stats.bytes),
+            stats.allocations,
+            human_bytes(stats.peak_bytes),
+            stats.total_allocations
+        );
     }
+
+    result
 }
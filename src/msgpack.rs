@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+
+use rug::{Float, Integer};
+
+use crate::{
+    datetime::DateTime,
+    dict::Dict,
+    lexer::DEFAULT_FLOAT_PRECISION,
+    list::List,
+    sync::{Rc, RefCell},
+    token::Literal,
+    value::{Value, ValueKey},
+};
+
+// MessagePack (https://github.com/msgpack/msgpack/blob/master/spec.md).
+// Integers must fit in 64 bits, floats are written as float 64 and datetimes
+// use the timestamp extension; functions, iterators and the other handles
+// can't be encoded.
+
+// deep enough for real data, and a stop for cyclic lists and dicts
+const MAX_DEPTH: usize = 512;
+
+const TIMESTAMP: i8 = -1;
+
+// the marker (and length) of a string, binary, array or map: the fix form
+// when there is one and `len` fits it (up to `max`), else the one with the
+// smallest length field that does
+struct Markers {
+    fix: Option<(u8, usize)>,
+    len8: Option<u8>,
+    len16: u8,
+    len32: u8,
+}
+
+fn length(output: &mut Vec<u8>, len: usize, markers: Markers) {
+    match (markers.fix, markers.len8) {
+        (Some((marker, max)), _) if len <= max => output.push(marker | len as u8),
+        (_, Some(marker)) if len <= u8::MAX as usize => output.extend([marker, len as u8]),
+        _ if len <= u16::MAX as usize => {
+            output.push(markers.len16);
+            output.extend((len as u16).to_be_bytes());
+        }
+        _ => {
+            output.push(markers.len32);
+            output.extend((len as u32).to_be_bytes());
+        }
+    }
+}
+
+fn integer(output: &mut Vec<u8>, i: &Integer) -> Result<(), String> {
+    if let Some(i) = i.to_i64() {
+        match i {
+            0..=0x7f => output.push(i as u8),
+            -32..=-1 => output.push(i as i8 as u8),
+            _ if i8::try_from(i).is_ok() => output.extend([0xd0, i as i8 as u8]),
+            _ if u8::try_from(i).is_ok() => output.extend([0xcc, i as u8]),
+            _ if i16::try_from(i).is_ok() => {
+                output.push(0xd1);
+                output.extend((i as i16).to_be_bytes())
+            }
+            _ if u16::try_from(i).is_ok() => {
+                output.push(0xcd);
+                output.extend((i as u16).to_be_bytes())
+            }
+            _ if i32::try_from(i).is_ok() => {
+                output.push(0xd2);
+                output.extend((i as i32).to_be_bytes())
+            }
+            _ if u32::try_from(i).is_ok() => {
+                output.push(0xce);
+                output.extend((i as u32).to_be_bytes())
+            }
+            _ => {
+                output.push(0xd3);
+                output.extend(i.to_be_bytes())
+            }
+        }
+        return Ok(());
+    }
+
+    match i.to_u64() {
+        Some(u) => {
+            output.push(0xcf);
+            output.extend(u.to_be_bytes());
+            Ok(())
+        }
+        None => Err(format!("{} doesn't fit in 64 bits", i)),
+    }
+}
+
+fn timestamp(output: &mut Vec<u8>, datetime: &DateTime) -> Result<(), String> {
+    let seconds = datetime.nanos.div_euclid(1_000_000_000);
+    let nanos = datetime.nanos.rem_euclid(1_000_000_000) as u32;
+    let seconds = i64::try_from(seconds).map_err(|_| "datetime out of range".to_string())?;
+    // timestamp 96: 4 bytes of nanoseconds, 8 of seconds
+    output.extend([0xc7, 12, TIMESTAMP as u8]);
+    output.extend(nanos.to_be_bytes());
+    output.extend(seconds.to_be_bytes());
+    Ok(())
+}
+
+fn write(output: &mut Vec<u8>, value: &Value, depth: usize) -> Result<(), String> {
+    if depth > MAX_DEPTH {
+        return Err("value is nested too deeply, or contains itself".to_string());
+    }
+
+    match value {
+        Value::Nil => output.push(0xc0),
+        Value::False => output.push(0xc2),
+        Value::True => output.push(0xc3),
+        Value::Literal(Literal::Integer(i)) => integer(output, i)?,
+        Value::Fixed(fixed) => integer(output, &fixed.to_integer())?,
+        Value::Literal(Literal::Float(f)) => {
+            output.push(0xcb);
+            output.extend(f.to_f64().to_be_bytes());
+        }
+        Value::Exact(exact) => {
+            output.push(0xcb);
+            output.extend(exact.approx(53).to_f64().to_be_bytes());
+        }
+        Value::Literal(literal @ (Literal::String(_) | Literal::Identifier(_))) => {
+            let s = literal.to_string();
+            let markers = Markers {
+                fix: Some((0xa0, 31)),
+                len8: Some(0xd9),
+                len16: 0xda,
+                len32: 0xdb,
+            };
+            length(output, s.len(), markers);
+            output.extend(s.as_bytes());
+        }
+        Value::Bytes(bytes) => {
+            let markers = Markers {
+                fix: None,
+                len8: Some(0xc4),
+                len16: 0xc5,
+                len32: 0xc6,
+            };
+            length(output, bytes.len(), markers);
+            output.extend(bytes.iter());
+        }
+        Value::List(list) => {
+            let list = list.borrow();
+            let markers = Markers {
+                fix: Some((0x90, 15)),
+                len8: None,
+                len16: 0xdc,
+                len32: 0xdd,
+            };
+            length(output, list.0.len(), markers);
+            for item in &list.0 {
+                write(output, item, depth + 1)?;
+            }
+        }
+        Value::Dict(dict) => {
+            let dict = dict.borrow();
+            let markers = Markers {
+                fix: Some((0x80, 15)),
+                len8: None,
+                len16: 0xde,
+                len32: 0xdf,
+            };
+            length(output, dict.0.len(), markers);
+            for (key, value) in &dict.0 {
+                write(output, &key.0, depth + 1)?;
+                write(output, value, depth + 1)?;
+            }
+        }
+        Value::DateTime(datetime) => timestamp(output, datetime)?,
+        other => return Err(format!("a {} can't be encoded", other.type_name())),
+    }
+    Ok(())
+}
+
+pub fn encode(value: &Value) -> Result<Vec<u8>, String> {
+    let mut output = Vec::new();
+    write(&mut output, value, 0)?;
+    Ok(output)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(n)
+            .filter(|&end| end <= self.bytes.len());
+        let end = end.ok_or_else(|| format!("truncated at byte {}", self.bytes.len()))?;
+        let taken = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn length(&mut self, size: usize) -> Result<usize, String> {
+        Ok(match size {
+            1 => self.array::<1>()?[0] as usize,
+            2 => u16::from_be_bytes(self.array()?) as usize,
+            _ => u32::from_be_bytes(self.array()?) as usize,
+        })
+    }
+
+    fn string(&mut self, len: usize) -> Result<Value, String> {
+        let at = self.position;
+        let bytes = self.take(len)?;
+        match std::str::from_utf8(bytes) {
+            Ok(s) => Ok(s.into()),
+            Err(_) => Err(format!("string at byte {} is not UTF-8", at)),
+        }
+    }
+
+    fn list(&mut self, len: usize, depth: usize) -> Result<Value, String> {
+        let mut items = Vec::with_capacity(len.min(self.bytes.len()));
+        for _ in 0..len {
+            items.push(self.value(depth + 1)?);
+        }
+        Ok(Value::List(Rc::new(RefCell::new(List(items)))))
+    }
+
+    fn dict(&mut self, len: usize, depth: usize) -> Result<Value, String> {
+        let mut entries = HashMap::with_capacity(len.min(self.bytes.len()));
+        for _ in 0..len {
+            let at = self.position;
+            let key = self.value(depth + 1)?;
+            let key = ValueKey::try_from(key)
+                .map_err(|_| format!("map key at byte {} can't be a dict key", at))?;
+            let value = self.value(depth + 1)?;
+            entries.insert(key, value);
+        }
+        Ok(Value::Dict(Rc::new(RefCell::new(Dict(entries)))))
+    }
+
+    fn ext(&mut self, len: usize) -> Result<Value, String> {
+        let at = self.position;
+        let kind = self.array::<1>()?[0] as i8;
+        let data = self.take(len)?;
+        let (seconds, nanos) = match (kind, data.len()) {
+            (TIMESTAMP, 4) => (u32::from_be_bytes(data.try_into().unwrap()) as i128, 0),
+            (TIMESTAMP, 8) => {
+                let data = u64::from_be_bytes(data.try_into().unwrap());
+                ((data & 0x3_ffff_ffff) as i128, (data >> 34) as i128)
+            }
+            (TIMESTAMP, 12) => {
+                let nanos = u32::from_be_bytes(data[..4].try_into().unwrap());
+                let seconds = i64::from_be_bytes(data[4..].try_into().unwrap());
+                (seconds as i128, nanos as i128)
+            }
+            _ => return Err(format!("unknown extension {} at byte {}", kind, at)),
+        };
+        Ok(Value::DateTime(DateTime {
+            nanos: seconds * 1_000_000_000 + nanos,
+            offset: 0,
+        }))
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, String> {
+        if depth > MAX_DEPTH {
+            return Err("data is nested too deeply".to_string());
+        }
+
+        let at = self.position;
+        let marker = self.array::<1>()?[0];
+        let int = |i: i128| Ok(Value::from(Integer::from(i)));
+        match marker {
+            0x00..=0x7f => int(marker as i128),
+            0x80..=0x8f => self.dict((marker & 0x0f) as usize, depth),
+            0x90..=0x9f => self.list((marker & 0x0f) as usize, depth),
+            0xa0..=0xbf => self.string((marker & 0x1f) as usize),
+            0xc0 => Ok(Value::Nil),
+            0xc2 => Ok(Value::False),
+            0xc3 => Ok(Value::True),
+            0xc4..=0xc6 => {
+                let len = self.length(1 << (marker - 0xc4))?;
+                Ok(self.take(len)?.to_vec().into())
+            }
+            0xc7..=0xc9 => {
+                let len = self.length(1 << (marker - 0xc7))?;
+                self.ext(len)
+            }
+            0xca => {
+                let f = f32::from_be_bytes(self.array()?);
+                Ok(Float::with_val(DEFAULT_FLOAT_PRECISION, f).into())
+            }
+            0xcb => {
+                let f = f64::from_be_bytes(self.array()?);
+                Ok(Float::with_val(DEFAULT_FLOAT_PRECISION, f).into())
+            }
+            0xcc => int(u8::from_be_bytes(self.array()?) as i128),
+            0xcd => int(u16::from_be_bytes(self.array()?) as i128),
+            0xce => int(u32::from_be_bytes(self.array()?) as i128),
+            0xcf => int(u64::from_be_bytes(self.array()?) as i128),
+            0xd0 => int(i8::from_be_bytes(self.array()?) as i128),
+            0xd1 => int(i16::from_be_bytes(self.array()?) as i128),
+            0xd2 => int(i32::from_be_bytes(self.array()?) as i128),
+            0xd3 => int(i64::from_be_bytes(self.array()?) as i128),
+            0xd4..=0xd8 => self.ext(1 << (marker - 0xd4)),
+            0xd9..=0xdb => {
+                let len = self.length(1 << (marker - 0xd9))?;
+                self.string(len)
+            }
+            0xdc | 0xdd => {
+                let len = self.length(if marker == 0xdc { 2 } else { 4 })?;
+                self.list(len, depth)
+            }
+            0xde | 0xdf => {
+                let len = self.length(if marker == 0xde { 2 } else { 4 })?;
+                self.dict(len, depth)
+            }
+            0xe0..=0xff => int(marker as i8 as i128),
+            0xc1 => Err(format!("unused marker 0xc1 at byte {}", at)),
+        }
+    }
+}
+
+// exactly one value, with nothing after it
+pub fn decode(bytes: &[u8]) -> Result<Value, String> {
+    let mut reader = Reader { bytes, position: 0 };
+    let value = reader.value(0)?;
+    match reader.position == bytes.len() {
+        true => Ok(value),
+        false => Err(format!("trailing data after byte {}", reader.position)),
+    }
+}
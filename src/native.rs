@@ -0,0 +1,316 @@
+// C ABI for native extension modules, loaded with `import native "lib.so";`.
+//
+// A plugin is a shared library exporting two symbols:
+//
+//     const uint32_t XI_ABI_VERSION = 1;
+//     void xi_register(const struct xi_registry *registry);
+//
+// `xi_register` calls `registry->register_function` once per builtin it
+// provides. `XI_ABI_VERSION` is checked against `ABI_VERSION` before
+// `xi_register` is called, and the library stays loaded for the rest of the
+// process. `include/xi.h` has
+// the same definitions for C.
+
+use std::{
+    ffi::{c_char, c_void, CStr, CString},
+    fmt::Display,
+};
+
+use miette::Report;
+
+use crate::{
+    context::Ctx,
+    function::{Arity, Function},
+    interpreter::RuntimeError,
+    report::{NativeAbiMismatch, NativeCallError, NativeLoadError},
+    sync::{Rc, RefCell},
+    token::{Literal, Span},
+    value::Value,
+};
+
+pub const ABI_VERSION: u32 = 1;
+
+pub const XI_NIL: u32 = 0;
+pub const XI_BOOL: u32 = 1;
+pub const XI_INT: u32 = 2;
+pub const XI_FLOAT: u32 = 3;
+pub const XI_STRING: u32 = 4;
+
+// Only the field selected by `kind` is meaningful. Strings are UTF-8 and not
+// nul-terminated. A string returned by a plugin only has to stay valid until
+// its function returns: it is copied right away.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct XiValue {
+    pub kind: u32,
+    pub boolean: bool,
+    pub int: i64,
+    pub float: f64,
+    pub string: *const c_char,
+    pub length: usize,
+}
+
+// Returns 0 on success. Otherwise the call fails, with `out` as the message
+// when it is a string.
+pub type XiFunction =
+    unsafe extern "C" fn(args: *const XiValue, count: usize, out: *mut XiValue) -> i32;
+
+#[repr(C)]
+pub struct XiRegistry {
+    pub abi_version: u32,
+    pub context: *mut c_void,
+    pub register_function: unsafe extern "C" fn(
+        context: *mut c_void,
+        name: *const c_char,
+        arity: usize,
+        function: XiFunction,
+    ),
+}
+
+impl XiValue {
+    fn nil() -> Self {
+        Self {
+            kind: XI_NIL,
+            boolean: false,
+            int: 0,
+            float: 0.0,
+            string: std::ptr::null(),
+            length: 0,
+        }
+    }
+
+    // `value` has to outlive the returned XiValue when it's a string
+    fn from_value(value: &Value) -> Result<Self, String> {
+        let nil = Self::nil();
+        Ok(match value {
+            Value::Nil => nil,
+            Value::True | Value::False => Self {
+                kind: XI_BOOL,
+                boolean: value.is_truthy(),
+                ..nil
+            },
+            Value::Literal(Literal::Integer(i)) => Self {
+                kind: XI_INT,
+                int: i
+                    .to_i64()
+                    .ok_or_else(|| format!("integer {} does not fit in 64 bits", i))?,
+                ..nil
+            },
+            Value::Literal(Literal::Float(f)) => Self {
+                kind: XI_FLOAT,
+                float: f.to_f64(),
+                ..nil
+            },
+            Value::Literal(Literal::String(s)) => Self {
+                kind: XI_STRING,
+                string: s.as_ptr() as *const c_char,
+                length: s.len(),
+                ..nil
+            },
+            other => {
+                return Err(format!(
+                    "cannot pass a {} to a native function",
+                    other.type_name()
+                ))
+            }
+        })
+    }
+
+    /// # Safety
+    ///
+    /// A string value must point to `length` readable bytes.
+    unsafe fn string(&self) -> Result<String, String> {
+        if self.string.is_null() {
+            return Ok(String::new());
+        }
+        let bytes = std::slice::from_raw_parts(self.string as *const u8, self.length);
+        String::from_utf8(bytes.to_vec()).map_err(|_| "native string is not UTF-8".to_string())
+    }
+
+    /// # Safety
+    ///
+    /// See [`XiValue::string`].
+    unsafe fn into_value(self) -> Result<Value, String> {
+        Ok(match self.kind {
+            XI_NIL => Value::Nil,
+            XI_BOOL => self.boolean.into(),
+            XI_INT => rug::Integer::from(self.int).into(),
+            XI_FLOAT => rug::Float::with_val(53, self.float).into(),
+            XI_STRING => self.string()?.into(),
+            kind => return Err(format!("native function returned unknown kind {}", kind)),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct NativeFunction {
+    name: String,
+    arity: usize,
+    function: XiFunction,
+}
+
+impl Display for NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(native {})", self.name)
+    }
+}
+
+impl Function for NativeFunction {
+    fn run(&self, _: &Rc<RefCell<Ctx>>, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let error = |message: String| {
+            RuntimeError::Report(
+                NativeCallError {
+                    name: self.name.clone(),
+                    message,
+                }
+                .into(),
+            )
+        };
+
+        let args = args
+            .iter()
+            .map(XiValue::from_value)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(error)?;
+
+        let mut out = XiValue::nil();
+        // SAFETY: the arguments point into `args`, which outlives the call;
+        // the plugin itself is trusted once it has been allowed to load
+        let status = unsafe { (self.function)(args.as_ptr(), args.len(), &mut out) };
+        let result = unsafe {
+            match status {
+                0 => out.into_value(),
+                _ if out.kind == XI_STRING => Err(out.string().unwrap_or_default()),
+                _ => Err(format!("failed with status {}", status)),
+            }
+        };
+
+        result.map_err(error)
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::Exact(self.arity)
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Open(String),
+    MissingSymbol(&'static str),
+    AbiMismatch(u32),
+}
+
+unsafe extern "C" fn register_function(
+    context: *mut c_void,
+    name: *const c_char,
+    arity: usize,
+    function: XiFunction,
+) {
+    let functions = &mut *(context as *mut Vec<NativeFunction>);
+    functions.push(NativeFunction {
+        name: CStr::from_ptr(name).to_string_lossy().into_owned(),
+        arity,
+        function,
+    });
+}
+
+fn dlerror() -> String {
+    // SAFETY: dlerror returns either null or a nul-terminated message
+    unsafe {
+        let message = libc::dlerror();
+        match message.is_null() {
+            true => "unknown error".to_string(),
+            false => CStr::from_ptr(message).to_string_lossy().into_owned(),
+        }
+    }
+}
+
+// A loaded library, closed when dropped unless it is kept
+struct Library(*mut c_void);
+
+impl Library {
+    // the handle is never closed: the registered functions point into it
+    fn keep(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        // SAFETY: nothing from the library is in use before it's kept
+        unsafe {
+            libc::dlclose(self.0);
+        }
+    }
+}
+
+// Loads the plugin at `path` and returns the functions it registered, by name.
+pub fn load(path: &str) -> Result<Vec<(String, Value)>, LoadError> {
+    let path =
+        CString::new(path).map_err(|_| LoadError::Open("path contains a nul byte".into()))?;
+
+    // SAFETY: loading a library runs its initializers; this is why native
+    // imports are opt-in
+    unsafe {
+        let handle = libc::dlopen(path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+        if handle.is_null() {
+            return Err(LoadError::Open(dlerror()));
+        }
+        let library = Library(handle);
+
+        let symbol = |name: &'static CStr| {
+            let symbol = libc::dlsym(library.0, name.as_ptr());
+            match symbol.is_null() {
+                true => Err(LoadError::MissingSymbol(name.to_str().unwrap())),
+                false => Ok(symbol),
+            }
+        };
+
+        let version = *(symbol(c"XI_ABI_VERSION")? as *const u32);
+        if version != ABI_VERSION {
+            return Err(LoadError::AbiMismatch(version));
+        }
+
+        let register: unsafe extern "C" fn(*const XiRegistry) =
+            std::mem::transmute(symbol(c"xi_register")?);
+
+        let mut functions: Vec<NativeFunction> = Vec::new();
+        let registry = XiRegistry {
+            abi_version: ABI_VERSION,
+            context: &mut functions as *mut Vec<NativeFunction> as *mut c_void,
+            register_function,
+        };
+        register(&registry);
+
+        library.keep();
+        Ok(functions
+            .into_iter()
+            .map(|f| (f.name.clone(), Value::Function(Rc::new(f))))
+            .collect())
+    }
+}
+
+impl LoadError {
+    pub fn into_report(self, span: &Span, path: &str) -> Report {
+        match self {
+            LoadError::Open(message) => NativeLoadError {
+                span: (*span).into(),
+                message: format!("cannot open `{}`: {}", path, message),
+            }
+            .into(),
+            LoadError::MissingSymbol(symbol) => NativeLoadError {
+                span: (*span).into(),
+                message: format!("`{}` does not export `{}`", path, symbol),
+            }
+            .into(),
+            LoadError::AbiMismatch(found) => NativeAbiMismatch {
+                span: (*span).into(),
+                help: format!(
+                    "the plugin was built for ABI version {}, this interpreter uses version {}",
+                    found, ABI_VERSION
+                ),
+            }
+            .into(),
+        }
+    }
+}
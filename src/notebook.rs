@@ -0,0 +1,120 @@
+// Markdown with runnable code: every ```ix fence (or ```xi) is a cell, run
+// in order in one session, and the annotated copy has what each cell printed
+// in an ```output fence right after it. The output fences of an earlier run
+// are dropped when the file is read, so a copy can be run again.
+
+const CODE: [&str; 2] = ["ix", "xi"];
+const OUTPUT: &str = "output";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Piece {
+    // lines kept as they are, fences other than cells included
+    Text(String),
+    // the fence lines and the code between them
+    Cell {
+        open: String,
+        code: String,
+        close: String,
+    },
+}
+
+// the info string of a fence opening line, when it is one
+fn fence(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    trimmed
+        .strip_prefix("```")
+        .map(|info| info.split_whitespace().next().unwrap_or(""))
+}
+
+pub fn parse(markdown: &str) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut lines = markdown.split_inclusive('\n').peekable();
+    // an output fence is only dropped right after a cell
+    let mut after_cell = false;
+
+    while let Some(line) = lines.next() {
+        let info = fence(line);
+        if info.is_some_and(|info| CODE.contains(&info)) {
+            let mut code = String::new();
+            let mut close = String::new();
+            for line in lines.by_ref() {
+                if fence(line) == Some("") {
+                    close = line.to_string();
+                    break;
+                }
+                code.push_str(line);
+            }
+            pieces.push(Piece::Text(std::mem::take(&mut text)));
+            pieces.push(Piece::Cell {
+                open: line.to_string(),
+                code,
+                close,
+            });
+            after_cell = true;
+            continue;
+        }
+
+        if after_cell && info == Some(OUTPUT) {
+            for line in lines.by_ref() {
+                if fence(line) == Some("") {
+                    break;
+                }
+            }
+            // and the blank line written before it
+            if text.trim().is_empty() {
+                text.clear();
+            }
+            after_cell = false;
+            continue;
+        }
+
+        if !line.trim().is_empty() {
+            after_cell = false;
+        }
+        // other fences are copied whole, so cells inside them aren't run
+        text.push_str(line);
+        if info.is_some() {
+            for line in lines.by_ref() {
+                text.push_str(line);
+                if fence(line) == Some("") {
+                    break;
+                }
+            }
+        }
+    }
+
+    pieces.push(Piece::Text(text));
+    pieces.retain(|piece| *piece != Piece::Text(String::new()));
+    pieces
+}
+
+// the pieces again, with `outputs[i]` after the i-th cell unless it's empty
+pub fn render(pieces: &[Piece], outputs: &[String]) -> String {
+    let mut markdown = String::new();
+    let mut outputs = outputs.iter();
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => markdown.push_str(text),
+            Piece::Cell { open, code, close } => {
+                markdown.push_str(open);
+                markdown.push_str(code);
+                markdown.push_str(close);
+                if !close.ends_with('\n') {
+                    markdown.push('\n');
+                }
+
+                let output = outputs.next().map_or("", String::as_str);
+                if !output.is_empty() {
+                    markdown.push_str("\n```output\n");
+                    markdown.push_str(output);
+                    if !output.ends_with('\n') {
+                        markdown.push('\n');
+                    }
+                    markdown.push_str("```\n");
+                }
+            }
+        }
+    }
+    markdown
+}
@@ -1,26 +1,136 @@
-use std::rc::Rc;
+use std::collections::HashMap;
 
 use miette::Result;
 
 use crate::{
-    expr::{Expr, ExprKind, Stmt, StmtKind},
-    report::{InvalidAssignmentTarget, UnexpectedEof, UnexpectedToken},
-    token::{Literal, Span, Token, TokenKind},
-    value::Value,
+    annotation::{self, Annotation},
+    expr::{Arm, Expr, ExprKind, Pattern, PatternKind, Stmt, StmtKind},
+    lexer::Lexer,
+    report::{
+        ElseWithoutIf, InvalidAnnotation, InvalidAssignmentTarget, MissingSemicolon,
+        NestingTooDeep, UnexpectedEof, UnexpectedToken, UnhashableKey, UnquoteOutsideQuote,
+    },
+    sync::Rc,
+    token::{Literal, Operator, Span, Token, TokenKind},
+    value::{Value, ValueKey},
 };
 
 pub struct Parser<'a> {
     tokens: &'a Vec<Token>,
     current: usize,
     current_id: usize,
+    depth: usize,
+    // how many `quote`s the parser is in, less the `unquote`s
+    quoting: usize,
+    // every identifier once, shared by the expressions that name it
+    names: HashMap<&'a str, Rc<str>>,
 }
 
+// A rule of the grammar in ISO-style EBNF: `{ }` repeats, `[ ]` is optional,
+// quoted strings are tokens and IDENTIFIER, STRING, BYTES, INTEGER and FLOAT
+// the tokens the lexer makes from literals. A rule is matched after the
+// token that chooses it, as in `"fn" function`.
+pub struct Rule {
+    pub name: &'static str,
+    pub ebnf: &'static str,
+}
+
+// the whole grammar, one `name = ebnf ;` rule per line
+pub fn ebnf() -> String {
+    let width = Parser::GRAMMAR
+        .iter()
+        .map(|rule| rule.name.len())
+        .max()
+        .unwrap_or(0);
+    let rules: Vec<_> = Parser::GRAMMAR
+        .iter()
+        .map(|rule| format!("{:width$} = {} ;\n", rule.name, rule.ebnf, width = width))
+        .collect();
+    rules.concat()
+}
+
+// code typed at the REPL, given to `-c` or run by `eval` may leave out the
+// semicolon ending its last statement, which files can't. It's added on a
+// line of its own, past any comment, when that's what the code is missing;
+// code that doesn't parse either way is left as it is, for its errors.
+pub fn terminated(code: &str) -> String {
+    let parses = |code: &str| {
+        let mut lexer = Lexer::new(code);
+        let tokens = lexer.scan_tokens();
+        tokens.is_ok_and(|tokens| Parser::new(tokens).parse().is_ok())
+    };
+    if code.trim_end().ends_with(';') || parses(code) {
+        return code.to_string();
+    }
+    let ended = format!("{}\n;", code.trim_end());
+    match parses(&ended) {
+        true => ended,
+        false => code.to_string(),
+    }
+}
+
+// deeper programs are rejected instead of overflowing the stack of the
+// recursive descent (and of the resolver and interpreter after it); each
+// link of a chain like `a + b + c` counts, as its tree is as deep as it is
+// long
+const MAX_NESTING: usize = 256;
+
 impl<'a> Parser<'a> {
+    // the grammar the functions below implement, declared next to each of
+    // them: from a whole program down to the primary expressions
+    pub const GRAMMAR: [Rule; 42] = [
+        Self::PROGRAM,
+        Self::DECLARATION,
+        Self::ANNOTATION,
+        Self::CLASS_DECLARATION,
+        Self::ACCESSOR,
+        Self::FUNCTION,
+        Self::LET_DECLARATION,
+        Self::IMPORT_DECLARATION,
+        Self::STATEMENT,
+        Self::BLOCK,
+        Self::IF_STATEMENT,
+        Self::CONDITION,
+        Self::FOR_STATEMENT,
+        Self::FOR_IN_STATEMENT,
+        Self::RETURN_STATEMENT,
+        Self::THROW_STATEMENT,
+        Self::TRY_STATEMENT,
+        Self::WHILE_STATEMENT,
+        Self::EXPRESSION_STATEMENT,
+        Self::EXPRESSION,
+        Self::ASSIGNMENT,
+        Self::CONDITIONAL,
+        Self::OR,
+        Self::AND,
+        Self::EQUALITY,
+        Self::COMPARISON,
+        Self::UNION,
+        Self::TERM,
+        Self::FACTOR,
+        Self::UNARY,
+        Self::CALL,
+        Self::ITEM,
+        Self::PRIMARY,
+        Self::LIST,
+        Self::DICT,
+        Self::SET,
+        Self::MATCH,
+        Self::ARM,
+        Self::PATTERN,
+        Self::LITERAL,
+        Self::LIST_PATTERN,
+        Self::DICT_PATTERN,
+    ];
+
     pub fn new(tokens: &'a Vec<Token>) -> Self {
         Self {
             tokens,
             current: 0,
             current_id: 0,
+            depth: 0,
+            quoting: 0,
+            names: HashMap::new(),
         }
     }
 
@@ -52,14 +162,40 @@ impl<'a> Parser<'a> {
     fn peek_force(&mut self) -> Result<&Token> {
         self.peek().ok_or(
             UnexpectedEof {
-                span: self.previous().span.into(),
+                span: self.previous_span().into(),
             }
             .into(),
         )
     }
 
-    fn previous(&self) -> &Token {
-        &self.tokens[self.current - 1]
+    fn previous(&self) -> Option<&Token> {
+        self.tokens.get(self.current.checked_sub(1)?)
+    }
+
+    fn previous_span(&self) -> Span {
+        self.previous().map(|token| token.span).unwrap_or_default()
+    }
+
+    // one level deeper, until the nested expression or the chain ends
+    fn deeper(&mut self) -> Result<()> {
+        if self.depth >= MAX_NESTING {
+            let span = match self.peek() {
+                Some(token) => token.span,
+                None => self.previous_span(),
+            };
+            return Err(NestingTooDeep { span: span.into() }.into());
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn nested<T>(&mut self, f: fn(&mut Self) -> Result<T>) -> Result<T> {
+        let depth = self.depth;
+        self.deeper()?;
+        let result = f(self);
+        self.depth = depth;
+        result
     }
 
     fn consume(&mut self, kind: TokenKind) -> Result<()> {
@@ -76,20 +212,57 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn previous_identifier(&self) -> String {
-        if let Literal::Identifier(name) = self.previous().clone().literal.unwrap() {
-            name
-        } else {
-            unreachable!();
+    // the `;` every simple statement (expressions, `let`, `return` and
+    // `import`) ends with, the last one of a file included
+    fn terminate(&mut self) -> Result<()> {
+        if self.next_is(|k| k == TokenKind::Semicolon).is_some() {
+            return Ok(());
+        }
+
+        Err(MissingSemicolon {
+            span: self.previous_span().into(),
+        }
+        .into())
+    }
+
+    fn previous_identifier(&mut self) -> Result<Rc<str>> {
+        let tokens = self.tokens;
+        match self.current.checked_sub(1).and_then(|i| tokens.get(i)) {
+            Some(Token {
+                literal: Some(Literal::Identifier(name)),
+                ..
+            }) => Ok(self
+                .names
+                .entry(name)
+                .or_insert_with(|| name.as_str().into())
+                .clone()),
+            Some(token) => Err(UnexpectedToken {
+                span: token.span.into(),
+                help: format!("wanted {:?}, found {:?}", TokenKind::Identifier, token.kind),
+            }
+            .into()),
+            None => Err(UnexpectedEof {
+                span: self.previous_span().into(),
+            }
+            .into()),
         }
     }
 
     fn span(&mut self, start: usize) -> Span {
-        let start_span = self.tokens[start].span;
-        let end_span = self.previous().span;
-        Span::new_range(start_span.offset, end_span.offset + end_span.length)
+        let start_span = self.tokens.get(start).map(|token| token.span);
+        let end_span = self.previous_span();
+        let start_span = start_span.unwrap_or(end_span);
+        Span::new_range(
+            start_span.offset,
+            (end_span.offset + end_span.length).max(start_span.offset),
+        )
     }
 
+    const PRIMARY: Rule = Rule {
+        name: "primary",
+        ebnf: r##""false" | "true" | "nil" | "this" | "super" "." IDENTIFIER | STRING | BYTES | FLOAT | INTEGER | IDENTIFIER | "[" list | "{" dict | "#{" set | "quote" "{" block | "unquote" "(" expression ")" | "match" match | "(" expression ")""##,
+    };
+
     fn primary(&mut self) -> Result<Box<Expr>> {
         let start = self.current;
 
@@ -122,9 +295,32 @@ impl<'a> Parser<'a> {
             }));
         }
 
-        if let Some(token) =
-            self.next_is(|k| matches!(k, TokenKind::String | TokenKind::Float | TokenKind::Integer))
-        {
+        if self.next_is(|k| k == TokenKind::This).is_some() {
+            return Ok(Box::new(Expr {
+                kind: ExprKind::This,
+                span: self.span(start),
+                id: self.next_id(),
+            }));
+        }
+
+        if self.next_is(|k| k == TokenKind::Super).is_some() {
+            self.consume(TokenKind::Dot)?;
+            self.consume(TokenKind::Identifier)?;
+            return Ok(Box::new(Expr {
+                kind: ExprKind::Super {
+                    method: self.previous_identifier()?,
+                },
+                span: self.span(start),
+                id: self.next_id(),
+            }));
+        }
+
+        if let Some(token) = self.next_is(|k| {
+            matches!(
+                k,
+                TokenKind::String | TokenKind::Bytes | TokenKind::Float | TokenKind::Integer
+            )
+        }) {
             return Ok(Box::new(Expr {
                 kind: ExprKind::Literal {
                     value: token.literal.clone().unwrap().into(),
@@ -138,7 +334,7 @@ impl<'a> Parser<'a> {
         if self.next_is(|k| k == TokenKind::Identifier).is_some() {
             return Ok(Box::new(Expr {
                 kind: ExprKind::Variable {
-                    name: self.previous_identifier(),
+                    name: self.previous_identifier()?,
                 },
                 span: self.span(start),
 
@@ -146,6 +342,53 @@ impl<'a> Parser<'a> {
             }));
         }
 
+        if self.next_is(|k| k == TokenKind::LeftSquare).is_some() {
+            return self.list();
+        }
+
+        if self.next_is(|k| k == TokenKind::LeftBrace).is_some() {
+            return self.dict();
+        }
+
+        if self.next_is(|k| k == TokenKind::HashLeftBrace).is_some() {
+            return self.set();
+        }
+
+        if self.next_is(|k| k == TokenKind::Quote).is_some() {
+            self.consume(TokenKind::LeftBrace)?;
+            self.quoting += 1;
+            let body = self.get_block();
+            self.quoting -= 1;
+            return Ok(Box::new(Expr {
+                kind: ExprKind::Quote { body: body? },
+                span: self.span(start),
+                id: self.next_id(),
+            }));
+        }
+
+        if self.next_is(|k| k == TokenKind::Match).is_some() {
+            return self.match_expression();
+        }
+
+        if self.next_is(|k| k == TokenKind::Unquote).is_some() {
+            if self.quoting == 0 {
+                return Err(UnquoteOutsideQuote {
+                    span: self.previous_span().into(),
+                }
+                .into());
+            }
+            self.consume(TokenKind::LeftParen)?;
+            self.quoting -= 1;
+            let value = self.expression();
+            self.quoting += 1;
+            self.consume(TokenKind::RightParen)?;
+            return Ok(Box::new(Expr {
+                kind: ExprKind::Unquote { value: value? },
+                span: self.span(start),
+                id: self.next_id(),
+            }));
+        }
+
         if self.next_is(|k| k == TokenKind::LeftParen).is_some() {
             let value = self.expression()?;
             self.consume(TokenKind::RightParen)?;
@@ -169,8 +412,11 @@ impl<'a> Parser<'a> {
         let mut args = Vec::new();
         if self.peek_force()?.kind != TokenKind::RightParen {
             loop {
-                args.push(*self.expression()?);
-                if self.next_is(|k| k == TokenKind::Comma).is_none() {
+                args.push(*self.item()?);
+                // a trailing comma is allowed before the closing bracket
+                if self.next_is(|k| k == TokenKind::Comma).is_none()
+                    || self.peek_force()?.kind == TokenKind::RightParen
+                {
                     break;
                 }
             }
@@ -185,25 +431,53 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    const CALL: Rule = Rule {
+        name: "call",
+        ebnf: r#"primary { "(" [ item { "," item } [ "," ] ] ")" | "[" ( expression [ ":" [ expression ] ] | ":" [ expression ] ) "]" | "." IDENTIFIER }"#,
+    };
+
     fn call(&mut self) -> Result<Box<Expr>> {
         let start = self.current;
+        let depth = self.depth;
         let mut expr = self.primary()?;
 
         loop {
             if self.next_is(|k| k == TokenKind::LeftParen).is_some() {
+                self.deeper()?;
                 expr = self.finish_call(start, expr)?;
             } else if self.next_is(|k| k == TokenKind::LeftSquare).is_some() {
-                let index = self.expression()?;
+                self.deeper()?;
+                let index = match self.peek_force()?.kind {
+                    TokenKind::Colon => None,
+                    _ => Some(self.expression()?),
+                };
+
+                let kind = match (index, self.next_is(|k| k == TokenKind::Colon)) {
+                    (start, Some(_)) => {
+                        let end = match self.peek_force()?.kind {
+                            TokenKind::RightSquare => None,
+                            _ => Some(self.expression()?),
+                        };
+                        ExprKind::Slice {
+                            obj: expr,
+                            start,
+                            end,
+                        }
+                    }
+                    (Some(index), None) => ExprKind::GetIndex { obj: expr, index },
+                    (None, None) => unreachable!("a missing index is followed by a colon"),
+                };
                 self.consume(TokenKind::RightSquare)?;
 
                 expr = Box::new(Expr {
-                    kind: ExprKind::GetIndex { obj: expr, index },
+                    kind,
                     span: self.span(start),
                     id: self.next_id(),
                 })
             } else if self.next_is(|k| k == TokenKind::Dot).is_some() {
+                self.deeper()?;
                 self.consume(TokenKind::Identifier)?;
-                let name = self.previous_identifier();
+                let name = self.previous_identifier()?;
                 expr = Box::new(Expr {
                     kind: ExprKind::Get { obj: expr, name },
                     span: self.span(start),
@@ -214,17 +488,46 @@ impl<'a> Parser<'a> {
             }
         }
 
+        self.depth = depth;
         Ok(expr)
     }
 
+    const ITEM: Rule = Rule {
+        name: "item",
+        ebnf: r#"[ "..." ] expression"#,
+    };
+
+    // an item of a list or set literal or an argument, which may be spread
+    fn item(&mut self) -> Result<Box<Expr>> {
+        let start = self.current;
+        match self.next_is(|k| k == TokenKind::DotDotDot) {
+            Some(_) => {
+                let value = self.expression()?;
+                Ok(Box::new(Expr {
+                    kind: ExprKind::Spread { value },
+                    span: self.span(start),
+                    id: self.next_id(),
+                }))
+            }
+            None => self.expression(),
+        }
+    }
+
+    const LIST: Rule = Rule {
+        name: "list",
+        ebnf: r#"[ item { "," item } [ "," ] ] "]""#,
+    };
+
     fn list(&mut self) -> Result<Box<Expr>> {
         let start = self.current;
 
         let mut items = Vec::new();
         if self.peek_force()?.kind != TokenKind::RightSquare {
             loop {
-                items.push(*self.expression()?);
-                if self.next_is(|k| k == TokenKind::Comma).is_none() {
+                items.push(*self.item()?);
+                if self.next_is(|k| k == TokenKind::Comma).is_none()
+                    || self.peek_force()?.kind == TokenKind::RightSquare
+                {
                     break;
                 }
             }
@@ -239,6 +542,177 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    const MATCH: Rule = Rule {
+        name: "match",
+        ebnf: r#""(" expression ")" "{" [ arm { "," arm } [ "," ] ] "}""#,
+    };
+
+    fn match_expression(&mut self) -> Result<Box<Expr>> {
+        let start = self.current - 1;
+
+        self.consume(TokenKind::LeftParen)?;
+        let scrutinee = self.expression()?;
+        self.consume(TokenKind::RightParen)?;
+        self.consume(TokenKind::LeftBrace)?;
+        let mut arms = Vec::new();
+        while self.peek_force()?.kind != TokenKind::RightBrace {
+            arms.push(self.arm()?);
+            if self.next_is(|k| k == TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+        self.consume(TokenKind::RightBrace)?;
+
+        Ok(Box::new(Expr {
+            kind: ExprKind::Match { scrutinee, arms },
+            span: self.span(start),
+            id: self.next_id(),
+        }))
+    }
+
+    const ARM: Rule = Rule {
+        name: "arm",
+        ebnf: r#"pattern "=>" expression"#,
+    };
+
+    fn arm(&mut self) -> Result<Arm> {
+        let pattern = self.pattern()?;
+        self.consume(TokenKind::FatArrow)?;
+        let body = self.expression()?;
+        Ok(Arm { pattern, body })
+    }
+
+    const PATTERN: Rule = Rule {
+        name: "pattern",
+        ebnf: r#""_" | IDENTIFIER | "[" list_pattern | "{" dict_pattern | literal"#,
+    };
+
+    fn pattern(&mut self) -> Result<Pattern> {
+        self.nested(Self::pattern_inner)
+    }
+
+    fn pattern_inner(&mut self) -> Result<Pattern> {
+        let start = self.current;
+        let kind = if self.next_is(|k| k == TokenKind::Underscore).is_some() {
+            PatternKind::Wildcard
+        } else if self.next_is(|k| k == TokenKind::Identifier).is_some() {
+            PatternKind::Binding {
+                name: self.previous_identifier()?,
+            }
+        } else if self.next_is(|k| k == TokenKind::LeftSquare).is_some() {
+            self.list_pattern()?
+        } else if self.next_is(|k| k == TokenKind::LeftBrace).is_some() {
+            self.dict_pattern()?
+        } else {
+            PatternKind::Literal {
+                value: self.literal()?,
+            }
+        };
+        Ok(Pattern {
+            kind,
+            span: self.span(start),
+        })
+    }
+
+    const LITERAL: Rule = Rule {
+        name: "literal",
+        ebnf: r#"[ "-" ] ( INTEGER | FLOAT ) | STRING | BYTES | "true" | "false" | "nil""#,
+    };
+
+    fn literal(&mut self) -> Result<Value> {
+        let negative = self.next_is(|k| k == TokenKind::Minus).is_some();
+        let token = self.peek_force()?;
+        let value = match token.kind {
+            TokenKind::Float | TokenKind::Integer => token.literal.clone().unwrap().into(),
+            TokenKind::String | TokenKind::Bytes if !negative => {
+                token.literal.clone().unwrap().into()
+            }
+            TokenKind::True if !negative => Value::True,
+            TokenKind::False if !negative => Value::False,
+            TokenKind::Nil if !negative => Value::Nil,
+            kind => {
+                return Err(UnexpectedToken {
+                    span: token.span.into(),
+                    help: match negative {
+                        true => format!("wanted a number, found {:?}", kind),
+                        false => format!("wanted a pattern, found {:?}", kind),
+                    },
+                }
+                .into())
+            }
+        };
+        self.next();
+        Ok(match negative {
+            true => (-value).expect("numbers can be negated"),
+            false => value,
+        })
+    }
+
+    const LIST_PATTERN: Rule = Rule {
+        name: "list_pattern",
+        ebnf: r#"{ pattern "," } [ pattern | ( IDENTIFIER | "_" ) "..." ] "]""#,
+    };
+
+    fn list_pattern(&mut self) -> Result<PatternKind> {
+        let mut items = Vec::new();
+        let mut rest = None;
+        while self.peek_force()?.kind != TokenKind::RightSquare {
+            let item = self.pattern()?;
+            if let Some(token) = self.next_is(|k| k == TokenKind::DotDotDot) {
+                if !matches!(
+                    item.kind,
+                    PatternKind::Binding { .. } | PatternKind::Wildcard
+                ) {
+                    return Err(UnexpectedToken {
+                        span: token.span.into(),
+                        help: "only a name or `_` takes the rest of a list".to_string(),
+                    }
+                    .into());
+                }
+                rest = Some(Box::new(item));
+                break;
+            }
+            items.push(item);
+            if self.next_is(|k| k == TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+        self.consume(TokenKind::RightSquare)?;
+        Ok(PatternKind::List { items, rest })
+    }
+
+    const DICT_PATTERN: Rule = Rule {
+        name: "dict_pattern",
+        ebnf: r#"[ literal ":" pattern { "," literal ":" pattern } [ "," ] ] "}""#,
+    };
+
+    // the keys are literals
+    fn dict_pattern(&mut self) -> Result<PatternKind> {
+        let mut entries = Vec::new();
+        while self.peek_force()?.kind != TokenKind::RightBrace {
+            let start = self.current;
+            let key = self.literal()?;
+            if ValueKey::try_from(key.clone()).is_err() {
+                return Err(UnhashableKey {
+                    span: self.span(start).into(),
+                }
+                .into());
+            }
+            self.consume(TokenKind::Colon)?;
+            entries.push((key, self.pattern()?));
+            if self.next_is(|k| k == TokenKind::Comma).is_none() {
+                break;
+            }
+        }
+        self.consume(TokenKind::RightBrace)?;
+        Ok(PatternKind::Dict { entries })
+    }
+
+    const DICT: Rule = Rule {
+        name: "dict",
+        ebnf: r#"[ expression ":" expression { "," expression ":" expression } [ "," ] ] "}""#,
+    };
+
     fn dict(&mut self) -> Result<Box<Expr>> {
         let start = self.current;
 
@@ -249,7 +723,9 @@ impl<'a> Parser<'a> {
                 self.consume(TokenKind::Colon)?;
                 let right = self.expression()?;
                 items.push((*left, *right));
-                if self.next_is(|k| k == TokenKind::Comma).is_none() {
+                if self.next_is(|k| k == TokenKind::Comma).is_none()
+                    || self.peek_force()?.kind == TokenKind::RightBrace
+                {
                     break;
                 }
             }
@@ -264,71 +740,151 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    const SET: Rule = Rule {
+        name: "set",
+        ebnf: r#"[ item { "," item } [ "," ] ] "}""#,
+    };
+
+    fn set(&mut self) -> Result<Box<Expr>> {
+        let start = self.current;
+
+        let mut items = Vec::new();
+        if self.peek_force()?.kind != TokenKind::RightBrace {
+            loop {
+                items.push(*self.item()?);
+                if self.next_is(|k| k == TokenKind::Comma).is_none()
+                    || self.peek_force()?.kind == TokenKind::RightBrace
+                {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenKind::RightBrace)?;
+
+        Ok(Box::new(Expr {
+            kind: ExprKind::SetLiteral { items },
+            span: self.span(start),
+            id: self.next_id(),
+        }))
+    }
+
+    const UNARY: Rule = Rule {
+        name: "unary",
+        ebnf: r#"( "!" | "-" ) primary | call"#,
+    };
+
     fn unary(&mut self) -> Result<Box<Expr>> {
         let start = self.current;
         if let Some(op) = self.next_is(|a| matches!(a, TokenKind::Bang | TokenKind::Minus)) {
             Ok(Box::new(Expr {
                 kind: ExprKind::Unary {
-                    op: op.clone(),
+                    op: op.into(),
                     right: self.primary()?,
                 },
                 span: self.span(start),
 
                 id: self.next_id(),
             }))
-        } else if let Some(_op) = self.next_is(|a| matches!(a, TokenKind::LeftSquare)) {
-            self.list()
-        } else if let Some(_op) = self.next_is(|a| matches!(a, TokenKind::LeftBrace)) {
-            self.dict()
         } else {
             self.call()
         }
     }
 
+    const FACTOR: Rule = Rule {
+        name: "factor",
+        ebnf: r#"unary { ( "/" | "*" ) unary }"#,
+    };
+
     fn factor(&mut self) -> Result<Box<Expr>> {
         let start = self.current;
+        let depth = self.depth;
         let mut expr = self.unary()?;
 
         while let Some(op) = self.next_is(|k| matches!(k, TokenKind::Slash | TokenKind::Star)) {
             expr = Box::new(Expr {
                 kind: ExprKind::Binary {
                     left: expr,
-                    op: op.clone(),
+                    op: op.into(),
                     right: self.unary()?,
                 },
                 span: self.span(start),
 
                 id: self.next_id(),
             });
+            self.deeper()?;
         }
 
+        self.depth = depth;
         Ok(expr)
     }
 
+    const TERM: Rule = Rule {
+        name: "term",
+        ebnf: r#"factor { ( "-" | "+" ) factor }"#,
+    };
+
     fn term(&mut self) -> Result<Box<Expr>> {
         let start = self.current;
+        let depth = self.depth;
         let mut expr = self.factor()?;
 
         while let Some(op) = self.next_is(|k| matches!(k, TokenKind::Minus | TokenKind::Plus)) {
             expr = Box::new(Expr {
                 kind: ExprKind::Binary {
                     left: expr,
-                    op: op.clone(),
+                    op: op.into(),
                     right: self.factor()?,
                 },
                 span: self.span(start),
 
                 id: self.next_id(),
             });
+            self.deeper()?;
         }
 
+        self.depth = depth;
         Ok(expr)
     }
 
-    fn comparison(&mut self) -> Result<Box<Expr>> {
+    const UNION: Rule = Rule {
+        name: "union",
+        ebnf: r#"term { "|" term }"#,
+    };
+
+    fn union(&mut self) -> Result<Box<Expr>> {
         let start = self.current;
+        let depth = self.depth;
         let mut expr = self.term()?;
 
+        while let Some(op) = self.next_is(|k| k == TokenKind::Pipe) {
+            expr = Box::new(Expr {
+                kind: ExprKind::Binary {
+                    left: expr,
+                    op: op.into(),
+                    right: self.term()?,
+                },
+                span: self.span(start),
+
+                id: self.next_id(),
+            });
+            self.deeper()?;
+        }
+
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    const COMPARISON: Rule = Rule {
+        name: "comparison",
+        ebnf: r#"union { ( ">" | ">=" | "<" | "<=" ) union }"#,
+    };
+
+    fn comparison(&mut self) -> Result<Box<Expr>> {
+        let start = self.current;
+        let depth = self.depth;
+        let mut expr = self.union()?;
+
         while let Some(op) = self.next_is(|k| {
             matches!(
                 k,
@@ -341,20 +897,28 @@ impl<'a> Parser<'a> {
             expr = Box::new(Expr {
                 kind: ExprKind::Binary {
                     left: expr,
-                    op: op.clone(),
-                    right: self.term()?,
+                    op: op.into(),
+                    right: self.union()?,
                 },
                 span: self.span(start),
 
                 id: self.next_id(),
             });
+            self.deeper()?;
         }
 
+        self.depth = depth;
         Ok(expr)
     }
 
+    const EQUALITY: Rule = Rule {
+        name: "equality",
+        ebnf: r#"comparison { ( "!=" | "==" ) comparison }"#,
+    };
+
     fn equality(&mut self) -> Result<Box<Expr>> {
         let start = self.current;
+        let depth = self.depth;
         let mut expr = self.comparison()?;
 
         while let Some(op) =
@@ -363,24 +927,33 @@ impl<'a> Parser<'a> {
             expr = Box::new(Expr {
                 kind: ExprKind::Binary {
                     left: expr,
-                    op: op.clone(),
+                    op: op.into(),
                     right: self.comparison()?,
                 },
                 span: self.span(start),
 
                 id: self.next_id(),
             });
+            self.deeper()?;
         }
 
+        self.depth = depth;
         Ok(expr)
     }
 
+    const AND: Rule = Rule {
+        name: "and",
+        ebnf: r#"equality { "and" equality }"#,
+    };
+
     fn and(&mut self) -> Result<Box<Expr>> {
         let start = self.current;
+        let depth = self.depth;
         let mut expr = self.equality()?;
 
-        while self.next_is(|k| k == TokenKind::And).is_some() {
-            let op = self.previous().clone();
+        while let Some(op) = self.next_is(|k| k == TokenKind::And) {
+            let op = Operator::from(op);
+            self.deeper()?;
             let right = self.equality()?;
             expr = Box::new(Expr {
                 kind: ExprKind::Logical {
@@ -394,15 +967,23 @@ impl<'a> Parser<'a> {
             })
         }
 
+        self.depth = depth;
         Ok(expr)
     }
 
+    const OR: Rule = Rule {
+        name: "or",
+        ebnf: r#"and { "or" and }"#,
+    };
+
     fn or(&mut self) -> Result<Box<Expr>> {
         let start = self.current;
+        let depth = self.depth;
         let mut expr = self.and()?;
 
-        while self.next_is(|k| k == TokenKind::Or).is_some() {
-            let op = self.previous().clone();
+        while let Some(op) = self.next_is(|k| k == TokenKind::Or) {
+            let op = Operator::from(op);
+            self.deeper()?;
             let right = self.and()?;
             expr = Box::new(Expr {
                 kind: ExprKind::Logical {
@@ -416,15 +997,49 @@ impl<'a> Parser<'a> {
             })
         }
 
+        self.depth = depth;
         Ok(expr)
     }
 
+    const CONDITIONAL: Rule = Rule {
+        name: "conditional",
+        ebnf: r#"or [ "?" expression ":" conditional ]"#,
+    };
+
+    // the branches nest to the right, so `a ? b : c ? d : e` is
+    // `a ? b : (c ? d : e)`
+    fn conditional(&mut self) -> Result<Box<Expr>> {
+        let start = self.current;
+        let cond = self.or()?;
+
+        if self.next_is(|k| k == TokenKind::Question).is_none() {
+            return Ok(cond);
+        }
+        let then_branch = self.expression()?;
+        self.consume(TokenKind::Colon)?;
+        let else_branch = self.nested(Self::conditional)?;
+        Ok(Box::new(Expr {
+            kind: ExprKind::Conditional {
+                cond,
+                then_branch,
+                else_branch,
+            },
+            span: self.span(start),
+            id: self.next_id(),
+        }))
+    }
+
+    const ASSIGNMENT: Rule = Rule {
+        name: "assignment",
+        ebnf: r#"conditional [ "=" expression ]"#,
+    };
+
     fn assignment(&mut self) -> Result<Box<Expr>> {
         let start = self.current;
-        let expr = self.or()?;
+        let expr = self.conditional()?;
 
         if self.next_is(|k| k == TokenKind::Equal).is_some() {
-            let value = self.assignment()?;
+            let value = self.expression()?;
 
             match expr.kind {
                 ExprKind::Get { obj, name } => Ok(Box::new(Expr {
@@ -452,8 +1067,13 @@ impl<'a> Parser<'a> {
         }
     }
 
+    const EXPRESSION: Rule = Rule {
+        name: "expression",
+        ebnf: r#"assignment"#,
+    };
+
     fn expression(&mut self) -> Result<Box<Expr>> {
-        self.assignment()
+        self.nested(Self::assignment)
     }
 
     fn _sync(&mut self) -> Result<()> {
@@ -464,12 +1084,17 @@ impl<'a> Parser<'a> {
 
             if matches!(
                 self.peek_force()?.kind,
-                TokenKind::Fn
+                TokenKind::At
+                    | TokenKind::Class
+                    | TokenKind::Fn
+                    | TokenKind::Import
                     | TokenKind::Let
                     | TokenKind::For
                     | TokenKind::If
                     | TokenKind::While
                     | TokenKind::Return
+                    | TokenKind::Throw
+                    | TokenKind::Try
             ) {
                 break;
             }
@@ -478,13 +1103,16 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
+    const EXPRESSION_STATEMENT: Rule = Rule {
+        name: "expression_statement",
+        ebnf: r#"expression ";""#,
+    };
+
     pub fn expression_statement(&mut self) -> Result<Box<Stmt>> {
         let start = self.current;
         let value = self.expression()?;
 
-        if self.peek().is_some() {
-            self.consume(TokenKind::Semicolon)?;
-        }
+        self.terminate()?;
 
         Ok(Box::new(Stmt {
             kind: StmtKind::Expression { expr: value },
@@ -494,36 +1122,78 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    const IF_STATEMENT: Rule = Rule {
+        name: "if_statement",
+        ebnf: r#"condition statement { "else" "if" condition statement } [ "else" statement ]"#,
+    };
+
+    // `if (c) a else if (d) b else e` is parsed as a chain, in a loop, and
+    // built into nested ifs from the last one back. An `else` belongs to the
+    // closest `if` without one: `if (c) if (d) a else b` elses `d`.
     pub fn if_statement(&mut self) -> Result<Box<Stmt>> {
-        let start = self.current - 1;
-        self.consume(TokenKind::LeftParen)?;
-        let cond = self.expression()?;
-        self.consume(TokenKind::RightParen)?;
+        let mut clauses = Vec::new();
+        let mut else_branch = None;
+        loop {
+            let start = self.current - 1;
+            let (binding, cond) = self.condition()?;
+            let then_branch = self.statement()?;
+            clauses.push((start, binding, cond, then_branch));
 
-        let then_branch = self.statement()?;
-        let else_branch = if self.next_is(|k| k == TokenKind::Else).is_some() {
-            Some(self.statement()?)
-        } else {
-            None
-        };
+            if self.next_is(|k| k == TokenKind::Else).is_none() {
+                break;
+            }
+            if self.next_is(|k| k == TokenKind::If).is_none() {
+                else_branch = Some(self.statement()?);
+                break;
+            }
+        }
 
-        Ok(Box::new(Stmt {
-            kind: StmtKind::If {
-                cond,
-                then_branch,
-                else_branch,
-            },
-            span: self.span(start),
+        let mut chain = None;
+        for (start, binding, cond, then_branch) in clauses.into_iter().rev() {
+            let stmt = Box::new(Stmt {
+                kind: StmtKind::If {
+                    binding,
+                    cond,
+                    then_branch,
+                    else_branch: chain.take().or(else_branch.take()),
+                },
+                span: self.span(start),
 
-            id: self.next_id(),
-        }))
+                id: self.next_id(),
+            });
+            chain = Some(stmt);
+        }
+
+        Ok(chain.expect("an if has at least one clause"))
     }
 
+    const FOR_STATEMENT: Rule = Rule {
+        name: "for_statement",
+        ebnf: r#""(" ( for_in_statement | ( ";" | "let" let_declaration | expression_statement ) [ expression ] ";" [ expression ] ")" statement )"#,
+    };
+
+    // `for (init; cond; increment) body` desugars into
+    // `{ init; while (cond) { body; increment; } }`. The statements made up
+    // along the way take the spans of what they're made from: the increment
+    // its own, the block around the body the body's, and only the loop and
+    // the outer block the whole `for`.
     pub fn for_statement(&mut self) -> Result<Box<Stmt>> {
         let start = self.current - 1;
 
         self.consume(TokenKind::LeftParen)?;
 
+        let ahead = |n: usize| self.tokens.get(self.current + n).map(|token| token.kind);
+        match (ahead(0), ahead(1), ahead(2), ahead(3)) {
+            (Some(TokenKind::Identifier), Some(TokenKind::In), ..)
+            | (
+                Some(TokenKind::Identifier),
+                Some(TokenKind::Comma),
+                Some(TokenKind::Identifier),
+                Some(TokenKind::In),
+            ) => return self.for_in_statement(start),
+            _ => {}
+        }
+
         let initializer = if self.next_is(|k| k == TokenKind::Semicolon).is_some() {
             None
         } else if self.next_is(|k| k == TokenKind::Let).is_some() {
@@ -553,24 +1223,27 @@ impl<'a> Parser<'a> {
         let mut body = self.statement()?;
 
         if let Some(increment) = increment {
+            let span = body.span;
+            let increment = Stmt {
+                span: increment.span,
+                kind: StmtKind::Expression { expr: increment },
+                id: self.next_id(),
+            };
             body = Box::new(Stmt {
                 kind: StmtKind::Block {
-                    statements: vec![
-                        *body,
-                        Stmt {
-                            kind: StmtKind::Expression { expr: increment },
-                            span: self.span(start),
-                            id: self.next_id(),
-                        },
-                    ],
+                    statements: vec![*body, increment],
                 },
-                span: self.span(start),
+                span,
                 id: self.next_id(),
             });
         }
 
         body = Box::new(Stmt {
-            kind: StmtKind::While { cond, body },
+            kind: StmtKind::While {
+                binding: None,
+                cond,
+                body,
+            },
             span: self.span(start),
             id: self.next_id(),
         });
@@ -588,6 +1261,46 @@ impl<'a> Parser<'a> {
         Ok(body)
     }
 
+    const FOR_IN_STATEMENT: Rule = Rule {
+        name: "for_in_statement",
+        ebnf: r#"IDENTIFIER [ "," IDENTIFIER ] "in" expression ")" statement"#,
+    };
+
+    // `for (name in iterable) body` or `for (name, value in iterable) body`,
+    // after the opening parenthesis
+    fn for_in_statement(&mut self, start: usize) -> Result<Box<Stmt>> {
+        self.consume(TokenKind::Identifier)?;
+        let name = self.previous_identifier()?;
+        let value = match self.next_is(|k| k == TokenKind::Comma) {
+            Some(_) => {
+                self.consume(TokenKind::Identifier)?;
+                Some(self.previous_identifier()?)
+            }
+            None => None,
+        };
+        self.consume(TokenKind::In)?;
+        let iterable = self.expression()?;
+        self.consume(TokenKind::RightParen)?;
+
+        let body = self.statement()?;
+
+        Ok(Box::new(Stmt {
+            kind: StmtKind::ForIn {
+                name,
+                value,
+                iterable,
+                body,
+            },
+            span: self.span(start),
+            id: self.next_id(),
+        }))
+    }
+
+    const RETURN_STATEMENT: Rule = Rule {
+        name: "return_statement",
+        ebnf: r#"[ expression ] ";""#,
+    };
+
     fn return_statement(&mut self) -> Result<Box<Stmt>> {
         let start = self.current - 1;
         let expr = if self.peek().is_some() {
@@ -600,9 +1313,7 @@ impl<'a> Parser<'a> {
             None
         };
 
-        if self.peek().is_some() {
-            self.consume(TokenKind::Semicolon)?;
-        }
+        self.terminate()?;
 
         Ok(Box::new(Stmt {
             kind: StmtKind::Return { expr },
@@ -612,22 +1323,102 @@ impl<'a> Parser<'a> {
         }))
     }
 
-    fn while_statement(&mut self) -> Result<Box<Stmt>> {
+    const THROW_STATEMENT: Rule = Rule {
+        name: "throw_statement",
+        ebnf: r#"expression ";""#,
+    };
+
+    fn throw_statement(&mut self) -> Result<Box<Stmt>> {
         let start = self.current - 1;
+        let value = self.expression()?;
+        self.terminate()?;
+
+        Ok(Box::new(Stmt {
+            kind: StmtKind::Throw { value },
+            span: self.span(start),
+            id: self.next_id(),
+        }))
+    }
+
+    const TRY_STATEMENT: Rule = Rule {
+        name: "try_statement",
+        ebnf: r#""{" block "catch" "(" IDENTIFIER ")" "{" block"#,
+    };
+
+    fn try_statement(&mut self) -> Result<Box<Stmt>> {
+        let start = self.current - 1;
+
+        self.consume(TokenKind::LeftBrace)?;
+        let body = self.block()?;
+        self.consume(TokenKind::Catch)?;
         self.consume(TokenKind::LeftParen)?;
+        self.consume(TokenKind::Identifier)?;
+        let name = self.previous_identifier()?;
+        self.consume(TokenKind::RightParen)?;
+        self.consume(TokenKind::LeftBrace)?;
+        let handler = self.block()?;
+
+        Ok(Box::new(Stmt {
+            kind: StmtKind::Try {
+                body,
+                name,
+                handler,
+            },
+            span: self.span(start),
+            id: self.next_id(),
+        }))
+    }
+
+    const CONDITION: Rule = Rule {
+        name: "condition",
+        ebnf: r#""(" [ "let" IDENTIFIER "=" ] expression ")""#,
+    };
+
+    // the parenthesized condition of an `if` or `while`: an expression, or
+    // `let name = expression`
+    fn condition(&mut self) -> Result<(Option<Rc<str>>, Box<Expr>)> {
+        self.consume(TokenKind::LeftParen)?;
+        let binding = match self.next_is(|k| k == TokenKind::Let) {
+            Some(_) => {
+                self.consume(TokenKind::Identifier)?;
+                let name = self.previous_identifier()?;
+                self.consume(TokenKind::Equal)?;
+                Some(name)
+            }
+            None => None,
+        };
         let cond = self.expression()?;
         self.consume(TokenKind::RightParen)?;
+        Ok((binding, cond))
+    }
+
+    const WHILE_STATEMENT: Rule = Rule {
+        name: "while_statement",
+        ebnf: r#"condition statement"#,
+    };
+
+    fn while_statement(&mut self) -> Result<Box<Stmt>> {
+        let start = self.current - 1;
+        let (binding, cond) = self.condition()?;
 
         let body = self.statement()?;
 
         Ok(Box::new(Stmt {
-            kind: StmtKind::While { cond, body },
+            kind: StmtKind::While {
+                binding,
+                cond,
+                body,
+            },
             span: self.span(start),
             id: self.next_id(),
         }))
     }
 
     fn get_block(&mut self) -> Result<Vec<Stmt>> {
+        self.nested(Self::block_inner)
+    }
+
+    fn block_inner(&mut self) -> Result<Vec<Stmt>> {
         let mut statements = Vec::new();
         while self.peek_force()?.kind != TokenKind::RightBrace {
             statements.push(*self.declaration()?);
@@ -638,6 +1429,11 @@ impl<'a> Parser<'a> {
         Ok(statements)
     }
 
+    const BLOCK: Rule = Rule {
+        name: "block",
+        ebnf: r#"{ declaration } "}""#,
+    };
+
     fn block(&mut self) -> Result<Box<Stmt>> {
         let start = self.current;
         let statements = self.get_block()?;
@@ -649,27 +1445,50 @@ impl<'a> Parser<'a> {
         }))
     }
 
+    const STATEMENT: Rule = Rule {
+        name: "statement",
+        ebnf: r#""for" for_statement | "if" if_statement | "return" return_statement | "throw" throw_statement | "try" try_statement | "while" while_statement | "{" block | expression_statement"#,
+    };
+
     fn statement(&mut self) -> Result<Box<Stmt>> {
+        self.nested(Self::statement_inner)
+    }
+
+    fn statement_inner(&mut self) -> Result<Box<Stmt>> {
         if self.next_is(|k| k == TokenKind::For).is_some() {
             self.for_statement()
         } else if self.next_is(|k| k == TokenKind::If).is_some() {
             self.if_statement()
         } else if self.next_is(|k| k == TokenKind::Return).is_some() {
             self.return_statement()
+        } else if self.next_is(|k| k == TokenKind::Throw).is_some() {
+            self.throw_statement()
+        } else if self.next_is(|k| k == TokenKind::Try).is_some() {
+            self.try_statement()
         } else if self.next_is(|k| k == TokenKind::While).is_some() {
             self.while_statement()
         } else if self.next_is(|k| k == TokenKind::LeftBrace).is_some() {
             self.block()
+        } else if let Some(token) = self.next_is(|k| k == TokenKind::Else) {
+            Err(ElseWithoutIf {
+                span: token.span.into(),
+            }
+            .into())
         } else {
             self.expression_statement()
         }
     }
 
+    const FUNCTION: Rule = Rule {
+        name: "function",
+        ebnf: r#"IDENTIFIER "(" [ IDENTIFIER { "," IDENTIFIER } [ "," ] ] ")" "{" block"#,
+    };
+
     fn function(&mut self) -> Result<Box<Stmt>> {
         let start = self.current;
 
         self.consume(TokenKind::Identifier)?;
-        let name = self.previous_identifier();
+        let name = self.previous_identifier()?;
 
         self.consume(TokenKind::LeftParen)?;
 
@@ -677,9 +1496,11 @@ impl<'a> Parser<'a> {
         if self.peek_force()?.kind != TokenKind::RightParen {
             loop {
                 self.consume(TokenKind::Identifier)?;
-                params.push(self.previous_identifier());
+                params.push(self.previous_identifier()?);
 
-                if self.next_is(|k| k == TokenKind::Comma).is_none() {
+                if self.next_is(|k| k == TokenKind::Comma).is_none()
+                    || self.peek_force()?.kind == TokenKind::RightParen
+                {
                     break;
                 }
             }
@@ -694,17 +1515,107 @@ impl<'a> Parser<'a> {
                 name,
                 params: Rc::new(params),
                 body: Rc::new(body),
+                annotations: Vec::new(),
             },
             span: self.span(start),
             id: self.next_id(),
         }))
     }
 
+    const CLASS_DECLARATION: Rule = Rule {
+        name: "class_declaration",
+        ebnf: r#"IDENTIFIER [ "<" IDENTIFIER ] "{" { function | accessor } "}""#,
+    };
+
+    fn class_declaration(&mut self) -> Result<Box<Stmt>> {
+        let start = self.current - 1;
+
+        self.consume(TokenKind::Identifier)?;
+        let name = self.previous_identifier()?;
+        let superclass = match self.next_is(|k| k == TokenKind::Less) {
+            Some(_) => {
+                self.consume(TokenKind::Identifier)?;
+                Some(self.previous_identifier()?)
+            }
+            None => None,
+        };
+
+        self.consume(TokenKind::LeftBrace)?;
+        let (mut methods, mut getters, mut setters) = (Vec::new(), Vec::new(), Vec::new());
+        while self.peek_force()?.kind != TokenKind::RightBrace {
+            match self.accessor() {
+                Some("get") => getters.push(*self.accessor_function(0)?),
+                Some(_) => setters.push(*self.accessor_function(1)?),
+                None => methods.push(*self.function()?),
+            }
+        }
+        self.consume(TokenKind::RightBrace)?;
+
+        Ok(Box::new(Stmt {
+            kind: StmtKind::Class {
+                name,
+                superclass,
+                methods,
+                getters,
+                setters,
+            },
+            span: self.span(start),
+            id: self.next_id(),
+        }))
+    }
+
+    const ACCESSOR: Rule = Rule {
+        name: "accessor",
+        ebnf: r#"( "get" IDENTIFIER "(" ")" | "set" IDENTIFIER "(" IDENTIFIER ")" ) "{" block"#,
+    };
+
+    // `get` or `set` in front of a method's name; they are names everywhere
+    // else
+    fn accessor(&self) -> Option<&'static str> {
+        let name = match self.peek() {
+            Some(Token {
+                literal: Some(Literal::Identifier(name)),
+                ..
+            }) => name.as_str(),
+            _ => return None,
+        };
+        let before_name = self.tokens.get(self.current + 1)?.kind == TokenKind::Identifier;
+        match name {
+            "get" if before_name => Some("get"),
+            "set" if before_name => Some("set"),
+            _ => None,
+        }
+    }
+
+    // a getter, which takes no parameters, or a setter, which takes the value
+    fn accessor_function(&mut self, params: usize) -> Result<Box<Stmt>> {
+        self.next(); // get or set
+        let function = self.function()?;
+        match &function.kind {
+            StmtKind::Function { params: found, .. } if found.len() != params => {
+                Err(UnexpectedToken {
+                    span: function.span.into(),
+                    help: match params {
+                        0 => "a getter takes no parameters".to_string(),
+                        _ => "a setter takes one parameter, the value".to_string(),
+                    },
+                }
+                .into())
+            }
+            _ => Ok(function),
+        }
+    }
+
+    const LET_DECLARATION: Rule = Rule {
+        name: "let_declaration",
+        ebnf: r#"IDENTIFIER [ "=" expression ] ";""#,
+    };
+
     fn let_declaration(&mut self) -> Result<Box<Stmt>> {
         let start = self.current;
 
         self.consume(TokenKind::Identifier)?;
-        let name = self.previous_identifier();
+        let name = self.previous_identifier()?;
 
         let initializer = if self.next_is(|k| k == TokenKind::Equal).is_some() {
             Some(self.expression()?)
@@ -712,21 +1623,75 @@ impl<'a> Parser<'a> {
             None
         };
 
-        if self.peek().is_some() {
-            self.consume(TokenKind::Semicolon)?;
-        }
+        self.terminate()?;
 
         Ok(Box::new(Stmt {
-            kind: StmtKind::Let { name, initializer },
+            kind: StmtKind::Let {
+                name,
+                initializer,
+                annotations: Vec::new(),
+            },
             span: self.span(start),
 
             id: self.next_id(),
         }))
     }
 
+    const IMPORT_DECLARATION: Rule = Rule {
+        name: "import_declaration",
+        ebnf: r#""native" STRING ";""#,
+    };
+
+    fn import_declaration(&mut self) -> Result<Box<Stmt>> {
+        let start = self.current - 1;
+
+        self.consume(TokenKind::Identifier)?;
+        if &*self.previous_identifier()? != "native" {
+            return Err(UnexpectedToken {
+                span: self.previous_span().into(),
+                help: "wanted `native`, only native modules can be imported".to_string(),
+            }
+            .into());
+        }
+
+        let path = match self.next_is(|k| k == TokenKind::String) {
+            Some(Token {
+                literal: Some(Literal::String(path)),
+                ..
+            }) => path.to_string(),
+            _ => {
+                let token = self.peek_force()?;
+                return Err(UnexpectedToken {
+                    span: token.span.into(),
+                    help: format!("wanted {:?}, found {:?}", TokenKind::String, token.kind),
+                }
+                .into());
+            }
+        };
+
+        self.terminate()?;
+
+        Ok(Box::new(Stmt {
+            kind: StmtKind::NativeImport { path },
+            span: self.span(start),
+            id: self.next_id(),
+        }))
+    }
+
+    const DECLARATION: Rule = Rule {
+        name: "declaration",
+        ebnf: r#"{ annotation } ( "fn" function | "let" let_declaration ) | "class" class_declaration | "import" import_declaration | statement"#,
+    };
+
     fn declaration(&mut self) -> Result<Box<Stmt>> {
-        if self.next_is(|k| k == TokenKind::Fn).is_some() {
+        if self.peek_force()?.kind == TokenKind::At {
+            self.annotated()
+        } else if self.next_is(|k| k == TokenKind::Class).is_some() {
+            self.class_declaration()
+        } else if self.next_is(|k| k == TokenKind::Fn).is_some() {
             self.function()
+        } else if self.next_is(|k| k == TokenKind::Import).is_some() {
+            self.import_declaration()
         } else if self.next_is(|k| k == TokenKind::Let).is_some() {
             self.let_declaration()
         } else {
@@ -734,6 +1699,84 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // the `fn` or `let` after the annotations, with them
+    fn annotated(&mut self) -> Result<Box<Stmt>> {
+        let mut annotations = Vec::new();
+        while self.next_is(|k| k == TokenKind::At).is_some() {
+            annotations.push(self.annotation()?);
+        }
+
+        let mut stmt = if self.next_is(|k| k == TokenKind::Fn).is_some() {
+            self.function()?
+        } else if self.next_is(|k| k == TokenKind::Let).is_some() {
+            self.let_declaration()?
+        } else {
+            let token = self.peek_force()?;
+            return Err(UnexpectedToken {
+                span: token.span.into(),
+                help: "annotations go in front of a `fn` or a `let`".to_string(),
+            }
+            .into());
+        };
+        let params = match &stmt.kind {
+            StmtKind::Function { params, .. } => Some(params.len()),
+            _ => None,
+        };
+        for annotation in &annotations {
+            if let Err(help) = annotation::check(annotation, params) {
+                return Err(InvalidAnnotation {
+                    span: annotation.span.into(),
+                    help,
+                }
+                .into());
+            }
+        }
+        match &mut stmt.kind {
+            StmtKind::Function {
+                annotations: slot, ..
+            }
+            | StmtKind::Let {
+                annotations: slot, ..
+            } => *slot = annotations,
+            _ => unreachable!("annotated declarations are functions or lets"),
+        }
+        Ok(stmt)
+    }
+
+    const ANNOTATION: Rule = Rule {
+        name: "annotation",
+        ebnf: r#""@" IDENTIFIER [ "(" [ literal { "," literal } [ "," ] ] ")" ]"#,
+    };
+
+    fn annotation(&mut self) -> Result<Annotation> {
+        let start = self.current - 1;
+
+        self.consume(TokenKind::Identifier)?;
+        let name = self.previous_identifier()?;
+
+        let mut args = Vec::new();
+        if self.next_is(|k| k == TokenKind::LeftParen).is_some() {
+            while self.peek_force()?.kind != TokenKind::RightParen {
+                args.push(self.literal()?);
+                if self.next_is(|k| k == TokenKind::Comma).is_none() {
+                    break;
+                }
+            }
+            self.consume(TokenKind::RightParen)?;
+        }
+
+        Ok(Annotation {
+            name,
+            args,
+            span: self.span(start),
+        })
+    }
+
+    const PROGRAM: Rule = Rule {
+        name: "program",
+        ebnf: r#"{ declaration }"#,
+    };
+
     pub fn parse(&mut self) -> Result<Vec<Stmt>> {
         let mut statements = Vec::new();
         while self.peek().is_some() {
@@ -0,0 +1,248 @@
+use std::fmt::Write;
+
+// Line charts of numbers: in braille for the terminal, where every
+// character is a grid of 2 by 4 dots, and as SVG files.
+
+pub const WIDTH: usize = 60;
+pub const HEIGHT: usize = 15;
+
+pub const SVG_WIDTH: usize = 640;
+pub const SVG_HEIGHT: usize = 400;
+
+const COLORS: [&str; 6] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b",
+];
+
+// the bit of the dot at column `x` and row `y` of a braille character
+const DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+pub struct Series {
+    pub name: Option<String>,
+    pub points: Vec<(f64, f64)>,
+}
+
+pub struct Options {
+    pub width: usize,
+    pub height: usize,
+    pub title: Option<String>,
+}
+
+// the smallest and largest value, spread apart when they are the same so
+// that a flat line lands in the middle
+fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = values
+        .filter(|value| value.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+            (min.min(value), max.max(value))
+        });
+    match (min.is_finite(), min == max) {
+        (false, _) => (0.0, 1.0),
+        (true, true) => (min - 0.5, max + 0.5),
+        (true, false) => (min, max),
+    }
+}
+
+// `value` placed on `0..=steps` according to where it is within `bounds`
+fn scale(value: f64, (min, max): (f64, f64), steps: usize) -> usize {
+    (((value - min) / (max - min)) * steps as f64).round() as usize
+}
+
+// a number for an axis: whole ones as they are, others with up to 3 decimals
+fn label(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{:.0}", value);
+    }
+    let label = format!("{:.3}", value);
+    label
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+// the points from `from` to `to`, both included, as Bresenham draws them
+fn line(from: (usize, usize), to: (usize, usize), mut dot: impl FnMut(usize, usize)) {
+    let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+    let (dx, dy) = ((to.0 as i64 - x).abs(), -(to.1 as i64 - y).abs());
+    let (sx, sy) = ((to.0 as i64 - x).signum(), (to.1 as i64 - y).signum());
+    let mut error = dx + dy;
+    loop {
+        dot(x as usize, y as usize);
+        if (x, y) == (to.0 as i64, to.1 as i64) {
+            return;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+}
+
+// the points joined by lines, `width` characters wide and `height` lines
+// high, with the bounds of y on the left and the ones of x below
+pub fn braille(points: &[(f64, f64)], width: usize, height: usize) -> String {
+    let points: Vec<_> = points
+        .iter()
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+        .copied()
+        .collect();
+    let xs = bounds(points.iter().map(|(x, _)| *x));
+    let ys = bounds(points.iter().map(|(_, y)| *y));
+    let (columns, rows) = (width * 2 - 1, height * 4 - 1);
+
+    let mut cells = vec![vec![0u32; width]; height];
+    let mut dot = |x: usize, y: usize| {
+        // the first row of dots is the top one
+        let y = rows - y;
+        cells[y / 4][x / 2] |= DOTS[x % 2][y % 4];
+    };
+    let dots: Vec<_> = points
+        .iter()
+        .map(|&(x, y)| (scale(x, xs, columns), scale(y, ys, rows)))
+        .collect();
+    match dots.as_slice() {
+        [only] => dot(only.0, only.1),
+        dots => {
+            for pair in dots.windows(2) {
+                line(pair[0], pair[1], &mut dot);
+            }
+        }
+    }
+
+    let (top, bottom) = (label(ys.1), label(ys.0));
+    let margin = top.chars().count().max(bottom.chars().count());
+    let mut output = String::new();
+    for (row, cells) in cells.iter().enumerate() {
+        let (label, axis) = match row {
+            0 => (top.as_str(), '┤'),
+            _ if row == height - 1 => (bottom.as_str(), '┤'),
+            _ => ("", '│'),
+        };
+        let _ = write!(output, "{:>margin$} {}", label, axis);
+        output.extend(
+            cells
+                .iter()
+                .map(|&bits| char::from_u32(0x2800 + bits).unwrap()),
+        );
+        output.push('\n');
+    }
+    let _ = writeln!(output, "{:>margin$} └{}", "", "─".repeat(width));
+    let (left, right) = (label(xs.0), label(xs.1));
+    let gap = (width + 1)
+        .saturating_sub(left.chars().count())
+        .max(right.chars().count() + 1);
+    let _ = writeln!(output, "{:>margin$}  {}{:>gap$}", "", left, right);
+    output
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// every series as a line of its own color, on axes with 5 ticks each, and a
+// legend of the ones with a name
+pub fn svg(series: &[Series], options: &Options) -> String {
+    let (width, height) = (options.width as f64, options.height as f64);
+    let (left, right, bottom) = (60.0, 20.0, 40.0);
+    let top = if options.title.is_some() { 40.0 } else { 20.0 };
+    let all = || series.iter().flat_map(|series| series.points.iter());
+    let xs = bounds(all().map(|(x, _)| *x));
+    let ys = bounds(all().map(|(_, y)| *y));
+    let x = |value: f64| left + (value - xs.0) / (xs.1 - xs.0) * (width - left - right);
+    let y = |value: f64| height - bottom - (value - ys.0) / (ys.1 - ys.0) * (height - top - bottom);
+
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" font-family="sans-serif" font-size="12">"#,
+        width, height, width, height
+    );
+    let _ = writeln!(output, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    if let Some(title) = &options.title {
+        let _ = writeln!(
+            output,
+            r#"<text x="{}" y="24" text-anchor="middle" font-size="16">{}</text>"#,
+            width / 2.0,
+            escape(title)
+        );
+    }
+
+    let _ = writeln!(
+        output,
+        r#"<polyline points="{l},{t} {l},{b} {r},{b}" fill="none" stroke="black"/>"#,
+        l = left,
+        t = top,
+        b = height - bottom,
+        r = width - right
+    );
+    for tick in 0..=4 {
+        let fraction = tick as f64 / 4.0;
+        let (tx, ty) = (
+            xs.0 + (xs.1 - xs.0) * fraction,
+            ys.0 + (ys.1 - ys.0) * fraction,
+        );
+        let _ = writeln!(
+            output,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+            x(tx),
+            height - bottom + 18.0,
+            label(tx)
+        );
+        let _ = writeln!(
+            output,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"#,
+            left - 6.0,
+            y(ty) + 4.0,
+            label(ty)
+        );
+    }
+
+    for (index, series) in series.iter().enumerate() {
+        let color = COLORS[index % COLORS.len()];
+        let points: Vec<_> = series
+            .points
+            .iter()
+            .filter(|(px, py)| px.is_finite() && py.is_finite())
+            .map(|&(px, py)| format!("{:.1},{:.1}", x(px), y(py)))
+            .collect();
+        let _ = writeln!(
+            output,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="2"/>"#,
+            points.join(" "),
+            color
+        );
+    }
+
+    let named = series
+        .iter()
+        .enumerate()
+        .filter_map(|(index, series)| Some((index, series.name.as_ref()?)));
+    for (line, (index, name)) in named.enumerate() {
+        let (lx, ly) = (width - right - 120.0, top + 16.0 * line as f64 + 8.0);
+        let _ = writeln!(
+            output,
+            r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="2"/>"#,
+            lx,
+            ly,
+            lx + 20.0,
+            ly,
+            COLORS[index % COLORS.len()]
+        );
+        let _ = writeln!(
+            output,
+            r#"<text x="{:.1}" y="{:.1}">{}</text>"#,
+            lx + 26.0,
+            ly + 4.0,
+            escape(name)
+        );
+    }
+    output.push_str("</svg>\n");
+    output
+}
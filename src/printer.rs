@@ -0,0 +1,299 @@
+use std::fmt::Write;
+
+use crate::{
+    annotation::Annotation,
+    expr::{Expr, ExprKind, Pattern, PatternKind, Stmt, StmtKind},
+    repr,
+    sync::Rc,
+    token::{Literal, TokenKind},
+    value::Value,
+};
+
+// Statements and expressions written back as source, on one line and with
+// the same spacing whatever the original formatting was. Two trees print the
+// same exactly when they have the same structure. Parentheses are kept where
+// the source had them and nowhere else, and `for` loops print as what they
+// desugar into.
+
+pub fn operator(kind: TokenKind) -> &'static str {
+    match kind {
+        TokenKind::Minus => "-",
+        TokenKind::Plus => "+",
+        TokenKind::Slash => "/",
+        TokenKind::Star => "*",
+        TokenKind::Pipe => "|",
+        TokenKind::Bang => "!",
+        TokenKind::BangEqual => "!=",
+        TokenKind::EqualEqual => "==",
+        TokenKind::Greater => ">",
+        TokenKind::GreaterEqual => ">=",
+        TokenKind::Less => "<",
+        TokenKind::LessEqual => "<=",
+        TokenKind::And => "and",
+        TokenKind::Or => "or",
+        other => unreachable!("{:?} is not an operator", other),
+    }
+}
+
+pub fn value(value: &Value) -> String {
+    match value {
+        Value::Literal(Literal::String(s)) => {
+            let mut quoted = String::new();
+            repr::string(&mut quoted, s);
+            quoted
+        }
+        other => other.to_string(),
+    }
+}
+
+fn list(exprs: &[Expr]) -> String {
+    exprs.iter().map(expr).collect::<Vec<_>>().join(", ")
+}
+
+pub fn expr(expr: &Expr) -> String {
+    match &expr.kind {
+        ExprKind::Assign { name, value } => format!("{} = {}", name, self::expr(value)),
+        ExprKind::Binary { left, op, right } | ExprKind::Logical { left, op, right } => {
+            format!(
+                "{} {} {}",
+                self::expr(left),
+                operator(op.kind),
+                self::expr(right)
+            )
+        }
+        ExprKind::Conditional {
+            cond,
+            then_branch,
+            else_branch,
+        } => format!(
+            "{} ? {} : {}",
+            self::expr(cond),
+            self::expr(then_branch),
+            self::expr(else_branch)
+        ),
+        ExprKind::Call { callee, args } => format!("{}({})", self::expr(callee), list(args)),
+        ExprKind::GetIndex { obj, index } => format!("{}[{}]", self::expr(obj), self::expr(index)),
+        ExprKind::Spread { value } => format!("...{}", self::expr(value)),
+        ExprKind::Slice { obj, start, end } => {
+            let bound = |bound: &Option<Box<Expr>>| bound.as_deref().map(self::expr);
+            format!(
+                "{}[{}:{}]",
+                self::expr(obj),
+                bound(start).unwrap_or_default(),
+                bound(end).unwrap_or_default()
+            )
+        }
+        ExprKind::SetIndex { obj, index, value } => format!(
+            "{}[{}] = {}",
+            self::expr(obj),
+            self::expr(index),
+            self::expr(value)
+        ),
+        ExprKind::List { items } => format!("[{}]", list(items)),
+        ExprKind::Dict { items } => {
+            let items: Vec<_> = items
+                .iter()
+                .map(|(key, value)| format!("{}: {}", self::expr(key), self::expr(value)))
+                .collect();
+            format!("{{{}}}", items.join(", "))
+        }
+        ExprKind::SetLiteral { items } => format!("#{{{}}}", list(items)),
+        ExprKind::Get { obj, name } => format!("{}.{}", self::expr(obj), name),
+        ExprKind::This => "this".to_string(),
+        ExprKind::Super { method } => format!("super.{}", method),
+        ExprKind::Grouping { value } => format!("({})", self::expr(value)),
+        ExprKind::Literal { value } => literal(value),
+        ExprKind::Set { obj, name, value } => {
+            format!("{}.{} = {}", self::expr(obj), name, self::expr(value))
+        }
+        ExprKind::Unary { op, right } => format!("{}{}", operator(op.kind), self::expr(right)),
+        ExprKind::Variable { name } => name.to_string(),
+        ExprKind::Quote { body } => format!("quote {}", block(body)),
+        ExprKind::Unquote { value } => format!("unquote({})", self::expr(value)),
+        ExprKind::Match { scrutinee, arms } => {
+            let arms: Vec<_> = arms
+                .iter()
+                .map(|arm| format!("{} => {}", pattern(&arm.pattern), self::expr(&arm.body)))
+                .collect();
+            format!(
+                "match ({}) {{ {} }}",
+                self::expr(scrutinee),
+                arms.join(", ")
+            )
+        }
+    }
+}
+
+// floats with all their digits and their precision, and rationals as the
+// call that makes them, to read back the same
+fn literal(value: &Value) -> String {
+    match value {
+        Value::Literal(Literal::Float(_)) | Value::Exact(_) => {
+            repr::repr(value).unwrap_or_else(|_| value.to_string())
+        }
+        value => self::value(value),
+    }
+}
+
+fn pattern(pattern: &Pattern) -> String {
+    match &pattern.kind {
+        PatternKind::Wildcard => "_".to_string(),
+        PatternKind::Literal { value } => literal(value),
+        PatternKind::Binding { name } => name.to_string(),
+        PatternKind::List { items, rest } => {
+            let mut items: Vec<_> = items.iter().map(self::pattern).collect();
+            if let Some(rest) = rest {
+                items.push(format!("{}...", self::pattern(rest)));
+            }
+            format!("[{}]", items.join(", "))
+        }
+        PatternKind::Dict { entries } => {
+            let entries: Vec<_> = entries
+                .iter()
+                .map(|(key, value)| format!("{}: {}", literal(key), self::pattern(value)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+    }
+}
+
+// each followed by a space
+fn annotations(annotations: &[Annotation]) -> String {
+    let mut output = String::new();
+    for annotation in annotations {
+        output.push('@');
+        output.push_str(&annotation.name);
+        if !annotation.args.is_empty() {
+            let args: Vec<_> = annotation.args.iter().map(literal).collect();
+            let _ = write!(output, "({})", args.join(", "));
+        }
+        output.push(' ');
+    }
+    output
+}
+
+fn condition(binding: &Option<Rc<str>>, cond: &Expr) -> String {
+    match binding {
+        Some(name) => format!("(let {} = {})", name, expr(cond)),
+        None => format!("({})", expr(cond)),
+    }
+}
+
+fn block(statements: &[Stmt]) -> String {
+    let mut output = String::from("{");
+    for statement in statements {
+        let _ = write!(output, " {}", stmt(statement));
+    }
+    output.push_str(" }");
+    output
+}
+
+pub fn stmt(stmt: &Stmt) -> String {
+    match &stmt.kind {
+        StmtKind::Block { statements } => block(statements),
+        StmtKind::Expression { expr } => format!("{};", self::expr(expr)),
+        StmtKind::Throw { value } => format!("throw {};", self::expr(value)),
+        StmtKind::Try {
+            body,
+            name,
+            handler,
+        } => format!(
+            "try {} catch ({}) {}",
+            self::stmt(body),
+            name,
+            self::stmt(handler)
+        ),
+        StmtKind::Function {
+            name,
+            params,
+            body,
+            annotations,
+        } => format!(
+            "{}fn {}({}) {}",
+            self::annotations(annotations),
+            name,
+            params.join(", "),
+            block(body)
+        ),
+        // methods are functions without the `fn`, and accessors have `get` or
+        // `set` instead
+        StmtKind::Class {
+            name,
+            superclass,
+            methods,
+            getters,
+            setters,
+        } => {
+            let mut output = format!("class {}", name);
+            if let Some(superclass) = superclass {
+                let _ = write!(output, " < {}", superclass);
+            }
+            output.push_str(" {");
+            let functions = methods
+                .iter()
+                .map(|method| ("", method))
+                .chain(getters.iter().map(|getter| ("get ", getter)))
+                .chain(setters.iter().map(|setter| ("set ", setter)));
+            for (prefix, function) in functions {
+                let function = self::stmt(function);
+                let function = function.strip_prefix("fn ").unwrap_or(&function);
+                let _ = write!(output, " {}{}", prefix, function);
+            }
+            output.push_str(" }");
+            output
+        }
+        StmtKind::ForIn {
+            name,
+            value,
+            iterable,
+            body,
+        } => format!(
+            "for ({}{} in {}) {}",
+            name,
+            value
+                .as_ref()
+                .map_or(String::new(), |value| format!(", {}", value)),
+            self::expr(iterable),
+            self::stmt(body)
+        ),
+        StmtKind::If {
+            binding,
+            cond,
+            then_branch,
+            else_branch,
+        } => {
+            let mut output = format!(
+                "if {} {}",
+                condition(binding, cond),
+                self::stmt(then_branch)
+            );
+            if let Some(else_branch) = else_branch {
+                let _ = write!(output, " else {}", self::stmt(else_branch));
+            }
+            output
+        }
+        StmtKind::Return { expr: Some(expr) } => format!("return {};", self::expr(expr)),
+        StmtKind::Return { expr: None } => "return;".to_string(),
+        StmtKind::Let {
+            name,
+            initializer: Some(initializer),
+            annotations,
+        } => format!(
+            "{}let {} = {};",
+            self::annotations(annotations),
+            name,
+            self::expr(initializer)
+        ),
+        StmtKind::Let {
+            name,
+            initializer: None,
+            annotations,
+        } => format!("{}let {};", self::annotations(annotations), name),
+        StmtKind::NativeImport { path } => format!("import native \"{}\";", path),
+        StmtKind::While {
+            binding,
+            cond,
+            body,
+        } => format!("while {} {}", condition(binding, cond), self::stmt(body)),
+    }
+}
@@ -0,0 +1,76 @@
+use std::{
+    fmt::Display,
+    io::{self, BufReader, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use crate::file::read_line;
+
+// A child process whose stdin and stdout are pipes held by the script; its
+// stderr goes wherever the interpreter's does.
+#[derive(Debug)]
+pub struct Process {
+    program: String,
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Process {
+    pub fn spawn(program: &str, args: &[String]) -> io::Result<Self> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take();
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        Ok(Self {
+            program: program.to_string(),
+            child,
+            stdin,
+            stdout,
+        })
+    }
+
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    pub fn write_stdin(&mut self, data: &[u8]) -> io::Result<()> {
+        match &mut self.stdin {
+            Some(stdin) => stdin.write_all(data).and_then(|()| stdin.flush()),
+            None => Err(io::Error::new(io::ErrorKind::BrokenPipe, "stdin is closed")),
+        }
+    }
+
+    // lets the child see the end of its input
+    pub fn close_stdin(&mut self) {
+        self.stdin = None;
+    }
+
+    // the next line without its line ending, or None at the end of the output
+    pub fn read_stdout_line(&mut self) -> io::Result<Option<String>> {
+        read_line(&mut self.stdout)
+    }
+
+    // closes stdin first so children reading until the end of their input
+    // can finish; None when the child was killed by a signal
+    pub fn wait(&mut self) -> io::Result<Option<i32>> {
+        self.close_stdin();
+        Ok(self.child.wait()?.code())
+    }
+
+    pub fn kill(&mut self) -> io::Result<()> {
+        match self.child.try_wait()? {
+            Some(_) => Ok(()),
+            None => self.child.kill(),
+        }
+    }
+}
+
+impl Display for Process {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(process {} {})", self.program, self.child.id())
+    }
+}
@@ -0,0 +1,96 @@
+use std::{
+    fmt::Display,
+    io::{IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+const WIDTH: u64 = 30;
+const REDRAW_INTERVAL: Duration = Duration::from_millis(50);
+
+// A progress bar drawn on stderr. When stderr isn't a terminal nothing is
+// drawn while ticking, and `finish` writes a single summary line.
+#[derive(Debug)]
+pub struct Progress {
+    total: u64,
+    current: u64,
+    started: Instant,
+    drawn: Option<Instant>,
+    terminal: bool,
+    finished: bool,
+}
+
+impl Progress {
+    pub fn new(total: u64) -> Self {
+        Self {
+            total,
+            current: 0,
+            started: Instant::now(),
+            drawn: None,
+            terminal: std::io::stderr().is_terminal(),
+            finished: false,
+        }
+    }
+
+    pub fn tick(&mut self, steps: u64) {
+        if self.finished {
+            return;
+        }
+        self.current = self.current.saturating_add(steps).min(self.total);
+        let due = match self.drawn {
+            Some(drawn) => drawn.elapsed() >= REDRAW_INTERVAL || self.current == self.total,
+            None => true,
+        };
+        if self.terminal && due {
+            self.draw();
+        }
+    }
+
+    pub fn finish(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        match self.terminal {
+            true => {
+                self.draw();
+                eprintln!();
+            }
+            false => eprintln!(
+                "{}/{} in {:.1?}",
+                self.current,
+                self.total,
+                self.started.elapsed()
+            ),
+        }
+    }
+
+    fn draw(&mut self) {
+        let filled = match self.total {
+            0 => WIDTH,
+            total => self.current * WIDTH / total,
+        };
+        let percent = match self.total {
+            0 => 100,
+            total => self.current * 100 / total,
+        };
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(
+            stderr,
+            "\r[{}{}] {}/{} {:>3}% {:.1?}",
+            "#".repeat(filled as usize),
+            ".".repeat((WIDTH - filled) as usize),
+            self.current,
+            self.total,
+            percent,
+            self.started.elapsed()
+        );
+        let _ = stderr.flush();
+        self.drawn = Some(Instant::now());
+    }
+}
+
+impl Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(progress {}/{})", self.current, self.total)
+    }
+}
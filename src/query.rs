@@ -0,0 +1,359 @@
+use rug::{Float, Integer};
+
+use crate::{
+    dict::Dict,
+    lexer::DEFAULT_FLOAT_PRECISION,
+    value::{Value, ValueKey},
+};
+
+// A small jq for `query(value, "...")`: filters joined by `|`, each one
+// turning every value the one before it gave into any number of values.
+//
+//     .                the value itself
+//     .name  ."name"   the entry of a dict, nil when it's missing
+//     .[n]             an item of a list, from the end when negative
+//     .[]              every item of a list, or every value of a dict
+//     select(cond)     the value, when `cond` holds for it
+//
+// Steps chain, as in `.users[0].name`. A condition compares two operands,
+// paths from the value or literals (numbers, strings, true, false, null),
+// with == != < <= > >=, or is one operand that must be truthy; conditions
+// join with `and` and `or`, `and` first. Strings are in double or single
+// quotes, the latter being easier to write inside of an xi string.
+
+#[derive(Debug)]
+enum Step {
+    Key(String),
+    Index(i64),
+    Each,
+}
+
+#[derive(Debug)]
+enum Operand {
+    Path(Vec<Step>),
+    Literal(Value),
+}
+
+#[derive(Debug)]
+enum Cond {
+    Test(Operand),
+    Compare(Operand, &'static str, Operand),
+    And(Box<Cond>, Box<Cond>),
+    Or(Box<Cond>, Box<Cond>),
+}
+
+#[derive(Debug)]
+enum Filter {
+    Path(Vec<Step>),
+    Select(Cond),
+}
+
+#[derive(Debug)]
+pub struct Query(Vec<Filter>);
+
+// longest first, so that `<=` wins over `<`
+const COMPARISONS: [&str; 6] = ["==", "!=", "<=", ">=", "<", ">"];
+
+impl Query {
+    pub fn parse(source: &str) -> Result<Query, String> {
+        let mut parser = Parser {
+            source,
+            position: 0,
+        };
+        let mut filters = vec![parser.filter()?];
+        while parser.eat("|") {
+            filters.push(parser.filter()?);
+        }
+        parser.whitespace();
+        match parser.position == source.len() {
+            true => Ok(Query(filters)),
+            false => Err(parser.error("expected `|`")),
+        }
+    }
+
+    // every value the last filter gave, in order
+    pub fn run(&self, value: Value) -> Result<Vec<Value>, String> {
+        let mut values = vec![value];
+        for filter in &self.0 {
+            let mut next = Vec::new();
+            for value in values {
+                match filter {
+                    Filter::Path(steps) => next.extend(path(steps, value)?),
+                    Filter::Select(cond) => {
+                        if test(cond, &value)? {
+                            next.push(value);
+                        }
+                    }
+                }
+            }
+            values = next;
+        }
+        Ok(values)
+    }
+}
+
+fn path(steps: &[Step], value: Value) -> Result<Vec<Value>, String> {
+    let mut values = vec![value];
+    for step in steps {
+        let mut next = Vec::new();
+        for value in values {
+            match (step, value) {
+                (_, Value::Nil) => next.push(Value::Nil),
+                (Step::Key(key), Value::Dict(dict)) => next.push(entry(&dict.borrow(), key)),
+                (Step::Index(index), Value::List(list)) => {
+                    let list = list.borrow();
+                    let index = match *index < 0 {
+                        true => list.0.len().checked_sub(index.unsigned_abs() as usize),
+                        false => Some(*index as usize),
+                    };
+                    let item = index.and_then(|index| list.0.get(index));
+                    next.push(item.cloned().unwrap_or(Value::Nil));
+                }
+                (Step::Each, Value::List(list)) => next.extend(list.borrow().0.iter().cloned()),
+                (Step::Each, Value::Dict(dict)) => next.extend(dict.borrow().0.values().cloned()),
+                (Step::Key(key), other) => {
+                    return Err(format!("cannot take .{} of a {}", key, other.type_name()))
+                }
+                (Step::Index(index), other) => {
+                    return Err(format!(
+                        "cannot take .[{}] of a {}",
+                        index,
+                        other.type_name()
+                    ))
+                }
+                (Step::Each, other) => {
+                    return Err(format!("cannot take .[] of a {}", other.type_name()))
+                }
+            }
+        }
+        values = next;
+    }
+    Ok(values)
+}
+
+fn entry(dict: &Dict, key: &str) -> Value {
+    let key = ValueKey::try_from(Value::from(key)).unwrap();
+    dict.0.get(&key).cloned().unwrap_or(Value::Nil)
+}
+
+// the first value of a path, or nil when it gives none
+fn operand(operand: &Operand, value: &Value) -> Result<Value, String> {
+    match operand {
+        Operand::Path(steps) => Ok(path(steps, value.clone())?
+            .into_iter()
+            .next()
+            .unwrap_or(Value::Nil)),
+        Operand::Literal(literal) => Ok(literal.clone()),
+    }
+}
+
+fn test(cond: &Cond, value: &Value) -> Result<bool, String> {
+    Ok(match cond {
+        Cond::Test(a) => operand(a, value)?.is_truthy(),
+        Cond::Compare(a, op, b) => {
+            let (a, b) = (operand(a, value)?, operand(b, value)?);
+            match *op {
+                "==" => a == b,
+                "!=" => a != b,
+                "<" => a < b,
+                "<=" => a <= b,
+                ">" => a > b,
+                _ => a >= b,
+            }
+        }
+        Cond::And(a, b) => test(a, value)? && test(b, value)?,
+        Cond::Or(a, b) => test(a, value)? || test(b, value)?,
+    })
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("{} at offset {}", message, self.position)
+    }
+
+    fn rest(&self) -> &str {
+        &self.source[self.position..]
+    }
+
+    fn whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    // skips `token` when it comes next, after any whitespace
+    fn eat(&mut self, token: &str) -> bool {
+        self.whitespace();
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        match self.eat(token) {
+            true => Ok(()),
+            false => Err(self.error(&format!("expected `{}`", token))),
+        }
+    }
+
+    // the longest prefix of what's left whose characters match
+    fn take(&mut self, accept: impl Fn(char) -> bool) -> &str {
+        let rest = &self.source[self.position..];
+        let length = rest.find(|c| !accept(c)).unwrap_or(rest.len());
+        self.position += length;
+        &rest[..length]
+    }
+
+    fn identifier(&mut self) -> String {
+        self.take(|c| c.is_ascii_alphanumeric() || c == '_')
+            .to_string()
+    }
+
+    fn filter(&mut self) -> Result<Filter, String> {
+        if self.eat("select") {
+            self.expect("(")?;
+            let cond = self.or()?;
+            self.expect(")")?;
+            return Ok(Filter::Select(cond));
+        }
+        Ok(Filter::Path(self.path()?))
+    }
+
+    fn path(&mut self) -> Result<Vec<Step>, String> {
+        self.expect(".")?;
+        let mut steps = Vec::new();
+        // the name right after the first `.`, if any
+        if let Some(step) = self.key()? {
+            steps.push(step);
+        }
+        loop {
+            if self.rest().starts_with('[') {
+                self.position += 1;
+                steps.push(self.index()?);
+            } else if self.rest().starts_with('.') {
+                self.position += 1;
+                match self.key()? {
+                    Some(step) => steps.push(step),
+                    None if self.rest().starts_with('[') => {}
+                    None => return Err(self.error("expected a name after `.`")),
+                }
+            } else {
+                return Ok(steps);
+            }
+        }
+    }
+
+    fn key(&mut self) -> Result<Option<Step>, String> {
+        if self.rest().starts_with(['"', '\'']) {
+            return Ok(Some(Step::Key(self.string()?)));
+        }
+        let name = self.identifier();
+        Ok((!name.is_empty()).then_some(Step::Key(name)))
+    }
+
+    // what's between `[` and `]`
+    fn index(&mut self) -> Result<Step, String> {
+        if self.eat("]") {
+            return Ok(Step::Each);
+        }
+        self.whitespace();
+        let step = match self.rest().starts_with(['"', '\'']) {
+            true => Step::Key(self.string()?),
+            false => {
+                let start = self.position;
+                self.eat("-");
+                self.take(|c| c.is_ascii_digit());
+                let index = self.source[start..self.position]
+                    .parse()
+                    .map_err(|_| self.error("expected an index"))?;
+                Step::Index(index)
+            }
+        };
+        self.expect("]")?;
+        Ok(step)
+    }
+
+    // what's between two of the quotes it starts with
+    fn string(&mut self) -> Result<String, String> {
+        let quote = &self.source[self.position..self.position + 1];
+        self.position += 1;
+        let string = self.take(|c| !quote.starts_with(c)).to_string();
+        self.expect(quote)?;
+        Ok(string)
+    }
+
+    fn or(&mut self) -> Result<Cond, String> {
+        let mut cond = self.and()?;
+        while self.eat("or") {
+            cond = Cond::Or(Box::new(cond), Box::new(self.and()?));
+        }
+        Ok(cond)
+    }
+
+    fn and(&mut self) -> Result<Cond, String> {
+        let mut cond = self.comparison()?;
+        while self.eat("and") {
+            cond = Cond::And(Box::new(cond), Box::new(self.comparison()?));
+        }
+        Ok(cond)
+    }
+
+    fn comparison(&mut self) -> Result<Cond, String> {
+        let left = self.operand()?;
+        for op in COMPARISONS {
+            if self.eat(op) {
+                return Ok(Cond::Compare(left, op, self.operand()?));
+            }
+        }
+        Ok(Cond::Test(left))
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        self.whitespace();
+        let rest = self.rest();
+        if rest.starts_with('.') {
+            return Ok(Operand::Path(self.path()?));
+        }
+        if rest.starts_with(['"', '\'']) {
+            return Ok(Operand::Literal(self.string()?.into()));
+        }
+        for (word, value) in [
+            ("true", Value::True),
+            ("false", Value::False),
+            ("null", Value::Nil),
+        ] {
+            if rest.starts_with(word) {
+                self.position += word.len();
+                return Ok(Operand::Literal(value));
+            }
+        }
+
+        let start = self.position;
+        self.eat("-");
+        self.take(|c| c.is_ascii_digit() || c == '.');
+        let number = &self.source[start..self.position];
+        if let Ok(integer) = number.parse::<Integer>() {
+            return Ok(Operand::Literal(integer.into()));
+        }
+        match Float::parse(number) {
+            Ok(float) => Ok(Operand::Literal(
+                Float::with_val(DEFAULT_FLOAT_PRECISION, float).into(),
+            )),
+            Err(_) => {
+                self.position = start;
+                Err(self.error("expected a path, a string, a number, true, false or null"))
+            }
+        }
+    }
+}
+
+// a list of what the query gave
+pub fn query(value: Value, source: &str) -> Result<Value, String> {
+    Ok(Query::parse(source)?.run(value)?.into())
+}
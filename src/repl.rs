@@ -0,0 +1,139 @@
+use std::{env, fmt::Write, path::PathBuf};
+
+use crate::datetime::DateTime;
+
+// How the REPL greets and prompts: read from a config file of `key = value`
+// lines, then overridden by the command line flags. The prompt and the
+// banner are templates, see `expand`.
+
+pub const DEFAULT_PROMPT: &str = "ix >> ";
+pub const DEFAULT_BANNER: &str = "xi {version}, :type and :time inspect an expression";
+
+#[derive(Clone, Debug)]
+pub struct ReplConfig {
+    pub prompt: String,
+    pub banner: String,
+    // no banner at startup
+    pub quiet: bool,
+}
+
+impl Default for ReplConfig {
+    fn default() -> Self {
+        Self {
+            prompt: DEFAULT_PROMPT.to_string(),
+            banner: DEFAULT_BANNER.to_string(),
+            quiet: false,
+        }
+    }
+}
+
+// $XDG_CONFIG_HOME/xi/repl.conf, or ~/.config/xi/repl.conf
+pub fn default_path() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME") {
+        Some(base) if !base.is_empty() => PathBuf::from(base),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("xi").join("repl.conf"))
+}
+
+impl ReplConfig {
+    // `prompt = "xi {n}> "`, `banner = ...` and `quiet = true`, one per line;
+    // `#` starts a comment line, and values keep their surrounding spaces
+    // when quoted
+    pub fn load(&mut self, source: &str) -> Result<(), String> {
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", index + 1))?;
+            let value = value.trim();
+            let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                Some(quoted) => quoted,
+                None => value,
+            };
+            match key.trim() {
+                "prompt" => self.prompt = value.to_string(),
+                "banner" => self.banner = value.to_string(),
+                "quiet" => {
+                    self.quiet = value.parse().map_err(|_| {
+                        format!(
+                            "line {}: `quiet` is true or false, found `{}`",
+                            index + 1,
+                            value
+                        )
+                    })?
+                }
+                key => return Err(format!("line {}: unknown key `{}`", index + 1, key)),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// the current directory, with the home directory as `~`
+fn dir() -> String {
+    let Ok(dir) = env::current_dir() else {
+        return "?".to_string();
+    };
+    if let Some(home) = env::var_os("HOME").filter(|home| !home.is_empty()) {
+        if let Ok(rest) = dir.strip_prefix(PathBuf::from(home)) {
+            return match rest.as_os_str().is_empty() {
+                true => "~".to_string(),
+                false => format!("~/{}", rest.display()),
+            };
+        }
+    }
+    dir.display().to_string()
+}
+
+// `template` with its placeholders replaced: {n} the number of the input
+// line, {time} the local time as HH:MM:SS, {dir} the current directory,
+// {version} xi's version and {env:NAME} the environment variable NAME (empty
+// when unset). `{{` and `}}` are literal braces.
+pub fn expand(template: &str, line: usize) -> Result<String, String> {
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(index) = rest.find(['{', '}']) {
+        output.push_str(&rest[..index]);
+        let brace = &rest[index..];
+        if let Some(after) = brace.strip_prefix("{{") {
+            output.push('{');
+            rest = after;
+            continue;
+        }
+        if let Some(after) = brace.strip_prefix("}}") {
+            output.push('}');
+            rest = after;
+            continue;
+        }
+        if brace.starts_with('}') {
+            return Err("unmatched `}`, write `}}` for a brace".to_string());
+        }
+
+        let end = brace
+            .find('}')
+            .ok_or_else(|| "unclosed `{`, write `{{` for a brace".to_string())?;
+        let _ = match &brace[1..end] {
+            "n" => write!(output, "{}", line),
+            "time" => {
+                let now = DateTime::now().in_zone("local")?;
+                write!(output, "{}", now.format("%H:%M:%S")?)
+            }
+            "dir" => write!(output, "{}", dir()),
+            "version" => write!(output, "{}", env!("CARGO_PKG_VERSION")),
+            name => match name.strip_prefix("env:") {
+                Some(var) => write!(output, "{}", env::var(var).unwrap_or_default()),
+                None => return Err(format!("unknown placeholder `{{{}}}`", name)),
+            },
+        };
+        rest = &brace[end + 1..];
+    }
+    output.push_str(rest);
+
+    Ok(output)
+}
@@ -1,9 +1,70 @@
-use miette::{Diagnostic, SourceSpan};
+use std::fmt::Display;
+
+use miette::{Diagnostic, LabeledSpan, Severity, SourceSpan};
 use thiserror::Error;
 
+use crate::json::Json;
+
+// 1-based line and column (in chars) of a byte offset into `source`
+pub fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = source.get(..offset).unwrap_or(source);
+    let line = before.matches('\n').count() + 1;
+    let column = match before.rfind('\n') {
+        Some(newline) => before[newline + 1..].chars().count() + 1,
+        None => before.chars().count() + 1,
+    };
+    (line, column)
+}
+
+pub fn to_json(diagnostic: &dyn Diagnostic, source: &str) -> Json {
+    let severity = match diagnostic.severity().unwrap_or(Severity::Error) {
+        Severity::Advice => "advice",
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    };
+
+    let spans = diagnostic
+        .labels()
+        .into_iter()
+        .flatten()
+        .map(|label| {
+            let (line, column) = line_column(source, label.offset());
+            Json::Object(vec![
+                ("offset".into(), label.offset().into()),
+                ("length".into(), label.len().into()),
+                ("line".into(), line.into()),
+                ("column".into(), column.into()),
+                ("label".into(), label.label().into()),
+            ])
+        })
+        .collect();
+
+    let related = diagnostic
+        .related()
+        .into_iter()
+        .flatten()
+        .map(|related| to_json(related, source))
+        .collect();
+
+    Json::Object(vec![
+        (
+            "code".into(),
+            diagnostic.code().map(|c| c.to_string()).into(),
+        ),
+        ("severity".into(), severity.into()),
+        ("message".into(), diagnostic.to_string().into()),
+        (
+            "help".into(),
+            diagnostic.help().map(|h| h.to_string()).into(),
+        ),
+        ("spans".into(), Json::Array(spans)),
+        ("related".into(), Json::Array(related)),
+    ])
+}
+
 #[derive(Error, Debug, Diagnostic)]
 #[error("unexpected character")]
-#[diagnostic(code(ix::lexer::unexpected_char))]
+#[diagnostic(code(E0001))]
 pub struct UnexpectedCharacter {
     #[label("here")]
     pub span: SourceSpan,
@@ -11,7 +72,7 @@ pub struct UnexpectedCharacter {
 
 #[derive(Error, Debug, Diagnostic)]
 #[error("malformed float precision")]
-#[diagnostic(code(ix::lexer::malformed_precision))]
+#[diagnostic(code(E0002))]
 pub struct MalformedFloatPrecision {
     #[label("here")]
     pub span: SourceSpan,
@@ -19,7 +80,7 @@ pub struct MalformedFloatPrecision {
 
 #[derive(Error, Debug, Diagnostic)]
 #[error("malformed number")]
-#[diagnostic(code(ix::lexer::malformed_number))]
+#[diagnostic(code(E0003))]
 pub struct MalformedNumber {
     #[label("here")]
     pub span: SourceSpan,
@@ -27,15 +88,45 @@ pub struct MalformedNumber {
 
 #[derive(Error, Debug, Diagnostic)]
 #[error("unterminated sequence")]
-#[diagnostic(code(ix::lexer::unterminated_sequence))]
+#[diagnostic(code(E0004))]
 pub struct UnterminatedSequence {
     #[label("here")]
     pub span: SourceSpan,
 }
 
+#[derive(Error, Debug, Diagnostic)]
+#[error("invalid escape sequence")]
+#[diagnostic(code(E0005))]
+pub struct InvalidEscape {
+    #[label("here")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("unknown number suffix `{suffix}`")]
+#[diagnostic(code(E0006))]
+pub struct UnknownNumberSuffix {
+    #[label("here")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+    pub suffix: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("literal out of range for `{kind}`")]
+#[diagnostic(code(E0007))]
+pub struct LiteralOutOfRange {
+    #[label("`{kind}` goes from {min} to {max}")]
+    pub span: SourceSpan,
+    pub kind: String,
+    pub min: i128,
+    pub max: i128,
+}
+
 #[derive(Error, Debug, Diagnostic)]
 #[error("unexpected token")]
-#[diagnostic(code(ix::parser::unexpected_token))]
+#[diagnostic(code(E0100))]
 pub struct UnexpectedToken {
     #[label("here")]
     pub span: SourceSpan,
@@ -43,9 +134,17 @@ pub struct UnexpectedToken {
     pub help: String,
 }
 
+#[derive(Error, Debug, Diagnostic)]
+#[error("expression nested too deeply")]
+#[diagnostic(code(E0101))]
+pub struct NestingTooDeep {
+    #[label("here")]
+    pub span: SourceSpan,
+}
+
 #[derive(Error, Debug, Diagnostic)]
 #[error("unexpected eof")]
-#[diagnostic(code(ix::lexer::unexpected_eof))]
+#[diagnostic(code(E0102))]
 pub struct UnexpectedEof {
     #[label("here")]
     pub span: SourceSpan,
@@ -53,31 +152,61 @@ pub struct UnexpectedEof {
 
 #[derive(Error, Debug, Diagnostic)]
 #[error("expected token")]
-#[diagnostic(code(ix::lexer::expected_token))]
+#[diagnostic(code(E0103))]
 pub struct ExpectedToken {
     #[label("here")]
     pub span: SourceSpan,
 }
 
+#[derive(Error, Debug, Diagnostic)]
+#[error("invalid annotation")]
+#[diagnostic(code(E0108))]
+pub struct InvalidAnnotation {
+    #[label("here")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+}
+
 #[derive(Error, Debug, Diagnostic)]
 #[error("unsupported operation")]
-#[diagnostic(code(ix::interpreter::unsupported_operation))]
+#[diagnostic(code(E0300))]
 pub struct UnsupportedOperation {
     #[label("here")]
     pub span: SourceSpan,
 }
 
+#[derive(Error, Debug, Diagnostic)]
+#[error("division by zero")]
+#[diagnostic(code(E0301))]
+pub struct DivisionByZero {
+    #[label("here")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error(
+    "this value can't be hashed: only nil, booleans, strings, integers, bytes, durations, datetimes, machine integers and functions can be dict keys or set items"
+)]
+#[diagnostic(code(E0302))]
+pub struct UnhashableKey {
+    #[label("here")]
+    pub span: SourceSpan,
+}
+
 #[derive(Error, Debug, Diagnostic)]
 #[error("undefined value")]
-#[diagnostic(code(ix::env::undefined_value))]
+#[diagnostic(code(E0303))]
 pub struct UndefinedValue {
     #[label("here")]
     pub span: SourceSpan,
+    #[help]
+    pub help: Option<String>,
 }
 
 #[derive(Error, Debug, Diagnostic)]
 #[error("undefined property")]
-#[diagnostic(code(ix::interpreter::undefined_property))]
+#[diagnostic(code(E0304))]
 pub struct UndefinedProperty {
     #[label("here")]
     pub span: SourceSpan,
@@ -85,15 +214,45 @@ pub struct UndefinedProperty {
 
 #[derive(Error, Debug, Diagnostic)]
 #[error("invalid assignment target")]
-#[diagnostic(code(ix::interpreter::invalid_assignment_target))]
+#[diagnostic(code(E0104))]
 pub struct InvalidAssignmentTarget {
     #[label("here")]
     pub span: SourceSpan,
 }
 
+#[derive(Error, Debug, Diagnostic)]
+#[error("`else` without a matching `if`")]
+#[diagnostic(
+    code(E0105),
+    help("an `else` must directly follow the statement or block of an `if`")
+)]
+pub struct ElseWithoutIf {
+    #[label("this `else`")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("missing semicolon")]
+#[diagnostic(code(E0106), help("statements end with `;`, the last one too"))]
+pub struct MissingSemicolon {
+    #[label("add `;` after this")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("`unquote` outside of `quote`")]
+#[diagnostic(
+    code(E0107),
+    help("`unquote` splices a value into the tree a `quote` builds")
+)]
+pub struct UnquoteOutsideQuote {
+    #[label("here")]
+    pub span: SourceSpan,
+}
+
 #[derive(Error, Debug, Diagnostic)]
 #[error("only functions and classes can be called")]
-#[diagnostic(code(ix::interpreter::callee_type_error))]
+#[diagnostic(code(E0305))]
 pub struct CalleeTypeError {
     #[label("here")]
     pub span: SourceSpan,
@@ -101,31 +260,31 @@ pub struct CalleeTypeError {
 
 #[derive(Error, Debug, Diagnostic)]
 #[error("only instances have properties")]
-#[diagnostic(code(ix::interpreter::instance_type_error))]
+#[diagnostic(code(E0306))]
 pub struct InstanceTypeError {
     #[label("here")]
     pub span: SourceSpan,
 }
 
 #[derive(Error, Debug, Diagnostic)]
-#[error("only lists and dicts can be indexed")]
-#[diagnostic(code(ix::interpreter::list_type_error))]
+#[error("only lists, dicts, strings and bytes can be indexed")]
+#[diagnostic(code(E0307))]
 pub struct IndexTypeError {
     #[label("here")]
     pub span: SourceSpan,
 }
 
 #[derive(Error, Debug, Diagnostic)]
-#[error("lists can only be indexed with integers up to `usize`")]
-#[diagnostic(code(ix::interpreter::invalid_index_error))]
+#[error("lists, strings and bytes can only be indexed with integers up to `usize`")]
+#[diagnostic(code(E0308))]
 pub struct ListIndexInvalidError {
     #[label("here")]
     pub span: SourceSpan,
 }
 
 #[derive(Error, Debug, Diagnostic)]
-#[error("dict key does not exist")]
-#[diagnostic(code(ix::interpreter::invalid_index_error))]
+#[error("index out of bounds")]
+#[diagnostic(code(E0309))]
 pub struct ListIndexOutOfBoundsError {
     #[label("here")]
     pub span: SourceSpan,
@@ -133,16 +292,315 @@ pub struct ListIndexOutOfBoundsError {
 
 #[derive(Error, Debug, Diagnostic)]
 #[error("dict key does not exist")]
-#[diagnostic(code(ix::interpreter::invalid_index_error))]
+#[diagnostic(code(E0310))]
 pub struct DictKeyError {
     #[label("here")]
     pub span: SourceSpan,
 }
 
+#[derive(Error, Debug, Diagnostic)]
+#[error("native imports are disabled")]
+#[diagnostic(
+    code(E0311),
+    help("run with `--allow-native` to load native extensions")
+)]
+pub struct NativeImportDenied {
+    #[label("here")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("{message}")]
+#[diagnostic(code(E0312))]
+pub struct NativeLoadError {
+    #[label("here")]
+    pub span: SourceSpan,
+    pub message: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("native extension ABI mismatch")]
+#[diagnostic(code(E0313))]
+pub struct NativeAbiMismatch {
+    #[label("here")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("native function `{name}`: {message}")]
+#[diagnostic(code(E0314))]
+pub struct NativeCallError {
+    pub name: String,
+    pub message: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("wrong number of arguments")]
+#[diagnostic(code(E0315))]
+pub struct ArgumentCountError {
+    #[label("here")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("argument {position} of `{name}` must be {expected}, found {found}")]
+#[diagnostic(code(E0316))]
+pub struct ArgumentTypeError {
+    pub name: String,
+    pub position: usize,
+    pub expected: &'static str,
+    pub found: &'static str,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("value is not iterable")]
+#[diagnostic(code(E0317))]
+pub struct NotIterable {
+    #[label("here")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("template has no value for `{key}`")]
+#[diagnostic(code(E0318))]
+pub struct TemplateKeyError {
+    pub key: String,
+    #[label("this placeholder")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("malformed template: {problem}")]
+#[diagnostic(code(E0319))]
+pub struct TemplateSyntaxError {
+    pub problem: &'static str,
+    #[label("here")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("{message}")]
+#[diagnostic(code(E0320))]
+pub struct IoError {
+    pub message: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("invalid argument to `{name}`: {message}")]
+#[diagnostic(code(E0321))]
+pub struct InvalidArgument {
+    pub name: String,
+    pub message: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("integer overflow")]
+#[diagnostic(code(E0322))]
+pub struct IntegerOverflow {
+    #[label("the result doesn't fit in its type")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("assertion failed: {message}")]
+#[diagnostic(code(E0323))]
+pub struct AssertionFailed {
+    pub message: String,
+    #[help]
+    pub help: Option<String>,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("`{name}` is disabled")]
+#[diagnostic(
+    code(E0324),
+    help("run with `--allow-eval` to let scripts run code they build")
+)]
+pub struct EvalDenied {
+    pub name: &'static str,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("superclass is not a class")]
+#[diagnostic(code(E0327))]
+pub struct SuperclassTypeError {
+    #[label("inherits here")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("uncaught throw of {value}")]
+#[diagnostic(
+    code(E0328),
+    help("catch it with `try {{ ... }} catch (error) {{ ... }}`")
+)]
+pub struct UncaughtThrow {
+    #[label("thrown here")]
+    pub span: SourceSpan,
+    pub value: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("no arm matches the value")]
+#[diagnostic(code(E0329))]
+pub struct NoMatchingArm {
+    #[label("matched here")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("`{name}` only has a getter")]
+#[diagnostic(
+    code(E0330),
+    help("give the class a `set {name}(value) {{ ... }}` to set it")
+)]
+pub struct PropertyWithoutSetter {
+    #[label("set here")]
+    pub span: SourceSpan,
+    pub name: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("interrupted")]
+#[diagnostic(code(E0326))]
+pub struct Interrupted {
+    #[label("stopped here")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("item is not a pair")]
+#[diagnostic(code(E0325))]
+pub struct NotAPair {
+    #[label("iterated here")]
+    pub span: SourceSpan,
+    #[help]
+    pub help: String,
+}
+
 #[derive(Error, Debug, Diagnostic)]
 #[error("illegal to read local variable in its own initializer")]
-#[diagnostic(code(ix::resolver::read_local_variable_in_own_initializer))]
+#[diagnostic(code(E0200))]
 pub struct ReadLocalVariableInOwnInitializer {
     #[label("here")]
     pub span: SourceSpan,
 }
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("assignment to undeclared variable `{name}`")]
+#[diagnostic(code(E0201))]
+pub struct UndeclaredAssignment {
+    #[label("here")]
+    pub span: SourceSpan,
+    pub name: String,
+    #[help]
+    pub help: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("`this` outside of a class")]
+#[diagnostic(code(E0202))]
+pub struct ThisOutsideClass {
+    #[label("here")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("`super` outside of a class")]
+#[diagnostic(code(E0203))]
+pub struct SuperOutsideClass {
+    #[label("here")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("`super` in a class without a superclass")]
+#[diagnostic(
+    code(E0204),
+    help("a class has a superclass when it is declared as `class Name < Superclass`")
+)]
+pub struct SuperWithoutSuperclass {
+    #[label("here")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("class `{name}` inherits from itself")]
+#[diagnostic(code(E0205))]
+pub struct InheritsFromItself {
+    #[label("here")]
+    pub span: SourceSpan,
+    pub name: String,
+}
+
+// a lint: its severity depends on how it was configured
+#[derive(Error, Debug)]
+#[error("integer operand is rounded to the float's precision")]
+pub struct PrecisionLoss {
+    pub span: SourceSpan,
+    pub severity: Severity,
+    pub needed: u32,
+    pub precision: u32,
+}
+
+impl Diagnostic for PrecisionLoss {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new("W0300"))
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        Some(self.severity)
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(format!(
+            "the integer needs {} bits but the float only has {}; give the float a `_{}` precision suffix to keep it exact",
+            self.needed, self.precision, self.needed
+        )))
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(std::iter::once(LabeledSpan::new_with_span(
+            Some("here".to_string()),
+            self.span,
+        ))))
+    }
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("assignment used as a condition")]
+#[diagnostic(
+    code(W0301),
+    severity(Warning),
+    help(
+        "use `==` to compare; wrap the assignment in another pair of parentheses if it is intended"
+    )
+)]
+pub struct AssignmentInCondition {
+    #[label("this assigns to `{name}`")]
+    pub span: SourceSpan,
+    pub name: String,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("`{name}` is deprecated")]
+#[diagnostic(code(W0302), severity(Warning))]
+pub struct DeprecatedUse {
+    #[label("used here")]
+    pub span: SourceSpan,
+    pub name: String,
+    #[help]
+    pub reason: Option<String>,
+}
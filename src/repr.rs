@@ -0,0 +1,149 @@
+use std::fmt::Write;
+
+use crate::{lexer::DEFAULT_FLOAT_PRECISION, token::Literal, value::Value};
+
+// A value written as the source that evaluates back to it, the same way
+// every time: dict keys and set items are sorted, strings are quoted with
+// `\` escapes, floats have every digit needed to read them back at their
+// precision, machine integers their suffix, and exacts are made of `pi`,
+// `sqrt` and `rational`. Functions, iterators, processes and the other
+// handles have no source form and are written as they print.
+
+// deep enough for real data, and a stop for values that contain themselves
+const MAX_DEPTH: usize = 512;
+
+pub(crate) fn string(output: &mut String, s: &str) {
+    output.push('"');
+    for c in s.chars() {
+        match c {
+            '\\' => output.push_str("\\\\"),
+            '"' => output.push_str("\\\""),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(output, "\\u{{{:x}}}", c as u32);
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+fn float(output: &mut String, f: &rug::Float) {
+    if f.is_nan() {
+        output.push_str("0.0 / 0.0");
+        return;
+    }
+    if f.is_infinite() {
+        let sign = if f.is_sign_negative() { "-" } else { "" };
+        let _ = write!(output, "{}1.0 / 0.0", sign);
+        return;
+    }
+
+    let digits = f.to_string_radix(10, None);
+    output.push_str(&digits);
+    if !digits.contains(['.', 'e']) {
+        output.push_str(".0");
+    }
+    if f.prec() != DEFAULT_FLOAT_PRECISION {
+        let _ = write!(output, "_{}", f.prec());
+    }
+}
+
+fn write(output: &mut String, value: &Value, depth: usize) -> Result<(), String> {
+    if depth > MAX_DEPTH {
+        return Err("value is nested too deeply, or contains itself".to_string());
+    }
+
+    match value {
+        Value::Literal(Literal::String(s)) => string(output, s),
+        Value::Literal(Literal::Float(f)) => float(output, f),
+        Value::Exact(exact) => output.push_str(&exact.source()),
+        // `-128i8` is the negation of `128i8`, which doesn't fit
+        Value::Fixed(fixed) if fixed.kind.signed && fixed.value == fixed.kind.min() => {
+            let _ = write!(output, "{}({})", fixed.kind.name(), fixed.value);
+        }
+        Value::List(list) => {
+            output.push('[');
+            for (index, item) in list.borrow().0.iter().enumerate() {
+                if index > 0 {
+                    output.push_str(", ");
+                }
+                write(output, item, depth + 1)?;
+            }
+            output.push(']');
+        }
+        Value::Dict(dict) => {
+            let dict = dict.borrow();
+            let mut entries = dict
+                .0
+                .iter()
+                .map(|(key, value)| {
+                    let mut key_repr = String::new();
+                    write(&mut key_repr, &key.0, depth + 1)?;
+                    Ok((key_repr, value))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            output.push('{');
+            for (index, (key, value)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    output.push_str(", ");
+                }
+                output.push_str(&key);
+                output.push_str(": ");
+                write(output, value, depth + 1)?;
+            }
+            output.push('}');
+        }
+        Value::Set(set) => {
+            let mut items = set
+                .0
+                .iter()
+                .map(|item| {
+                    let mut item_repr = String::new();
+                    write(&mut item_repr, &item.0, depth + 1)?;
+                    Ok(item_repr)
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            items.sort();
+
+            output.push_str("#{");
+            output.push_str(&items.join(", "));
+            output.push('}');
+        }
+        Value::Duration(duration) => {
+            let _ = write!(output, "duration_ns({})", duration.0);
+        }
+        Value::DateTime(datetime) => {
+            let instant = format!("date(1970, 1, 1) + duration_ns({})", datetime.nanos);
+            match datetime.offset {
+                0 => output.push_str(&instant),
+                offset => {
+                    let sign = if offset < 0 { '-' } else { '+' };
+                    let minutes = offset.unsigned_abs() / 60;
+                    let _ = write!(
+                        output,
+                        "in_zone({}, \"{}{:02}:{:02}\")",
+                        instant,
+                        sign,
+                        minutes / 60,
+                        minutes % 60
+                    );
+                }
+            }
+        }
+        other => {
+            let _ = write!(output, "{}", other);
+        }
+    }
+    Ok(())
+}
+
+pub fn repr(value: &Value) -> Result<String, String> {
+    let mut output = String::new();
+    write(&mut output, value, 0)?;
+    Ok(output)
+}
@@ -1,35 +1,159 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use miette::Report;
 
 use crate::{
+    annotation::{self, Annotation},
+    ast,
+    env::{closest, Env, Slot},
     expr::{Expr, ExprKind, Stmt, StmtKind},
-    report::ReadLocalVariableInOwnInitializer,
+    report::{
+        AssignmentInCondition, DeprecatedUse, InheritsFromItself,
+        ReadLocalVariableInOwnInitializer, SuperOutsideClass, SuperWithoutSuperclass,
+        ThisOutsideClass, UndeclaredAssignment,
+    },
+    sync::{Rc, RefCell},
+    value::Value,
 };
 
 #[derive(Default, Debug)]
 pub struct Resolver {
     pub scopes: Vec<HashMap<String, bool>>,
     pub locals: HashMap<usize, usize>,
+    // where each variable expression last found its name, filled in as the
+    // program runs
+    pub slots: RefCell<HashMap<usize, Slot>>,
+    pub warnings: Vec<Report>,
+    // names declared at the top level, by this program or before it (the
+    // builtins, earlier REPL lines); None when they can't all be known, as
+    // after a native import
+    pub globals: Option<HashSet<String>>,
+    // the class declarations the code being resolved is in, innermost last:
+    // whether each has a superclass
+    classes: Vec<bool>,
+    // the reason, if any, of each name in scope declared `@deprecated`, by
+    // the scope it is in: its index in `scopes`, None for the globals
+    deprecated: HashMap<(Option<usize>, String), Option<String>>,
 }
 
 impl Resolver {
-    pub fn resolve(&mut self, statements: &Vec<Stmt>) -> Result<(), Report> {
-        if statements.len() > 1 {
-            for stmt in statements {
-                self.visit_stmt(stmt)?;
+    pub fn with_globals<'a>(names: impl Iterator<Item = &'a str>) -> Self {
+        Self {
+            globals: Some(names.map(str::to_string).collect()),
+            ..Default::default()
+        }
+    }
+
+    // for code run in `env`: its enclosing scopes, up to the globals, are
+    // already open
+    pub fn within(env: &Rc<RefCell<Env>>) -> Self {
+        let mut scopes = Vec::new();
+        let mut current = env.clone();
+        loop {
+            let enclosing = current.borrow().enclosing.clone();
+            match enclosing {
+                Some(enclosing) => {
+                    let names = current
+                        .borrow()
+                        .names()
+                        .map(|name| (name.to_string(), true))
+                        .collect();
+                    scopes.push(names);
+                    current = enclosing;
+                }
+                None => break,
+            }
+        }
+        scopes.reverse();
+
+        let globals = current.borrow().names().map(str::to_string).collect();
+        Self {
+            scopes,
+            globals: Some(globals),
+            ..Default::default()
+        }
+    }
+
+    pub fn resolve(&mut self, statements: &[Stmt]) -> Result<(), Report> {
+        // top-level declarations are visible from every function, wherever
+        // they are in the program
+        for stmt in statements {
+            match &stmt.kind {
+                StmtKind::Let {
+                    name, annotations, ..
+                }
+                | StmtKind::Function {
+                    name, annotations, ..
+                } => {
+                    if let Some(globals) = &mut self.globals {
+                        globals.insert(name.to_string());
+                    }
+                    self.deprecate(name, annotations);
+                }
+                StmtKind::Class { name, .. } => {
+                    if let Some(globals) = &mut self.globals {
+                        globals.insert(name.to_string());
+                    }
+                }
+                StmtKind::NativeImport { .. } => self.globals = None,
+                _ => {}
             }
         }
 
+        for stmt in statements {
+            self.visit_stmt(stmt)?;
+        }
+
         Ok(())
     }
 
+    // `if (x = 1)`: the parentheses belong to the `if`, so a condition that is
+    // itself parenthesized, `if ((x = 1))`, is taken as intended
+    fn check_condition(&mut self, cond: &Expr) {
+        if let ExprKind::Assign { name, .. } = &cond.kind {
+            self.warnings.push(
+                AssignmentInCondition {
+                    span: cond.span.into(),
+                    name: name.to_string(),
+                }
+                .into(),
+            );
+        }
+    }
+
     fn begin_scope(&mut self) {
         self.scopes.push(Default::default())
     }
 
     fn end_scope(&mut self) {
         self.scopes.pop();
+        let depth = self.scopes.len();
+        self.deprecated
+            .retain(|(scope, _), _| *scope != Some(depth));
+    }
+
+    // `name`, being declared in the innermost scope, is deprecated or, when
+    // it shadows one that is, no longer
+    fn deprecate(&mut self, name: &str, annotations: &[Annotation]) {
+        let key = (self.scopes.len().checked_sub(1), name.to_string());
+        match annotation::deprecation(annotations) {
+            Some(reason) => self.deprecated.insert(key, reason),
+            None => self.deprecated.remove(&key),
+        };
+    }
+
+    fn check_deprecated(&mut self, expr: &Expr, name: &str) {
+        let scope = self.scopes.iter().rposition(|s| s.contains_key(name));
+        if let Some(reason) = self.deprecated.get(&(scope, name.to_string())) {
+            self.warnings.push(
+                DeprecatedUse {
+                    span: expr.span.into(),
+                    name: name.to_string(),
+                    reason: reason.clone(),
+                }
+                .into(),
+            );
+        }
     }
 
     fn define(&mut self, string: &str) {
@@ -53,11 +177,32 @@ impl Resolver {
         }
     }
 
+    fn check_declared(&self, expr: &Expr, name: &str) -> Result<(), Report> {
+        let Some(globals) = &self.globals else {
+            return Ok(());
+        };
+        if globals.contains(name) || self.scopes.iter().any(|s| s.contains_key(name)) {
+            return Ok(());
+        }
+
+        let candidates = self.scopes.iter().flat_map(|s| s.keys()).chain(globals);
+        let help = match closest(name, candidates) {
+            Some(similar) => format!("did you mean `{}`?", similar),
+            None => format!("declare it first with `let {} = ...;`", name),
+        };
+        Err(UndeclaredAssignment {
+            span: expr.span.into(),
+            name: name.to_string(),
+            help,
+        }
+        .into())
+    }
+
     fn visit_expr(&mut self, expr: &Expr) -> Result<(), Report> {
         match &expr.kind {
             ExprKind::Variable { name } => {
                 if let Some(scope) = self.scopes.last() {
-                    if !scope.get(name).unwrap_or(&true) {
+                    if !scope.get(name.as_ref()).unwrap_or(&true) {
                         // check if it exists and it its set at false
                         return Err(ReadLocalVariableInOwnInitializer {
                             span: expr.span.into(),
@@ -66,9 +211,11 @@ impl Resolver {
                     }
                 }
 
+                self.check_deprecated(expr, name);
                 self.resolve_local(expr.id, name)
             }
             ExprKind::Assign { name, value } => {
+                self.check_declared(expr, name)?;
                 self.visit_expr(value)?;
                 self.resolve_local(expr.id, name)
             }
@@ -77,19 +224,59 @@ impl Resolver {
                 self.visit_expr(right)?;
             }
             ExprKind::Call { callee, args } => {
-                self.visit_expr(callee)?;
+                match &callee.kind {
+                    // `x.f()` may call the function `f` (see the interpreter)
+                    ExprKind::Get { obj, name } => {
+                        self.visit_expr(obj)?;
+                        self.resolve_local(callee.id, name)
+                    }
+                    _ => self.visit_expr(callee)?,
+                }
                 for arg in args {
                     self.visit_expr(arg)?;
                 }
             }
-            ExprKind::Grouping { value } => {
+            ExprKind::Grouping { value } | ExprKind::Spread { value } => {
                 self.visit_expr(value)?;
             }
             ExprKind::Literal { value: _ } => {}
+            ExprKind::This => {
+                if self.classes.is_empty() {
+                    return Err(ThisOutsideClass {
+                        span: expr.span.into(),
+                    }
+                    .into());
+                }
+                self.resolve_local(expr.id, "this")
+            }
+            ExprKind::Super { .. } => match self.classes.last() {
+                Some(true) => self.resolve_local(expr.id, "super"),
+                Some(false) => {
+                    return Err(SuperWithoutSuperclass {
+                        span: expr.span.into(),
+                    }
+                    .into())
+                }
+                None => {
+                    return Err(SuperOutsideClass {
+                        span: expr.span.into(),
+                    }
+                    .into())
+                }
+            },
             ExprKind::Logical { left, op: _, right } => {
                 self.visit_expr(left)?;
                 self.visit_expr(right)?;
             }
+            ExprKind::Conditional {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.visit_expr(cond)?;
+                self.visit_expr(then_branch)?;
+                self.visit_expr(else_branch)?;
+            }
             ExprKind::Unary { op: _, right } => {
                 self.visit_expr(right)?;
             }
@@ -104,7 +291,7 @@ impl Resolver {
                 self.visit_expr(obj)?;
                 self.visit_expr(value)?;
             }
-            ExprKind::List { items } => {
+            ExprKind::List { items } | ExprKind::SetLiteral { items } => {
                 for item in items {
                     self.visit_expr(item)?;
                 }
@@ -112,20 +299,80 @@ impl Resolver {
             ExprKind::GetIndex { obj, index } => {
                 self.visit_expr(obj)?;
                 self.visit_expr(index)?;
-            },
+            }
+            ExprKind::Slice { obj, start, end } => {
+                self.visit_expr(obj)?;
+                for bound in [start, end].into_iter().flatten() {
+                    self.visit_expr(bound)?;
+                }
+            }
             ExprKind::SetIndex { obj, index, value } => {
                 self.visit_expr(obj)?;
                 self.visit_expr(index)?;
                 self.visit_expr(value)?;
-            },
+            }
             ExprKind::Dict { items } => {
                 for (left, right) in items {
                     self.visit_expr(left)?;
                     self.visit_expr(right)?;
                 }
-            },
+            }
+            // the quoted code is data; only the unquoted values are evaluated,
+            // where the quote is
+            ExprKind::Quote { body } => {
+                let mut result = Ok(());
+                ast::quote(body, &mut |value| {
+                    if result.is_ok() {
+                        result = self.visit_expr(value);
+                    }
+                    Value::Nil
+                });
+                result?;
+            }
+            ExprKind::Unquote { .. } => unreachable!("unquotes are resolved with their quote"),
+            // every arm has a scope of its own, with the names its pattern binds
+            ExprKind::Match { scrutinee, arms } => {
+                self.visit_expr(scrutinee)?;
+                for arm in arms {
+                    self.begin_scope();
+                    for name in arm.pattern.bindings() {
+                        self.declare(name);
+                        self.define(name);
+                    }
+                    self.visit_expr(&arm.body)?;
+                    self.end_scope();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // `stmt` with the variable bound by an `if (let ...)` or `while (let ...)`
+    fn visit_bound(&mut self, binding: &Option<Rc<str>>, stmt: &Stmt) -> Result<(), Report> {
+        match binding {
+            Some(name) => {
+                self.begin_scope();
+                self.declare(name);
+                self.define(name);
+                self.visit_stmt(stmt)?;
+                self.end_scope();
+                Ok(())
+            }
+            None => self.visit_stmt(stmt),
         }
+    }
 
+    fn visit_function(&mut self, params: &[Rc<str>], body: &[Stmt]) -> Result<(), Report> {
+        self.begin_scope();
+        for param in params {
+            self.declare(param);
+            self.define(param);
+        }
+        for statement in body {
+            self.visit_stmt(statement)?;
+        }
+        self.end_scope();
         Ok(())
     }
 
@@ -138,35 +385,79 @@ impl Resolver {
                 }
                 self.end_scope()
             }
-            StmtKind::Let { name, initializer } => {
+            StmtKind::Let {
+                name,
+                initializer,
+                annotations,
+            } => {
                 self.declare(name);
                 if let Some(initializer) = initializer {
                     self.visit_expr(initializer)?
                 }
                 self.define(name);
+                self.deprecate(name, annotations);
             }
-            StmtKind::Function { name, params, body } => {
+            StmtKind::Function {
+                name,
+                params,
+                body,
+                annotations,
+            } => {
                 self.declare(name);
                 self.define(name);
+                self.deprecate(name, annotations);
+                self.visit_function(params, body)?;
+            }
+            StmtKind::Class {
+                name,
+                superclass,
+                methods,
+                getters,
+                setters,
+            } => {
+                self.declare(name);
+                self.define(name);
+                if superclass.as_ref() == Some(name) {
+                    return Err(InheritsFromItself {
+                        span: stmt.span.into(),
+                        name: name.to_string(),
+                    }
+                    .into());
+                }
 
-                self.begin_scope();
-                for param in params.iter() {
-                    self.declare(param);
-                    self.define(param);
+                // the scope with `super`, then the one `bind` gives every
+                // method
+                if let Some(superclass) = superclass {
+                    self.resolve_local(stmt.id, superclass);
+                    self.begin_scope();
+                    self.define("super");
                 }
-                for statement in body.iter() {
-                    self.visit_stmt(statement)?;
+                self.begin_scope();
+                self.define("this");
+                self.classes.push(superclass.is_some());
+                for method in methods.iter().chain(getters).chain(setters) {
+                    if let StmtKind::Function { params, body, .. } = &method.kind {
+                        self.visit_function(params, body)?;
+                    }
                 }
+                self.classes.pop();
                 self.end_scope();
+                if superclass.is_some() {
+                    self.end_scope();
+                }
             }
             StmtKind::Expression { expr } => self.visit_expr(expr)?,
             StmtKind::If {
+                binding,
                 cond,
                 then_branch,
                 else_branch,
             } => {
+                if binding.is_none() {
+                    self.check_condition(cond);
+                }
                 self.visit_expr(cond)?;
-                self.visit_stmt(then_branch)?;
+                self.visit_bound(binding, then_branch)?;
                 if let Some(else_branch) = else_branch {
                     self.visit_stmt(else_branch)?;
                 }
@@ -176,10 +467,43 @@ impl Resolver {
                     self.visit_expr(expr)?;
                 }
             }
-            StmtKind::While { cond, body } => {
+            StmtKind::Throw { value } => self.visit_expr(value)?,
+            StmtKind::Try {
+                body,
+                name,
+                handler,
+            } => {
+                self.visit_stmt(body)?;
+                self.visit_bound(&Some(name.clone()), handler)?;
+            }
+            StmtKind::While {
+                binding,
+                cond,
+                body,
+            } => {
+                if binding.is_none() {
+                    self.check_condition(cond);
+                }
                 self.visit_expr(cond)?;
+                self.visit_bound(binding, body)?;
+            }
+            StmtKind::ForIn {
+                name,
+                value,
+                iterable,
+                body,
+            } => {
+                self.visit_expr(iterable)?;
+
+                self.begin_scope();
+                for name in std::iter::once(name).chain(value) {
+                    self.declare(name);
+                    self.define(name);
+                }
                 self.visit_stmt(body)?;
+                self.end_scope();
             }
+            StmtKind::NativeImport { .. } => {}
         }
 
         Ok(())
@@ -0,0 +1,172 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use clap::ValueEnum;
+use miette::{Report, Severity};
+
+use crate::{
+    function::Function,
+    scheduler::Scheduler,
+    sync::{Rc, RefCell},
+    token::Span,
+    value::Value,
+};
+
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq)]
+pub enum Lint {
+    #[default]
+    Allow,
+    Warn,
+    Deny,
+}
+
+// whether `color`, `bold`, `style` and `print_diff` add ANSI escapes: `auto`
+// does unless stdout isn't a terminal or NO_COLOR is set
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl Lint {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Lint::Deny => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Config {
+    pub precision_loss: Lint,
+    pub color: ColorChoice,
+    pub allow_native: bool,
+    pub allow_eval: bool,
+    // how many of the most evaluated expressions to report, when counting
+    pub counts: Option<usize>,
+    // the names given with `--define`, with their values if they have one
+    pub defines: HashMap<String, Option<String>>,
+}
+
+impl Config {
+    // what `cfg(name)` gives: the value of `--define name=value`, true for
+    // `--define name` and nil for a name that wasn't defined
+    pub fn define(&self, name: &str) -> Value {
+        match self.defines.get(name) {
+            Some(Some(value)) => value.as_str().into(),
+            Some(None) => Value::True,
+            None => Value::Nil,
+        }
+    }
+}
+
+// the functions registered with `on_exit`, `on_error` and `on`, in order.
+// They are kept for a whole session, across the runs of every REPL line.
+#[derive(Debug, Default)]
+pub struct Hooks {
+    pub exit: RefCell<Vec<Rc<dyn Function>>>,
+    pub error: RefCell<Vec<Rc<dyn Function>>>,
+    // by event name
+    pub events: RefCell<HashMap<String, Vec<Rc<dyn Function>>>>,
+}
+
+// state shared by every context of a single run
+#[derive(Debug, Default)]
+pub struct Runtime {
+    pub config: Config,
+    pub hooks: Rc<Hooks>,
+    pub scheduler: Scheduler,
+    // where `print` and `println` write instead of stdout, when captured
+    pub output: Option<Rc<RefCell<String>>>,
+    // raised from another thread to stop the run at its next expression
+    pub interrupt: Option<Arc<AtomicBool>>,
+    warnings: RefCell<Vec<Report>>,
+    warned: RefCell<HashSet<usize>>,
+    counts: RefCell<HashMap<usize, (Span, u64)>>,
+}
+
+impl Runtime {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_hooks(self, hooks: Rc<Hooks>) -> Self {
+        Self { hooks, ..self }
+    }
+
+    pub fn with_output(self, output: Option<Rc<RefCell<String>>>) -> Self {
+        Self { output, ..self }
+    }
+
+    pub fn with_interrupt(self, interrupt: Option<Arc<AtomicBool>>) -> Self {
+        Self { interrupt, ..self }
+    }
+
+    pub fn interrupted(&self) -> bool {
+        self.interrupt
+            .as_ref()
+            .is_some_and(|interrupt| interrupt.load(Ordering::Relaxed))
+    }
+
+    pub fn print(&self, text: &str) {
+        match &self.output {
+            Some(output) => output.borrow_mut().push_str(text),
+            None => print!("{}", text),
+        }
+    }
+
+    // records a warning for the node `id` (once), or fails when denied
+    pub fn lint(
+        &self,
+        level: Lint,
+        id: usize,
+        report: impl FnOnce(Severity) -> Report,
+    ) -> Result<(), Report> {
+        match level {
+            Lint::Allow => Ok(()),
+            Lint::Warn => {
+                if self.warned.borrow_mut().insert(id) {
+                    self.warnings.borrow_mut().push(report(level.severity()));
+                }
+                Ok(())
+            }
+            Lint::Deny => Err(report(level.severity())),
+        }
+    }
+
+    pub fn warn(&self, report: Report) {
+        self.warnings.borrow_mut().push(report);
+    }
+
+    pub fn take_warnings(&self) -> Vec<Report> {
+        self.warnings.take()
+    }
+
+    // counts an evaluation of the expression `id`, when counting is on
+    pub fn count(&self, id: usize, span: Span) {
+        if self.config.counts.is_some() {
+            self.counts.borrow_mut().entry(id).or_insert((span, 0)).1 += 1;
+        }
+    }
+
+    // the spans of the most evaluated expressions, most evaluated first
+    pub fn hot_spots(&self) -> Vec<(Span, u64)> {
+        let mut counts: Vec<_> = self.counts.borrow().values().copied().collect();
+        counts.sort_by(|(a, a_count), (b, b_count)| {
+            b_count.cmp(a_count).then(a.offset.cmp(&b.offset))
+        });
+        counts.truncate(self.config.counts.unwrap_or(0));
+        counts
+    }
+}
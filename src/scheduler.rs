@@ -0,0 +1,72 @@
+use std::time::{Duration, Instant};
+
+use crate::function::Function;
+use crate::sync::{Cell, Rc, RefCell};
+
+#[derive(Debug)]
+struct Timer {
+    id: u64,
+    due: Instant,
+    interval: Option<Duration>,
+    function: Rc<dyn Function>,
+}
+
+// Functions to call later, on the monotonic clock, by `run_loop`
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    next_id: Cell<u64>,
+    timers: RefCell<Vec<Timer>>,
+}
+
+impl Scheduler {
+    // calls `function` after `delay` and, with an interval, every `delay`
+    // after that
+    pub fn schedule(&self, function: Rc<dyn Function>, delay: Duration, repeat: bool) -> u64 {
+        let id = self.next_id.get() + 1;
+        self.next_id.set(id);
+        self.timers.borrow_mut().push(Timer {
+            id,
+            due: Instant::now() + delay,
+            interval: repeat.then_some(delay),
+            function,
+        });
+        id
+    }
+
+    // false when there is no such timer, because it ran or was cancelled
+    pub fn cancel(&self, id: u64) -> bool {
+        let mut timers = self.timers.borrow_mut();
+        let before = timers.len();
+        timers.retain(|timer| timer.id != id);
+        timers.len() != before
+    }
+
+    // waits for the timer due first and gives its function, rescheduling it
+    // when it repeats; `None` once there are no timers left. Timers due at the
+    // same time run in the order they were set.
+    pub fn next(&self) -> Option<Rc<dyn Function>> {
+        let (index, due) = self
+            .timers
+            .borrow()
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, timer)| (timer.due, timer.id))
+            .map(|(index, timer)| (index, timer.due))?;
+
+        let now = Instant::now();
+        if due > now {
+            std::thread::sleep(due - now);
+        }
+
+        let mut timers = self.timers.borrow_mut();
+        let timer = &mut timers[index];
+        let function = timer.function.clone();
+        match timer.interval {
+            Some(interval) => timer.due += interval,
+            None => {
+                timers.remove(index);
+            }
+        }
+        Some(function)
+    }
+}
@@ -0,0 +1,117 @@
+use std::cmp::Ordering;
+
+use rug::Integer;
+
+// Semantic versions (https://semver.org): `major.minor.patch`, then an
+// optional `-pre.release` and `+build`, ordered by their precedence. A
+// leading `v` is allowed, as tags often have one.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Identifier {
+    Numeric(Integer),
+    Alphanumeric(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Version {
+    pub major: Integer,
+    pub minor: Integer,
+    pub patch: Integer,
+    pub pre: Vec<Identifier>,
+    pub build: Vec<String>,
+}
+
+// digits without a leading zero, unless it's a lone one
+fn numeric(part: &str) -> Option<Integer> {
+    let digits = !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit());
+    match digits && (part == "0" || !part.starts_with('0')) {
+        true => part.parse().ok(),
+        false => None,
+    }
+}
+
+// dot-separated parts of letters, digits and hyphens, none of them empty
+fn identifiers<'a>(what: &str, text: &'a str) -> Result<Vec<&'a str>, String> {
+    text.split('.')
+        .map(|part| {
+            let valid = !part.is_empty()
+                && part
+                    .bytes()
+                    .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-');
+            match valid {
+                true => Ok(part),
+                false => Err(format!("`{}` isn't a valid {} identifier", part, what)),
+            }
+        })
+        .collect()
+}
+
+impl Version {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let version = text.strip_prefix('v').unwrap_or(text);
+        let (version, build) = match version.split_once('+') {
+            Some((version, build)) => (version, identifiers("build", build)?),
+            None => (version, Vec::new()),
+        };
+        let (version, pre) = match version.split_once('-') {
+            Some((version, pre)) => (version, identifiers("pre-release", pre)?),
+            None => (version, Vec::new()),
+        };
+
+        let core: Vec<_> = version.split('.').collect();
+        let [major, minor, patch] = core.as_slice() else {
+            return Err(format!("`{}` isn't major.minor.patch", version));
+        };
+        let number =
+            |part: &str| numeric(part).ok_or_else(|| format!("`{}` isn't a version number", part));
+        let pre = pre
+            .into_iter()
+            .map(
+                |part| match part.bytes().all(|byte| byte.is_ascii_digit()) {
+                    true => number(part).map(Identifier::Numeric),
+                    false => Ok(Identifier::Alphanumeric(part.to_string())),
+                },
+            )
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            major: number(major)?,
+            minor: number(minor)?,
+            patch: number(patch)?,
+            pre,
+            build: build.into_iter().map(str::to_string).collect(),
+        })
+    }
+
+    // the build is left out, and a pre-release comes before its release
+    pub fn precedence(&self, other: &Self) -> Ordering {
+        let core = (&self.major, &self.minor, &self.patch).cmp(&(
+            &other.major,
+            &other.minor,
+            &other.patch,
+        ));
+        core.then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => self.pre.cmp(&other.pre),
+        })
+    }
+}
+
+// numbers before words, numbers by value and words by their bytes
+impl Ord for Identifier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Numeric(lhs), Self::Numeric(rhs)) => lhs.cmp(rhs),
+            (Self::Numeric(_), Self::Alphanumeric(_)) => Ordering::Less,
+            (Self::Alphanumeric(_), Self::Numeric(_)) => Ordering::Greater,
+            (Self::Alphanumeric(lhs), Self::Alphanumeric(rhs)) => lhs.cmp(rhs),
+        }
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
@@ -0,0 +1,365 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::{
+    os::unix::net::{UnixListener, UnixStream},
+    path::Path,
+};
+
+use crate::{env::Env, json::Json, lexer::KEYWORDS};
+
+// `xi serve`: one session shared by every client of a socket, spoken to in
+// newline-delimited JSON. A request is an object with an "op" and an
+// optional "id", which its response repeats:
+//
+//   {"op": "evaluate", "code": "..."}    {"output", "value", "error"}
+//   {"op": "complete", "prefix": "..."}  {"completions": [...]}
+//   {"op": "interrupt"}                  {"interrupted": true or false}
+//   {"op": "reset"}                      {"reset": true}
+//
+// "output" is what the code printed, "value" its value as printed, null when
+// it's nil, and "error" its diagnostic as `--error-format json` writes it.
+// Requests run one at a time, in the order they arrive, except interrupts:
+// they are answered at once and stop the evaluation running, if any. A
+// request that can't be read is answered with just an "error".
+//
+// `Client` is the other end, which `xi attach` uses.
+
+// how often a client waiting for a response checks whether to interrupt it
+const POLL: Duration = Duration::from_millis(100);
+
+pub enum Request {
+    Evaluate(String),
+    Complete(String),
+    Reset,
+}
+
+// interrupts don't wait for their turn
+enum Op {
+    Run(Request),
+    Interrupt,
+}
+
+// whether an evaluation is running, and the flag that stops it
+#[derive(Default)]
+pub struct Control {
+    pub interrupt: Arc<AtomicBool>,
+    running: AtomicBool,
+}
+
+impl Control {
+    pub fn start(&self) {
+        self.interrupt.store(false, Ordering::Relaxed);
+        self.running.store(true, Ordering::Relaxed);
+    }
+
+    pub fn finish(&self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+
+    // raises the flag, when there is an evaluation to stop
+    pub fn raise(&self) -> bool {
+        let running = self.running.load(Ordering::Relaxed);
+        if running {
+            self.interrupt.store(true, Ordering::Relaxed);
+        }
+        running
+    }
+}
+
+pub enum Listener {
+    #[cfg(unix)]
+    Unix(UnixListener),
+    Tcp(TcpListener),
+}
+
+enum Stream {
+    #[cfg(unix)]
+    Unix(UnixStream),
+    Tcp(TcpStream),
+}
+
+impl Stream {
+    fn try_clone(&self) -> io::Result<Self> {
+        Ok(match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => Self::Unix(stream.try_clone()?),
+            Self::Tcp(stream) => Self::Tcp(stream.try_clone()?),
+        })
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.set_read_timeout(timeout),
+            Self::Tcp(stream) => stream.set_read_timeout(timeout),
+        }
+    }
+}
+
+impl io::Read for Stream {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.read(buffer),
+            Self::Tcp(stream) => stream.read(buffer),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.write(buffer),
+            Self::Tcp(stream) => stream.write(buffer),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.flush(),
+            Self::Tcp(stream) => stream.flush(),
+        }
+    }
+}
+
+impl Listener {
+    // a socket left behind by a server that is gone is replaced
+    #[cfg(unix)]
+    pub fn unix(path: &Path) -> io::Result<Self> {
+        match UnixListener::bind(path) {
+            Err(err) if err.kind() == io::ErrorKind::AddrInUse => {
+                if UnixStream::connect(path).is_ok() {
+                    return Err(err);
+                }
+                std::fs::remove_file(path)?;
+                UnixListener::bind(path).map(Self::Unix)
+            }
+            result => result.map(Self::Unix),
+        }
+    }
+
+    pub fn tcp(address: &str) -> io::Result<Self> {
+        TcpListener::bind(address).map(Self::Tcp)
+    }
+
+    fn accept(&self) -> io::Result<Stream> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(listener) => listener.accept().map(|(stream, _)| Stream::Unix(stream)),
+            Self::Tcp(listener) => listener.accept().map(|(stream, _)| Stream::Tcp(stream)),
+        }
+    }
+}
+
+// a request to run in the session, and where to answer it
+pub struct Incoming {
+    pub request: Request,
+    id: Json,
+    client: Arc<Mutex<Stream>>,
+}
+
+impl Incoming {
+    pub fn reply(self, fields: Vec<(String, Json)>) {
+        respond(&self.client, self.id, fields);
+    }
+}
+
+// a client that went away is no longer answered
+fn respond(client: &Mutex<Stream>, id: Json, fields: Vec<(String, Json)>) {
+    let mut response = vec![("id".to_string(), id)];
+    response.extend(fields);
+    let mut client = client.lock().unwrap();
+    let _ = writeln!(client, "{}", Json::Object(response)).and_then(|_| client.flush());
+}
+
+fn parse(line: &str) -> (Json, Result<Op, String>) {
+    let json = match Json::parse(line) {
+        Ok(json @ Json::Object(_)) => json,
+        Ok(_) => return (Json::Null, Err("a request must be an object".to_string())),
+        Err(message) => return (Json::Null, Err(message)),
+    };
+    let id = json.get("id").cloned().unwrap_or(Json::Null);
+    let text = |field: &str| match json.get(field).and_then(Json::as_str) {
+        Some(text) => Ok(text.to_string()),
+        None => Err(format!("\"{}\" must be a string", field)),
+    };
+    let op = match json.get("op").and_then(Json::as_str) {
+        Some("evaluate") => text("code").map(|code| Op::Run(Request::Evaluate(code))),
+        Some("complete") => text("prefix").map(|prefix| Op::Run(Request::Complete(prefix))),
+        Some("reset") => Ok(Op::Run(Request::Reset)),
+        Some("interrupt") => Ok(Op::Interrupt),
+        Some(op) => Err(format!(
+            "unknown op `{}`, try evaluate, complete, interrupt or reset",
+            op
+        )),
+        None => Err("\"op\" must be a string".to_string()),
+    };
+    (id, op)
+}
+
+fn error(message: String) -> Vec<(String, Json)> {
+    let error = Json::Object(vec![
+        ("code".to_string(), Json::Null),
+        ("message".to_string(), message.into()),
+    ]);
+    vec![("error".to_string(), error)]
+}
+
+// reads the requests of one client until it hangs up
+fn client(stream: Stream, control: &Control, requests: &Sender<Incoming>) -> io::Result<()> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let stream = Arc::new(Mutex::new(stream));
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse(&line) {
+            (id, Ok(Op::Run(request))) => {
+                let incoming = Incoming {
+                    request,
+                    id,
+                    client: stream.clone(),
+                };
+                if requests.send(incoming).is_err() {
+                    break;
+                }
+            }
+            (id, Ok(Op::Interrupt)) => {
+                let interrupted = control.raise();
+                respond(
+                    &stream,
+                    id,
+                    vec![("interrupted".into(), interrupted.into())],
+                );
+            }
+            (id, Err(message)) => respond(&stream, id, error(message)),
+        }
+    }
+    Ok(())
+}
+
+// accepts clients in the background, each with a thread of its own reading
+// its requests; the ones to run come out of the receiver
+pub fn listen(listener: Listener, control: Arc<Control>) -> Receiver<Incoming> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        while let Ok(stream) = listener.accept() {
+            let (control, sender) = (control.clone(), sender.clone());
+            thread::spawn(move || client(stream, &control, &sender));
+        }
+    });
+    receiver
+}
+
+// the keywords and the names defined in `env`, builtins included, that
+// start with `prefix`, in order
+pub fn completions(env: &Env, prefix: &str) -> Vec<String> {
+    let mut names: Vec<_> = KEYWORDS
+        .iter()
+        .map(|(keyword, _)| *keyword)
+        .chain(env.names())
+        .filter(|name| name.starts_with(prefix))
+        .map(str::to_string)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+pub struct Client {
+    reader: BufReader<Stream>,
+    writer: Stream,
+    last_id: usize,
+}
+
+impl Client {
+    #[cfg(unix)]
+    pub fn unix(path: &Path) -> io::Result<Self> {
+        Self::new(Stream::Unix(UnixStream::connect(path)?))
+    }
+
+    pub fn tcp(address: &str) -> io::Result<Self> {
+        Self::new(Stream::Tcp(TcpStream::connect(address)?))
+    }
+
+    fn new(stream: Stream) -> io::Result<Self> {
+        stream.set_read_timeout(Some(POLL))?;
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            last_id: 0,
+        })
+    }
+
+    fn send(&mut self, fields: Vec<(String, Json)>) -> io::Result<usize> {
+        self.last_id += 1;
+        let mut request = vec![("id".to_string(), self.last_id.into())];
+        request.extend(fields);
+        writeln!(self.writer, "{}", Json::Object(request))?;
+        self.writer.flush()?;
+        Ok(self.last_id)
+    }
+
+    pub fn request(&mut self, fields: Vec<(String, Json)>) -> io::Result<Json> {
+        self.request_or_interrupt(fields, || false)
+    }
+
+    // sends a request and waits for its response, sending an interrupt
+    // whenever `interrupt` says so meanwhile
+    pub fn request_or_interrupt(
+        &mut self,
+        fields: Vec<(String, Json)>,
+        interrupt: impl Fn() -> bool,
+    ) -> io::Result<Json> {
+        let id = self.send(fields)?;
+        // a read that times out keeps what it read of the line so far
+        let mut line = Vec::new();
+        loop {
+            match self.reader.read_until(b'\n', &mut line) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "the server hung up",
+                    ))
+                }
+                Ok(_) if line.ends_with(b"\n") => {
+                    let text = String::from_utf8_lossy(&line);
+                    let response = Json::parse(&text)
+                        .map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))?;
+                    // the responses to interrupts are dropped
+                    if matches!(response.get("id"), Some(Json::Number(n)) if *n == id.to_string()) {
+                        return Ok(response);
+                    }
+                    line.clear();
+                }
+                Ok(_) => {}
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    if interrupt() {
+                        self.send(vec![("op".to_string(), "interrupt".into())])?;
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
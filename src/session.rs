@@ -0,0 +1,130 @@
+use miette::Report;
+
+use crate::{datetime::DateTime, json::Json, value::Value};
+
+// A REPL session as it was typed: every input line, when it was entered and
+// what it gave, saved by `--record` and run again by `--replay`. The file is
+// {"version": 1, "entries": [{"time", "input", "result", "error"}]}, with
+// "result" the value as printed, or "error" the {"code", "message"} of the
+// error instead; both are null for `:` commands, which print their own.
+
+const VERSION: usize = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Command,
+    Value(String),
+    Error {
+        code: Option<String>,
+        message: String,
+    },
+}
+
+impl Outcome {
+    pub fn new(result: &Result<Value, Report>) -> Self {
+        match result {
+            Ok(value) => Self::Value(value.to_string()),
+            Err(report) => Self::Error {
+                code: report.code().map(|code| code.to_string()),
+                message: report.to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub time: String,
+    pub input: String,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Session {
+    pub entries: Vec<Entry>,
+}
+
+impl Session {
+    pub fn record(&mut self, input: &str, outcome: Outcome) {
+        self.entries.push(Entry {
+            time: DateTime::now().to_string(),
+            input: input.to_string(),
+            outcome,
+        });
+    }
+
+    pub fn to_json(&self) -> Json {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let (result, error) = match &entry.outcome {
+                    Outcome::Command => (Json::Null, Json::Null),
+                    Outcome::Value(value) => (value.as_str().into(), Json::Null),
+                    Outcome::Error { code, message } => (
+                        Json::Null,
+                        Json::Object(vec![
+                            ("code".to_string(), code.clone().into()),
+                            ("message".to_string(), message.as_str().into()),
+                        ]),
+                    ),
+                };
+                Json::Object(vec![
+                    ("time".to_string(), entry.time.as_str().into()),
+                    ("input".to_string(), entry.input.as_str().into()),
+                    ("result".to_string(), result),
+                    ("error".to_string(), error),
+                ])
+            })
+            .collect();
+        Json::Object(vec![
+            ("version".to_string(), VERSION.into()),
+            ("entries".to_string(), Json::Array(entries)),
+        ])
+    }
+
+    pub fn from_json(json: &Json) -> Result<Self, String> {
+        match json.get("version") {
+            Some(Json::Number(version)) if *version == VERSION.to_string() => {}
+            Some(Json::Number(version)) => {
+                return Err(format!("unsupported session version {}", version))
+            }
+            _ => return Err("not a session: no \"version\"".to_string()),
+        }
+        let Some(Json::Array(entries)) = json.get("entries") else {
+            return Err("not a session: no \"entries\" list".to_string());
+        };
+
+        let entries = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let field = |name: &str| {
+                    entry
+                        .get(name)
+                        .and_then(Json::as_str)
+                        .map(str::to_string)
+                        .ok_or_else(|| format!("entry {}: no string \"{}\"", index, name))
+                };
+                let outcome = match (entry.get("result"), entry.get("error")) {
+                    (Some(Json::String(value)), _) => Outcome::Value(value.clone()),
+                    (_, Some(error @ Json::Object(_))) => Outcome::Error {
+                        code: error.get("code").and_then(Json::as_str).map(str::to_string),
+                        message: error
+                            .get("message")
+                            .and_then(Json::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                    },
+                    _ => Outcome::Command,
+                };
+                Ok(Entry {
+                    time: field("time").unwrap_or_default(),
+                    input: field("input")?,
+                    outcome,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { entries })
+    }
+}
@@ -0,0 +1,39 @@
+use std::{collections::HashSet, fmt::Display};
+
+use crate::value::{Value, ValueError, ValueKey};
+
+// Sets of the values dicts can have as keys, written `#{1, 2, 3}`. Sets
+// don't change: `union`, `intersection` and `difference` make a new one.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Set(pub HashSet<ValueKey>);
+
+impl Set {
+    // the distinct `values`, unless one of them can't be hashed
+    pub fn new(values: impl IntoIterator<Item = Value>) -> Result<Self, ValueError> {
+        let items = values
+            .into_iter()
+            .map(ValueKey::try_from)
+            .collect::<Result<_, _>>()?;
+        Ok(Self(items))
+    }
+
+    pub fn contains(&self, value: Value) -> bool {
+        ValueKey::try_from(value).is_ok_and(|key| self.0.contains(&key))
+    }
+}
+
+impl Display for Set {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{{")?;
+        for e in self.0.iter().take(1) {
+            write!(f, "{}", e)?;
+        }
+        for e in self.0.iter().skip(1) {
+            write!(f, ", {}", e)?;
+        }
+        write!(f, "}}")?;
+
+        Ok(())
+    }
+}
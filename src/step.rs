@@ -0,0 +1,278 @@
+use miette::Report;
+
+use crate::{
+    context::Ctx,
+    expr::{Expr, Stmt, StmtKind},
+    interpreter::{
+        for_in_iterator, for_in_scope, hoisted, visit_expr, visit_stmt, Interpreter, Program,
+        RuntimeError,
+    },
+    iterator::Iter,
+    sync::{Rc, RefCell},
+    value::Value,
+};
+
+// A program run a few steps at a time, for a host that has its own loop to
+// get back to, like a game's frames or a GUI's events.
+//
+// A step is a statement outside of functions: an expression, a `let`, or the
+// condition of an `if` or a loop, with the body left for the steps after it.
+// A function call runs whole within the step that makes it.
+
+pub enum StepResult {
+    // there are steps left
+    Running,
+    // the value of the last statement, as `Interpreter::run` gives it
+    Done(Value),
+    // the `on_error` hooks have already run
+    Error(Report),
+}
+
+enum Frame<'a> {
+    Statements {
+        ctx: Rc<RefCell<Ctx>>,
+        statements: &'a [Stmt],
+        next: usize,
+        // a `{}` block, which is nil as a statement
+        block: bool,
+    },
+    While {
+        ctx: Rc<RefCell<Ctx>>,
+        binding: Option<&'a str>,
+        cond: &'a Expr,
+        body: &'a Stmt,
+    },
+    ForIn {
+        ctx: Rc<RefCell<Ctx>>,
+        name: &'a str,
+        value: Option<&'a str>,
+        iterable: &'a Expr,
+        iterator: Iter,
+        body: &'a Stmt,
+    },
+}
+
+pub struct Execution<'a> {
+    interpreter: &'a Interpreter,
+    context: Rc<RefCell<Ctx>>,
+    // the innermost last
+    frames: Vec<Frame<'a>>,
+    value: Value,
+}
+
+impl<'a> Execution<'a> {
+    pub fn new(interpreter: &'a Interpreter, program: &'a Program) -> Self {
+        let context = interpreter.context(program);
+        let frames = vec![Frame::Statements {
+            ctx: context.clone(),
+            statements: &program.statements,
+            next: 0,
+            block: false,
+        }];
+        let mut execution = Self {
+            interpreter,
+            context,
+            frames,
+            value: Value::Nil,
+        };
+        execution.settle();
+        execution
+    }
+
+    // once it's done, it stays done with the same value; after an error, the
+    // value is nil
+    pub fn step(&mut self) -> StepResult {
+        match self.advance() {
+            Ok(()) if self.frames.is_empty() => StepResult::Done(self.value.clone()),
+            Ok(()) => StepResult::Running,
+            Err(error) => {
+                self.frames.clear();
+                match error {
+                    RuntimeError::Return(value) => {
+                        self.value = value;
+                        StepResult::Done(self.value.clone())
+                    }
+                    error => {
+                        self.value = Value::Nil;
+                        let report = error.unwind().unwrap_err();
+                        StepResult::Error(self.interpreter.failed(&self.context, report))
+                    }
+                }
+            }
+        }
+    }
+
+    // up to `limit` steps, stopping early when the program ends
+    pub fn steps(&mut self, limit: usize) -> StepResult {
+        for _ in 0..limit {
+            match self.step() {
+                StepResult::Running => {}
+                result => return result,
+            }
+        }
+        match self.frames.is_empty() {
+            true => StepResult::Done(self.value.clone()),
+            false => StepResult::Running,
+        }
+    }
+
+    fn advance(&mut self) -> Result<(), RuntimeError> {
+        let Some(frame) = self.frames.last_mut() else {
+            return Ok(());
+        };
+        match frame {
+            Frame::Statements {
+                ctx,
+                statements,
+                next,
+                ..
+            } => {
+                let statements: &'a [Stmt] = statements;
+                let (ctx, stmt) = (ctx.clone(), &statements[*next]);
+                *next += 1;
+                self.enter(&ctx, stmt)?;
+            }
+            Frame::While {
+                ctx,
+                binding,
+                cond,
+                body,
+            } => {
+                let (ctx, binding, cond, body) = (ctx.clone(), *binding, *cond, *body);
+                let value = visit_expr(&ctx, cond)?;
+                match binding {
+                    Some(name) if value != Value::Nil => {
+                        let new_env = Ctx::with_parent(&ctx);
+                        new_env.borrow_mut().define(name, value);
+                        self.push(new_env, body);
+                    }
+                    None if value.is_truthy() => self.push(ctx, body),
+                    _ => self.end(),
+                }
+            }
+            Frame::ForIn {
+                ctx,
+                name,
+                value,
+                iterable,
+                iterator,
+                body,
+            } => {
+                let (ctx, name, value, iterable, body) =
+                    (ctx.clone(), *name, *value, *iterable, *body);
+                // not borrowed while the body runs, which may use it too
+                let item = iterator.borrow_mut().next(&ctx)?;
+                match item {
+                    Some(item) => {
+                        let new_env = for_in_scope(&ctx, name, value, item, iterable)?;
+                        self.push(new_env, body);
+                    }
+                    None => self.end(),
+                }
+            }
+        }
+        self.settle();
+        Ok(())
+    }
+
+    // drops the statements that have all run, so that an execution with
+    // nothing left to run is done
+    fn settle(&mut self) {
+        while let Some(Frame::Statements {
+            statements,
+            next,
+            block,
+            ..
+        }) = self.frames.last()
+        {
+            if *next < statements.len() {
+                break;
+            }
+            if *block {
+                self.value = Value::Nil;
+            }
+            self.frames.pop();
+        }
+    }
+
+    // runs `stmt`, or what decides how it goes on, as `visit_stmt` would
+    fn enter(&mut self, ctx: &Rc<RefCell<Ctx>>, stmt: &'a Stmt) -> Result<(), RuntimeError> {
+        match &stmt.kind {
+            StmtKind::Block { statements } => self.frames.push(Frame::Statements {
+                ctx: Ctx::with_parent(ctx),
+                statements,
+                next: 0,
+                block: true,
+            }),
+            StmtKind::If {
+                binding,
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                let value = visit_expr(ctx, cond)?;
+                if let (Some(name), false) = (binding, value == Value::Nil) {
+                    let new_env = Ctx::with_parent(ctx);
+                    new_env.borrow_mut().define(name, value);
+                    self.push(new_env, then_branch);
+                } else if binding.is_none() && value.is_truthy() {
+                    self.push(ctx.clone(), then_branch);
+                } else if let Some(else_branch) = else_branch {
+                    self.push(ctx.clone(), else_branch);
+                } else {
+                    self.value = Value::Nil;
+                }
+            }
+            StmtKind::While {
+                binding,
+                cond,
+                body,
+            } => {
+                let ctx = match binding {
+                    Some(_) => ctx.clone(),
+                    None => hoisted(ctx, cond, body).unwrap_or_else(|| ctx.clone()),
+                };
+                self.frames.push(Frame::While {
+                    ctx,
+                    binding: binding.as_deref(),
+                    cond,
+                    body,
+                });
+            }
+            StmtKind::ForIn {
+                name,
+                value,
+                iterable,
+                body,
+            } => {
+                let iterator = for_in_iterator(ctx, iterable, value.is_some())?;
+                self.frames.push(Frame::ForIn {
+                    ctx: ctx.clone(),
+                    name,
+                    value: value.as_deref(),
+                    iterable,
+                    iterator,
+                    body,
+                });
+            }
+            _ => self.value = visit_stmt(ctx, stmt)?,
+        }
+        Ok(())
+    }
+
+    // the branch or the body of a loop, whose value is the statement's
+    fn push(&mut self, ctx: Rc<RefCell<Ctx>>, stmt: &'a Stmt) {
+        self.frames.push(Frame::Statements {
+            ctx,
+            statements: std::slice::from_ref(stmt),
+            next: 0,
+            block: false,
+        });
+    }
+
+    // the end of a loop, which is nil as a statement
+    fn end(&mut self) {
+        self.value = Value::Nil;
+        self.frames.pop();
+    }
+}
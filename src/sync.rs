@@ -0,0 +1,215 @@
+// The shared and mutable cells every value is made of. They are the ones of
+// `std::rc` and `std::cell`, unless the `sync` feature is on: then `Rc` is an
+// `Arc` and the cells are locks with the same methods, so that interpreters
+// and their values can be sent to other threads, at the cost of atomic
+// counts and locking on every access.
+//
+// A value shared by threads is waited for while another borrows it; a
+// thread borrowing what it already borrows mutably, or mutably what it
+// already borrows, panics as it would with a `RefCell`.
+
+#[cfg(not(feature = "sync"))]
+pub use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+};
+
+#[cfg(feature = "sync")]
+pub use locked::{Cell, RefCell};
+#[cfg(feature = "sync")]
+pub use std::sync::{Arc as Rc, Weak};
+
+// what the builtins and the iterators must be, to be kept in values
+#[cfg(not(feature = "sync"))]
+pub trait MaybeSync {}
+#[cfg(not(feature = "sync"))]
+impl<T: ?Sized> MaybeSync for T {}
+
+#[cfg(feature = "sync")]
+pub trait MaybeSync: Send + Sync {}
+#[cfg(feature = "sync")]
+impl<T: ?Sized + Send + Sync> MaybeSync for T {}
+
+#[cfg(feature = "sync")]
+mod locked {
+    use std::{
+        cell::UnsafeCell,
+        fmt,
+        ops::{Deref, DerefMut},
+        sync::{Condvar, Mutex, MutexGuard, PoisonError},
+        thread::{self, ThreadId},
+    };
+
+    // the threads borrowing a cell, one entry per borrow
+    #[derive(Default)]
+    struct Borrows {
+        writer: Option<ThreadId>,
+        readers: Vec<ThreadId>,
+    }
+
+    // A lock that is waited for when another thread holds it, and panics as
+    // a `RefCell` would when the thread asking holds it already, which
+    // waiting on would never end. Readers don't wait for writers waiting,
+    // so a thread borrowing a value it's reading again never blocks.
+    #[derive(Default)]
+    pub struct RefCell<T: ?Sized> {
+        borrows: Mutex<Borrows>,
+        released: Condvar,
+        value: UnsafeCell<T>,
+    }
+
+    // SAFETY: the value is only reached through the guards, which the
+    // borrows make either one writer or any number of readers
+    unsafe impl<T: ?Sized + Send> Send for RefCell<T> {}
+    unsafe impl<T: ?Sized + Send + Sync> Sync for RefCell<T> {}
+
+    impl<T> RefCell<T> {
+        pub fn new(value: T) -> Self {
+            Self {
+                borrows: Mutex::default(),
+                released: Condvar::new(),
+                value: UnsafeCell::new(value),
+            }
+        }
+    }
+
+    impl<T: Default> RefCell<T> {
+        pub fn take(&self) -> T {
+            std::mem::take(&mut *self.borrow_mut())
+        }
+    }
+
+    impl<T: ?Sized> RefCell<T> {
+        // the borrows, once `ready` says they allow one more by this thread
+        fn wait(&self, ready: impl Fn(&Borrows, ThreadId) -> bool) -> MutexGuard<'_, Borrows> {
+            let me = thread::current().id();
+            let mut borrows = self.borrows.lock().unwrap_or_else(PoisonError::into_inner);
+            while !ready(&borrows, me) {
+                borrows = self
+                    .released
+                    .wait(borrows)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+            borrows
+        }
+
+        pub fn borrow(&self) -> Ref<'_, T> {
+            let mut borrows = self.wait(|borrows, me| match borrows.writer {
+                Some(writer) if writer == me => panic!("already mutably borrowed"),
+                writer => writer.is_none(),
+            });
+            borrows.readers.push(thread::current().id());
+            Ref(self)
+        }
+
+        pub fn borrow_mut(&self) -> RefMut<'_, T> {
+            let mut borrows = self.wait(|borrows, me| {
+                if borrows.writer == Some(me) || borrows.readers.contains(&me) {
+                    panic!("already borrowed");
+                }
+                borrows.writer.is_none() && borrows.readers.is_empty()
+            });
+            borrows.writer = Some(thread::current().id());
+            RefMut(self)
+        }
+
+        fn release(&self, release: impl FnOnce(&mut Borrows)) {
+            release(&mut self.borrows.lock().unwrap_or_else(PoisonError::into_inner));
+            self.released.notify_all();
+        }
+    }
+
+    pub struct Ref<'a, T: ?Sized>(&'a RefCell<T>);
+
+    impl<T: ?Sized> Deref for Ref<'_, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            // SAFETY: no writer while this reader is counted
+            unsafe { &*self.0.value.get() }
+        }
+    }
+
+    impl<T: ?Sized> Drop for Ref<'_, T> {
+        fn drop(&mut self) {
+            let me = thread::current().id();
+            self.0.release(|borrows| {
+                if let Some(i) = borrows.readers.iter().position(|&reader| reader == me) {
+                    borrows.readers.swap_remove(i);
+                }
+            });
+        }
+    }
+
+    impl<T: ?Sized + fmt::Display> fmt::Display for Ref<'_, T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            (**self).fmt(f)
+        }
+    }
+
+    pub struct RefMut<'a, T: ?Sized>(&'a RefCell<T>);
+
+    impl<T: ?Sized> Deref for RefMut<'_, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            // SAFETY: this is the only borrow while it's the writer
+            unsafe { &*self.0.value.get() }
+        }
+    }
+
+    impl<T: ?Sized> DerefMut for RefMut<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            // SAFETY: as for `deref`
+            unsafe { &mut *self.0.value.get() }
+        }
+    }
+
+    impl<T: ?Sized> Drop for RefMut<'_, T> {
+        fn drop(&mut self) {
+            self.0.release(|borrows| borrows.writer = None);
+        }
+    }
+
+    impl<T: Clone> Clone for RefCell<T> {
+        fn clone(&self) -> Self {
+            Self::new(self.borrow().clone())
+        }
+    }
+
+    impl<T: ?Sized + fmt::Debug> fmt::Debug for RefCell<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("RefCell").field(&&*self.borrow()).finish()
+        }
+    }
+
+    #[derive(Default)]
+    pub struct Cell<T>(Mutex<T>);
+
+    impl<T: Copy> Cell<T> {
+        pub fn new(value: T) -> Self {
+            Self(Mutex::new(value))
+        }
+
+        pub fn get(&self) -> T {
+            *self.0.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        pub fn set(&self, value: T) {
+            *self.0.lock().unwrap_or_else(PoisonError::into_inner) = value;
+        }
+    }
+
+    impl<T: Copy + fmt::Debug> fmt::Debug for Cell<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Cell").field(&self.get()).finish()
+        }
+    }
+}
+
+#[cfg(feature = "sync")]
+const _: () = {
+    fn send<T: Send + Sync>() {}
+    let _ = send::<crate::interpreter::Interpreter>;
+    let _ = send::<crate::value::Value>;
+};
@@ -0,0 +1,425 @@
+use std::{cmp::Ordering, collections::HashMap, fmt::Display};
+
+use rug::{Float, Integer};
+
+use crate::{
+    dict::Dict,
+    lexer::DEFAULT_FLOAT_PRECISION,
+    sync::{Rc, RefCell},
+    token::Literal,
+    value::{Value, ValueKey},
+};
+
+// Rows of named values kept column by column, so that picking columns or
+// rows, grouping and aggregating walk plain vectors instead of a dict per
+// row. Tables don't change: every operation makes a new one.
+
+#[derive(Debug)]
+pub struct Column {
+    pub name: Rc<str>,
+    pub values: Vec<Value>,
+}
+
+// the rows of every group, in the order their first row comes in, and the
+// columns that `group_by` made them from
+#[derive(Debug)]
+pub struct Groups {
+    pub keys: Vec<usize>,
+    pub rows: Vec<Vec<usize>>,
+}
+
+#[derive(Debug)]
+pub struct Table {
+    pub columns: Vec<Column>,
+    pub groups: Option<Groups>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Aggregate {
+    Count,
+    Sum,
+    Mean,
+    Min,
+    Max,
+    First,
+    Last,
+}
+
+impl Aggregate {
+    pub const NAMES: [&'static str; 7] = ["count", "sum", "mean", "min", "max", "first", "last"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "count" => Some(Self::Count),
+            "sum" => Some(Self::Sum),
+            "mean" => Some(Self::Mean),
+            "min" => Some(Self::Min),
+            "max" => Some(Self::Max),
+            "first" => Some(Self::First),
+            "last" => Some(Self::Last),
+            _ => None,
+        }
+    }
+
+    // the aggregate of the values that aren't nil
+    fn apply(self, values: impl Iterator<Item = Value>) -> Result<Value, String> {
+        let mut values = values.filter(|value| *value != Value::Nil);
+        match self {
+            Self::Count => Ok(Integer::from(values.count()).into()),
+            Self::First => Ok(values.next().unwrap_or(Value::Nil)),
+            Self::Last => Ok(values.last().unwrap_or(Value::Nil)),
+            Self::Sum | Self::Mean => {
+                let (mut sum, mut count) = (Value::from(Integer::new()), 0);
+                for value in values {
+                    let found = value.type_name();
+                    sum = (sum + value).map_err(|_| format!("can't add up {} values", found))?;
+                    count += 1;
+                }
+                match (self, count) {
+                    (Self::Sum, _) => Ok(sum),
+                    (_, 0) => Ok(Value::Nil),
+                    _ => {
+                        let count = Float::with_val(DEFAULT_FLOAT_PRECISION, count);
+                        (sum / count.into()).map_err(|_| "can't average these values".to_string())
+                    }
+                }
+            }
+            Self::Min | Self::Max => {
+                let wanted = match self {
+                    Self::Min => Ordering::Less,
+                    _ => Ordering::Greater,
+                };
+                let mut best: Option<Value> = None;
+                for value in values {
+                    best = match best {
+                        None => Some(value),
+                        Some(current) => match value.partial_cmp(&current) {
+                            Some(ordering) if ordering == wanted => Some(value),
+                            Some(_) => Some(current),
+                            None => return Err(incomparable(&value, &current)),
+                        },
+                    };
+                }
+                Ok(best.unwrap_or(Value::Nil))
+            }
+        }
+    }
+}
+
+fn incomparable(lhs: &Value, rhs: &Value) -> String {
+    format!(
+        "can't compare {} and {} values",
+        lhs.type_name(),
+        rhs.type_name()
+    )
+}
+
+// nils after everything else, in either order
+fn compare(lhs: &Value, rhs: &Value, descending: bool) -> Result<Ordering, String> {
+    match (lhs, rhs) {
+        (Value::Nil, Value::Nil) => Ok(Ordering::Equal),
+        (Value::Nil, _) => Ok(Ordering::Greater),
+        (_, Value::Nil) => Ok(Ordering::Less),
+        _ => match lhs.partial_cmp(rhs) {
+            Some(ordering) if descending => Ok(ordering.reverse()),
+            Some(ordering) => Ok(ordering),
+            None => Err(incomparable(lhs, rhs)),
+        },
+    }
+}
+
+impl Table {
+    // a column for every key any of the dicts has, sorted by name, and nil
+    // where a dict doesn't have it
+    pub fn from_rows(rows: &[Value]) -> Result<Self, String> {
+        let mut names: Vec<Rc<str>> = Vec::new();
+        let mut dicts = Vec::with_capacity(rows.len());
+        for (index, row) in rows.iter().enumerate() {
+            let Value::Dict(dict) = row else {
+                return Err(format!(
+                    "row {} is a {}, not a dict",
+                    index,
+                    row.type_name()
+                ));
+            };
+            for key in dict.borrow().0.keys() {
+                match &key.0 {
+                    Value::Literal(Literal::String(name)) => {
+                        if !names.contains(name) {
+                            names.push(name.clone());
+                        }
+                    }
+                    other => {
+                        return Err(format!(
+                            "row {} has a key {} that isn't a string",
+                            index, other
+                        ))
+                    }
+                }
+            }
+            dicts.push(dict.clone());
+        }
+        names.sort();
+
+        let columns = names
+            .into_iter()
+            .map(|name| {
+                let key = ValueKey(Value::Literal(Literal::String(name.clone())));
+                let values = dicts
+                    .iter()
+                    .map(|dict| dict.borrow().0.get(&key).cloned().unwrap_or(Value::Nil))
+                    .collect();
+                Column { name, values }
+            })
+            .collect();
+        Ok(Self {
+            columns,
+            groups: None,
+        })
+    }
+
+    fn with_columns(columns: Vec<Column>) -> Self {
+        Self {
+            columns,
+            groups: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns.first().map_or(0, |column| column.values.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // the index of every column called one of `names`, in their order
+    fn find(&self, names: &[String]) -> Result<Vec<usize>, String> {
+        names
+            .iter()
+            .map(|name| {
+                self.columns
+                    .iter()
+                    .position(|column| *column.name == **name)
+                    .ok_or_else(|| format!("there is no column `{}`", name))
+            })
+            .collect()
+    }
+
+    pub fn row(&self, row: usize) -> Value {
+        let entries = self.columns.iter().map(|column| {
+            let key = ValueKey(Value::Literal(Literal::String(column.name.clone())));
+            (key, column.values[row].clone())
+        });
+        Value::Dict(Rc::new(RefCell::new(Dict(entries.collect()))))
+    }
+
+    // the rows at `rows`, in that order
+    pub fn take(&self, rows: &[usize]) -> Self {
+        let columns = self.columns.iter().map(|column| Column {
+            name: column.name.clone(),
+            values: rows.iter().map(|&row| column.values[row].clone()).collect(),
+        });
+        Self::with_columns(columns.collect())
+    }
+
+    // the rows whose `name` is `value`
+    pub fn matching(&self, name: &str, value: &Value) -> Result<Self, String> {
+        let column = &self.columns[self.find(&[name.to_string()])?[0]];
+        let rows: Vec<_> = (0..self.len())
+            .filter(|&row| column.values[row] == *value)
+            .collect();
+        Ok(self.take(&rows))
+    }
+
+    pub fn select(&self, names: &[String]) -> Result<Self, String> {
+        let columns = self.find(names)?.into_iter().map(|index| {
+            let column = &self.columns[index];
+            Column {
+                name: column.name.clone(),
+                values: column.values.clone(),
+            }
+        });
+        Ok(Self::with_columns(columns.collect()))
+    }
+
+    // the same rows, grouped by the values they have for `names`
+    pub fn group_by(&self, names: &[String]) -> Result<Self, String> {
+        let keys = self.find(names)?;
+        let mut groups: HashMap<Vec<ValueKey>, usize> = HashMap::new();
+        let mut rows: Vec<Vec<usize>> = Vec::new();
+        for row in 0..self.len() {
+            let key = keys
+                .iter()
+                .map(|&column| {
+                    let value = self.columns[column].values[row].clone();
+                    ValueKey::try_from(value)
+                        .map_err(|_| format!("can't group by a value of row {}", row))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let group = *groups.entry(key).or_insert_with(|| {
+                rows.push(Vec::new());
+                rows.len() - 1
+            });
+            rows[group].push(row);
+        }
+        let columns = self.columns.iter().map(|column| Column {
+            name: column.name.clone(),
+            values: column.values.clone(),
+        });
+        Ok(Self {
+            columns: columns.collect(),
+            groups: Some(Groups { keys, rows }),
+        })
+    }
+
+    // a row per group, or a single one when the table isn't grouped, with
+    // the columns grouped by and then a column `name` for every aggregate
+    pub fn agg(&self, aggregates: &[(String, Aggregate, String)]) -> Result<Self, String> {
+        let all;
+        let (keys, groups) = match &self.groups {
+            Some(groups) => (groups.keys.as_slice(), groups.rows.as_slice()),
+            None => {
+                all = [(0..self.len()).collect::<Vec<_>>()];
+                (&[][..], &all[..])
+            }
+        };
+
+        let mut columns: Vec<Column> = keys
+            .iter()
+            .map(|&key| {
+                let column = &self.columns[key];
+                Column {
+                    name: column.name.clone(),
+                    values: groups
+                        .iter()
+                        .map(|rows| column.values[rows[0]].clone())
+                        .collect(),
+                }
+            })
+            .collect();
+        for (name, aggregate, source) in aggregates {
+            let source = &self.columns[self.find(std::slice::from_ref(source))?[0]];
+            let values = groups
+                .iter()
+                .map(|rows| {
+                    let values = rows.iter().map(|&row| source.values[row].clone());
+                    aggregate
+                        .apply(values)
+                        .map_err(|message| format!("`{}`: {}", name, message))
+                })
+                .collect::<Result<_, _>>()?;
+            columns.push(Column {
+                name: name.as_str().into(),
+                values,
+            });
+        }
+        Ok(Self::with_columns(columns))
+    }
+
+    // the rows ordered by the first of `names`, then by the next one when
+    // they're equal, and so on; rows that are equal throughout keep their
+    // order
+    pub fn sort_by(&self, names: &[String], descending: bool) -> Result<Self, String> {
+        let keys = self.find(names)?;
+        let mut rows: Vec<usize> = (0..self.len()).collect();
+        let mut error = None;
+        rows.sort_by(|&lhs, &rhs| {
+            for &key in &keys {
+                let values = &self.columns[key].values;
+                match compare(&values[lhs], &values[rhs], descending) {
+                    Ok(Ordering::Equal) => continue,
+                    Ok(ordering) => return ordering,
+                    Err(message) => {
+                        error.get_or_insert(message);
+                        return Ordering::Equal;
+                    }
+                }
+            }
+            Ordering::Equal
+        });
+        match error {
+            Some(message) => Err(message),
+            None => Ok(self.take(&rows)),
+        }
+    }
+
+    // a header of the column names and a line per row, with fields that
+    // have a comma, a quote or a line break in quotes
+    pub fn to_csv(&self) -> String {
+        fn field(value: &Value) -> String {
+            let text = match value {
+                Value::Nil => String::new(),
+                value => value.to_string(),
+            };
+            match text.contains([',', '"', '\n', '\r']) {
+                true => format!("\"{}\"", text.replace('"', "\"\"")),
+                false => text,
+            }
+        }
+
+        let mut csv = String::new();
+        let header: Vec<_> = self
+            .columns
+            .iter()
+            .map(|column| field(&Value::from(&*column.name)))
+            .collect();
+        csv.push_str(&header.join(","));
+        csv.push('\n');
+        for row in 0..self.len() {
+            let fields: Vec<_> = self
+                .columns
+                .iter()
+                .map(|column| field(&column.values[row]))
+                .collect();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+// the columns lined up under their names
+impl Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cells: Vec<Vec<String>> = self
+            .columns
+            .iter()
+            .map(|column| {
+                std::iter::once(column.name.to_string())
+                    .chain(column.values.iter().map(|value| value.to_string()))
+                    .collect()
+            })
+            .collect();
+        let widths: Vec<usize> = cells
+            .iter()
+            .map(|cells| {
+                cells
+                    .iter()
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        for line in 0..=self.len() {
+            if line > 0 {
+                writeln!(f)?;
+            }
+            let fields: Vec<_> = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cells, &width)| format!("{:<width$}", cells[line]))
+                .collect();
+            write!(f, "{}", fields.join("  ").trim_end())?;
+        }
+        if let Some(groups) = &self.groups {
+            let keys: Vec<_> = groups
+                .keys
+                .iter()
+                .map(|&key| &*self.columns[key].name)
+                .collect();
+            write!(f, "\n({} groups by {})", groups.rows.len(), keys.join(", "))?;
+        }
+        Ok(())
+    }
+}
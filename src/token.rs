@@ -2,6 +2,9 @@ use std::hash::Hash;
 
 use miette::SourceSpan;
 
+use crate::fixed::Fixed;
+use crate::sync::Rc;
+
 #[derive(Default, Debug, Clone, Copy, Hash)]
 pub struct Span {
     pub offset: usize,
@@ -27,12 +30,18 @@ impl From<Span> for SourceSpan {
     }
 }
 
+// Numbers and strings are shared, so evaluating a literal, or reading a
+// variable, copies a pointer rather than the digits or the text
 #[derive(Debug, Clone)]
 pub enum Literal {
     Identifier(String),
-    String(String),
-    Integer(rug::Integer),
-    Float(rug::Float),
+    String(Rc<str>),
+    Integer(Rc<rug::Integer>),
+    Float(Rc<rug::Float>),
+    Bytes(Rc<[u8]>),
+    Fixed(Fixed),
+    // `0.1r`, exactly a tenth
+    Rational(Rc<rug::Rational>),
 }
 
 #[derive(Debug, Clone)]
@@ -52,6 +61,26 @@ impl Token {
     }
 }
 
+// The operator of a unary, binary or logical expression: the token without
+// its literal, which operators don't have
+#[derive(Debug, Clone, Copy)]
+pub struct Operator {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+impl Operator {
+    pub fn new(kind: TokenKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+impl From<&Token> for Operator {
+    fn from(token: &Token) -> Self {
+        Self::new(token.kind, token.span)
+    }
+}
+
 #[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
 pub enum TokenKind {
     // Single-character tokens.
@@ -70,34 +99,54 @@ pub enum TokenKind {
     Slash,
     Star,
     Pipe,
+    Question,
+    Underscore,
+    At,
 
     // One or two character tokens.
     Bang,
     BangEqual,
     Equal,
     EqualEqual,
+    FatArrow,
+    HashLeftBrace,
     Greater,
     GreaterEqual,
     Less,
     LessEqual,
 
+    // Three character tokens.
+    DotDotDot,
+
     // Literals.
     Identifier,
     String,
+    Bytes,
     Integer,
     Float,
 
     // Keywords.
     And,
+    Catch,
+    Class,
     Else,
     False,
     Fn,
     For,
     If,
+    Import,
+    In,
     Nil,
     Or,
+    Quote,
     Return,
+    Super,
+    This,
+    Throw,
     True,
+    Try,
+    Unquote,
     Let,
+    Match,
     While,
 }
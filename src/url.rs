@@ -0,0 +1,148 @@
+// URLs split into their parts (RFC 3986), and the percent-encoding of their
+// components and of `application/x-www-form-urlencoded` query strings.
+
+pub struct Url {
+    pub scheme: String,
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+    pub path: String,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+}
+
+// letters, digits and `-._~` are never encoded
+fn unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+pub fn encode(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for &byte in text.as_bytes() {
+        match unreserved(byte) {
+            true => output.push(byte as char),
+            false => output.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    output
+}
+
+// `%XX` as the byte it encodes and `+` as a space; the bytes must make up
+// UTF-8
+pub fn decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut at = 0;
+    while at < bytes.len() {
+        match bytes[at] {
+            b'%' => {
+                let hex = bytes
+                    .get(at + 1..at + 3)
+                    .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+                let Some(byte) = hex else {
+                    return Err(format!(
+                        "`%` at byte {} isn't followed by two hex digits",
+                        at
+                    ));
+                };
+                output.push(byte);
+                at += 3;
+            }
+            b'+' => {
+                output.push(b' ');
+                at += 1;
+            }
+            byte => {
+                output.push(byte);
+                at += 1;
+            }
+        }
+    }
+    String::from_utf8(output).map_err(|_| "it decodes to bytes that aren't UTF-8".to_string())
+}
+
+// `a=1&b=2` as its pairs, in order and decoded; a pair without `=` has an
+// empty value
+pub fn parse_query(query: &str) -> Result<Vec<(String, String)>, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((decode(key)?, decode(value)?))
+        })
+        .collect()
+}
+
+pub fn query_string(pairs: &[(String, String)]) -> String {
+    let pairs: Vec<_> = pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", encode(key), encode(value)))
+        .collect();
+    pairs.join("&")
+}
+
+impl Url {
+    // `scheme://user@host:port/path?query#fragment`, where everything but
+    // the scheme and the host may be missing
+    pub fn parse(url: &str) -> Result<Self, String> {
+        let (scheme, rest) = url
+            .split_once("://")
+            .ok_or_else(|| "it has no `scheme://`".to_string())?;
+        let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if !valid_scheme {
+            return Err(format!("`{}` isn't a scheme", scheme));
+        }
+
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment.to_string())),
+            None => (rest, None),
+        };
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query.to_string())),
+            None => (rest, None),
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => rest.split_at(slash),
+            None => (rest, ""),
+        };
+        let (user, address) = match authority.rsplit_once('@') {
+            Some((user, address)) => (Some(user.to_string()), address),
+            None => (None, authority),
+        };
+        // the brackets of an IPv6 address hold colons of their own
+        let port_colon = match address.rfind(']') {
+            Some(bracket) => address[bracket..].find(':').map(|colon| bracket + colon),
+            None => address.rfind(':'),
+        };
+        let (host, port) = match port_colon {
+            Some(colon) => {
+                let port = &address[colon + 1..];
+                let port = port
+                    .parse::<u16>()
+                    .map_err(|_| format!("`{}` isn't a port", port))?;
+                (&address[..colon], Some(port))
+            }
+            None => (address, None),
+        };
+        if host.is_empty() {
+            return Err("it has no host".to_string());
+        }
+
+        Ok(Self {
+            scheme: scheme.to_lowercase(),
+            user,
+            host: host.to_lowercase(),
+            port,
+            path: match path {
+                "" => "/".to_string(),
+                path => path.to_string(),
+            },
+            query,
+            fragment,
+        })
+    }
+}
@@ -1,26 +1,61 @@
 use std::hash::{Hash, Hasher};
 use std::{
-    cell::RefCell,
     fmt::Display,
-    ops::{Add, Div, Mul, Neg, Not, Sub},
-    rc::Rc,
+    ops::{Add, BitOr, Div, Mul, Neg, Not, Sub},
 };
 
 use miette::Report;
-use rug::{integer::TryFromIntegerError, Float, Integer};
+use rug::{
+    float::Round,
+    integer::TryFromIntegerError,
+    ops::{DivFrom, NegAssign, SubFrom},
+    Float, Integer,
+};
 use thiserror::Error;
 
 use crate::{
+    class::{Class, Instance},
+    datetime::{DateTime, Duration},
     dict::Dict,
+    exact::{self, Exact},
+    file::File,
+    fixed::{self, Fixed, Op},
     function::Function,
+    iterator::ValueIterator,
     list::List,
-    report::UnsupportedOperation,
+    process::Process,
+    progress::Progress,
+    report::{DivisionByZero, IntegerOverflow, UnhashableKey, UnsupportedOperation},
+    set::Set,
+    sync::{Rc, RefCell, Weak},
+    table::Table,
     token::{Literal, Span},
 };
 
 #[derive(Debug, Clone)]
 pub struct ValueKey(pub Value);
 
+impl TryFrom<Value> for ValueKey {
+    type Error = ValueError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::True
+            | Value::False
+            | Value::Nil
+            | Value::Literal(Literal::Identifier(_))
+            | Value::Literal(Literal::String(_))
+            | Value::Literal(Literal::Integer(_))
+            | Value::Bytes(_)
+            | Value::Duration(_)
+            | Value::DateTime(_)
+            | Value::Fixed(_)
+            | Value::Function(_) => Ok(Self(value)),
+            _ => Err(ValueError::UnhashableValue),
+        }
+    }
+}
+
 impl Eq for ValueKey {}
 
 impl PartialEq for ValueKey {
@@ -36,7 +71,14 @@ impl Hash for ValueKey {
             Value::Literal(Literal::Identifier(value)) => value.hash(state),
             Value::Literal(Literal::String(value)) => value.hash(state),
             Value::Literal(Literal::Integer(value)) => value.hash(state),
-            _ => panic!("unsupported hashing"),
+            Value::Bytes(value) => value.hash(state),
+            Value::Duration(value) => value.hash(state),
+            Value::DateTime(value) => value.nanos.hash(state),
+            // as the integer it equals
+            Value::Fixed(value) => value.to_integer().hash(state),
+            Value::Function(value) => Rc::as_ptr(value).cast::<()>().hash(state),
+            // keys are built through `TryFrom`, which rejects everything else
+            _ => core::mem::discriminant(&self.0).hash(state),
         }
     }
 }
@@ -56,12 +98,60 @@ pub enum Value {
     Function(Rc<dyn Function>),
     List(Rc<RefCell<List>>),
     Dict(Rc<RefCell<Dict>>),
+    Set(Rc<Set>),
+    Bytes(Rc<[u8]>),
+    Duration(Duration),
+    DateTime(DateTime),
+    Fixed(Fixed),
+    Exact(Rc<Exact>),
+    Iterator(Rc<RefCell<dyn ValueIterator>>),
+    Process(Rc<RefCell<Process>>),
+    File(Rc<RefCell<File>>),
+    Progress(Rc<RefCell<Progress>>),
+    Table(Rc<Table>),
+    Class(Rc<Class>),
+    Instance(Rc<RefCell<Instance>>),
+    Weak(WeakRef),
+}
+
+// A reference that doesn't keep its list, dict or instance alive
+#[derive(Debug, Clone)]
+pub enum WeakRef {
+    List(Weak<RefCell<List>>),
+    Dict(Weak<RefCell<Dict>>),
+    Instance(Weak<RefCell<Instance>>),
+}
+
+impl WeakRef {
+    pub fn new(value: &Value) -> Option<Self> {
+        match value {
+            Value::List(list) => Some(Self::List(Rc::downgrade(list))),
+            Value::Dict(dict) => Some(Self::Dict(Rc::downgrade(dict))),
+            Value::Instance(instance) => Some(Self::Instance(Rc::downgrade(instance))),
+            _ => None,
+        }
+    }
+
+    // the value, unless nothing else refers to it anymore
+    pub fn upgrade(&self) -> Option<Value> {
+        match self {
+            Self::List(list) => list.upgrade().map(Value::List),
+            Self::Dict(dict) => dict.upgrade().map(Value::Dict),
+            Self::Instance(instance) => instance.upgrade().map(Value::Instance),
+        }
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum ValueError {
     #[error("unsupported operation")]
     UnsupportedOperation,
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("integer overflow")]
+    Overflow,
+    #[error("unhashable value")]
+    UnhashableValue,
     #[error("data store disconnected")]
     IntegerConversionError(#[from] TryFromIntegerError),
 }
@@ -73,6 +163,53 @@ impl Value {
     pub fn is_truthy(&self) -> bool {
         !matches!(self, Value::False | Value::Nil)
     }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::True | Value::False => "bool",
+            Value::Nil => "nil",
+            Value::Literal(Literal::Identifier(_)) => "identifier",
+            Value::Literal(Literal::String(_)) => "string",
+            Value::Literal(Literal::Integer(_)) => "integer",
+            Value::Literal(Literal::Float(_)) => "float",
+            Value::Literal(Literal::Bytes(_)) | Value::Bytes(_) => "bytes",
+            Value::Duration(_) => "duration",
+            Value::DateTime(_) => "datetime",
+            Value::Literal(Literal::Fixed(value)) | Value::Fixed(value) => value.kind.name(),
+            Value::Literal(Literal::Rational(_)) | Value::Exact(_) => "exact",
+            Value::Function(_) => "function",
+            Value::List(_) => "list",
+            Value::Dict(_) => "dict",
+            Value::Set(_) => "set",
+            Value::Iterator(_) => "iterator",
+            Value::Process(_) => "process",
+            Value::File(_) => "file",
+            Value::Progress(_) => "progress",
+            Value::Table(_) => "table",
+            Value::Class(_) => "class",
+            Value::Instance(_) => "instance",
+            Value::Weak(_) => "weak",
+        }
+    }
+
+    // mixed arithmetic happens at the float's precision: when the integer
+    // operand would be rounded to fit it, returns the bits it needs and the
+    // precision it gets
+    pub fn precision_loss(&self, other: &Self) -> Option<(u32, u32)> {
+        match (self, other) {
+            (Self::Literal(Literal::Integer(i)), Self::Literal(Literal::Float(f)))
+            | (Self::Literal(Literal::Float(f)), Self::Literal(Literal::Integer(i))) => {
+                let (_, rounding) = Float::with_val_round(f.prec(), &**i, Round::Nearest);
+                if rounding == std::cmp::Ordering::Equal {
+                    return None;
+                }
+
+                let needed = i.significant_bits() - i.find_one(0).unwrap_or(0);
+                Some((needed, f.prec()))
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Not for Value {
@@ -83,13 +220,29 @@ impl Not for Value {
     }
 }
 
+// `op` on the number of an operand, in place unless something else, like a
+// variable, shares it
+fn apply<T: Clone>(mut number: Rc<T>, op: impl FnOnce(&mut T)) -> Rc<T> {
+    op(Rc::make_mut(&mut number));
+    number
+}
+
+// the same for a commutative `op`, on whichever operand isn't shared
+fn commute<T: Clone>(lhs: Rc<T>, rhs: Rc<T>, op: impl FnOnce(&mut T, &T)) -> Rc<T> {
+    let (number, other) = match Rc::strong_count(&lhs) {
+        1 => (lhs, rhs),
+        _ => (rhs, lhs),
+    };
+    apply(number, |number| op(number, &other))
+}
+
 impl Neg for Literal {
     type Output = Result<Self, ValueError>;
 
     fn neg(self) -> Self::Output {
         match self {
-            Self::Integer(i) => Ok(i.neg().into()),
-            Self::Float(f) => Ok(f.neg().into()),
+            Self::Integer(i) => Ok(Self::Integer(apply(i, |n| n.neg_assign()))),
+            Self::Float(f) => Ok(Self::Float(apply(f, |n| n.neg_assign()))),
             _ => Err(ValueError::UnsupportedOperation),
         }
     }
@@ -101,6 +254,9 @@ impl Neg for Value {
     fn neg(self) -> Self::Output {
         match self {
             Self::Literal(literal) => Ok(literal.neg()?.into()),
+            Self::Duration(d) => Ok(Self::Duration(Duration(d.0.neg()))),
+            Self::Fixed(f) => fixed::arithmetic(Op::Sub, Integer::new().into(), Self::Fixed(f)),
+            Self::Exact(e) => Ok((-(*e).clone()).into_value()),
             _ => Err(ValueError::UnsupportedOperation),
         }
     }
@@ -111,10 +267,12 @@ impl Add for Literal {
 
     fn add(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
-            (Self::Float(lhs), Self::Float(rhs)) => Ok(lhs.add(rhs).into()),
-            (Self::Integer(lhs), Self::Float(rhs)) => Ok(lhs.add(rhs).into()),
-            (Self::Float(lhs), Self::Integer(rhs)) => Ok(lhs.add(rhs).into()),
-            (Self::Integer(lhs), Self::Integer(rhs)) => Ok(lhs.add(rhs).into()),
+            (Self::Float(lhs), Self::Float(rhs)) => Ok(Self::Float(apply(lhs, |n| *n += &*rhs))),
+            (Self::Integer(lhs), Self::Float(rhs)) => Ok(Self::Float(apply(rhs, |n| *n += &*lhs))),
+            (Self::Float(lhs), Self::Integer(rhs)) => Ok(Self::Float(apply(lhs, |n| *n += &*rhs))),
+            (Self::Integer(lhs), Self::Integer(rhs)) => {
+                Ok(Self::Integer(commute(lhs, rhs, |n, m| *n += m)))
+            }
             (Self::String(lhs), rhs) => Ok(format!("{}{}", lhs, rhs).into()),
             (lhs, Self::String(rhs)) => Ok(format!("{}{}", lhs, rhs).into()),
             _ => Err(ValueError::UnsupportedOperation),
@@ -122,6 +280,28 @@ impl Add for Literal {
     }
 }
 
+// `a | b` is a new dict with the entries of both; `b` wins on shared keys
+impl BitOr for Value {
+    type Output = Result<Self, ValueError>;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Self::Dict(lhs), Self::Dict(rhs)) => {
+                let mut merged = lhs.borrow().clone();
+                merged
+                    .0
+                    .extend(rhs.borrow().0.iter().map(|(k, v)| (k.clone(), v.clone())));
+                Ok(Self::Dict(Rc::new(RefCell::new(merged))))
+            }
+            (lhs @ Self::Fixed(_), rhs) | (lhs, rhs @ Self::Fixed(_)) => {
+                let (lhs, rhs) = fixed::operands(lhs, rhs)?;
+                Ok(Self::Fixed(lhs.bits(rhs, |a, b| a | b)))
+            }
+            _ => Err(ValueError::UnsupportedOperation),
+        }
+    }
+}
+
 impl Add for Value {
     type Output = Result<Self, ValueError>;
 
@@ -130,6 +310,22 @@ impl Add for Value {
             (Self::Literal(lhs), Self::Literal(rhs)) => Ok(lhs.add(rhs)?.into()),
             (Self::Literal(Literal::String(lhs)), rhs) => Ok(format!("{}{}", lhs, rhs).into()),
             (lhs, Self::Literal(Literal::String(rhs))) => Ok(format!("{}{}", lhs, rhs).into()),
+            (Self::Bytes(lhs), Self::Bytes(rhs)) => {
+                Ok(Self::Bytes([&lhs[..], &rhs[..]].concat().into()))
+            }
+            (Self::Duration(lhs), Self::Duration(rhs)) => checked(lhs.0.checked_add(rhs.0)),
+            (Self::DateTime(lhs), Self::Duration(rhs))
+            | (Self::Duration(rhs), Self::DateTime(lhs)) => {
+                let nanos = lhs.nanos.checked_add(rhs.0);
+                let nanos = nanos.ok_or(ValueError::UnsupportedOperation)?;
+                Ok(Self::DateTime(DateTime { nanos, ..lhs }))
+            }
+            (lhs @ Self::Fixed(_), rhs) | (lhs, rhs @ Self::Fixed(_)) => {
+                fixed::arithmetic(Op::Add, lhs, rhs)
+            }
+            (lhs @ Self::Exact(_), rhs) | (lhs, rhs @ Self::Exact(_)) => {
+                exact::arithmetic(Op::Add, lhs, rhs)
+            }
             _ => Err(ValueError::UnsupportedOperation),
         }
     }
@@ -140,10 +336,14 @@ impl Sub for Literal {
 
     fn sub(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
-            (Self::Float(lhs), Self::Float(rhs)) => Ok(lhs.sub(rhs).into()),
-            (Self::Integer(lhs), Self::Float(rhs)) => Ok(lhs.sub(rhs).into()),
-            (Self::Float(lhs), Self::Integer(rhs)) => Ok(lhs.sub(rhs).into()),
-            (Self::Integer(lhs), Self::Integer(rhs)) => Ok(lhs.sub(rhs).into()),
+            (Self::Float(lhs), Self::Float(rhs)) => Ok(Self::Float(apply(lhs, |n| *n -= &*rhs))),
+            (Self::Integer(lhs), Self::Float(rhs)) => {
+                Ok(Self::Float(apply(rhs, |n| n.sub_from(&*lhs))))
+            }
+            (Self::Float(lhs), Self::Integer(rhs)) => Ok(Self::Float(apply(lhs, |n| *n -= &*rhs))),
+            (Self::Integer(lhs), Self::Integer(rhs)) => {
+                Ok(Self::Integer(apply(lhs, |n| *n -= &*rhs)))
+            }
             _ => Err(ValueError::UnsupportedOperation),
         }
     }
@@ -155,6 +355,19 @@ impl Sub for Value {
     fn sub(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
             (Self::Literal(lhs), Self::Literal(rhs)) => Ok(lhs.sub(rhs)?.into()),
+            (Self::Duration(lhs), Self::Duration(rhs)) => checked(lhs.0.checked_sub(rhs.0)),
+            (Self::DateTime(lhs), Self::DateTime(rhs)) => checked(lhs.nanos.checked_sub(rhs.nanos)),
+            (Self::DateTime(lhs), Self::Duration(rhs)) => {
+                let nanos = lhs.nanos.checked_sub(rhs.0);
+                let nanos = nanos.ok_or(ValueError::UnsupportedOperation)?;
+                Ok(Self::DateTime(DateTime { nanos, ..lhs }))
+            }
+            (lhs @ Self::Fixed(_), rhs) | (lhs, rhs @ Self::Fixed(_)) => {
+                fixed::arithmetic(Op::Sub, lhs, rhs)
+            }
+            (lhs @ Self::Exact(_), rhs) | (lhs, rhs @ Self::Exact(_)) => {
+                exact::arithmetic(Op::Sub, lhs, rhs)
+            }
             _ => Err(ValueError::UnsupportedOperation),
         }
     }
@@ -165,10 +378,15 @@ impl Div for Literal {
 
     fn div(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
-            (Self::Float(lhs), Self::Float(rhs)) => Ok(lhs.div(rhs).into()),
-            (Self::Integer(lhs), Self::Float(rhs)) => Ok(lhs.div(rhs).into()),
-            (Self::Float(lhs), Self::Integer(rhs)) => Ok(lhs.div(rhs).into()),
-            (Self::Integer(lhs), Self::Integer(rhs)) => Ok(lhs.div(rhs).into()),
+            (Self::Float(lhs), Self::Float(rhs)) => Ok(Self::Float(apply(lhs, |n| *n /= &*rhs))),
+            (Self::Integer(lhs), Self::Float(rhs)) => {
+                Ok(Self::Float(apply(rhs, |n| n.div_from(&*lhs))))
+            }
+            (Self::Float(lhs), Self::Integer(rhs)) => Ok(Self::Float(apply(lhs, |n| *n /= &*rhs))),
+            (Self::Integer(_), Self::Integer(rhs)) if *rhs == 0 => Err(ValueError::DivisionByZero),
+            (Self::Integer(lhs), Self::Integer(rhs)) => {
+                Ok(Self::Integer(apply(lhs, |n| *n /= &*rhs)))
+            }
             _ => Err(ValueError::UnsupportedOperation),
         }
     }
@@ -180,6 +398,24 @@ impl Div for Value {
     fn div(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
             (Self::Literal(lhs), Self::Literal(rhs)) => Ok(lhs.div(rhs)?.into()),
+            (Self::Duration(lhs), Self::Literal(Literal::Integer(rhs))) => {
+                let rhs = rhs.to_i128().ok_or(ValueError::UnsupportedOperation)?;
+                match rhs {
+                    0 => Err(ValueError::DivisionByZero),
+                    _ => checked(lhs.0.checked_div(rhs)),
+                }
+            }
+            // how many times `rhs` fits in `lhs`
+            (Self::Duration(lhs), Self::Duration(rhs)) => match rhs.0 {
+                0 => Err(ValueError::DivisionByZero),
+                _ => Ok(Integer::from(lhs.0 / rhs.0).into()),
+            },
+            (lhs @ Self::Fixed(_), rhs) | (lhs, rhs @ Self::Fixed(_)) => {
+                fixed::arithmetic(Op::Div, lhs, rhs)
+            }
+            (lhs @ Self::Exact(_), rhs) | (lhs, rhs @ Self::Exact(_)) => {
+                exact::arithmetic(Op::Div, lhs, rhs)
+            }
             _ => Err(ValueError::UnsupportedOperation),
         }
     }
@@ -190,23 +426,52 @@ impl Mul for Literal {
 
     fn mul(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
-            (Self::Float(lhs), Self::Float(rhs)) => Ok(lhs.mul(rhs).into()),
-            (Self::Integer(lhs), Self::Float(rhs)) => Ok(lhs.mul(rhs).into()),
-            (Self::Float(lhs), Self::Integer(rhs)) => Ok(lhs.mul(rhs).into()),
-            (Self::Integer(lhs), Self::Integer(rhs)) => Ok(lhs.mul(rhs).into()),
-            (Self::String(lhs), Self::Integer(rhs)) => Ok(lhs.repeat(rhs.try_into()?).into()),
-            (Self::Integer(lhs), Self::String(rhs)) => Ok(rhs.repeat(lhs.try_into()?).into()),
+            (Self::Float(lhs), Self::Float(rhs)) => Ok(Self::Float(apply(lhs, |n| *n *= &*rhs))),
+            (Self::Integer(lhs), Self::Float(rhs)) => Ok(Self::Float(apply(rhs, |n| *n *= &*lhs))),
+            (Self::Float(lhs), Self::Integer(rhs)) => Ok(Self::Float(apply(lhs, |n| *n *= &*rhs))),
+            (Self::Integer(lhs), Self::Integer(rhs)) => {
+                Ok(Self::Integer(commute(lhs, rhs, |n, m| *n *= m)))
+            }
+            (Self::String(lhs), Self::Integer(rhs)) => Ok(repeat(&lhs, &rhs)?.into()),
+            (Self::Integer(lhs), Self::String(rhs)) => Ok(repeat(&rhs, &lhs)?.into()),
             _ => Err(ValueError::UnsupportedOperation),
         }
     }
 }
 
+// a duration, unless the arithmetic producing it overflowed
+fn checked(nanos: Option<i128>) -> Result<Value, ValueError> {
+    match nanos {
+        Some(nanos) => Ok(Value::Duration(Duration(nanos))),
+        None => Err(ValueError::UnsupportedOperation),
+    }
+}
+
+fn repeat(string: &str, times: &Integer) -> Result<String, ValueError> {
+    let times: usize = times.try_into()?;
+    match string.len().checked_mul(times) {
+        Some(length) if length <= isize::MAX as usize => Ok(string.repeat(times)),
+        _ => Err(ValueError::UnsupportedOperation),
+    }
+}
+
 impl Mul for Value {
     type Output = Result<Self, ValueError>;
 
     fn mul(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
             (Self::Literal(lhs), Self::Literal(rhs)) => Ok(lhs.mul(rhs)?.into()),
+            (Self::Duration(lhs), Self::Literal(Literal::Integer(rhs)))
+            | (Self::Literal(Literal::Integer(rhs)), Self::Duration(lhs)) => {
+                let rhs = rhs.to_i128().ok_or(ValueError::UnsupportedOperation)?;
+                checked(lhs.0.checked_mul(rhs))
+            }
+            (lhs @ Self::Fixed(_), rhs) | (lhs, rhs @ Self::Fixed(_)) => {
+                fixed::arithmetic(Op::Mul, lhs, rhs)
+            }
+            (lhs @ Self::Exact(_), rhs) | (lhs, rhs @ Self::Exact(_)) => {
+                exact::arithmetic(Op::Mul, lhs, rhs)
+            }
             _ => Err(ValueError::UnsupportedOperation),
         }
     }
@@ -219,6 +484,7 @@ impl PartialEq for Literal {
             (Self::String(l0), Self::String(r0)) => l0 == r0,
             (Self::Integer(l0), Self::Integer(r0)) => l0 == r0,
             (Self::Float(l0), Self::Float(r0)) => l0 == r0,
+            (Self::Bytes(l0), Self::Bytes(r0)) => l0 == r0,
             _ => false,
         }
     }
@@ -228,6 +494,27 @@ impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Literal(l0), Self::Literal(r0)) => l0 == r0,
+            (Self::Bytes(l0), Self::Bytes(r0)) => l0 == r0,
+            (Self::Set(l0), Self::Set(r0)) => l0 == r0,
+            (Self::Duration(l0), Self::Duration(r0)) => l0 == r0,
+            (Self::DateTime(l0), Self::DateTime(r0)) => l0 == r0,
+            (Self::Fixed(l0), Self::Fixed(r0)) => l0 == r0,
+            (Self::Fixed(l0), Self::Literal(Literal::Integer(r0)))
+            | (Self::Literal(Literal::Integer(r0)), Self::Fixed(l0)) => **r0 == l0.value,
+            // an exact equal to an integer is one, so only an exact can be
+            (Self::Exact(l0), Self::Exact(r0)) => l0 == r0,
+            (Self::Exact(_), Self::Literal(Literal::Integer(_)))
+            | (Self::Literal(Literal::Integer(_)), Self::Exact(_)) => false,
+            (Self::Exact(e), Self::Literal(Literal::Float(f)))
+            | (Self::Literal(Literal::Float(f)), Self::Exact(e)) => e.approx(f.prec()) == **f,
+            // functions are only equal to themselves
+            (Self::Function(l0), Self::Function(r0)) => Rc::ptr_eq(l0, r0),
+            (Self::Process(l0), Self::Process(r0)) => Rc::ptr_eq(l0, r0),
+            (Self::File(l0), Self::File(r0)) => Rc::ptr_eq(l0, r0),
+            (Self::Progress(l0), Self::Progress(r0)) => Rc::ptr_eq(l0, r0),
+            (Self::Table(l0), Self::Table(r0)) => Rc::ptr_eq(l0, r0),
+            (Self::Class(l0), Self::Class(r0)) => Rc::ptr_eq(l0, r0),
+            (Self::Instance(l0), Self::Instance(r0)) => Rc::ptr_eq(l0, r0),
             _ => core::mem::discriminant(self) == core::mem::discriminant(other),
         }
     }
@@ -237,8 +524,8 @@ impl PartialOrd for Literal {
     fn partial_cmp(&self, rhs: &Self) -> Option<std::cmp::Ordering> {
         match (self, rhs) {
             (Self::Float(lhs), Self::Float(rhs)) => lhs.partial_cmp(rhs),
-            (Self::Integer(lhs), Self::Float(rhs)) => lhs.partial_cmp(rhs),
-            (Self::Float(lhs), Self::Integer(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Integer(lhs), Self::Float(rhs)) => (**lhs).partial_cmp(&**rhs),
+            (Self::Float(lhs), Self::Integer(rhs)) => (**lhs).partial_cmp(&**rhs),
             (Self::Integer(lhs), Self::Integer(rhs)) => lhs.partial_cmp(rhs),
             (Self::String(lhs), Self::String(rhs)) => lhs.partial_cmp(rhs),
             (Self::Identifier(lhs), Self::Identifier(rhs)) => lhs.partial_cmp(rhs),
@@ -251,6 +538,25 @@ impl PartialOrd for Value {
     fn partial_cmp(&self, rhs: &Self) -> Option<std::cmp::Ordering> {
         match (self, rhs) {
             (Self::Literal(lhs), Self::Literal(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Bytes(lhs), Self::Bytes(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Duration(lhs), Self::Duration(rhs)) => lhs.partial_cmp(rhs),
+            (Self::DateTime(lhs), Self::DateTime(rhs)) => lhs.partial_cmp(rhs),
+            (Self::Fixed(lhs), Self::Fixed(rhs)) if lhs.kind == rhs.kind => {
+                lhs.value.partial_cmp(&rhs.value)
+            }
+            (Self::Fixed(lhs), Self::Literal(Literal::Integer(rhs))) => (**rhs)
+                .partial_cmp(&lhs.value)
+                .map(std::cmp::Ordering::reverse),
+            (Self::Literal(Literal::Integer(lhs)), Self::Fixed(rhs)) => {
+                (**lhs).partial_cmp(&rhs.value)
+            }
+            (Self::Exact(_), _) | (_, Self::Exact(_)) => {
+                let exact = |value: &Value| match value {
+                    Value::Literal(Literal::Float(f)) => Some(Exact::Rational(f.to_rational()?)),
+                    value => Exact::from_value(value),
+                };
+                exact(self)?.partial_cmp(&exact(rhs)?)
+            }
             (Self::True, Self::False) => true.partial_cmp(&false),
             (Self::False, Self::True) => false.partial_cmp(&true),
             _ => None,
@@ -258,15 +564,28 @@ impl PartialOrd for Value {
     }
 }
 
+// bytes, machine integers and rationals are the literals that aren't kept as
+// a `Value::Literal`
 impl From<Literal> for Value {
     fn from(literal: Literal) -> Self {
-        Self::Literal(literal)
+        match literal {
+            Literal::Bytes(bytes) => Self::Bytes(bytes),
+            Literal::Fixed(fixed) => Self::Fixed(fixed),
+            Literal::Rational(rational) => Exact::Rational((*rational).clone()).into_value(),
+            literal => Self::Literal(literal),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Bytes(bytes.into())
     }
 }
 
 impl From<Integer> for Literal {
     fn from(integer: Integer) -> Self {
-        Self::Integer(integer)
+        Self::Integer(Rc::new(integer))
     }
 }
 
@@ -278,7 +597,7 @@ impl From<Integer> for Value {
 
 impl From<Float> for Literal {
     fn from(float: Float) -> Self {
-        Self::Float(float)
+        Self::Float(Rc::new(float))
     }
 }
 
@@ -288,9 +607,21 @@ impl From<Float> for Value {
     }
 }
 
+impl From<i64> for Value {
+    fn from(integer: i64) -> Self {
+        Integer::from(integer).into()
+    }
+}
+
+impl From<f64> for Value {
+    fn from(float: f64) -> Self {
+        Float::with_val(53, float).into()
+    }
+}
+
 impl From<&str> for Literal {
     fn from(string: &str) -> Self {
-        Self::String(string.to_string())
+        Self::String(string.into())
     }
 }
 
@@ -302,7 +633,7 @@ impl From<&str> for Value {
 
 impl From<String> for Literal {
     fn from(string: String) -> Self {
-        Self::String(string)
+        Self::String(string.into())
     }
 }
 
@@ -321,6 +652,12 @@ impl From<bool> for Value {
     }
 }
 
+impl From<Vec<Value>> for Value {
+    fn from(items: Vec<Value>) -> Self {
+        Self::List(Rc::new(RefCell::new(List(items))))
+    }
+}
+
 impl From<Rc<dyn Function>> for Value {
     fn from(value: Rc<dyn Function>) -> Self {
         Self::Function(value)
@@ -334,6 +671,18 @@ impl ValueError {
                 span: (*span).into(),
             }
             .into(),
+            ValueError::DivisionByZero => DivisionByZero {
+                span: (*span).into(),
+            }
+            .into(),
+            ValueError::Overflow => IntegerOverflow {
+                span: (*span).into(),
+            }
+            .into(),
+            ValueError::UnhashableValue => UnhashableKey {
+                span: (*span).into(),
+            }
+            .into(),
             ValueError::IntegerConversionError(_) => UnsupportedOperation {
                 span: (*span).into(),
             }
@@ -349,8 +698,28 @@ impl Display for Literal {
             Self::String(value) => value.fmt(f),
             Self::Integer(value) => value.fmt(f),
             Self::Float(value) => value.fmt(f),
+            Self::Bytes(value) => fmt_bytes(value, f),
+            Self::Fixed(value) => value.fmt(f),
+            Self::Rational(value) => value.fmt(f),
+        }
+    }
+}
+
+// `b"..."`, escaping what isn't printable ASCII
+fn fmt_bytes(bytes: &[u8], f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "b\"")?;
+    for &byte in bytes {
+        match byte {
+            b'\\' => write!(f, "\\\\")?,
+            b'"' => write!(f, "\\\"")?,
+            b'\n' => write!(f, "\\n")?,
+            b'\r' => write!(f, "\\r")?,
+            b'\t' => write!(f, "\\t")?,
+            0x20..=0x7e => write!(f, "{}", byte as char)?,
+            _ => write!(f, "\\x{:02x}", byte)?,
         }
     }
+    write!(f, "\"")
 }
 
 impl Display for Value {
@@ -363,6 +732,23 @@ impl Display for Value {
             Self::Function(value) => Display::fmt(&value, f),
             Self::List(value) => Display::fmt(&value.borrow(), f),
             Self::Dict(value) => Display::fmt(&value.borrow(), f),
+            Self::Set(value) => value.fmt(f),
+            Self::Bytes(value) => fmt_bytes(value, f),
+            Self::Duration(value) => value.fmt(f),
+            Self::DateTime(value) => value.fmt(f),
+            Self::Fixed(value) => value.fmt(f),
+            Self::Exact(value) => value.fmt(f),
+            Self::Iterator(_) => write!(f, "(iterator)"),
+            Self::Process(value) => value.borrow().fmt(f),
+            Self::File(value) => value.borrow().fmt(f),
+            Self::Progress(value) => value.borrow().fmt(f),
+            Self::Table(value) => value.fmt(f),
+            Self::Class(value) => value.fmt(f),
+            Self::Instance(value) => value.borrow().fmt(f),
+            Self::Weak(value) => match value.upgrade() {
+                Some(value) => write!(f, "(weak {})", value.type_name()),
+                None => write!(f, "(weak, dropped)"),
+            },
         }
     }
 }